@@ -0,0 +1,24 @@
+//! Build script: embeds the git commit hash (if available) for `miniclaw --version`.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    println!("cargo:rerun-if-env-changed=MINICLAW_GIT_COMMIT");
+
+    if std::env::var("MINICLAW_GIT_COMMIT").is_ok() {
+        return;
+    }
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=10", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=MINICLAW_GIT_COMMIT={}", commit);
+    }
+}
@@ -2,20 +2,26 @@
 
 #![allow(dead_code)]
 
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
 use tokio::sync::mpsc;
 
-use crate::config::{AppConfig, ModelEntry};
+use crate::config::{AppConfig, CompactionStrategy, LlmConfig, ModelEntry};
 use crate::llm::anthropic::AnthropicProvider;
-use crate::llm::openai_compatible::OpenAiCompatibleProvider;
+use crate::llm::ollama::OllamaProvider;
+use crate::llm::openai_compatible::{AuthStyle, OpenAiCompatibleProvider};
 use crate::llm::LlmProvider;
 use crate::rules;
+use crate::tools::bash::BashTool;
+use crate::tools::fetch::FetchTool;
 use crate::tools::risk::{self, RiskLevel};
-use crate::tools::{create_default_router, ToolRouter};
+use crate::tools::{create_default_router, ToolOutput, ToolRouter};
 use crate::trusted_workspaces;
-use crate::types::{ChatRequest, ChatResponse, Message, Role, StreamChunk, TokenUsage};
+use crate::types::{
+    ChatRequest, ChatResponse, Message, Role, StreamChunk, TokenUsage, ToolCall, ToolChoice,
+};
 
 /// Events emitted by the Agent during processing, allowing the TUI
 /// to display real-time progress (tool calls, intermediate text, etc.).
@@ -28,42 +34,168 @@ pub enum AgentEvent {
     LlmText(String),
     /// A tool is about to be executed.
     ToolStart { name: String, arguments: String },
-    /// A tool finished executing.
+    /// A tool finished executing. `result` is the tool output (or error/denial message).
     ToolEnd {
         name: String,
         arguments: String,
         success: bool,
+        result: String,
+        /// Structured data from the tool (`ToolOutput::metadata`), e.g. `{"files": [...]}`.
+        metadata: serde_json::Value,
     },
+    /// `agent.plan_first`: the tool-free plan is ready and waits for a `ConfirmDecision`
+    /// (content may already have been streamed).
+    PlanReady(String),
     /// A dangerous tool call needs user confirmation before execution.
     ToolConfirm {
         name: String,
         arguments: String,
         description: String,
     },
+    /// The model failed with a transient error; this turn continues on the fallback model.
+    ModelFallback {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    /// Non-fatal problem to show the user, e.g. a stream cut at `llm.max_stream_bytes`.
+    Warning(String),
     /// Final response ready (content may be empty if already streamed).
     Done(String),
     /// An error occurred.
     Error(String),
 }
 
+/// User's answer to an `AgentEvent::ToolConfirm` or `AgentEvent::PlanReady` prompt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfirmDecision {
+    Deny,
+    /// Allow this call only.
+    Once,
+    /// Allow this call and auto-approve identical calls for the rest of the session.
+    Always,
+}
+
+/// Provider reachability from the last health check (`ui.health_check_interval_secs`).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ApiStatus {
+    /// Not checked yet, or checks are disabled.
+    #[default]
+    Unknown,
+    Online,
+    Offline,
+}
+
+/// Max chars of tool output carried in `AgentEvent::ToolEnd::result`.
+/// The full output still goes to the LLM; this only bounds what the UI receives.
+pub const TOOL_END_RESULT_CAP: usize = 4000;
+
+/// Truncate a tool result to [`TOOL_END_RESULT_CAP`] chars for event delivery.
+fn truncate_tool_result(text: &str) -> String {
+    let total = text.chars().count();
+    if total <= TOOL_END_RESULT_CAP {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(TOOL_END_RESULT_CAP).collect();
+    out.push_str(&format!("\n… [truncated, {} chars total]", total));
+    out
+}
+
+/// Upper bound for a runtime `/iter` change of the iteration budget.
+pub const MAX_ITERATIONS_CEILING: u32 = 200;
+
+/// Max number of fallback hops within one turn (guards against long or cyclic chains).
+const MAX_FALLBACK_HOPS: usize = 3;
+
+/// Re-asks after an empty reply (no text, no tool calls) before giving up on the turn.
+const MAX_EMPTY_RESPONSE_RETRIES: u32 = 1;
+
+/// Extra user message sent with the retry after an empty reply (not kept in history).
+const EMPTY_RESPONSE_NUDGE: &str =
+    "Your previous reply was empty. Please answer the last message or call a tool.";
+
+/// Final reply of a turn whose model kept returning nothing.
+const EMPTY_RESPONSE_MESSAGE: &str = "[model returned no content]";
+
+/// Extra user message for the tool-free first request of `agent.plan_first` (not kept in history).
+const PLAN_FIRST_PROMPT: &str =
+    "Before doing anything, reply with a short numbered plan of the steps and tool calls \
+    you intend to make for the request above. Do not call any tools yet; the user will \
+    review the plan first.";
+
+/// Sent after the user approves the plan of `agent.plan_first`.
+const PLAN_APPROVED_MESSAGE: &str = "The plan is approved. Go ahead and carry it out.";
+
+/// Tool result fed back to the model for a call skipped by `agent.dry_run`
+/// (anything but a read-only tool, see `risk::is_read_only_tool`).
+fn dry_run_result(tool_call: &ToolCall) -> String {
+    format!(
+        "[dry run] Would execute: {}. Nothing was run; continue as if it succeeded.",
+        risk::describe_tool_call(&tool_call.name, &tool_call.arguments)
+    )
+}
+
+/// Instructions for the summarization request of `compaction_strategy = "summarize"`.
+const COMPACTION_SUMMARY_PROMPT: &str =
+    "Summarize the following earlier part of a conversation between a user and an AI \
+    assistant with tools. Keep the user's goals, decisions made, important facts, file \
+    paths and the state of unfinished work. Reply with the summary only, in a few \
+    short paragraphs or bullet points.";
+
+/// First line of the user-role note that replaces summarized history.
+const COMPACTION_SUMMARY_PREFIX: &str = "[Summary of earlier conversation]";
+
+/// Max chars of each message (or tool-call arguments) sent for summarization.
+const SUMMARY_MESSAGE_CAP: usize = 2000;
+
+/// Cut `text` to at most `max` chars, marking the cut.
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max).collect();
+    format!("{}…", cut)
+}
+
+/// Builds an LLM provider for a resolved model entry, its API key and the `[llm]` settings.
+type ProviderFactory = fn(&str, &ModelEntry, &LlmConfig) -> Result<Box<dyn LlmProvider>>;
+
 /// Cumulative usage statistics tracked across the session.
 #[derive(Debug, Clone, Default)]
 pub struct SessionStats {
     pub total_input_tokens: u64,
     pub total_output_tokens: u64,
     pub request_count: u64,
+    /// Estimated USD spent, from the `input_price` / `output_price` of the model used for
+    /// each request. `None` until a request to a priced model is recorded.
+    pub total_cost: Option<f64>,
 }
 
 impl SessionStats {
-    fn record_usage(&mut self, usage: &Option<TokenUsage>) {
+    fn record_usage(&mut self, usage: &Option<TokenUsage>, model: Option<&ModelEntry>) {
         if let Some(u) = usage {
             self.total_input_tokens += u.input_tokens;
             self.total_output_tokens += u.output_tokens;
+            if let Some(cost) = model.and_then(|m| request_cost(u, m)) {
+                *self.total_cost.get_or_insert(0.0) += cost;
+            }
         }
         self.request_count += 1;
     }
 }
 
+/// USD cost of one request's usage, or `None` when the model has no prices configured.
+fn request_cost(usage: &TokenUsage, model: &ModelEntry) -> Option<f64> {
+    if model.input_price.is_none() && model.output_price.is_none() {
+        return None;
+    }
+    let per_token = |price: Option<f64>| price.unwrap_or(0.0) / 1_000_000.0;
+    Some(
+        usage.input_tokens as f64 * per_token(model.input_price)
+            + usage.output_tokens as f64 * per_token(model.output_price),
+    )
+}
+
 pub struct Agent {
     llm: Box<dyn LlmProvider>,
     tool_router: ToolRouter,
@@ -74,6 +206,19 @@ pub struct Agent {
     current_model_id: String,
     /// Project root (working directory). Used for trusted workspace check.
     project_root: PathBuf,
+    /// Creates providers for model switches and fallbacks (swappable in tests).
+    provider_factory: ProviderFactory,
+    /// Signatures of dangerous calls the user chose to always allow this session
+    /// (see `risk::approval_signature`).
+    approved_calls: HashSet<String>,
+    /// Use `chat_completion_stream` (default) or plain `chat_completion` for LLM calls.
+    streaming: bool,
+    /// Tool-loop iteration budget per message; starts at `agent.max_iterations`.
+    max_iterations: u32,
+    /// Per-session environment for bash commands (`/env set`).
+    session_env: BTreeMap<String, String>,
+    /// Skip state-changing tool calls (see `dry_run_result`); starts at `agent.dry_run`.
+    dry_run: bool,
 }
 
 impl Agent {
@@ -96,6 +241,8 @@ impl Agent {
             .unwrap_or_else(|| current_model_id.clone());
         let system_prompt = Self::build_system_prompt(&config, project_root, &model_display);
         let messages = vec![Message::system(&system_prompt)];
+        let max_iterations = config.agent.max_iterations;
+        let dry_run = config.agent.dry_run;
         Self {
             llm,
             tool_router,
@@ -104,6 +251,55 @@ impl Agent {
             stats: SessionStats::default(),
             current_model_id,
             project_root: project_root.to_path_buf(),
+            provider_factory: Self::create_provider_for_model,
+            approved_calls: HashSet::new(),
+            streaming: true,
+            max_iterations,
+            session_env: BTreeMap::new(),
+            dry_run,
+        }
+    }
+
+    /// Whether LLM responses are streamed.
+    pub fn streaming(&self) -> bool {
+        self.streaming
+    }
+
+    /// Switch between streaming and non-streaming LLM calls (for debugging providers).
+    pub fn set_streaming(&mut self, streaming: bool) {
+        self.streaming = streaming;
+    }
+
+    /// Whether tool calls are only described, not executed.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    pub fn max_iterations(&self) -> u32 {
+        self.max_iterations
+    }
+
+    /// Change the iteration budget, clamped to `1..=MAX_ITERATIONS_CEILING`.
+    /// Returns the value actually applied.
+    pub fn set_max_iterations(&mut self, n: u32) -> u32 {
+        self.max_iterations = n.clamp(1, MAX_ITERATIONS_CEILING);
+        self.max_iterations
+    }
+
+    /// Probe the current provider's endpoint.
+    pub async fn check_health(&self) -> ApiStatus {
+        Self::probe_health(self.llm.as_ref()).await
+    }
+
+    /// Probe `llm`'s endpoint without an agent around it (background health checks).
+    pub async fn probe_health(llm: &dyn LlmProvider) -> ApiStatus {
+        match llm.health_check().await {
+            Ok(()) => ApiStatus::Online,
+            Err(_) => ApiStatus::Offline,
         }
     }
 
@@ -112,6 +308,11 @@ impl Agent {
         &self.current_model_id
     }
 
+    /// Number of tools registered for this agent.
+    pub fn tool_count(&self) -> usize {
+        self.tool_router.len()
+    }
+
     /// Returns the display name for the current model.
     pub fn current_model_display(&self) -> String {
         self.config
@@ -148,6 +349,8 @@ impl Agent {
         let date = chrono::Local::now().format("%Y-%m-%d %H:%M");
         let os = std::env::consts::OS;
         let model = model_display;
+        let shell_program = config.tools.shell_program();
+        let shell = crate::tools::bash::shell_name(&shell_program);
 
         let mut prompt = format!(
             r#"You are miniclaw, an interactive terminal AI assistant for software engineering tasks.
@@ -165,13 +368,14 @@ You have access to the following tools. Use them proactively to accomplish tasks
 ### read_file
 Read the contents of a file. Use this to understand existing code before making changes.
 - Always read a file before editing it
-- For large files, read the relevant sections
+- For large files, read the relevant sections with `offset` (1-based line) and `limit`
 
 ### write_file
 Create a new file or overwrite an existing file with complete content.
 - Use for creating new files (scripts, configs, templates)
 - Auto-creates parent directories
 - For modifying existing files, prefer `edit` over `write_file`
+- For very large files, write the first part, then add the rest in later calls with `append: true`
 
 ### edit
 Make precise text replacements in existing files.
@@ -180,8 +384,12 @@ Make precise text replacements in existing files.
 - Safer than write_file for modifications — proves you know the current content
 - Use `replace_all: true` to replace all occurrences
 
+### multi_edit
+Apply several `edit`-style replacements to one file in one call (`edits: [{{old_text, new_text, replace_all}}]`).
+- Edits apply in order; if any `old_text` is not found, nothing is written
+
 ### bash
-Execute shell commands via bash.
+Execute shell commands via {shell}; write commands in its syntax.
 - Use for: building, testing, searching (grep/rg/find), git operations, installing packages
 - Commands have a timeout (default 30s, configurable)
 - Output is captured (stdout + stderr)
@@ -189,6 +397,33 @@ Execute shell commands via bash.
 
 ### list_directory
 List files and directories at a path with optional recursive traversal.
+- Paths matched by `.gitignore` are skipped; pass `respect_gitignore: false` to include them
+
+### env_info
+Get OS, architecture, shell and versions of installed tools (rustc, cargo, node, python, git).
+- Prefer this over running several `--version` commands via bash
+
+### hash_file
+Compute the sha256 (default), md5 or blake3 hash of a file, or of each file in a directory.
+
+### grep
+Search file contents with a regular expression; returns `file:line:content` lines.
+- Prefer this over grep/rg via bash; use `glob` (e.g. `*.rs`) to narrow the files searched
+
+### glob
+Find files by pattern (`**/*.rs`, `src/{{lib,main}}.rs`); newest first.
+- Prefer this over recursive list_directory when you know the file name pattern
+
+### count_tokens
+Estimate the token count of `text` or a file (`path`), with char and line counts.
+
+### delete_file
+Delete a file inside the project (`recursive: true` for a directory). Always requires user confirmation.
+- Prefer this over `rm` via bash; paths outside the project need `allow_outside_project: true`
+
+### fetch
+GET an http/https URL and return the body as text (HTML converted to readable text, `max_bytes` caps the size).
+- Prefer this over `curl` via bash for documentation and API responses; localhost/private addresses are refused
 
 ## Guidelines
 
@@ -210,42 +445,66 @@ List files and directories at a path with optional recursive traversal.
 
         // Append user's custom system prompt from config
         let custom = config.agent.system_prompt.trim();
-        if !custom.is_empty()
-            && custom != "You are a helpful AI assistant. You can use tools to help the user with tasks like reading files, writing files, executing commands, and more. Be concise and helpful."
-        {
+        if !custom.is_empty() && custom != crate::config::DEFAULT_SYSTEM_PROMPT {
             prompt.push_str(&format!("\n\n## Custom Instructions\n{}", custom));
         }
 
+        // User-level instructions come before project rules so projects can refine them
+        let instructions_path = match config.agent.instructions_file.as_deref() {
+            Some(path) => Some(crate::paths::expand_home(path)),
+            None => crate::paths::instructions_path().ok(),
+        };
+        if let Some(file) = instructions_path
+            .as_deref()
+            .and_then(rules::load_user_instructions)
+        {
+            prompt.push_str(&format!(
+                "\n\n## User Instructions\n<user_instructions>\n{}\n</user_instructions>",
+                file.content.trim()
+            ));
+        }
+
         // Append project rules (CLAUDE.md etc.)
-        if let Some(rules_ctx) = rules::build_rules_context(project_root) {
+        if let Some(rules_ctx) = rules::build_rules_context(project_root, &config.agent) {
             prompt.push_str(&format!(
                 "\n\n## Project Rules\n<project_rules>\n{}\n</project_rules>",
                 rules_ctx
             ));
         }
 
+        // Always append the explicit suffix, independent of the custom-instructions check
+        if let Some(suffix) = config.agent.append_system_prompt.as_deref() {
+            let suffix = suffix.trim();
+            if !suffix.is_empty() {
+                prompt.push_str(&format!("\n\n{}", suffix));
+            }
+        }
+
         prompt
     }
 
     /// Rough token estimation: ~4 chars per token for English, ~2 for CJK.
-    fn estimate_tokens(text: &str) -> u64 {
+    pub(crate) fn estimate_tokens(text: &str) -> u64 {
         let char_count = text.chars().count() as u64;
         (char_count / 3).max(1)
     }
 
+    /// Estimate the tokens one message takes in the context.
+    fn estimate_message_tokens(m: &Message) -> u64 {
+        let content_tokens = Self::estimate_tokens(&m.content);
+        let tool_tokens: u64 = m
+            .tool_calls
+            .iter()
+            .map(|tc| Self::estimate_tokens(&tc.arguments) + 10)
+            .sum();
+        content_tokens + tool_tokens + 4 // overhead per message
+    }
+
     /// Estimate total tokens across all messages.
     pub fn estimate_context_tokens(&self) -> u64 {
         self.messages
             .iter()
-            .map(|m| {
-                let content_tokens = Self::estimate_tokens(&m.content);
-                let tool_tokens: u64 = m
-                    .tool_calls
-                    .iter()
-                    .map(|tc| Self::estimate_tokens(&tc.arguments) + 10)
-                    .sum();
-                content_tokens + tool_tokens + 4 // overhead per message
-            })
+            .map(Self::estimate_message_tokens)
             .sum()
     }
 
@@ -262,30 +521,133 @@ List files and directories at a path with optional recursive traversal.
             .unwrap_or(self.config.llm.context_window)
     }
 
-    /// Truncate old messages if approaching the context window limit.
-    /// Keeps the system prompt (first message) and the most recent messages.
-    fn compact_context(&mut self) {
+    /// Compact old messages if approaching the context window limit.
+    /// Keeps the system prompt (first message) and the last `agent.keep_recent_messages`
+    /// messages; only older messages are evicted, oldest first. With
+    /// `agent.compaction_strategy = "summarize"` the evicted messages are replaced by a
+    /// user-role note holding an LLM-written summary; if that call fails they are
+    /// dropped as with "truncate".
+    async fn compact_context(&mut self) {
         let limit = self.context_window();
         let threshold = (limit as f64 * 0.85) as u64;
 
-        if self.estimate_context_tokens() <= threshold {
+        let mut total = self.estimate_context_tokens();
+        if total <= threshold {
             return;
         }
 
-        // Keep system prompt (index 0) and remove oldest non-system messages
-        while self.messages.len() > 2 && self.estimate_context_tokens() > threshold {
-            self.messages.remove(1);
+        // Start of the protected recent window. Widen it so tool results at the
+        // boundary keep the assistant message that issued their tool calls.
+        let keep = self.config.agent.keep_recent_messages;
+        let mut window_start = self.messages.len().saturating_sub(keep).max(1);
+        // `get`: with keep_recent_messages = 0 the window starts past the end
+        while window_start > 1
+            && self
+                .messages
+                .get(window_start)
+                .is_some_and(|m| m.role == Role::Tool)
+        {
+            window_start -= 1;
         }
+
+        // Keep system prompt (index 0) and evict the oldest messages before the window,
+        // together with tool results orphaned by evicting their assistant message
+        let mut end = 1;
+        while end < window_start && total > threshold {
+            total -= Self::estimate_message_tokens(&self.messages[end]);
+            end += 1;
+            while end < window_start && self.messages[end].role == Role::Tool {
+                total -= Self::estimate_message_tokens(&self.messages[end]);
+                end += 1;
+            }
+        }
+        let evicted: Vec<Message> = self.messages.drain(1..end).collect();
+
+        if self.config.agent.compaction_strategy == CompactionStrategy::Summarize
+            && !evicted.is_empty()
+        {
+            if let Ok(summary) = self.summarize_messages(&evicted).await {
+                // A user-role note: as an assistant turn the model would treat the
+                // summary as something it said itself
+                self.messages.insert(
+                    1,
+                    Message::note(format!("{}\n{}", COMPACTION_SUMMARY_PREFIX, summary)),
+                );
+            }
+        }
+    }
+
+    /// Ask the current model for a summary of `messages`. Calls the provider directly
+    /// (no tools, no history) so it never re-enters `compact_context`.
+    async fn summarize_messages(&mut self, messages: &[Message]) -> Result<String> {
+        let mut transcript = String::new();
+        for m in messages {
+            let label = match m.role {
+                Role::System => "System",
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+                Role::Tool => "Tool result",
+            };
+            transcript.push_str(&format!(
+                "{}: {}\n",
+                label,
+                truncate_chars(&m.content, SUMMARY_MESSAGE_CAP)
+            ));
+            for tc in &m.tool_calls {
+                transcript.push_str(&format!(
+                    "Assistant called {}({})\n",
+                    tc.name,
+                    truncate_chars(&tc.arguments, SUMMARY_MESSAGE_CAP)
+                ));
+            }
+        }
+
+        let entry = self.config.get_model_entry(&self.current_model_id);
+        let model = entry
+            .as_ref()
+            .map(|e| e.model.clone())
+            .unwrap_or_else(|| self.config.llm.model.clone());
+        let max_tokens = entry
+            .as_ref()
+            .map(|e| e.max_tokens)
+            .filter(|&t| t > 0)
+            .unwrap_or(self.config.llm.max_tokens);
+        let request = ChatRequest {
+            model,
+            messages: vec![
+                Message::system(COMPACTION_SUMMARY_PROMPT),
+                Message::user(transcript),
+            ],
+            tools: vec![],
+            max_tokens,
+            enable_search: None,
+            reasoning_effort: None,
+            user: self.config.llm.user_id.clone(),
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: None,
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: None,
+        };
+        let response = self.llm.chat_completion(&request).await?;
+        self.stats.record_usage(&response.usage, entry.as_ref());
+        let summary = response.content.trim();
+        if summary.is_empty() {
+            bail!("empty summary");
+        }
+        Ok(summary.to_string())
     }
 
     pub async fn process_message(
         &mut self,
         user_input: &str,
         event_tx: Option<mpsc::UnboundedSender<AgentEvent>>,
-        mut confirm_rx: Option<&mut mpsc::UnboundedReceiver<bool>>,
+        mut confirm_rx: Option<&mut mpsc::UnboundedReceiver<ConfirmDecision>>,
     ) -> Result<String> {
         self.messages.push(Message::user(user_input));
-        self.compact_context();
+        self.compact_context().await;
 
         let emit = |evt: AgentEvent| {
             if let Some(tx) = &event_tx {
@@ -294,7 +656,13 @@ List files and directories at a path with optional recursive traversal.
         };
 
         let mut iterations = 0;
-        let max_iterations = self.config.agent.max_iterations;
+        let max_iterations = self.max_iterations;
+        // Fallback model used for the rest of this turn after the primary failed.
+        let mut fallback: Option<(String, Box<dyn LlmProvider>)> = None;
+        let mut tried_models = vec![self.current_model_id.clone()];
+        let mut empty_retries = 0;
+        // Plan-first needs someone to approve the plan, so it only applies with a confirm channel
+        let mut awaiting_plan = self.config.agent.plan_first && confirm_rx.is_some();
 
         loop {
             iterations += 1;
@@ -307,19 +675,37 @@ List files and directories at a path with optional recursive traversal.
                 return Ok(msg);
             }
 
-            let model_entry = self.current_model_entry().unwrap_or_else(|| ModelEntry {
-                id: self.current_model_id.clone(),
-                name: String::new(),
-                provider: self.config.llm.provider.clone(),
-                model: self.config.llm.model.clone(),
-                api_base: self.config.llm.api_base.clone(),
-                context_window: self.config.llm.context_window,
-                max_tokens: self.config.llm.max_tokens,
-                tools: vec![],
-                enable_search: false,
-                api_key: None,
-                api_key_env: None,
-            });
+            let turn_model_id = fallback
+                .as_ref()
+                .map(|(id, _)| id.clone())
+                .unwrap_or_else(|| self.current_model_id.clone());
+            let model_entry = self
+                .config
+                .get_model_entry(&turn_model_id)
+                .unwrap_or_else(|| ModelEntry {
+                    id: turn_model_id.clone(),
+                    name: String::new(),
+                    provider: self.config.llm.provider.clone(),
+                    model: self.config.llm.model.clone(),
+                    api_base: self.config.llm.api_base.clone(),
+                    context_window: self.config.llm.context_window,
+                    max_tokens: self.config.llm.max_tokens,
+                    tools: vec![],
+                    enable_search: false,
+                    api_key: None,
+                    api_key_env: None,
+                    fallback: None,
+                    url_template: None,
+                    auth_header: None,
+                    reasoning_effort: None,
+                    extra_body: None,
+                    thinking_budget: None,
+                    input_price: None,
+                    output_price: None,
+                    temperature: self.config.llm.temperature,
+                    top_p: self.config.llm.top_p,
+                    prompt_caching: false,
+                });
 
             let max_tokens = if model_entry.max_tokens > 0 {
                 model_entry.max_tokens
@@ -329,6 +715,12 @@ List files and directories at a path with optional recursive traversal.
 
             let mut tools = self.tools_for_model(&model_entry);
             let mut request_messages = self.messages.clone();
+            if empty_retries > 0 {
+                request_messages.push(Message::user(EMPTY_RESPONSE_NUDGE));
+            }
+            if awaiting_plan {
+                request_messages.push(Message::user(PLAN_FIRST_PROMPT));
+            }
             // When model has native web search: inject strong instruction and modify bash tool
             if model_entry.enable_search {
                 if let Some(first) = request_messages.first_mut() {
@@ -360,44 +752,185 @@ List files and directories at a path with optional recursive traversal.
                 } else {
                     None
                 },
+                reasoning_effort: model_entry.reasoning_effort.clone(),
+                user: self.config.llm.user_id.clone(),
+                extra_body: model_entry.extra_body.clone(),
+                thinking_budget: model_entry.thinking_budget,
+                tool_choice: awaiting_plan.then_some(ToolChoice::None),
+                temperature: model_entry.temperature,
+                top_p: model_entry.top_p,
+                prompt_caching: model_entry.prompt_caching,
+                max_stream_bytes: (self.config.llm.max_stream_bytes > 0)
+                    .then_some(self.config.llm.max_stream_bytes),
             };
 
-            let (chunk_tx, mut chunk_rx) = mpsc::unbounded_channel::<StreamChunk>();
-
-            let event_tx_clone = event_tx.clone();
-            let forward_handle = tokio::spawn(async move {
-                while let Some(chunk) = chunk_rx.recv().await {
-                    if let StreamChunk::TextDelta(delta) = chunk {
+            let llm = match &fallback {
+                Some((_, provider)) => provider.as_ref(),
+                None => self.llm.as_ref(),
+            };
+            // Text already shown for this attempt; a fallback would be appended to it
+            let mut partial_output = false;
+            let result = if self.streaming {
+                let (chunk_tx, mut chunk_rx) =
+                    mpsc::channel::<StreamChunk>(crate::llm::STREAM_CHANNEL_CAPACITY);
+
+                let event_tx_clone = event_tx.clone();
+                let forward_handle = tokio::spawn(async move {
+                    let mut streamed = false;
+                    while let Some(chunk) = chunk_rx.recv().await {
+                        let event = match chunk {
+                            StreamChunk::TextDelta(delta) => {
+                                streamed = true;
+                                AgentEvent::StreamDelta(delta)
+                            }
+                            StreamChunk::Warning(msg) => AgentEvent::Warning(msg),
+                            StreamChunk::Done => continue,
+                        };
                         if let Some(tx) = &event_tx_clone {
-                            let _ = tx.send(AgentEvent::StreamDelta(delta));
+                            let _ = tx.send(event);
                         }
                     }
+                    streamed
+                });
+
+                let result = llm.chat_completion_stream(&request, chunk_tx).await;
+                partial_output = forward_handle.await.unwrap_or(true);
+                result
+            } else {
+                let result = llm.chat_completion(&request).await;
+                // Nothing was streamed, so surface text sent alongside tool calls
+                if let Ok(response) = &result {
+                    if response.has_tool_calls() && !response.content.is_empty() {
+                        emit(AgentEvent::LlmText(response.content.clone()));
+                    }
                 }
-            });
+                result
+            };
 
-            let response: ChatResponse = self
-                .llm
-                .chat_completion_stream(&request, chunk_tx)
-                .await
-                .context("LLM streaming call failed")?;
+            let mut response: ChatResponse = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    let next = if partial_output {
+                        None
+                    } else {
+                        self.next_fallback(&model_entry, &e, &tried_models)
+                    };
+                    if let Some((next_id, provider)) = next {
+                        emit(AgentEvent::ModelFallback {
+                            from: turn_model_id,
+                            to: next_id.clone(),
+                            reason: e.to_string(),
+                        });
+                        tried_models.push(next_id.clone());
+                        fallback = Some((next_id, provider));
+                        // The failed attempt doesn't count as an iteration
+                        iterations -= 1;
+                        continue;
+                    }
+                    if crate::llm::is_timeout_error(&e) {
+                        let msg = format!(
+                            "LLM request timed out: no response from {} for {}s \
+                             (llm.request_timeout_secs)",
+                            turn_model_id, self.config.llm.request_timeout_secs
+                        );
+                        emit(AgentEvent::Error(msg.clone()));
+                        return Err(e.context(msg));
+                    }
+                    return Err(e.context("LLM streaming call failed"));
+                }
+            };
 
-            let _ = forward_handle.await;
+            self.stats.record_usage(&response.usage, Some(&model_entry));
 
-            self.stats.record_usage(&response.usage);
+            if awaiting_plan {
+                // Nothing runs before approval, even if the model ignored tool_choice "none"
+                response.tool_calls.clear();
+            }
+            // A real reply ends the streak, so the nudge only follows an empty reply
+            if response.has_tool_calls() || !response.content.trim().is_empty() {
+                empty_retries = 0;
+            }
+            if awaiting_plan && !response.content.trim().is_empty() {
+                awaiting_plan = false;
+                let mut plan = Message::assistant(&response.content);
+                plan.thinking = response.thinking;
+                self.messages.push(plan);
+                emit(AgentEvent::PlanReady(response.content.clone()));
+
+                let decision = match confirm_rx.as_mut() {
+                    Some(rx) => rx.recv().await.unwrap_or(ConfirmDecision::Deny),
+                    None => ConfirmDecision::Deny,
+                };
+                if decision == ConfirmDecision::Deny {
+                    emit(AgentEvent::Done(String::new()));
+                    return Ok(response.content);
+                }
+                self.messages.push(Message::note(PLAN_APPROVED_MESSAGE));
+                continue;
+            }
 
             if response.has_tool_calls() {
-                self.messages.push(Message::assistant_with_tool_calls(
+                let mut assistant = Message::assistant_with_tool_calls(
                     &response.content,
                     response.tool_calls.clone(),
-                ));
+                );
+                assistant.thinking = response.thinking.clone();
+                self.messages.push(assistant);
+
+                if self.dry_run {
+                    for tool_call in &response.tool_calls {
+                        emit(AgentEvent::ToolStart {
+                            name: tool_call.name.clone(),
+                            arguments: tool_call.arguments.clone(),
+                        });
+                        // Reads still run so the plan builds on what is really there;
+                        // shell and SQL never do, however harmless the command looks
+                        let result = if risk::is_read_only_tool(&tool_call.name) {
+                            self.tool_router
+                                .execute_structured(&tool_call.name, &tool_call.arguments)
+                                .await
+                        } else {
+                            Ok(ToolOutput::from(dry_run_result(tool_call)))
+                        };
+                        self.record_tool_result(tool_call, result, &emit);
+                    }
+                    continue;
+                }
+
+                // Independent read-only calls run concurrently; results keep call order.
+                // Shell commands always run one at a time, in order.
+                let all_read_only = response
+                    .tool_calls
+                    .iter()
+                    .all(|tc| risk::is_read_only_tool(&tc.name));
+                if all_read_only && response.tool_calls.len() > 1 {
+                    for tool_call in &response.tool_calls {
+                        emit(AgentEvent::ToolStart {
+                            name: tool_call.name.clone(),
+                            arguments: tool_call.arguments.clone(),
+                        });
+                    }
+                    let results =
+                        futures_util::future::join_all(response.tool_calls.iter().map(|tc| {
+                            self.tool_router.execute_structured(&tc.name, &tc.arguments)
+                        }))
+                        .await;
+                    for (tool_call, result) in response.tool_calls.iter().zip(results) {
+                        self.record_tool_result(tool_call, result, &emit);
+                    }
+                    continue;
+                }
 
                 for tool_call in &response.tool_calls {
                     let risk = risk::assess_risk(&tool_call.name, &tool_call.arguments);
 
                     if risk == RiskLevel::Dangerous {
-                        // Trusted workspace: auto-approve dangerous tool calls
+                        let signature =
+                            risk::approval_signature(&tool_call.name, &tool_call.arguments);
+                        // Trusted workspace or "always" approval: auto-approve dangerous tool calls
                         let approved = match trusted_workspaces::is_trusted(&self.project_root) {
                             Ok(true) => true,
+                            _ if self.approved_calls.contains(&signature) => true,
                             _ => {
                                 let desc =
                                     risk::describe_tool_call(&tool_call.name, &tool_call.arguments);
@@ -407,11 +940,14 @@ List files and directories at a path with optional recursive traversal.
                                     description: desc,
                                 });
 
-                                if let Some(rx) = confirm_rx.as_mut() {
-                                    rx.recv().await.unwrap_or(false)
-                                } else {
-                                    false
+                                let decision = match confirm_rx.as_mut() {
+                                    Some(rx) => rx.recv().await.unwrap_or(ConfirmDecision::Deny),
+                                    None => ConfirmDecision::Deny,
+                                };
+                                if decision == ConfirmDecision::Always {
+                                    self.approved_calls.insert(signature);
                                 }
+                                decision != ConfirmDecision::Deny
                             }
                         };
 
@@ -422,6 +958,8 @@ List files and directories at a path with optional recursive traversal.
                                 name: tool_call.name.clone(),
                                 arguments: tool_call.arguments.clone(),
                                 success: false,
+                                result: deny_msg.clone(),
+                                metadata: serde_json::Value::Null,
                             });
                             self.messages
                                 .push(Message::tool_result(&tool_call.id, &deny_msg));
@@ -436,51 +974,117 @@ List files and directories at a path with optional recursive traversal.
 
                     let result = self
                         .tool_router
-                        .execute(&tool_call.name, &tool_call.arguments)
+                        .execute_structured(&tool_call.name, &tool_call.arguments)
                         .await;
-
-                    let (result_text, success) = match result {
-                        Ok(output) => (output, true),
-                        Err(e) => (format!("Error: {}", e), false),
-                    };
-
-                    emit(AgentEvent::ToolEnd {
-                        name: tool_call.name.clone(),
-                        arguments: tool_call.arguments.clone(),
-                        success,
-                    });
-
-                    self.messages
-                        .push(Message::tool_result(&tool_call.id, &result_text));
+                    self.record_tool_result(tool_call, result, &emit);
                 }
                 continue;
             }
 
-            self.messages.push(Message::assistant(&response.content));
+            if response.content.trim().is_empty() {
+                if empty_retries < MAX_EMPTY_RESPONSE_RETRIES {
+                    empty_retries += 1;
+                    continue;
+                }
+                // Keep user/assistant alternation with a visible placeholder
+                self.messages
+                    .push(Message::assistant(EMPTY_RESPONSE_MESSAGE));
+                emit(AgentEvent::Done(EMPTY_RESPONSE_MESSAGE.to_string()));
+                return Ok(EMPTY_RESPONSE_MESSAGE.to_string());
+            }
+
+            let mut assistant = Message::assistant(&response.content);
+            assistant.thinking = response.thinking;
+            self.messages.push(assistant);
             emit(AgentEvent::Done(response.content.clone()));
             return Ok(response.content);
         }
     }
 
+    /// Emit `ToolEnd` for a finished call and add its result to the history.
+    fn record_tool_result(
+        &mut self,
+        tool_call: &ToolCall,
+        result: Result<ToolOutput>,
+        emit: &impl Fn(AgentEvent),
+    ) {
+        let (result_text, metadata, success) = match result {
+            Ok(output) => (output.text, output.metadata, true),
+            Err(e) => (format!("Error: {}", e), serde_json::Value::Null, false),
+        };
+
+        emit(AgentEvent::ToolEnd {
+            name: tool_call.name.clone(),
+            arguments: tool_call.arguments.clone(),
+            success,
+            result: truncate_tool_result(&result_text),
+            metadata,
+        });
+
+        self.messages
+            .push(Message::tool_result(&tool_call.id, &result_text));
+    }
+
     /// Factory method: create a new Agent from config (creates LLM provider + tool router).
     pub fn create(config: &AppConfig, project_root: &Path) -> Result<Self> {
         Self::create_with_model(config, project_root, None)
     }
 
-    /// Create Agent with a specific model. Pass None to use config default.
+    /// Create Agent with a specific model id or alias. Pass None to use config default.
+    /// An unknown model or alias, including a bad `llm.default_model`, is an error.
     pub fn create_with_model(
         config: &AppConfig,
         project_root: &Path,
         model_id: Option<&str>,
     ) -> Result<Self> {
-        let model_id = model_id
-            .map(String::from)
-            .unwrap_or_else(|| config.default_model_id());
-        let api_key = config.api_key_for_model(&model_id)?;
+        let model_id = match model_id.or(config.llm.default_model.as_deref()) {
+            Some(name) => config.resolve_model_id(name)?,
+            None => config.default_model_id(),
+        };
+        let llm = Self::create_provider(config, &model_id)?;
+        let mut tool_router = create_default_router();
+        tool_router.set_pretty_json(config.tools.pretty_json);
+        tool_router.set_allow_state_writes(config.tools.allow_state_writes);
+        tool_router.set_max_retries(config.tools.tool_max_retries);
+        tool_router.set_warn_on_secret_write(config.tools.warn_on_secret_write);
+        tool_router.set_output_cleanup(
+            config.tools.strip_ansi,
+            config.tools.trim_trailing_whitespace,
+        );
+        tool_router.set_network_concurrency(config.tools.max_network_concurrency);
+        if let Ok(dir) = crate::paths::tool_output_dir() {
+            tool_router.set_spill_large_results(
+                config
+                    .tools
+                    .spill_large_results
+                    .then_some(config.tools.spill_threshold_bytes),
+                dir,
+            );
+        }
+        tool_router.replace(Box::new(FetchTool {
+            allow_private: config.tools.fetch_allow_private,
+        }));
+        tool_router.replace(Box::new(BashTool::new(
+            config.tools.shell_program(),
+            config.tools.bash_env.clone(),
+        )));
+        Ok(Self::new(
+            llm,
+            tool_router,
+            config.clone(),
+            project_root,
+            model_id,
+        ))
+    }
+
+    /// Build just the provider for `model_id` (an id from `config.models`, or the
+    /// legacy `[llm]` settings when unknown).
+    pub fn create_provider(config: &AppConfig, model_id: &str) -> Result<Box<dyn LlmProvider>> {
+        let api_key = config.api_key_for_model(model_id)?;
         let entry = config
-            .get_model_entry(&model_id)
+            .get_model_entry(model_id)
             .unwrap_or_else(|| ModelEntry {
-                id: model_id.clone(),
+                id: model_id.to_string(),
                 name: String::new(),
                 provider: config.llm.provider.clone(),
                 model: config.llm.model.clone(),
@@ -491,51 +1095,92 @@ List files and directories at a path with optional recursive traversal.
                 enable_search: false,
                 api_key: None,
                 api_key_env: None,
+                fallback: None,
+                url_template: None,
+                auth_header: None,
+                reasoning_effort: None,
+                extra_body: None,
+                thinking_budget: None,
+                input_price: None,
+                output_price: None,
+                temperature: config.llm.temperature,
+                top_p: config.llm.top_p,
+                prompt_caching: false,
             });
-        let llm = Self::create_provider_for_model(&api_key, &entry)?;
-        let tool_router = create_default_router();
-        Ok(Self::new(
-            llm,
-            tool_router,
-            config.clone(),
-            project_root,
-            model_id,
-        ))
+        Self::create_provider_for_model(&api_key, &entry, &config.llm)
     }
 
     fn create_provider_for_model(
         api_key: &str,
         entry: &ModelEntry,
+        llm_config: &LlmConfig,
     ) -> Result<Box<dyn LlmProvider>> {
+        let timeout = llm_config.request_timeout();
         let llm: Box<dyn LlmProvider> = match entry.provider.as_str() {
-            "anthropic" => Box::new(AnthropicProvider::new(
-                api_key.to_string(),
-                entry.api_base.clone(),
-            )),
-            "openai_compatible" | "openai" => Box::new(OpenAiCompatibleProvider::new(
-                api_key.to_string(),
-                entry.api_base.clone(),
-            )),
+            "anthropic" => Box::new(
+                AnthropicProvider::new(api_key.to_string(), entry.api_base.clone())
+                    .with_max_retries(llm_config.max_retries)
+                    .with_timeout(timeout),
+            ),
+            "openai_compatible" | "openai" => Box::new(
+                OpenAiCompatibleProvider::new(api_key.to_string(), entry.api_base.clone())
+                    .with_url_template(entry.url_template.clone())
+                    .with_auth_style(AuthStyle::from_config(entry.auth_header.as_deref())?)
+                    .with_max_retries(llm_config.max_retries)
+                    .with_timeout(timeout),
+            ),
+            "ollama" => Box::new(
+                OllamaProvider::new(api_key.to_string(), entry.api_base.clone())
+                    .with_max_retries(llm_config.max_retries)
+                    .with_timeout(timeout),
+            ),
             other => bail!(
-                "Unknown provider: '{}'. Supported: 'anthropic', 'openai_compatible'",
-                other
+                "Unknown provider: '{}'. Supported: {}",
+                other,
+                crate::llm::SUPPORTED_PROVIDERS.join(", ")
             ),
         };
         Ok(llm)
     }
 
+    /// Pick the fallback model for a failed call, if the error is transient and
+    /// the entry's fallback is configured, untried and within [`MAX_FALLBACK_HOPS`].
+    fn next_fallback(
+        &self,
+        entry: &ModelEntry,
+        err: &anyhow::Error,
+        tried: &[String],
+    ) -> Option<(String, Box<dyn LlmProvider>)> {
+        if !crate::llm::is_retryable_error(err) || tried.len() > MAX_FALLBACK_HOPS {
+            return None;
+        }
+        let next_id = self
+            .config
+            .resolve_model_id(entry.fallback.as_ref()?)
+            .ok()?;
+        if tried.contains(&next_id) {
+            return None;
+        }
+        let next_entry = self.config.get_model_entry(&next_id)?;
+        let api_key = self.config.api_key_for_model(&next_id).ok()?;
+        let provider = (self.provider_factory)(&api_key, &next_entry, &self.config.llm).ok()?;
+        Some((next_id, provider))
+    }
+
     /// Switch to a different model. Recreates the LLM provider.
+    /// `model_id` may be a `[model_aliases]` name.
     pub fn switch_model(&mut self, model_id: &str, config: &AppConfig) -> Result<()> {
-        let entry = config.get_model_entry(model_id).with_context(|| {
+        let model_id = config.resolve_model_id(model_id)?;
+        let entry = config.get_model_entry(&model_id).with_context(|| {
             format!(
                 "Model '{}' not found. Use /model to list available models.",
                 model_id
             )
         })?;
-        let api_key = config.api_key_for_model(model_id)?;
-        let llm = Self::create_provider_for_model(&api_key, &entry)?;
+        let api_key = config.api_key_for_model(&model_id)?;
+        let llm = (self.provider_factory)(&api_key, &entry, &config.llm)?;
         self.llm = llm;
-        self.current_model_id = model_id.to_string();
+        self.current_model_id = model_id;
         Ok(())
     }
 
@@ -548,7 +1193,1183 @@ List files and directories at a path with optional recursive traversal.
         self.messages = messages;
     }
 
+    /// Add text for the model to consider on the next turn (e.g. `/run` output).
+    pub fn add_context(&mut self, text: impl Into<String>) {
+        self.messages.push(Message::note(text));
+    }
+
+    /// Set the session's `/env` variables, layered over `tools.bash_env` for bash commands.
+    pub fn set_session_env(&mut self, env: BTreeMap<String, String>) {
+        self.session_env = env;
+        self.tool_router.replace(Box::new(self.bash_tool()));
+    }
+
+    /// A bash tool with `tools.bash_env` overridden by the session's `/env` variables.
+    pub fn bash_tool(&self) -> BashTool {
+        let mut env = self.config.tools.bash_env.clone();
+        env.extend(self.session_env.clone());
+        BashTool::new(self.config.tools.shell_program(), env)
+    }
+
     pub fn clear_history(&mut self) {
         self.messages.truncate(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ToolCall;
+    use async_trait::async_trait;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    /// Provider that replays a fixed sequence of responses and keeps the requests it got.
+    struct ScriptedProvider {
+        responses: Mutex<VecDeque<ChatResponse>>,
+        requests: std::sync::Arc<Mutex<Vec<ChatRequest>>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(responses: Vec<ChatResponse>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into()),
+                requests: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        async fn chat_completion(&self, request: &ChatRequest) -> Result<ChatResponse> {
+            self.requests.lock().unwrap().push(request.clone());
+            self.responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .context("script exhausted")
+        }
+
+        fn name(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    fn tool_call_response(name: &str, arguments: String) -> ChatResponse {
+        ChatResponse {
+            content: String::new(),
+            tool_calls: vec![ToolCall {
+                id: "call_1".to_string(),
+                name: name.to_string(),
+                arguments,
+            }],
+            usage: None,
+            thinking: vec![],
+        }
+    }
+
+    fn text_response(text: &str) -> ChatResponse {
+        ChatResponse {
+            content: text.to_string(),
+            tool_calls: vec![],
+            usage: None,
+            thinking: vec![],
+        }
+    }
+
+    /// Run one message through a scripted agent and collect the emitted events.
+    fn run_scripted(responses: Vec<ChatResponse>, dir: &Path) -> Vec<AgentEvent> {
+        rt().block_on(async {
+            let provider = ScriptedProvider::new(responses);
+            let mut agent = Agent::new(
+                Box::new(provider),
+                create_default_router(),
+                AppConfig::default(),
+                dir,
+                "test".to_string(),
+            );
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            agent.process_message("hi", Some(tx), None).await.unwrap();
+            let mut events = Vec::new();
+            while let Ok(evt) = rx.try_recv() {
+                events.push(evt);
+            }
+            events
+        })
+    }
+
+    #[test]
+    fn test_empty_response_retries_then_falls_back() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // One empty reply: retried with a nudge, the real answer is used
+        let events = run_scripted(vec![text_response(""), text_response("answer")], dir.path());
+        assert!(matches!(events.last(), Some(AgentEvent::Done(t)) if t == "answer"));
+
+        // Keeps coming back empty: a clear placeholder instead of a blank turn
+        rt().block_on(async {
+            let provider = ScriptedProvider::new(vec![text_response(""), text_response("  \n")]);
+            let mut agent = Agent::new(
+                Box::new(provider),
+                create_default_router(),
+                AppConfig::default(),
+                dir.path(),
+                "test".to_string(),
+            );
+            let reply = agent.process_message("hi", None, None).await.unwrap();
+            assert_eq!(reply, EMPTY_RESPONSE_MESSAGE);
+            let last = agent.history().last().unwrap();
+            assert_eq!(last.role, Role::Assistant);
+            assert_eq!(last.content, EMPTY_RESPONSE_MESSAGE);
+            // The nudge is not kept in history
+            assert!(agent
+                .history()
+                .iter()
+                .all(|m| m.content != EMPTY_RESPONSE_NUDGE));
+        });
+    }
+
+    #[test]
+    fn test_empty_response_retry_resets_after_real_reply() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = ScriptedProvider::new(vec![
+            text_response(""),
+            tool_call_response("list_directory", r#"{"path": "."}"#.to_string()),
+            text_response(""),
+            text_response("done"),
+        ]);
+        let requests = provider.requests.clone();
+        let mut agent = Agent::new(
+            Box::new(provider),
+            create_default_router(),
+            AppConfig::default(),
+            dir.path(),
+            "test".to_string(),
+        );
+        let reply = rt()
+            .block_on(agent.process_message("hi", None, None))
+            .unwrap();
+        // The empty reply after the tool call gets its own retry
+        assert_eq!(reply, "done");
+
+        let requests = requests.lock().unwrap();
+        let nudged: Vec<bool> = requests
+            .iter()
+            .map(|r| r.messages.iter().any(|m| m.content == EMPTY_RESPONSE_NUDGE))
+            .collect();
+        assert_eq!(nudged, [false, true, false, true]);
+    }
+
+    #[test]
+    fn test_tool_end_carries_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "line one\nline two").unwrap();
+        let args = serde_json::json!({ "path": file }).to_string();
+
+        let events = run_scripted(
+            vec![tool_call_response("read_file", args), text_response("done")],
+            dir.path(),
+        );
+        let (success, result) = events
+            .iter()
+            .find_map(|e| match e {
+                AgentEvent::ToolEnd {
+                    success, result, ..
+                } => Some((*success, result.clone())),
+                _ => None,
+            })
+            .expect("ToolEnd emitted");
+        assert!(success);
+        assert_eq!(result, "line one\nline two");
+    }
+
+    /// Read-only tool (named like `grep`) that sleeps, then echoes its `tag`.
+    struct SlowTool;
+
+    #[async_trait]
+    impl crate::tools::Tool for SlowTool {
+        fn name(&self) -> &str {
+            "grep"
+        }
+
+        fn description(&self) -> &str {
+            "slow"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(&self, params: serde_json::Value) -> Result<String> {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            Ok(params["tag"].as_str().unwrap_or_default().to_string())
+        }
+    }
+
+    #[test]
+    fn test_safe_tool_calls_run_concurrently_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls: Vec<ToolCall> = ["a", "b", "c"]
+            .iter()
+            .map(|tag| ToolCall {
+                id: format!("call_{}", tag),
+                name: "grep".to_string(),
+                arguments: serde_json::json!({ "tag": tag }).to_string(),
+            })
+            .collect();
+        let provider = ScriptedProvider::new(vec![
+            ChatResponse {
+                content: String::new(),
+                tool_calls: calls,
+                usage: None,
+                thinking: vec![],
+            },
+            text_response("done"),
+        ]);
+        let mut router = ToolRouter::new();
+        router.register(Box::new(SlowTool));
+        let mut agent = Agent::new(
+            Box::new(provider),
+            router,
+            AppConfig::default(),
+            dir.path(),
+            "test".to_string(),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let started = std::time::Instant::now();
+        rt().block_on(agent.process_message("hi", Some(tx), None))
+            .unwrap();
+        // Three 300ms calls overlapped
+        assert!(started.elapsed() < std::time::Duration::from_millis(800));
+
+        let results: Vec<(String, String)> = agent
+            .history()
+            .iter()
+            .filter(|m| m.role == Role::Tool)
+            .map(|m| (m.tool_call_id.clone().unwrap(), m.content.clone()))
+            .collect();
+        let expected: Vec<(String, String)> = ["a", "b", "c"]
+            .iter()
+            .map(|t| (format!("call_{}", t), t.to_string()))
+            .collect();
+        assert_eq!(results, expected);
+
+        let mut order = Vec::new();
+        while let Ok(evt) = rx.try_recv() {
+            match evt {
+                AgentEvent::ToolStart { .. } => order.push("start".to_string()),
+                AgentEvent::ToolEnd { result, .. } => order.push(result),
+                _ => {}
+            }
+        }
+        assert_eq!(order, ["start", "start", "start", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_bash_calls_run_sequentially() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls: Vec<ToolCall> = ["one", "two"]
+            .iter()
+            .map(|tag| ToolCall {
+                id: format!("call_{}", tag),
+                name: "bash".to_string(),
+                arguments: serde_json::json!({ "command": format!("echo {}", tag) }).to_string(),
+            })
+            .collect();
+        let provider = ScriptedProvider::new(vec![
+            ChatResponse {
+                content: String::new(),
+                tool_calls: calls,
+                usage: None,
+                thinking: vec![],
+            },
+            text_response("done"),
+        ]);
+        let mut agent = Agent::new(
+            Box::new(provider),
+            create_default_router(),
+            AppConfig::default(),
+            dir.path(),
+            "test".to_string(),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        rt().block_on(agent.process_message("hi", Some(tx), None))
+            .unwrap();
+
+        let mut order = Vec::new();
+        while let Ok(evt) = rx.try_recv() {
+            match evt {
+                AgentEvent::ToolStart { .. } => order.push("start".to_string()),
+                AgentEvent::ToolEnd { result, .. } => order.push(result.trim().to_string()),
+                _ => {}
+            }
+        }
+        assert_eq!(order, ["start", "one", "start", "two"]);
+    }
+
+    #[test]
+    fn test_dry_run_intercepts_git_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        // Every part is `git`, which `assess_risk` rates Safe
+        let command = format!(
+            "git init -q {0} && git -C {0} -c user.name=t -c user.email=t@t \
+             commit -q --allow-empty -m wip",
+            dir.path().display()
+        );
+        let provider = ScriptedProvider::new(vec![
+            tool_call_response(
+                "bash",
+                serde_json::json!({ "command": command }).to_string(),
+            ),
+            text_response("planned"),
+        ]);
+        let mut config = AppConfig::default();
+        config.agent.dry_run = true;
+        let mut agent = Agent::new(
+            Box::new(provider),
+            create_default_router(),
+            config,
+            dir.path(),
+            "test".to_string(),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        rt().block_on(agent.process_message("commit it", Some(tx), None))
+            .unwrap();
+
+        assert!(!dir.path().join(".git").exists());
+        let result = std::iter::from_fn(|| rx.try_recv().ok())
+            .find_map(|evt| match evt {
+                AgentEvent::ToolEnd { result, .. } => Some(result),
+                _ => None,
+            })
+            .expect("ToolEnd emitted");
+        assert!(result.starts_with("[dry run] Would execute"), "{}", result);
+        assert!(result.contains("commit -q --allow-empty"), "{}", result);
+    }
+
+    #[test]
+    fn test_dry_run_describes_changes_and_runs_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("keep.txt");
+        std::fs::write(&existing, "original").unwrap();
+        let marker = dir.path().join("marker");
+        let calls = vec![
+            ToolCall {
+                id: "call_w".to_string(),
+                name: "write_file".to_string(),
+                arguments:
+                    serde_json::json!({ "path": dir.path().join("new.txt"), "content": "x" })
+                        .to_string(),
+            },
+            ToolCall {
+                id: "call_e".to_string(),
+                name: "edit".to_string(),
+                arguments: serde_json::json!({
+                    "path": existing, "old_text": "original", "new_text": "changed"
+                })
+                .to_string(),
+            },
+            ToolCall {
+                id: "call_b".to_string(),
+                name: "bash".to_string(),
+                arguments: serde_json::json!({ "command": format!("touch {}", marker.display()) })
+                    .to_string(),
+            },
+            ToolCall {
+                id: "call_r".to_string(),
+                name: "read_file".to_string(),
+                arguments: serde_json::json!({ "path": existing }).to_string(),
+            },
+        ];
+        let provider = ScriptedProvider::new(vec![
+            ChatResponse {
+                content: String::new(),
+                tool_calls: calls,
+                usage: None,
+                thinking: vec![],
+            },
+            text_response("planned"),
+        ]);
+        let mut config = AppConfig::default();
+        config.agent.dry_run = true;
+        let mut agent = Agent::new(
+            Box::new(provider),
+            create_default_router(),
+            config,
+            dir.path(),
+            "test".to_string(),
+        );
+        assert!(agent.dry_run());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let reply = rt()
+            .block_on(agent.process_message("hi", Some(tx), None))
+            .unwrap();
+        assert_eq!(reply, "planned");
+
+        assert!(!dir.path().join("new.txt").exists());
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "original");
+        assert!(!marker.exists());
+
+        let ends: Vec<(String, bool, String)> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|evt| match evt {
+                AgentEvent::ToolEnd {
+                    name,
+                    success,
+                    result,
+                    ..
+                } => Some((name, success, result)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ends.len(), 4);
+        assert!(ends[..3]
+            .iter()
+            .all(|(_, success, result)| *success && result.starts_with("[dry run] Would execute")));
+        // Read-only calls run for real
+        assert_eq!(ends[3].0, "read_file");
+        assert!(ends[3].1 && ends[3].2.contains("original"), "{:?}", ends[3]);
+        let tool_results = agent
+            .history()
+            .iter()
+            .filter(|m| m.role == Role::Tool && m.content.starts_with("[dry run]"))
+            .count();
+        assert_eq!(tool_results, 3);
+    }
+
+    #[test]
+    fn test_switch_model_resolves_alias() {
+        let toml = r#"
+[llm]
+provider = "openai_compatible"
+model = "qwen-plus"
+api_key = "test-key"
+max_tokens = 4096
+
+[[llm.models]]
+id = "qwen-plus"
+model = "qwen-plus"
+
+[[llm.models]]
+id = "qwen-turbo"
+model = "qwen-turbo"
+
+[model_aliases]
+fast = "qwen-turbo"
+broken = "no-such-model"
+
+[agent]
+max_iterations = 20
+system_prompt = ""
+
+[tools]
+enabled = []
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let mut agent = Agent::new(
+            Box::new(ScriptedProvider::new(vec![])),
+            create_default_router(),
+            config.clone(),
+            dir.path(),
+            "qwen-plus".to_string(),
+        );
+
+        agent.switch_model("fast", &config).unwrap();
+        assert_eq!(agent.current_model_id(), "qwen-turbo");
+
+        let err = agent.switch_model("slow", &config).unwrap_err().to_string();
+        assert!(err.contains("'slow' not found"), "{}", err);
+        let err = agent
+            .switch_model("broken", &config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown model 'no-such-model'"), "{}", err);
+        assert_eq!(agent.current_model_id(), "qwen-turbo");
+
+        // Creating an agent fails loudly too, instead of using the default model
+        let created = Agent::create_with_model(&config, dir.path(), Some("fast")).unwrap();
+        assert_eq!(created.current_model_id(), "qwen-turbo");
+        let err = Agent::create_with_model(&config, dir.path(), Some("qwen-trubo"))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("'qwen-trubo' not found"),
+            "{}",
+            err
+        );
+        let mut typo_default = config.clone();
+        typo_default.llm.default_model = Some("fsat".to_string());
+        assert!(Agent::create(&typo_default, dir.path()).is_err());
+    }
+
+    /// Provider that always fails with a rate-limit error.
+    struct OverloadedProvider;
+
+    #[async_trait]
+    impl LlmProvider for OverloadedProvider {
+        async fn chat_completion(&self, _request: &ChatRequest) -> Result<ChatResponse> {
+            bail!("API error (429 Too Many Requests): rate limited")
+        }
+
+        fn name(&self) -> &str {
+            "overloaded"
+        }
+    }
+
+    /// Provider that streams some text, then fails with a rate-limit error.
+    struct MidStreamFailProvider;
+
+    #[async_trait]
+    impl LlmProvider for MidStreamFailProvider {
+        async fn chat_completion(&self, _request: &ChatRequest) -> Result<ChatResponse> {
+            bail!("API error (429 Too Many Requests): rate limited")
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _request: &ChatRequest,
+            chunk_tx: mpsc::Sender<StreamChunk>,
+        ) -> Result<ChatResponse> {
+            let _ = chunk_tx
+                .send(StreamChunk::TextDelta("Half an ans".to_string()))
+                .await;
+            bail!("API error (429 Too Many Requests): rate limited")
+        }
+
+        fn name(&self) -> &str {
+            "mid-stream-fail"
+        }
+    }
+
+    /// Models whose API name starts with "down" are overloaded; others answer with their name.
+    fn fallback_test_factory(
+        _api_key: &str,
+        entry: &ModelEntry,
+        _llm_config: &LlmConfig,
+    ) -> Result<Box<dyn LlmProvider>> {
+        if entry.model.starts_with("down") {
+            return Ok(Box::new(OverloadedProvider));
+        }
+        Ok(Box::new(ScriptedProvider::new(vec![text_response(
+            &format!("answer from {}", entry.model),
+        )])))
+    }
+
+    fn fallback_test_config() -> AppConfig {
+        let toml = r#"
+[llm]
+provider = "openai_compatible"
+model = "primary"
+api_key = "test-key"
+max_tokens = 4096
+
+[[llm.models]]
+id = "primary"
+model = "down-primary"
+fallback = "backup"
+
+[[llm.models]]
+id = "backup"
+model = "backup"
+
+[[llm.models]]
+id = "loop-a"
+model = "down-a"
+fallback = "loop-b"
+
+[[llm.models]]
+id = "loop-b"
+model = "down-b"
+fallback = "loop-a"
+
+[agent]
+max_iterations = 20
+system_prompt = ""
+
+[tools]
+enabled = []
+"#;
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_fallback_model_used_on_overload() {
+        let dir = tempfile::tempdir().unwrap();
+        rt().block_on(async {
+            let mut agent = Agent::new(
+                Box::new(OverloadedProvider),
+                create_default_router(),
+                fallback_test_config(),
+                dir.path(),
+                "primary".to_string(),
+            );
+            agent.provider_factory = fallback_test_factory;
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let reply = agent.process_message("hi", Some(tx), None).await.unwrap();
+            assert_eq!(reply, "answer from backup");
+            // Fallback only applies to this turn
+            assert_eq!(agent.current_model_id(), "primary");
+
+            let mut saw_fallback = false;
+            while let Ok(evt) = rx.try_recv() {
+                if let AgentEvent::ModelFallback { from, to, reason } = evt {
+                    assert_eq!((from.as_str(), to.as_str()), ("primary", "backup"));
+                    assert!(reason.contains("429"));
+                    saw_fallback = true;
+                }
+            }
+            assert!(saw_fallback);
+        });
+    }
+
+    #[test]
+    fn test_no_fallback_after_partial_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        rt().block_on(async {
+            let mut agent = Agent::new(
+                Box::new(MidStreamFailProvider),
+                create_default_router(),
+                fallback_test_config(),
+                dir.path(),
+                "primary".to_string(),
+            );
+            agent.provider_factory = fallback_test_factory;
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let err = agent
+                .process_message("hi", Some(tx), None)
+                .await
+                .unwrap_err();
+            assert!(format!("{:#}", err).contains("429"));
+            while let Ok(evt) = rx.try_recv() {
+                assert!(
+                    !matches!(evt, AgentEvent::ModelFallback { .. }),
+                    "fell back after streaming text"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_fallback_cycle_terminates() {
+        let dir = tempfile::tempdir().unwrap();
+        rt().block_on(async {
+            let mut agent = Agent::new(
+                Box::new(OverloadedProvider),
+                create_default_router(),
+                fallback_test_config(),
+                dir.path(),
+                "loop-a".to_string(),
+            );
+            agent.provider_factory = fallback_test_factory;
+            let err = agent.process_message("hi", None, None).await.unwrap_err();
+            assert!(format!("{:#}", err).contains("429"));
+        });
+    }
+
+    #[test]
+    fn test_tool_end_result_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("big.txt");
+        let content = "é".repeat(TOOL_END_RESULT_CAP + 500);
+        std::fs::write(&file, &content).unwrap();
+        let args = serde_json::json!({ "path": file }).to_string();
+
+        let events = run_scripted(
+            vec![tool_call_response("read_file", args), text_response("done")],
+            dir.path(),
+        );
+        let result = events
+            .iter()
+            .find_map(|e| match e {
+                AgentEvent::ToolEnd { result, .. } => Some(result.clone()),
+                _ => None,
+            })
+            .expect("ToolEnd emitted");
+        let expected = format!(
+            "{}\n… [truncated, {} chars total]",
+            "é".repeat(TOOL_END_RESULT_CAP),
+            TOOL_END_RESULT_CAP + 500
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_compaction_keeps_recent_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.llm.context_window = 200;
+        config.agent.keep_recent_messages = 4;
+        let mut agent = Agent::new(
+            Box::new(ScriptedProvider::new(vec![])),
+            create_default_router(),
+            config,
+            dir.path(),
+            "test".to_string(),
+        );
+        let big = "word ".repeat(100);
+        for i in 0..5 {
+            agent.messages.push(Message::user(format!("{} {}", i, big)));
+            agent.messages.push(Message::assistant(big.clone()));
+        }
+        agent.messages.push(Message::assistant_with_tool_calls(
+            "",
+            vec![ToolCall {
+                id: "call_1".to_string(),
+                name: "read_file".to_string(),
+                arguments: "{}".to_string(),
+            }],
+        ));
+        agent
+            .messages
+            .push(Message::tool_result("call_1", big.clone()));
+        agent
+            .messages
+            .push(Message::tool_result("call_1", big.clone()));
+        agent
+            .messages
+            .push(Message::tool_result("call_1", big.clone()));
+        agent.messages.push(Message::assistant(big.clone()));
+
+        // Over budget even after trimming, but the recent window survives intact
+        rt().block_on(agent.compact_context());
+        assert_eq!(agent.messages[0].role, Role::System);
+        let tail = &agent.messages[1..];
+        assert!(tail.len() >= 4);
+        // Window boundary fell on tool results, so their tool-call message is kept too
+        assert_eq!(tail.len(), 5);
+        assert_eq!(tail[0].tool_calls.len(), 1);
+        assert!(tail[1..4].iter().all(|m| m.role == Role::Tool));
+    }
+
+    #[test]
+    fn test_compaction_with_empty_recent_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.llm.context_window = 200;
+        config.agent.keep_recent_messages = 0;
+        let mut agent = Agent::new(
+            Box::new(ScriptedProvider::new(vec![])),
+            create_default_router(),
+            config,
+            dir.path(),
+            "test".to_string(),
+        );
+        let big = "word ".repeat(100);
+        agent.messages.push(Message::user(big.clone()));
+        agent.messages.push(Message::assistant(big));
+
+        rt().block_on(agent.compact_context());
+        assert_eq!(agent.messages.len(), 1);
+        assert_eq!(agent.messages[0].role, Role::System);
+    }
+
+    #[test]
+    fn test_compaction_summarizes_evicted_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.llm.context_window = 400;
+        config.agent.keep_recent_messages = 2;
+        config.agent.compaction_strategy = CompactionStrategy::Summarize;
+        let fill = |agent: &mut Agent| {
+            let big = "word ".repeat(100);
+            for i in 0..4 {
+                agent.messages.push(Message::user(format!("{} {}", i, big)));
+                agent.messages.push(Message::assistant(big.clone()));
+            }
+        };
+        let mut agent = Agent::new(
+            Box::new(ScriptedProvider::new(vec![text_response(
+                "User counted from 0 to 3.",
+            )])),
+            create_default_router(),
+            config,
+            dir.path(),
+            "test".to_string(),
+        );
+        fill(&mut agent);
+
+        rt().block_on(agent.compact_context());
+        assert_eq!(agent.messages[0].role, Role::System);
+        assert_eq!(agent.messages[1].role, Role::User);
+        assert_eq!(
+            agent.messages[1].content,
+            format!("{}\nUser counted from 0 to 3.", COMPACTION_SUMMARY_PREFIX)
+        );
+        assert!(agent.messages[2..].len() >= 2);
+        assert!(agent.messages[2..]
+            .iter()
+            .all(|m| !m.content.starts_with("0 ")));
+        assert_eq!(agent.stats.request_count, 1);
+
+        // Summarization failed (script exhausted): the messages are dropped instead
+        agent.messages.truncate(1);
+        fill(&mut agent);
+        let before = agent.messages.len();
+        rt().block_on(agent.compact_context());
+        assert!(agent.messages.len() < before);
+        assert!(agent
+            .messages
+            .iter()
+            .all(|m| !m.content.starts_with(COMPACTION_SUMMARY_PREFIX)));
+    }
+
+    #[test]
+    fn test_always_approval_skips_repeat_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("scratch");
+        let args =
+            serde_json::json!({ "command": format!("rm -rf {}", target.display()) }).to_string();
+        let provider = ScriptedProvider::new(vec![
+            tool_call_response("bash", args.clone()),
+            tool_call_response("bash", args),
+            text_response("done"),
+        ]);
+        let mut agent = Agent::new(
+            Box::new(provider),
+            create_default_router(),
+            AppConfig::default(),
+            dir.path(),
+            "test".to_string(),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (confirm_tx, mut confirm_rx) = mpsc::unbounded_channel();
+        // Only one answer is available; a second prompt would be denied
+        confirm_tx.send(ConfirmDecision::Always).unwrap();
+        drop(confirm_tx);
+        rt().block_on(agent.process_message("clean up", Some(tx), Some(&mut confirm_rx)))
+            .unwrap();
+
+        let mut confirms = 0;
+        let mut successes = 0;
+        while let Ok(evt) = rx.try_recv() {
+            match evt {
+                AgentEvent::ToolConfirm { .. } => confirms += 1,
+                AgentEvent::ToolEnd { success: true, .. } => successes += 1,
+                _ => {}
+            }
+        }
+        assert_eq!(confirms, 1);
+        assert_eq!(successes, 2);
+    }
+
+    #[test]
+    fn test_cost_recorded_only_for_priced_models() {
+        let usage = Some(TokenUsage {
+            input_tokens: 10_000,
+            output_tokens: 2_000,
+        });
+        let mut model = AppConfig::default().list_models().remove(0);
+        let mut stats = SessionStats::default();
+        stats.record_usage(&usage, Some(&model));
+        assert_eq!(stats.total_cost, None);
+
+        model.input_price = Some(3.0);
+        model.output_price = Some(15.0);
+        stats.record_usage(&usage, Some(&model));
+        stats.record_usage(&usage, Some(&model));
+        let cost = stats.total_cost.unwrap();
+        assert!((cost - 0.12).abs() < 1e-9, "{}", cost);
+        assert_eq!(stats.total_input_tokens, 30_000);
+    }
+
+    #[test]
+    fn test_plan_first_waits_for_approval_before_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let provider = ScriptedProvider::new(vec![
+            text_response("1. List the directory\n2. Summarize it"),
+            tool_call_response("list_directory", r#"{"path": "."}"#.to_string()),
+            text_response("done"),
+        ]);
+        let requests = provider.requests.clone();
+        let mut config = AppConfig::default();
+        config.agent.plan_first = true;
+        let mut agent = Agent::new(
+            Box::new(provider),
+            create_default_router(),
+            config,
+            dir.path(),
+            "test".to_string(),
+        );
+        let rt = rt();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (confirm_tx, mut confirm_rx) = mpsc::unbounded_channel();
+        let handle = rt.spawn(async move {
+            let reply = agent
+                .process_message("tidy up", Some(tx), Some(&mut confirm_rx))
+                .await;
+            (reply, agent)
+        });
+
+        let plan = rt.block_on(async {
+            loop {
+                match rx.recv().await {
+                    Some(AgentEvent::PlanReady(plan)) => break plan,
+                    Some(AgentEvent::ToolStart { name, .. }) => {
+                        panic!("{} ran before approval", name)
+                    }
+                    Some(_) => {}
+                    None => panic!("no plan"),
+                }
+            }
+        });
+        assert!(plan.starts_with("1. List the directory"));
+        rt.block_on(async { tokio::time::sleep(std::time::Duration::from_millis(50)).await });
+        {
+            let requests = requests.lock().unwrap();
+            assert_eq!(requests.len(), 1, "agent must wait for approval");
+            assert_eq!(requests[0].tool_choice, Some(ToolChoice::None));
+            assert!(!requests[0].tools.is_empty());
+            assert_eq!(
+                requests[0].messages.last().unwrap().content,
+                PLAN_FIRST_PROMPT
+            );
+        }
+
+        confirm_tx.send(ConfirmDecision::Once).unwrap();
+        let (reply, agent) = rt.block_on(handle).unwrap();
+        assert_eq!(reply.unwrap(), "done");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1..].iter().all(|r| r.tool_choice.is_none()));
+        assert!(agent
+            .messages
+            .iter()
+            .any(|m| m.content == PLAN_APPROVED_MESSAGE));
+        assert!(agent
+            .messages
+            .iter()
+            .all(|m| m.content != PLAN_FIRST_PROMPT));
+        // /replay re-sends only what the user typed; approval is asked again
+        assert_eq!(crate::session::user_turns(&agent.messages), ["tidy up"]);
+    }
+
+    #[test]
+    fn test_replayed_turns_skip_added_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let scripted = |responses| {
+            let provider = ScriptedProvider::new(responses);
+            let requests = provider.requests.clone();
+            let agent = Agent::new(
+                Box::new(provider),
+                create_default_router(),
+                AppConfig::default(),
+                dir.path(),
+                "test".to_string(),
+            );
+            (agent, requests)
+        };
+        let rt = rt();
+
+        let (mut original, _) = scripted(vec![text_response("one"), text_response("two")]);
+        rt.block_on(original.process_message("first", None, None))
+            .unwrap();
+        original.add_context("Output of `ls` (run by the user with /run):\n```\na.rs\n```");
+        rt.block_on(original.process_message("second", None, None))
+            .unwrap();
+        let turns = crate::session::user_turns(&original.messages);
+        assert_eq!(turns, ["first", "second"]);
+
+        let (mut replay, requests) = scripted(vec![text_response("one"), text_response("two")]);
+        for turn in &turns {
+            rt.block_on(replay.process_message(turn, None, None))
+                .unwrap();
+        }
+        let requests = requests.lock().unwrap();
+        let sent: Vec<&str> = requests[1]
+            .messages
+            .iter()
+            .filter(|m| m.role == Role::User)
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(sent, ["first", "second"]);
+    }
+
+    /// Provider that records which completion method was called.
+    struct MethodRecordingProvider {
+        calls: std::sync::Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for MethodRecordingProvider {
+        async fn chat_completion(&self, _request: &ChatRequest) -> Result<ChatResponse> {
+            self.calls.lock().unwrap().push("chat_completion");
+            Ok(text_response("plain"))
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _request: &ChatRequest,
+            _chunk_tx: mpsc::Sender<StreamChunk>,
+        ) -> Result<ChatResponse> {
+            self.calls.lock().unwrap().push("chat_completion_stream");
+            Ok(text_response("streamed"))
+        }
+
+        fn name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    #[test]
+    fn test_streaming_flag_selects_completion_method() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let mut agent = Agent::new(
+            Box::new(MethodRecordingProvider {
+                calls: calls.clone(),
+            }),
+            create_default_router(),
+            AppConfig::default(),
+            dir.path(),
+            "test".to_string(),
+        );
+        assert!(agent.streaming());
+        let rt = rt();
+        assert_eq!(
+            rt.block_on(agent.process_message("one", None, None))
+                .unwrap(),
+            "streamed"
+        );
+
+        agent.set_streaming(false);
+        assert_eq!(
+            rt.block_on(agent.process_message("two", None, None))
+                .unwrap(),
+            "plain"
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["chat_completion_stream", "chat_completion"]
+        );
+    }
+
+    /// Provider whose endpoint can't be reached.
+    struct UnreachableProvider;
+
+    #[async_trait]
+    impl LlmProvider for UnreachableProvider {
+        async fn chat_completion(&self, _request: &ChatRequest) -> Result<ChatResponse> {
+            bail!("connection refused")
+        }
+
+        async fn health_check(&self) -> Result<()> {
+            bail!("https://api.example.test unreachable: connection refused")
+        }
+
+        fn name(&self) -> &str {
+            "unreachable"
+        }
+    }
+
+    #[test]
+    fn test_health_check_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = |llm: Box<dyn LlmProvider>| {
+            Agent::new(
+                llm,
+                create_default_router(),
+                AppConfig::default(),
+                dir.path(),
+                "test".to_string(),
+            )
+        };
+        let rt = rt();
+        assert_eq!(
+            rt.block_on(agent(Box::new(UnreachableProvider)).check_health()),
+            ApiStatus::Offline
+        );
+        // Default implementation: no probe, assumed reachable
+        assert_eq!(
+            rt.block_on(agent(Box::new(ScriptedProvider::new(vec![]))).check_health()),
+            ApiStatus::Online
+        );
+    }
+
+    #[test]
+    fn test_max_iterations_runtime_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = serde_json::json!({ "path": dir.path() }).to_string();
+        let responses = (0..5)
+            .map(|_| tool_call_response("list_directory", args.clone()))
+            .collect();
+        let mut agent = Agent::new(
+            Box::new(ScriptedProvider::new(responses)),
+            create_default_router(),
+            AppConfig::default(),
+            dir.path(),
+            "test".to_string(),
+        );
+        assert_eq!(agent.max_iterations(), 20);
+        assert_eq!(agent.set_max_iterations(50), 50);
+        assert_eq!(agent.max_iterations(), 50);
+        assert_eq!(agent.set_max_iterations(10_000), MAX_ITERATIONS_CEILING);
+        assert_eq!(agent.set_max_iterations(0), 1);
+
+        agent.set_max_iterations(3);
+        let reply = rt()
+            .block_on(agent.process_message("loop", None, None))
+            .unwrap();
+        assert_eq!(reply, "[Agent stopped: reached maximum of 3 iterations]");
+    }
+
+    #[test]
+    fn test_append_system_prompt_always_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let suffix = "Prefer `edit` over `write_file` and always show diffs.";
+
+        // Default system_prompt: custom instructions are skipped, suffix still appended
+        let mut config = AppConfig::default();
+        config.agent.append_system_prompt = Some(suffix.to_string());
+        let prompt = Agent::build_system_prompt(&config, dir.path(), "m");
+        assert!(!prompt.contains("## Custom Instructions"));
+        assert!(prompt.trim_end().ends_with(suffix));
+
+        // Custom system_prompt: both appear, suffix last
+        config.agent.system_prompt = "Answer in haiku.".to_string();
+        let prompt = Agent::build_system_prompt(&config, dir.path(), "m");
+        assert!(prompt.contains("## Custom Instructions\nAnswer in haiku."));
+        assert!(prompt.trim_end().ends_with(suffix));
+
+        config.agent.append_system_prompt = None;
+        let prompt = Agent::build_system_prompt(&config, dir.path(), "m");
+        assert!(!prompt.contains(suffix));
+    }
+
+    #[test]
+    fn test_system_prompt_names_configured_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.tools.shell = Some("/usr/bin/fish".to_string());
+        let prompt = Agent::build_system_prompt(&config, dir.path(), "m");
+        assert!(prompt.contains("Execute shell commands via fish;"));
+    }
+
+    #[test]
+    fn test_user_instructions_precede_project_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        std::fs::write(project.join("CLAUDE.md"), "Run cargo fmt.").unwrap();
+        let instructions = dir.path().join("instructions.md");
+        std::fs::write(&instructions, "Never commit secrets.").unwrap();
+
+        let mut config = AppConfig::default();
+        config.agent.instructions_file = Some(instructions.display().to_string());
+        let prompt = Agent::build_system_prompt(&config, &project, "m");
+        let user = prompt.find("Never commit secrets.").unwrap();
+        let rules = prompt.find("Run cargo fmt.").unwrap();
+        assert!(user < rules);
+        assert!(prompt.contains("## User Instructions"));
+
+        // A missing file is skipped
+        config.agent.instructions_file = Some(dir.path().join("none.md").display().to_string());
+        let prompt = Agent::build_system_prompt(&config, &project, "m");
+        assert!(!prompt.contains("## User Instructions"));
+        assert!(prompt.contains("Run cargo fmt."));
+    }
+}
@@ -0,0 +1,72 @@
+//! Build information: crate version, git commit, compiled-in providers and tools.
+
+use crate::llm::SUPPORTED_PROVIDERS;
+use crate::tools::create_default_router;
+
+/// Crate version from Cargo.toml.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Short git commit hash, embedded by build.rs when built from a git checkout.
+pub const GIT_COMMIT: Option<&str> = option_env!("MINICLAW_GIT_COMMIT");
+
+/// One-line version string, e.g. "miniclaw 0.1.0 (abc1234567)".
+pub fn version_line() -> String {
+    match GIT_COMMIT {
+        Some(commit) => format!("miniclaw {} ({})", VERSION, commit),
+        None => format!("miniclaw {}", VERSION),
+    }
+}
+
+/// Compile-time cargo features that change behavior.
+fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "telegram") {
+        features.push("telegram");
+    }
+    if cfg!(feature = "sqlite") {
+        features.push("sqlite");
+    }
+    features
+}
+
+/// Full build info printed by `miniclaw --version`.
+pub fn long_version() -> String {
+    let tools: Vec<String> = create_default_router()
+        .definitions()
+        .into_iter()
+        .map(|d| d.name)
+        .collect();
+    let features = enabled_features();
+    let mut out = version_line();
+    out.push_str(&format!("\nproviders: {}", SUPPORTED_PROVIDERS.join(", ")));
+    out.push_str(&format!("\ntools: {}", tools.join(", ")));
+    out.push_str(&format!(
+        "\nfeatures: {}",
+        if features.is_empty() {
+            "(none)".to_string()
+        } else {
+            features.join(", ")
+        }
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_matches_cargo() {
+        assert_eq!(VERSION, env!("CARGO_PKG_VERSION"));
+        assert!(version_line().starts_with(&format!("miniclaw {}", env!("CARGO_PKG_VERSION"))));
+    }
+
+    #[test]
+    fn test_long_version_lists_providers_and_tools() {
+        let info = long_version();
+        assert!(info.contains("anthropic"));
+        assert!(info.contains("openai_compatible"));
+        assert!(info.contains("read_file"));
+        assert!(info.contains("bash"));
+    }
+}
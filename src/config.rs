@@ -2,19 +2,27 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Every section and field has a default, so an older or partial config still
+/// loads; unknown fields are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
+    #[serde(default)]
     pub llm: LlmConfig,
+    #[serde(default)]
     pub agent: AgentConfig,
+    #[serde(default)]
     pub tools: ToolsConfig,
     #[serde(default)]
     pub ui: UiConfig,
     /// Telegram bot config. Used when running `miniclaw telegram`.
     #[serde(default)]
     pub telegram: Option<TelegramConfig>,
+    /// Short names for model ids (`[model_aliases]`), e.g. `fast = "dashscope/qwen-turbo"`.
+    #[serde(default)]
+    pub model_aliases: HashMap<String, String>,
 }
 
 /// Telegram bot configuration.
@@ -38,9 +46,21 @@ pub struct ProviderConfig {
     /// Env var for API key (e.g. CODING_PLAN_API_KEY).
     #[serde(default)]
     pub api_key_env: Option<String>,
-    /// API format: "openai_compatible" or "anthropic".
+    /// API format: "openai_compatible", "anthropic" or "ollama".
     #[serde(default = "default_provider_api")]
     pub api: String,
+    /// Request URL template for gateways that put the model in the path; see [`ModelEntry::url_template`].
+    #[serde(default)]
+    pub url_template: Option<String>,
+    /// Auth header style: "bearer" (default) or "api-key".
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    /// Default `max_tokens` for models that leave it at 0 (before the `[llm]` value).
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Default `context_window` for models that leave it at 0 (before the `[llm]` value).
+    #[serde(default)]
+    pub context_window: Option<u64>,
 }
 
 fn default_provider_api() -> String {
@@ -74,6 +94,62 @@ pub struct RawModelEntry {
     pub api_key: Option<String>,
     #[serde(default)]
     pub api_key_env: Option<String>,
+    /// Model id (or alias) to fail over to when this model is overloaded/unavailable.
+    #[serde(default)]
+    pub fallback: Option<String>,
+    #[serde(default)]
+    pub url_template: Option<String>,
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_reasoning_effort")]
+    pub reasoning_effort: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_extra_body")]
+    pub extra_body: Option<serde_json::Value>,
+    #[serde(default)]
+    pub thinking_budget: Option<u32>,
+    #[serde(default)]
+    pub input_price: Option<f64>,
+    #[serde(default)]
+    pub output_price: Option<f64>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub prompt_caching: bool,
+}
+
+/// Allowed values for `reasoning_effort` (OpenAI reasoning models).
+pub const REASONING_EFFORTS: &[&str] = &["low", "medium", "high"];
+
+fn deserialize_reasoning_effort<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    match value {
+        Some(v) if !REASONING_EFFORTS.contains(&v.as_str()) => {
+            Err(serde::de::Error::custom(format!(
+                "invalid reasoning_effort '{}', expected one of: {}",
+                v,
+                REASONING_EFFORTS.join(", ")
+            )))
+        }
+        other => Ok(other),
+    }
+}
+
+fn deserialize_extra_body<'de, D>(deserializer: D) -> Result<Option<serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    match value {
+        Some(v) if !v.is_object() => Err(serde::de::Error::custom(
+            "extra_body must be a table of request fields, e.g. { top_k = 20 }",
+        )),
+        other => Ok(other),
+    }
 }
 
 /// Resolved model entry used at runtime. Built from RawModelEntry + ProviderConfig.
@@ -99,9 +175,47 @@ pub struct ModelEntry {
     pub api_key: Option<String>,
     #[serde(default)]
     pub api_key_env: Option<String>,
+    #[serde(default)]
+    pub fallback: Option<String>,
+    /// OpenAI-compatible request URL template with `{base}` and `{model}` placeholders,
+    /// e.g. `{base}/deployments/{model}/chat/completions?api-version=2024-06-01`.
+    /// When it contains `{model}`, the `model` body field is omitted.
+    #[serde(default)]
+    pub url_template: Option<String>,
+    /// Auth header style: "bearer" (`Authorization: Bearer`, default) or "api-key".
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    /// `reasoning_effort` sent to OpenAI-compatible reasoning models: low / medium / high.
+    #[serde(default)]
+    pub reasoning_effort: Option<String>,
+    /// Extra top-level request body fields (e.g. `top_k`, `enable_thinking`).
+    /// Never overrides fields miniclaw sets itself.
+    #[serde(default)]
+    pub extra_body: Option<serde_json::Value>,
+    /// Anthropic extended thinking `budget_tokens`. Enables thinking when set;
+    /// must be below `max_tokens`.
+    #[serde(default)]
+    pub thinking_budget: Option<u32>,
+    /// USD per million input tokens, for the session cost estimate.
+    #[serde(default)]
+    pub input_price: Option<f64>,
+    /// USD per million output tokens, for the session cost estimate.
+    #[serde(default)]
+    pub output_price: Option<f64>,
+    /// Sampling temperature (e.g. 0 for deterministic output); falls back to `[llm]`.
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    /// Nucleus sampling `top_p`; falls back to `[llm]`.
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    /// Anthropic prompt caching: mark the system prompt and the latest large tool
+    /// result with `cache_control` so agent loops re-read them from cache.
+    #[serde(default)]
+    pub prompt_caching: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LlmConfig {
     pub provider: String,
     pub model: String,
@@ -122,6 +236,48 @@ pub struct LlmConfig {
     pub models: Vec<RawModelEntry>,
     #[serde(default)]
     pub default_model: Option<String>,
+    /// Stable end-user id sent with each request (OpenAI `user`, Anthropic
+    /// `metadata.user_id`) for provider abuse monitoring. Omitted when unset.
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Retries for transient API failures (429/5xx, connection errors) with
+    /// exponential backoff, honoring `Retry-After`. 0 = fail immediately.
+    #[serde(default = "default_llm_max_retries")]
+    pub max_retries: u32,
+    /// Default sampling temperature for all models; omitted from requests when unset.
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    /// Default nucleus sampling `top_p` for all models; omitted when unset.
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    /// Max bytes of streamed text (content plus tool-call arguments) kept per
+    /// response; the rest is dropped with a warning. 0 = unlimited.
+    #[serde(default = "default_max_stream_bytes")]
+    pub max_stream_bytes: usize,
+    /// Seconds an LLM call may wait for a response (and a stream may stall
+    /// between chunks) before failing. 0 = no timeout.
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+impl LlmConfig {
+    /// `request_timeout_secs` as a duration; None when disabled.
+    pub fn request_timeout(&self) -> Option<std::time::Duration> {
+        (self.request_timeout_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.request_timeout_secs))
+    }
+}
+
+fn default_llm_max_retries() -> u32 {
+    3
+}
+
+fn default_request_timeout_secs() -> u64 {
+    120
+}
+
+fn default_max_stream_bytes() -> usize {
+    2 * 1024 * 1024
 }
 
 fn default_context_window() -> u64 {
@@ -133,14 +289,127 @@ fn default_api_key_env() -> String {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AgentConfig {
     pub max_iterations: u32,
     pub system_prompt: String,
+    /// Extra instructions always appended to the end of the system prompt.
+    #[serde(default)]
+    pub append_system_prompt: Option<String>,
+    /// Number of most recent non-system messages that context compaction never removes.
+    #[serde(default = "default_keep_recent_messages")]
+    pub keep_recent_messages: usize,
+    /// User-level instructions file (`~/` allowed) injected before project rules in every
+    /// project; defaults to `instructions.md` in the data directory.
+    #[serde(default)]
+    pub instructions_file: Option<String>,
+    /// What happens to messages evicted near the context limit: "truncate" (default)
+    /// drops them, "summarize" replaces them with an LLM-written summary.
+    #[serde(default)]
+    pub compaction_strategy: CompactionStrategy,
+    /// Show rule file (CLAUDE.md) paths relative to the project root in the prompt
+    /// instead of absolute paths.
+    #[serde(default)]
+    pub rules_relative_paths: bool,
+    /// Header before each rule file; `{path}` is replaced with its path and an empty
+    /// string omits the header. Default: "# Rules from {path}".
+    #[serde(default)]
+    pub rules_header: Option<String>,
+    /// Text between rule files. Default: a `---` line surrounded by blank lines.
+    #[serde(default)]
+    pub rules_separator: Option<String>,
+    /// Ask for a tool-free plan first and run tools only after the user approves it
+    /// (interactive sessions only).
+    #[serde(default)]
+    pub plan_first: bool,
+    /// Describe state-changing tool calls instead of running them; the model gets a
+    /// placeholder result and keeps going, so the turn previews a full plan.
+    /// Read-only tools still run; `bash` and `sqlite_query` never do.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Context compaction strategy (`agent.compaction_strategy`).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompactionStrategy {
+    /// Drop the oldest messages.
+    #[default]
+    Truncate,
+    /// Replace the oldest messages with a summary from the current model.
+    Summarize,
 }
 
+fn default_keep_recent_messages() -> usize {
+    6
+}
+
+/// Legacy default for `agent.system_prompt`; treated as "no custom instructions".
+pub const DEFAULT_SYSTEM_PROMPT: &str =
+    "You are a helpful AI assistant. You can use tools to help \
+    the user with tasks like reading files, writing files, executing commands, \
+    and more. Be concise and helpful.";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ToolsConfig {
     pub enabled: Vec<String>,
+    /// Pretty-print bash output that parses as a JSON object/array before sending it to the LLM.
+    #[serde(default)]
+    pub pretty_json: bool,
+    /// Allow write_file/edit to modify miniclaw's own config, sessions and state files.
+    #[serde(default)]
+    pub allow_state_writes: bool,
+    /// Retries for failed read-only tools and bash calls marked `retriable` (max 5).
+    #[serde(default)]
+    pub tool_max_retries: u32,
+    /// Refuse write_file content that looks like a credential (AWS/GitHub/API keys, private keys).
+    #[serde(default)]
+    pub warn_on_secret_write: bool,
+    /// Remove ANSI escape sequences (colors, cursor moves) from tool results.
+    #[serde(default = "bool_true")]
+    pub strip_ansi: bool,
+    /// Trim trailing whitespace from each line of tool results.
+    #[serde(default)]
+    pub trim_trailing_whitespace: bool,
+    /// Max network tool calls in flight at once per session (0 = unlimited).
+    #[serde(default = "default_max_network_concurrency")]
+    pub max_network_concurrency: usize,
+    /// Let the fetch tool reach localhost and private network addresses.
+    #[serde(default)]
+    pub fetch_allow_private: bool,
+    /// Save tool results over `spill_threshold_bytes` under the data dir and send the
+    /// model a pointer to it instead of the full text.
+    #[serde(default)]
+    pub spill_large_results: bool,
+    #[serde(default = "default_spill_threshold_bytes")]
+    pub spill_threshold_bytes: usize,
+    /// Extra environment variables for bash commands; a session's `/env set` values override these.
+    #[serde(default)]
+    pub bash_env: BTreeMap<String, String>,
+    /// Shell the `bash` tool runs commands with. Unset = `$SHELL`, falling back to bash.
+    #[serde(default)]
+    pub shell: Option<String>,
+}
+
+impl ToolsConfig {
+    /// The shell program for the `bash` tool: `shell`, else `$SHELL`, else "bash".
+    pub fn shell_program(&self) -> String {
+        self.shell
+            .clone()
+            .or_else(|| std::env::var("SHELL").ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "bash".to_string())
+    }
+}
+
+fn default_max_network_concurrency() -> usize {
+    4
+}
+
+fn default_spill_threshold_bytes() -> usize {
+    100_000
 }
 
 /// UI widget visibility configuration.
@@ -155,6 +424,85 @@ pub struct UiConfig {
     /// Pet name displayed in the pet panel.
     #[serde(default = "default_pet_name")]
     pub pet_name: String,
+    /// Show a truncated preview of each tool's output under its status line.
+    #[serde(default)]
+    pub show_tool_output: bool,
+    /// Show a short preview of `write_file`/`edit` arguments in the tool progress line.
+    #[serde(default)]
+    pub preview_tool_args: bool,
+    /// Show a dim `HH:MM` timestamp before each conversation message.
+    #[serde(default)]
+    pub show_timestamps: bool,
+    /// How multiple tabs share the screen: "auto" (default), "split" or "single".
+    #[serde(default)]
+    pub tab_layout: TabLayout,
+    /// Directory (relative to the project root unless absolute) where each non-empty
+    /// session is exported as Markdown on `/quit` and tab close.
+    #[serde(default)]
+    pub auto_export_dir: Option<String>,
+    /// Most tabs open at once (`/new`, `[+]`, `/load`, `/import`); 0 = unlimited.
+    #[serde(default = "default_max_tabs")]
+    pub max_tabs: usize,
+    /// Label before user input: the `miniclaw cli` prompt (default "> ") and the
+    /// TUI conversation label (default "You: ").
+    #[serde(default)]
+    pub prompt_user: Option<String>,
+    /// Label before assistant replies: printed before each `miniclaw cli` reply
+    /// (default: none) and the TUI reply heading (default "Assistant:").
+    #[serde(default)]
+    pub prompt_assistant: Option<String>,
+    /// Check API reachability at startup and then every N seconds, shown in
+    /// the header as "API: online/offline"; 0 = disabled.
+    #[serde(default)]
+    pub health_check_interval_secs: u64,
+    /// Save and unload background tabs idle for this many minutes; they reload
+    /// from the saved session when selected. 0 = never.
+    #[serde(default)]
+    pub unload_idle_tabs_after_mins: u64,
+    /// Context usage (percent of the window) above which the idle pet turns worried,
+    /// as a nudge to `/compact`. 0 = never.
+    #[serde(default = "default_pet_worried_percent")]
+    pub pet_worried_percent: u8,
+    /// Pet animation speed multiplier (wall-clock based): 2.0 = twice as fast,
+    /// 0 = still picture.
+    #[serde(default = "default_pet_animation_speed")]
+    pub pet_animation_speed: f64,
+    /// Max width in columns of the conversation text, for wide terminals. 0 = full width.
+    #[serde(default)]
+    pub max_content_width: u16,
+    /// Where the capped conversation sits in a wider pane.
+    #[serde(default)]
+    pub content_align: ContentAlign,
+    /// Render only the last N messages of a tab; older ones stay behind a marker
+    /// until `/all`. 0 = render everything.
+    #[serde(default = "default_render_window")]
+    pub render_window: usize,
+}
+
+fn default_max_tabs() -> usize {
+    20
+}
+
+/// Session tab layout (`ui.tab_layout`).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TabLayout {
+    /// Split side by side when each column would be wide enough, else single.
+    #[default]
+    Auto,
+    /// Always show all tabs side by side.
+    Split,
+    /// Always show only the active tab full-width; switch via the tab bar.
+    Single,
+}
+
+/// Placement of the conversation when `ui.max_content_width` caps it (`ui.content_align`).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentAlign {
+    #[default]
+    Center,
+    Left,
 }
 
 fn bool_true() -> bool {
@@ -165,71 +513,245 @@ fn default_pet_name() -> String {
     "huhu".to_string()
 }
 
+fn default_pet_worried_percent() -> u8 {
+    85
+}
+
+fn default_pet_animation_speed() -> f64 {
+    1.0
+}
+
+fn default_render_window() -> usize {
+    500
+}
+
 impl Default for UiConfig {
     fn default() -> Self {
         Self {
             show_stats: true,
             show_pet: true,
             pet_name: default_pet_name(),
+            show_tool_output: false,
+            preview_tool_args: false,
+            show_timestamps: false,
+            tab_layout: TabLayout::default(),
+            auto_export_dir: None,
+            max_tabs: default_max_tabs(),
+            prompt_user: None,
+            prompt_assistant: None,
+            health_check_interval_secs: 0,
+            unload_idle_tabs_after_mins: 0,
+            pet_worried_percent: default_pet_worried_percent(),
+            pet_animation_speed: default_pet_animation_speed(),
+            max_content_width: 0,
+            content_align: ContentAlign::default(),
+            render_window: default_render_window(),
         }
     }
 }
 
-impl Default for AppConfig {
+impl Default for LlmConfig {
     fn default() -> Self {
         Self {
-            llm: LlmConfig {
-                provider: "openai_compatible".to_string(),
-                model: "qwen-plus".to_string(),
-                api_base: Some("https://dashscope.aliyuncs.com/compatible-mode/v1".to_string()),
-                api_key: None,
-                api_key_env: "LLM_API_KEY".to_string(),
-                max_tokens: 4096,
-                context_window: default_context_window(),
-                providers: HashMap::new(),
-                models: vec![],
-                default_model: None,
-            },
-            agent: AgentConfig {
-                max_iterations: 20,
-                system_prompt: "You are a helpful AI assistant. You can use tools to help \
-                    the user with tasks like reading files, writing files, executing commands, \
-                    and more. Be concise and helpful."
-                    .to_string(),
-            },
-            tools: ToolsConfig {
-                enabled: vec![
-                    "read_file".to_string(),
-                    "write_file".to_string(),
-                    "list_directory".to_string(),
-                    "exec_command".to_string(),
-                ],
-            },
-            ui: UiConfig::default(),
-            telegram: None,
+            provider: "openai_compatible".to_string(),
+            model: "qwen-plus".to_string(),
+            api_base: Some("https://dashscope.aliyuncs.com/compatible-mode/v1".to_string()),
+            api_key: None,
+            api_key_env: default_api_key_env(),
+            max_tokens: 4096,
+            context_window: default_context_window(),
+            providers: HashMap::new(),
+            models: vec![],
+            default_model: None,
+            user_id: None,
+            max_retries: default_llm_max_retries(),
+            temperature: None,
+            top_p: None,
+            max_stream_bytes: default_max_stream_bytes(),
+            request_timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 20,
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            append_system_prompt: None,
+            keep_recent_messages: default_keep_recent_messages(),
+            instructions_file: None,
+            compaction_strategy: CompactionStrategy::default(),
+            rules_relative_paths: false,
+            rules_header: None,
+            rules_separator: None,
+            plan_first: false,
+            dry_run: false,
+        }
+    }
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: vec![
+                "read_file".to_string(),
+                "write_file".to_string(),
+                "list_directory".to_string(),
+                "exec_command".to_string(),
+            ],
+            pretty_json: false,
+            allow_state_writes: false,
+            tool_max_retries: 0,
+            warn_on_secret_write: false,
+            strip_ansi: true,
+            trim_trailing_whitespace: false,
+            max_network_concurrency: default_max_network_concurrency(),
+            fetch_allow_private: false,
+            spill_large_results: false,
+            spill_threshold_bytes: default_spill_threshold_bytes(),
+            bash_env: BTreeMap::new(),
+            shell: None,
+        }
+    }
+}
+
+/// Fields that fall back to a default silently but usually should be set;
+/// reported at startup when absent from the config file.
+const RECOMMENDED_FIELDS: &[(&str, &str)] =
+    &[("llm", "provider"), ("llm", "model"), ("llm", "max_tokens")];
+
+/// Dotted names of the [`RECOMMENDED_FIELDS`] missing from a config file's TOML.
+fn missing_recommended_fields(value: &toml::Table) -> Vec<String> {
+    RECOMMENDED_FIELDS
+        .iter()
+        .filter(|(section, key)| value.get(*section).and_then(|s| s.get(*key)).is_none())
+        .map(|(section, key)| format!("{}.{}", section, key))
+        .collect()
+}
+
+fn read_config_table(path: &Path) -> Result<toml::Table> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))
+}
+
+/// Project overlay keys applied in workspaces that aren't trusted: they pick a model,
+/// narrow the tool set or adjust the prompt, but can't redirect API traffic, loosen
+/// tool safety or change where state is written.
+const UNTRUSTED_PROJECT_KEYS: &[(&str, &str)] = &[
+    ("llm", "model"),
+    ("tools", "enabled"),
+    ("agent", "system_prompt"),
+    ("agent", "append_system_prompt"),
+];
+
+/// Keep only [`UNTRUSTED_PROJECT_KEYS`] of `overlay`; returns the dotted names of
+/// the keys that were dropped.
+fn restrict_untrusted_overlay(overlay: &mut toml::Table) -> Vec<String> {
+    let mut dropped = Vec::new();
+    overlay.retain(|section, value| {
+        let toml::Value::Table(table) = value else {
+            dropped.push(section.to_string());
+            return false;
+        };
+        table.retain(|key, _| {
+            let allowed = UNTRUSTED_PROJECT_KEYS.contains(&(section, key));
+            if !allowed {
+                dropped.push(format!("{}.{}", section, key));
+            }
+            allowed
+        });
+        !table.is_empty()
+    });
+    dropped
+}
+
+/// Merge `overlay` into `base`: nested tables merge key by key, any other value
+/// (including arrays such as `[[llm.models]]`) replaces the base value.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
         }
     }
 }
 
 impl AppConfig {
     pub fn config_path() -> Result<PathBuf> {
-        let home = dirs::home_dir().context("Could not determine home directory")?;
-        Ok(home.join(".miniclaw").join("config.toml"))
+        crate::paths::config_path()
     }
 
-    pub fn load() -> Result<Self> {
-        let config_path = Self::config_path()?;
+    /// Project-local overlay path: `<project_dir>/.miniclaw/config.toml`.
+    pub fn project_config_path(project_dir: &Path) -> PathBuf {
+        project_dir.join(".miniclaw").join("config.toml")
+    }
 
-        let mut config = if config_path.exists() {
-            let content = std::fs::read_to_string(&config_path).with_context(|| {
-                format!("Failed to read config file: {}", config_path.display())
-            })?;
-            toml::from_str(&content).with_context(|| {
-                format!("Failed to parse config file: {}", config_path.display())
-            })?
+    /// Load from `path` (from `--config`), or from the data directory's `config.toml` when `None`.
+    /// `.miniclaw/config.toml` in the current directory is merged over it per field
+    /// (only harmless keys unless the directory is a trusted workspace), and the
+    /// `MINICLAW_*` environment variables override both.
+    pub fn load_from(path: Option<&Path>) -> Result<Self> {
+        let config_path = match path {
+            Some(path) => path.to_path_buf(),
+            None => Self::config_path()?,
+        };
+        let cwd = std::env::current_dir().ok();
+        let project_path = cwd.as_deref().map(Self::project_config_path);
+        let trusted = cwd
+            .as_deref()
+            .is_some_and(|dir| crate::trusted_workspaces::is_trusted(dir).unwrap_or(false));
+        Self::load_layers(&config_path, project_path.as_deref(), trusted)
+    }
+
+    /// Load `config_path`, merge `project_path` over it when that file exists (all of
+    /// it when `trusted`, else only [`UNTRUSTED_PROJECT_KEYS`]), then apply
+    /// environment overrides.
+    fn load_layers(config_path: &Path, project_path: Option<&Path>, trusted: bool) -> Result<Self> {
+        let mut table = if config_path.exists() {
+            let table = read_config_table(config_path)?;
+            for field in missing_recommended_fields(&table) {
+                eprintln!("[Config] {} not set in config; using the default", field);
+            }
+            table
         } else {
-            Self::default()
+            toml::Table::new()
         };
+        let project_path = project_path.filter(|p| p.is_file() && *p != config_path);
+        if let Some(project_path) = project_path {
+            let mut overlay = read_config_table(project_path)?;
+            if !trusted {
+                let dropped = restrict_untrusted_overlay(&mut overlay);
+                if !dropped.is_empty() {
+                    eprintln!(
+                        "[Config] Ignoring {} from {}: workspace not trusted (/trust to apply)",
+                        dropped.join(", "),
+                        project_path.display()
+                    );
+                }
+            }
+            merge_tables(&mut table, overlay);
+            eprintln!(
+                "[Config] Using project overrides from {}",
+                project_path.display()
+            );
+        }
+
+        let mut config: Self = table.try_into().with_context(|| match project_path {
+            Some(project) => format!(
+                "Invalid config: {} merged with project overrides {} (project values win)",
+                config_path.display(),
+                project.display()
+            ),
+            None => format!("Failed to parse config file: {}", config_path.display()),
+        })?;
 
         if let Ok(provider) = std::env::var("MINICLAW_PROVIDER") {
             config.llm.provider = provider;
@@ -240,6 +762,12 @@ impl AppConfig {
         if let Ok(api_base) = std::env::var("MINICLAW_API_BASE") {
             config.llm.api_base = Some(api_base);
         }
+        for id in config.sampling_ignored_by_thinking() {
+            eprintln!(
+                "[Config] Model '{}': temperature/top_p are not sent while thinking_budget is set",
+                id
+            );
+        }
 
         Ok(config)
     }
@@ -253,7 +781,8 @@ impl AppConfig {
         std::env::var(&self.llm.api_key_env).with_context(|| {
             format!(
                 "API key not found. Either:\n  \
-                 1. Set api_key in config file: {}\n  \
+                 1. Set api_key in config file: {} \
+                 (or in a project's .miniclaw/config.toml, which takes precedence)\n  \
                  2. Set environment variable: export {}=your-key",
                 Self::config_path()
                     .map(|p| p.display().to_string())
@@ -263,18 +792,30 @@ impl AppConfig {
         })
     }
 
-    pub fn save_default() -> Result<PathBuf> {
-        let config_path = Self::config_path()?;
+    /// Write this config as TOML, creating the parent directory if needed.
+    pub fn save_to(&self, config_path: &Path) -> Result<()> {
         if let Some(parent) = config_path.parent() {
             std::fs::create_dir_all(parent).with_context(|| {
                 format!("Failed to create config directory: {}", parent.display())
             })?;
         }
-        let default = Self::default();
-        let content = toml::to_string_pretty(&default).context("Failed to serialize config")?;
-        std::fs::write(&config_path, content)
-            .with_context(|| format!("Failed to write config file: {}", config_path.display()))?;
-        Ok(config_path)
+        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        std::fs::write(config_path, content)
+            .with_context(|| format!("Failed to write config file: {}", config_path.display()))
+    }
+
+    /// Anthropic models whose temperature/top_p are dropped because extended
+    /// thinking is on (the API rejects them together).
+    fn sampling_ignored_by_thinking(&self) -> Vec<String> {
+        self.list_models()
+            .into_iter()
+            .filter(|m| {
+                m.provider == "anthropic"
+                    && m.thinking_budget.is_some()
+                    && (m.temperature.is_some() || m.top_p.is_some())
+            })
+            .map(|m| m.id)
+            .collect()
     }
 
     /// Returns the list of available models. Resolves provider hierarchy when provider_id is set.
@@ -297,6 +838,17 @@ impl AppConfig {
                 enable_search: false,
                 api_key: None,
                 api_key_env: None,
+                fallback: None,
+                url_template: None,
+                auth_header: None,
+                reasoning_effort: None,
+                extra_body: None,
+                thinking_budget: None,
+                input_price: None,
+                output_price: None,
+                temperature: self.llm.temperature,
+                top_p: self.llm.top_p,
+                prompt_caching: false,
             }];
         }
         let mut result = Vec::new();
@@ -319,17 +871,32 @@ impl AppConfig {
                     context_window: if raw.context_window > 0 {
                         raw.context_window
                     } else {
-                        self.llm.context_window
+                        prov.context_window
+                            .filter(|&w| w > 0)
+                            .unwrap_or(self.llm.context_window)
                     },
                     max_tokens: if raw.max_tokens > 0 {
                         raw.max_tokens
                     } else {
-                        self.llm.max_tokens
+                        prov.max_tokens
+                            .filter(|&t| t > 0)
+                            .unwrap_or(self.llm.max_tokens)
                     },
                     tools: raw.tools.clone(),
                     enable_search: raw.enable_search,
                     api_key: raw.api_key.clone().or(prov.api_key.clone()),
                     api_key_env: raw.api_key_env.clone().or(prov.api_key_env.clone()),
+                    fallback: raw.fallback.clone(),
+                    url_template: raw.url_template.clone().or(prov.url_template.clone()),
+                    auth_header: raw.auth_header.clone().or(prov.auth_header.clone()),
+                    reasoning_effort: raw.reasoning_effort.clone(),
+                    extra_body: raw.extra_body.clone(),
+                    thinking_budget: raw.thinking_budget,
+                    input_price: raw.input_price,
+                    output_price: raw.output_price,
+                    temperature: raw.temperature.or(self.llm.temperature),
+                    top_p: raw.top_p.or(self.llm.top_p),
+                    prompt_caching: raw.prompt_caching,
                 }
             } else {
                 ModelEntry {
@@ -360,6 +927,17 @@ impl AppConfig {
                     enable_search: raw.enable_search,
                     api_key: raw.api_key.clone(),
                     api_key_env: raw.api_key_env.clone(),
+                    fallback: raw.fallback.clone(),
+                    url_template: raw.url_template.clone(),
+                    auth_header: raw.auth_header.clone(),
+                    reasoning_effort: raw.reasoning_effort.clone(),
+                    extra_body: raw.extra_body.clone(),
+                    thinking_budget: raw.thinking_budget,
+                    input_price: raw.input_price,
+                    output_price: raw.output_price,
+                    temperature: raw.temperature.or(self.llm.temperature),
+                    top_p: raw.top_p.or(self.llm.top_p),
+                    prompt_caching: raw.prompt_caching,
                 }
             };
             result.push(entry);
@@ -374,13 +952,46 @@ impl AppConfig {
             return "default".to_string();
         }
         if let Some(ref default) = self.llm.default_model {
-            if models.iter().any(|m| m.id == *default) {
-                return default.clone();
+            if let Ok(id) = self.resolve_model_id(default) {
+                return id;
             }
         }
         models[0].id.clone()
     }
 
+    /// Resolve a model id or `[model_aliases]` name to a configured model id.
+    /// Exact model ids take precedence over aliases.
+    pub fn resolve_model_id(&self, name: &str) -> Result<String> {
+        let models = self.list_models();
+        if models.iter().any(|m| m.id == name) {
+            return Ok(name.to_string());
+        }
+        match self.model_aliases.get(name) {
+            Some(target) if models.iter().any(|m| m.id == *target) => Ok(target.clone()),
+            Some(target) => anyhow::bail!(
+                "Model alias '{}' points to unknown model '{}'. Check [model_aliases] in config.",
+                name,
+                target
+            ),
+            None => anyhow::bail!(
+                "Model or alias '{}' not found. Use /model to list available models.",
+                name
+            ),
+        }
+    }
+
+    /// Aliases that point to the given model id, sorted.
+    pub fn aliases_for(&self, model_id: &str) -> Vec<&str> {
+        let mut aliases: Vec<&str> = self
+            .model_aliases
+            .iter()
+            .filter(|(_, target)| target.as_str() == model_id)
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        aliases.sort_unstable();
+        aliases
+    }
+
     /// Get model entry by id. Returns None if not found.
     /// Resolves context_window/max_tokens 0 to [llm] defaults.
     pub fn get_model_entry(&self, id: &str) -> Option<ModelEntry> {
@@ -417,15 +1028,356 @@ impl AppConfig {
                     });
                 }
             }
+            // Local Ollama needs no key, and the global key belongs to another provider
+            if e.provider == "ollama" {
+                return Ok(String::new());
+            }
         }
         self.api_key()
     }
+
+    /// Where `api_key_for_model` would take the key from, without revealing it.
+    pub fn api_key_source(&self, model_id: &str) -> String {
+        let env_state = |env: &str| match std::env::var(env) {
+            Ok(v) if !v.is_empty() => "set",
+            _ => "not set",
+        };
+        if let Some(e) = self.get_model_entry(model_id) {
+            if e.api_key.as_deref().is_some_and(|k| !k.is_empty()) {
+                return "api_key in model/provider config".to_string();
+            }
+            if let Some(env) = e.api_key_env.as_deref().filter(|env| !env.is_empty()) {
+                return format!("env {} ({})", env, env_state(env));
+            }
+            if e.provider == "ollama" {
+                return "none (ollama needs no key)".to_string();
+            }
+        }
+        if self.llm.api_key.as_deref().is_some_and(|k| !k.is_empty()) {
+            return "[llm] api_key in config".to_string();
+        }
+        format!(
+            "env {} ({}, [llm] default)",
+            self.llm.api_key_env,
+            env_state(&self.llm.api_key_env)
+        )
+    }
+
+    /// Fully-resolved settings for a model id or alias, for `/model info`.
+    pub fn model_info(&self, name: &str) -> Result<String> {
+        let id = self.resolve_model_id(name)?;
+        let m = self
+            .get_model_entry(&id)
+            .with_context(|| format!("Model '{}' not found", id))?;
+        let mut lines = vec![format!("[Model info: {}]", m.id)];
+        let mut field = |key: &str, value: String| lines.push(format!("  {}: {}", key, value));
+        field("name", m.name.clone());
+        field("provider", m.provider.clone());
+        field("model", m.model.clone());
+        field(
+            "api_base",
+            m.api_base
+                .clone()
+                .unwrap_or_else(|| "(provider default)".to_string()),
+        );
+        field("context_window", m.context_window.to_string());
+        field("max_tokens", m.max_tokens.to_string());
+        field(
+            "tools",
+            if m.tools.is_empty() {
+                "(all enabled tools)".to_string()
+            } else {
+                m.tools.join(", ")
+            },
+        );
+        field("api_key", self.api_key_source(&id));
+        let aliases = self.aliases_for(&id);
+        if !aliases.is_empty() {
+            field("aliases", aliases.join(", "));
+        }
+        if m.enable_search {
+            field("enable_search", "true".to_string());
+        }
+        if m.prompt_caching {
+            field("prompt_caching", "true".to_string());
+        }
+        let optional = [
+            ("fallback", m.fallback.clone()),
+            ("url_template", m.url_template.clone()),
+            ("auth_header", m.auth_header.clone()),
+            ("reasoning_effort", m.reasoning_effort.clone()),
+            ("thinking_budget", m.thinking_budget.map(|v| v.to_string())),
+            ("temperature", m.temperature.map(|v| v.to_string())),
+            ("top_p", m.top_p.map(|v| v.to_string())),
+            ("input_price", m.input_price.map(|v| format!("${}/M", v))),
+            ("output_price", m.output_price.map(|v| format!("${}/M", v))),
+            ("extra_body", m.extra_body.as_ref().map(|v| v.to_string())),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                field(key, value);
+            }
+        }
+        Ok(lines.join("\n"))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_load_from_explicit_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.toml");
+        std::fs::write(
+            &path,
+            "[llm]\nmodel = \"project-model\"\nmax_tokens = 1234\n",
+        )
+        .unwrap();
+        let config = AppConfig::load_from(Some(&path)).unwrap();
+        assert_eq!(config.llm.model, "project-model");
+        assert_eq!(config.llm.max_tokens, 1234);
+        assert_eq!(config.agent.max_iterations, 20);
+    }
+
+    #[test]
+    fn test_explicit_config_ignores_default_config() {
+        let default_path = AppConfig::config_path().unwrap();
+        std::fs::create_dir_all(default_path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &default_path,
+            "[llm]\nmodel = \"default-model\"\nmax_tokens = 999\n",
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let explicit = dir.path().join("other.toml");
+        std::fs::write(&explicit, "[llm]\nmodel = \"explicit-model\"\n").unwrap();
+
+        let config = AppConfig::load_from(Some(&explicit)).unwrap();
+        assert_eq!(config.llm.model, "explicit-model");
+        assert_eq!(config.llm.max_tokens, LlmConfig::default().max_tokens);
+
+        // The chosen file is protected from tools like the default one
+        crate::paths::set_config_path_override(explicit.clone());
+        assert!(crate::paths::is_protected(&explicit));
+        assert!(crate::paths::is_protected(&default_path));
+    }
+
+    #[test]
+    fn test_project_overlay_wins_per_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("config.toml");
+        std::fs::write(
+            &home,
+            "[llm]\nprovider = \"anthropic\"\nmodel = \"home-model\"\nmax_tokens = 2048\n\n[agent]\nmax_iterations = 7\n",
+        )
+        .unwrap();
+        let project = dir.path().join("repo");
+        let overlay = AppConfig::project_config_path(&project);
+        std::fs::create_dir_all(overlay.parent().unwrap()).unwrap();
+        std::fs::write(
+            &overlay,
+            "[llm]\nmodel = \"project-model\"\n\n[tools]\nenabled = [\"read_file\"]\n",
+        )
+        .unwrap();
+
+        let config = AppConfig::load_layers(&home, Some(&overlay), true).unwrap();
+        assert_eq!(config.llm.model, "project-model");
+        assert_eq!(config.llm.provider, "anthropic");
+        assert_eq!(config.llm.max_tokens, 2048);
+        assert_eq!(config.agent.max_iterations, 7);
+        assert_eq!(config.tools.enabled, vec!["read_file"]);
+
+        // No overlay file: the home config alone
+        let missing = project.join("nope.toml");
+        let config = AppConfig::load_layers(&home, Some(&missing), true).unwrap();
+        assert_eq!(config.llm.model, "home-model");
+    }
+
+    #[test]
+    fn test_untrusted_project_overlay_limited_to_harmless_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("config.toml");
+        std::fs::write(
+            &home,
+            "[llm]\nmodel = \"home-model\"\napi_base = \"https://api.example.com/v1\"\n",
+        )
+        .unwrap();
+        let overlay = dir.path().join("overlay.toml");
+        std::fs::write(
+            &overlay,
+            "[llm]\nmodel = \"project-model\"\napi_base = \"https://evil.example/v1\"\n\n\
+             [tools]\nenabled = [\"read_file\"]\nallow_state_writes = true\n\n\
+             [agent]\nappend_system_prompt = \"Use tabs.\"\n",
+        )
+        .unwrap();
+
+        let config = AppConfig::load_layers(&home, Some(&overlay), false).unwrap();
+        assert_eq!(config.llm.model, "project-model");
+        assert_eq!(config.tools.enabled, vec!["read_file"]);
+        assert_eq!(
+            config.agent.append_system_prompt.as_deref(),
+            Some("Use tabs.")
+        );
+        assert_eq!(
+            config.llm.api_base.as_deref(),
+            Some("https://api.example.com/v1")
+        );
+        assert!(!config.tools.allow_state_writes);
+
+        let config = AppConfig::load_layers(&home, Some(&overlay), true).unwrap();
+        assert_eq!(
+            config.llm.api_base.as_deref(),
+            Some("https://evil.example/v1")
+        );
+        assert!(config.tools.allow_state_writes);
+    }
+
+    #[test]
+    fn test_sampling_ignored_with_thinking_reported() {
+        let toml = r#"
+[[llm.models]]
+id = "think"
+provider = "anthropic"
+model = "claude-sonnet"
+thinking_budget = 4000
+temperature = 0.2
+
+[[llm.models]]
+id = "plain"
+provider = "anthropic"
+model = "claude-sonnet"
+temperature = 0.2
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.sampling_ignored_by_thinking(), ["think"]);
+    }
+
+    #[test]
+    fn test_provider_token_defaults_inherited() {
+        let toml = r#"
+[llm]
+max_tokens = 4096
+context_window = 65536
+
+[llm.providers.big]
+base_url = "https://big.example/v1"
+context_window = 1000000
+max_tokens = 32000
+
+[llm.providers.plain]
+base_url = "https://plain.example/v1"
+
+[[llm.models]]
+provider_id = "big"
+id = "inherits"
+model = "inherits"
+
+[[llm.models]]
+provider_id = "big"
+id = "overrides"
+model = "overrides"
+context_window = 200000
+
+[[llm.models]]
+provider_id = "plain"
+id = "fallback"
+model = "fallback"
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        let inherits = config.get_model_entry("big/inherits").unwrap();
+        assert_eq!(inherits.context_window, 1_000_000);
+        assert_eq!(inherits.max_tokens, 32000);
+        let overrides = config.get_model_entry("big/overrides").unwrap();
+        assert_eq!(overrides.context_window, 200_000);
+        assert_eq!(overrides.max_tokens, 32000);
+        let fallback = config.get_model_entry("plain/fallback").unwrap();
+        assert_eq!(fallback.context_window, 65536);
+        assert_eq!(fallback.max_tokens, 4096);
+    }
+
+    #[test]
+    fn test_sampling_params_fall_back_to_llm() {
+        let toml = r#"
+[llm]
+temperature = 0.0
+
+[[llm.models]]
+id = "default-sampling"
+model = "a"
+
+[[llm.models]]
+id = "creative"
+model = "b"
+temperature = 1.2
+top_p = 0.95
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        let plain = config.get_model_entry("default-sampling").unwrap();
+        assert_eq!((plain.temperature, plain.top_p), (Some(0.0), None));
+        let creative = config.get_model_entry("creative").unwrap();
+        assert_eq!(
+            (creative.temperature, creative.top_p),
+            (Some(1.2), Some(0.95))
+        );
+    }
+
+    #[test]
+    fn test_minimal_llm_only_config_loads() {
+        let content = "[llm]\nmodel = \"claude-sonnet\"\napi_key = \"k\"\nfuture_option = true\n";
+        let config: AppConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.llm.model, "claude-sonnet");
+        assert_eq!(config.llm.provider, "openai_compatible");
+        assert_eq!(config.llm.max_tokens, 4096);
+        assert_eq!(config.agent.max_iterations, 20);
+        assert!(config.tools.strip_ansi);
+        assert_eq!(
+            missing_recommended_fields(&content.parse().unwrap()),
+            vec!["llm.provider", "llm.max_tokens"]
+        );
+
+        let empty: AppConfig = toml::from_str("").unwrap();
+        assert_eq!(empty.llm.model, "qwen-plus");
+    }
+
+    #[test]
+    fn test_reasoning_effort_validated() {
+        let entry = |effort: &str| {
+            toml::from_str::<RawModelEntry>(&format!(
+                "id = \"o3-mini\"\nmodel = \"o3-mini\"\nreasoning_effort = \"{}\"",
+                effort
+            ))
+        };
+        assert_eq!(
+            entry("low").unwrap().reasoning_effort.as_deref(),
+            Some("low")
+        );
+        let err = entry("max").unwrap_err();
+        assert!(err.to_string().contains("invalid reasoning_effort 'max'"));
+        let raw: RawModelEntry = toml::from_str("id = \"m\"\nmodel = \"m\"").unwrap();
+        assert!(raw.reasoning_effort.is_none());
+    }
+
+    #[test]
+    fn test_extra_body_must_be_table() {
+        let raw: RawModelEntry = toml::from_str(
+            "id = \"qwen\"\nmodel = \"qwen3\"\nextra_body = { top_k = 20, enable_thinking = false }",
+        )
+        .unwrap();
+        let extra = raw.extra_body.unwrap();
+        assert_eq!(extra["top_k"], 20);
+        assert_eq!(extra["enable_thinking"], false);
+
+        let err = toml::from_str::<RawModelEntry>("id = \"m\"\nmodel = \"m\"\nextra_body = 3")
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("extra_body must be a table"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn test_model_entry_tools_and_enable_search() {
         let toml = r#"
@@ -519,6 +1471,30 @@ enabled = ["read_file", "write_file"]
         );
     }
 
+    #[test]
+    fn test_ollama_model_needs_no_api_key() {
+        let toml = r#"
+[llm]
+provider = "openai_compatible"
+model = "qwen-plus"
+api_key_env = "MINICLAW_TEST_UNSET_KEY"
+
+[[llm.models]]
+id = "local"
+provider = "ollama"
+model = "llama3.1"
+"#;
+        let mut config: AppConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.api_key_for_model("local").unwrap(), "");
+        assert!(config.api_key_for_model("qwen-plus").is_err());
+        assert_eq!(config.api_key_source("local"), "none (ollama needs no key)");
+
+        // The cloud provider's key is never sent to the local server
+        config.llm.api_key = Some("cloud-key".to_string());
+        assert_eq!(config.api_key_for_model("local").unwrap(), "");
+        assert_eq!(config.api_key_for_model("qwen-plus").unwrap(), "cloud-key");
+    }
+
     #[test]
     fn test_provider_hierarchy() {
         let toml = r#"
@@ -604,4 +1580,76 @@ enabled = ["read_file", "write_file"]
         assert_eq!(kimi.model, "kimi-k2.5");
         assert_eq!(kimi.context_window, 262144);
     }
+
+    #[test]
+    fn test_model_info_shows_inherited_settings() {
+        let toml = r#"
+[llm]
+model = "kimi"
+api_key_env = "LLM_API_KEY"
+max_tokens = 4096
+
+[llm.providers.coding_plan]
+base_url = "https://coding.dashscope.aliyuncs.com/v1"
+api_key_env = "MINICLAW_TEST_UNSET_KEY"
+context_window = 262144
+
+[[llm.models]]
+provider_id = "coding_plan"
+id = "kimi-k2.5"
+model = "kimi-k2.5"
+tools = ["read_file"]
+
+[model_aliases]
+kimi = "coding_plan/kimi-k2.5"
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        let info = config.model_info("kimi").unwrap();
+        assert!(info.starts_with("[Model info: coding_plan/kimi-k2.5]"));
+        assert!(info.contains("  api_base: https://coding.dashscope.aliyuncs.com/v1"));
+        assert!(info.contains("  context_window: 262144"));
+        assert!(info.contains("  max_tokens: 4096"));
+        assert!(info.contains("  tools: read_file"));
+        assert!(info.contains("  api_key: env MINICLAW_TEST_UNSET_KEY (not set)"));
+        assert!(info.contains("  aliases: kimi"));
+        assert!(config.model_info("missing").is_err());
+    }
+
+    #[test]
+    fn test_model_aliases() {
+        let toml = r#"
+[llm]
+provider = "openai_compatible"
+model = "qwen-plus"
+max_tokens = 4096
+default_model = "smart"
+
+[[llm.models]]
+id = "qwen-turbo"
+model = "qwen-turbo"
+
+[[llm.models]]
+id = "qwen-max"
+model = "qwen-max"
+
+[model_aliases]
+fast = "qwen-turbo"
+smart = "qwen-max"
+best = "qwen-max"
+
+[agent]
+max_iterations = 20
+system_prompt = ""
+
+[tools]
+enabled = []
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.default_model_id(), "qwen-max");
+        assert_eq!(config.resolve_model_id("fast").unwrap(), "qwen-turbo");
+        assert_eq!(config.resolve_model_id("qwen-turbo").unwrap(), "qwen-turbo");
+        assert!(config.resolve_model_id("nope").is_err());
+        assert_eq!(config.aliases_for("qwen-max"), ["best", "smart"]);
+        assert_eq!(config.aliases_for("qwen-turbo"), ["fast"]);
+    }
 }
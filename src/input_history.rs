@@ -0,0 +1,151 @@
+//! Submitted user inputs, persisted across runs.
+//!
+//! Stored in `<data_dir>/input_history` (default ~/.miniclaw), one JSON string
+//! per line so multi-line inputs survive. Only the newest [`MAX_ENTRIES`] are kept.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+/// Maximum number of entries kept in the history file.
+pub const MAX_ENTRIES: usize = 1000;
+
+/// In-memory input history with an optional browse cursor (Up/Down recall).
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    /// Index into `entries` while browsing; `None` when editing a fresh input.
+    cursor: Option<usize>,
+    path: Option<PathBuf>,
+}
+
+impl InputHistory {
+    /// Load the history from the data directory. Errors are treated as empty history.
+    pub fn load_default() -> Self {
+        let path = paths::input_history_path().ok();
+        let entries = path.as_deref().map(load).unwrap_or_default();
+        Self {
+            entries,
+            cursor: None,
+            path,
+        }
+    }
+
+    /// Record a submitted input and append it to the history file.
+    pub fn push(&mut self, entry: &str) {
+        self.cursor = None;
+        if entry.trim().is_empty() || self.entries.last().map(String::as_str) == Some(entry) {
+            return;
+        }
+        self.entries.push(entry.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            let excess = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..excess);
+        }
+        if let Some(path) = &self.path {
+            let _ = save(path, &self.entries);
+        }
+    }
+
+    pub fn is_browsing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Step back to an older entry.
+    pub fn previous(&mut self) -> Option<&str> {
+        let idx = match self.cursor {
+            Some(0) => 0,
+            Some(i) => i - 1,
+            None => self.entries.len().checked_sub(1)?,
+        };
+        self.cursor = Some(idx);
+        self.entries.get(idx).map(String::as_str)
+    }
+
+    /// Step forward to a newer entry; `None` once past the newest (back to an empty input).
+    pub fn next(&mut self) -> Option<&str> {
+        let idx = self.cursor? + 1;
+        if idx >= self.entries.len() {
+            self.cursor = None;
+            return None;
+        }
+        self.cursor = Some(idx);
+        self.entries.get(idx).map(String::as_str)
+    }
+}
+
+/// Read the history file, keeping the newest [`MAX_ENTRIES`]. Missing or unreadable
+/// files and malformed lines are skipped.
+pub fn load(path: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<String> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+    entries
+}
+
+/// Write the newest [`MAX_ENTRIES`] of `entries` to the history file.
+pub fn save(path: &Path, entries: &[String]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let start = entries.len().saturating_sub(MAX_ENTRIES);
+    let mut content = String::new();
+    for entry in &entries[start..] {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    std::fs::write(path, content).context("Failed to write input_history")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input_history");
+        let entries: Vec<String> = (0..MAX_ENTRIES + 5).map(|i| format!("msg {}", i)).collect();
+        save(&path, &entries).unwrap();
+
+        let loaded = load(&path);
+        assert_eq!(loaded.len(), MAX_ENTRIES);
+        assert_eq!(loaded.first().unwrap(), "msg 5");
+        assert_eq!(loaded.last().unwrap(), &format!("msg {}", MAX_ENTRIES + 4));
+
+        save(&path, &["line one\nline two".to_string()]).unwrap();
+        assert_eq!(load(&path), vec!["line one\nline two"]);
+        assert!(load(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_browse_and_push() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input_history");
+        let mut history = InputHistory {
+            path: Some(path.clone()),
+            ..Default::default()
+        };
+        history.push("first");
+        history.push("second");
+        history.push("second");
+        assert_eq!(load(&path), vec!["first", "second"]);
+
+        assert_eq!(history.previous(), Some("second"));
+        assert_eq!(history.previous(), Some("first"));
+        assert_eq!(history.previous(), Some("first"));
+        assert_eq!(history.next(), Some("second"));
+        assert_eq!(history.next(), None);
+        assert!(!history.is_browsing());
+    }
+}
@@ -7,12 +7,16 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
 use super::LlmProvider;
-use crate::types::{ChatRequest, ChatResponse, Role, StreamChunk, TokenUsage, ToolCall};
+use crate::types::{
+    ChatRequest, ChatResponse, Role, StreamChunk, ThinkingBlock, TokenUsage, ToolCall, ToolChoice,
+};
 
 pub struct AnthropicProvider {
     api_key: String,
     api_base: String,
     client: reqwest::Client,
+    /// Retries for transient HTTP failures (`llm.max_retries`).
+    max_retries: u32,
 }
 
 // --- API Request Types ---
@@ -22,10 +26,96 @@ struct ApiRequest {
     model: String,
     max_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<ApiSystem>,
     messages: Vec<ApiMessage>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tools: Vec<ApiTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ApiToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<ApiMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ApiThinking>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+}
+
+/// Plain string, or one text block carrying `cache_control` when caching.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ApiSystem {
+    Text(String),
+    Blocks(Vec<ApiSystemBlock>),
+}
+
+#[derive(Serialize)]
+struct ApiSystemBlock {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: String,
+    cache_control: CacheControl,
+}
+
+/// Prompt caching breakpoint: the prefix up to and including this block is cached.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+impl CacheControl {
+    fn ephemeral() -> Self {
+        Self {
+            kind: "ephemeral".to_string(),
+        }
+    }
+}
+
+/// Tool results shorter than this aren't worth a cache breakpoint.
+const CACHE_MIN_TOOL_RESULT_CHARS: usize = 4096;
+
+/// Put a cache breakpoint on the most recent tool result of at least
+/// [`CACHE_MIN_TOOL_RESULT_CHARS`], so later loop iterations reuse everything up to it.
+fn mark_latest_large_tool_result(messages: &mut [ApiMessage]) {
+    let latest = messages
+        .iter_mut()
+        .rev()
+        .filter_map(|m| match &mut m.content {
+            ApiContent::Blocks(blocks) => Some(blocks.iter_mut().rev()),
+            ApiContent::Text(_) => None,
+        })
+        .flatten()
+        .find_map(|block| match block {
+            ContentBlock::ToolResult {
+                content,
+                cache_control,
+                ..
+            } if content.chars().count() >= CACHE_MIN_TOOL_RESULT_CHARS => Some(cache_control),
+            _ => None,
+        });
+    if let Some(cache_control) = latest {
+        *cache_control = Some(CacheControl::ephemeral());
+    }
+}
+
+#[derive(Serialize)]
+struct ApiToolChoice {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct ApiMetadata {
+    user_id: String,
+}
+
+#[derive(Serialize)]
+struct ApiThinking {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    budget_tokens: u32,
 }
 
 #[derive(Serialize)]
@@ -56,7 +146,13 @@ enum ContentBlock {
     ToolResult {
         tool_use_id: String,
         content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
     },
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
 }
 
 #[derive(Serialize)]
@@ -89,10 +185,23 @@ impl AnthropicProvider {
         Self {
             api_key,
             api_base: api_base.unwrap_or_else(|| "https://api.anthropic.com".to_string()),
-            client: reqwest::Client::new(),
+            client: super::http_client(None),
+            max_retries: 0,
         }
     }
 
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Fail calls that get no response, or whose stream stalls, for `timeout`
+    /// (`llm.request_timeout_secs`).
+    pub fn with_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+        self.client = super::http_client(timeout);
+        self
+    }
+
     fn build_api_request(&self, request: &ChatRequest) -> ApiRequest {
         let mut system = None;
         let mut api_messages: Vec<ApiMessage> = Vec::new();
@@ -116,6 +225,16 @@ impl AnthropicProvider {
                         });
                     } else {
                         let mut blocks = Vec::new();
+                        // With thinking on, the tool-use turn must start with its thinking
+                        if request.thinking_budget.is_some() {
+                            blocks.extend(msg.thinking.iter().map(|t| match &t.redacted {
+                                Some(data) => ContentBlock::RedactedThinking { data: data.clone() },
+                                None => ContentBlock::Thinking {
+                                    thinking: t.thinking.clone(),
+                                    signature: t.signature.clone(),
+                                },
+                            }));
+                        }
                         if !msg.content.is_empty() {
                             blocks.push(ContentBlock::Text {
                                 text: msg.content.clone(),
@@ -140,6 +259,7 @@ impl AnthropicProvider {
                     let block = ContentBlock::ToolResult {
                         tool_use_id: msg.tool_call_id.clone().unwrap_or_default(),
                         content: msg.content.clone(),
+                        cache_control: None,
                     };
                     api_messages.push(ApiMessage {
                         role: "user".to_string(),
@@ -149,6 +269,21 @@ impl AnthropicProvider {
             }
         }
 
+        let system = system.map(|text| {
+            if request.prompt_caching {
+                ApiSystem::Blocks(vec![ApiSystemBlock {
+                    kind: "text",
+                    text,
+                    cache_control: CacheControl::ephemeral(),
+                }])
+            } else {
+                ApiSystem::Text(text)
+            }
+        });
+        if request.prompt_caching {
+            mark_latest_large_tool_result(&mut api_messages);
+        }
+
         let tools: Vec<ApiTool> = request
             .tools
             .iter()
@@ -164,13 +299,53 @@ impl AnthropicProvider {
             max_tokens: request.max_tokens,
             system,
             messages: api_messages,
+            tool_choice: request
+                .tool_choice
+                .filter(|_| !tools.is_empty())
+                .map(|choice| ApiToolChoice {
+                    kind: match choice {
+                        ToolChoice::None => "none",
+                    },
+                }),
             tools,
+            metadata: request.user.clone().map(|user_id| ApiMetadata { user_id }),
+            thinking: request.thinking_budget.map(|budget_tokens| ApiThinking {
+                kind: "enabled",
+                budget_tokens,
+            }),
+            // The API rejects sampling changes while extended thinking is on
+            temperature: request
+                .temperature
+                .filter(|_| request.thinking_budget.is_none()),
+            top_p: request.top_p.filter(|_| request.thinking_budget.is_none()),
+        }
+    }
+
+    /// Final JSON body: the standard request, stream flag, then `extra_body`.
+    fn request_body(&self, request: &ChatRequest, stream: bool) -> Result<serde_json::Value> {
+        if let Some(budget) = request.thinking_budget {
+            if budget >= request.max_tokens {
+                anyhow::bail!(
+                    "thinking_budget ({}) must be less than max_tokens ({}) for model '{}'",
+                    budget,
+                    request.max_tokens,
+                    request.model
+                );
+            }
+        }
+        let mut body = serde_json::to_value(self.build_api_request(request))
+            .context("Failed to serialize request")?;
+        if stream {
+            body["stream"] = serde_json::json!(true);
         }
+        super::merge_extra_body(&mut body, request.extra_body.as_ref());
+        Ok(body)
     }
 
     fn parse_response(&self, api_response: ApiResponse) -> ChatResponse {
         let mut content = String::new();
         let mut tool_calls = Vec::new();
+        let mut thinking = Vec::new();
 
         for block in api_response.content {
             match block {
@@ -182,6 +357,20 @@ impl AnthropicProvider {
                         arguments: serde_json::to_string(&input).unwrap_or_default(),
                     });
                 }
+                ContentBlock::Thinking {
+                    thinking: text,
+                    signature,
+                } => thinking.push(ThinkingBlock {
+                    thinking: text,
+                    signature,
+                    redacted: None,
+                }),
+                // Encrypted reasoning can't be shown, but must be replayed with the tool calls
+                ContentBlock::RedactedThinking { data } => thinking.push(ThinkingBlock {
+                    thinking: String::new(),
+                    signature: String::new(),
+                    redacted: Some(data),
+                }),
                 ContentBlock::ToolResult { .. } => {}
             }
         }
@@ -195,6 +384,7 @@ impl AnthropicProvider {
             content,
             tool_calls,
             usage,
+            thinking,
         }
     }
 }
@@ -209,17 +399,17 @@ struct StreamToolCallAccumulator {
 #[async_trait]
 impl LlmProvider for AnthropicProvider {
     async fn chat_completion(&self, request: &ChatRequest) -> Result<ChatResponse> {
-        let api_request = self.build_api_request(request);
+        let body = self.request_body(request, false)?;
         let url = format!("{}/v1/messages", self.api_base.trim_end_matches('/'));
 
-        let response = self
+        let request = self
             .client
             .post(&url)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&api_request)
-            .send()
+            .json(&body);
+        let response = super::send_with_retry(request, self.max_retries)
             .await
             .context("Failed to send request to Anthropic API")?;
 
@@ -240,22 +430,20 @@ impl LlmProvider for AnthropicProvider {
     async fn chat_completion_stream(
         &self,
         request: &ChatRequest,
-        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+        chunk_tx: mpsc::Sender<StreamChunk>,
     ) -> Result<ChatResponse> {
-        let api_request = self.build_api_request(request);
+        let body = self.request_body(request, true)?;
         let url = format!("{}/v1/messages", self.api_base.trim_end_matches('/'));
+        let mut cap = super::StreamCap::new(request.max_stream_bytes);
 
-        let mut body = serde_json::to_value(&api_request).context("Failed to serialize request")?;
-        body["stream"] = serde_json::json!(true);
-
-        let response = self
+        let request = self
             .client
             .post(&url)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&body)
-            .send()
+            .json(&body);
+        let response = super::send_with_retry(request, self.max_retries)
             .await
             .context("Failed to send streaming request to Anthropic API")?;
 
@@ -269,6 +457,7 @@ impl LlmProvider for AnthropicProvider {
         let mut buffer = String::new();
         let mut content = String::new();
         let mut tool_calls: Vec<StreamToolCallAccumulator> = Vec::new();
+        let mut thinking: Vec<ThinkingBlock> = Vec::new();
         let mut input_tokens: u64 = 0;
         let mut output_tokens: u64 = 0;
         let mut current_event_type = String::new();
@@ -327,6 +516,25 @@ impl LlmProvider for AnthropicProvider {
                                     name,
                                     arguments: String::new(),
                                 });
+                            } else if block_type == "thinking" {
+                                thinking.push(ThinkingBlock {
+                                    thinking: String::new(),
+                                    signature: String::new(),
+                                    redacted: None,
+                                });
+                            } else if block_type == "redacted_thinking" {
+                                // Arrives whole in the start event; no deltas follow
+                                thinking.push(ThinkingBlock {
+                                    thinking: String::new(),
+                                    signature: String::new(),
+                                    redacted: Some(
+                                        block
+                                            .get("data")
+                                            .and_then(|v| v.as_str())
+                                            .unwrap_or("")
+                                            .to_string(),
+                                    ),
+                                });
                             }
                         }
                     }
@@ -337,9 +545,12 @@ impl LlmProvider for AnthropicProvider {
                             match delta_type {
                                 "text_delta" => {
                                     if let Some(text) = delta.get("text").and_then(|v| v.as_str()) {
-                                        content.push_str(text);
-                                        let _ =
-                                            chunk_tx.send(StreamChunk::TextDelta(text.to_string()));
+                                        let kept = cap.append(&mut content, text, &chunk_tx).await;
+                                        if !kept.is_empty() {
+                                            let _ = chunk_tx
+                                                .send(StreamChunk::TextDelta(kept.to_string()))
+                                                .await;
+                                        }
                                     }
                                 }
                                 "input_json_delta" => {
@@ -347,10 +558,26 @@ impl LlmProvider for AnthropicProvider {
                                         delta.get("partial_json").and_then(|v| v.as_str())
                                     {
                                         if let Some(tc) = tool_calls.last_mut() {
-                                            tc.arguments.push_str(json);
+                                            cap.append(&mut tc.arguments, json, &chunk_tx).await;
                                         }
                                     }
                                 }
+                                "thinking_delta" => {
+                                    if let (Some(text), Some(block)) = (
+                                        delta.get("thinking").and_then(|v| v.as_str()),
+                                        thinking.last_mut(),
+                                    ) {
+                                        block.thinking.push_str(text);
+                                    }
+                                }
+                                "signature_delta" => {
+                                    if let (Some(sig), Some(block)) = (
+                                        delta.get("signature").and_then(|v| v.as_str()),
+                                        thinking.last_mut(),
+                                    ) {
+                                        block.signature.push_str(sig);
+                                    }
+                                }
                                 _ => {}
                             }
                         }
@@ -362,14 +589,14 @@ impl LlmProvider for AnthropicProvider {
                         }
                     }
                     "message_stop" => {
-                        let _ = chunk_tx.send(StreamChunk::Done);
+                        let _ = chunk_tx.send(StreamChunk::Done).await;
                     }
                     _ => {}
                 }
             }
         }
 
-        let _ = chunk_tx.send(StreamChunk::Done);
+        let _ = chunk_tx.send(StreamChunk::Done).await;
 
         let final_tool_calls = tool_calls
             .into_iter()
@@ -393,10 +620,196 @@ impl LlmProvider for AnthropicProvider {
             content,
             tool_calls: final_tool_calls,
             usage,
+            thinking,
         })
     }
 
+    async fn health_check(&self) -> Result<()> {
+        super::probe_endpoint(&self.client, &self.api_base).await
+    }
+
     fn name(&self) -> &str {
         "Anthropic"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Message;
+
+    #[test]
+    fn test_metadata_user_id_only_when_set() {
+        let provider = AnthropicProvider::new("sk-ant".to_string(), None);
+        let mut request = ChatRequest {
+            model: "claude-sonnet".to_string(),
+            messages: vec![Message::system("sys"), Message::user("hi")],
+            tools: vec![],
+            max_tokens: 16,
+            enable_search: None,
+            reasoning_effort: None,
+            user: None,
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: None,
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: None,
+        };
+        let body = serde_json::to_value(provider.build_api_request(&request)).unwrap();
+        assert!(body.get("metadata").is_none());
+
+        request.user = Some("team-alpha".to_string());
+        let body = serde_json::to_value(provider.build_api_request(&request)).unwrap();
+        assert_eq!(body["metadata"]["user_id"], "team-alpha");
+        assert!(body.get("temperature").is_none());
+
+        request.temperature = Some(0.2);
+        let body = serde_json::to_value(provider.build_api_request(&request)).unwrap();
+        assert_eq!(body["temperature"], 0.2);
+        assert!(body.get("top_p").is_none());
+    }
+
+    #[test]
+    fn test_thinking_budget_serialized_and_replayed() {
+        let provider = AnthropicProvider::new("sk-ant".to_string(), None);
+        let mut assistant = Message::assistant_with_tool_calls(
+            "",
+            vec![ToolCall {
+                id: "toolu_1".to_string(),
+                name: "read_file".to_string(),
+                arguments: "{}".to_string(),
+            }],
+        );
+        assistant.thinking = vec![
+            ThinkingBlock {
+                thinking: "Need the file first.".to_string(),
+                signature: "sig".to_string(),
+                redacted: None,
+            },
+            ThinkingBlock {
+                thinking: String::new(),
+                signature: String::new(),
+                redacted: Some("opaque".to_string()),
+            },
+        ];
+        let mut request = ChatRequest {
+            model: "claude-sonnet".to_string(),
+            messages: vec![Message::user("hi"), assistant],
+            tools: vec![],
+            max_tokens: 16000,
+            enable_search: None,
+            reasoning_effort: None,
+            user: None,
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: None,
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: None,
+        };
+        let body = provider.request_body(&request, false).unwrap();
+        assert!(body.get("thinking").is_none());
+        assert_eq!(body["messages"][1]["content"][0]["type"], "tool_use");
+
+        request.thinking_budget = Some(8000);
+        let body = provider.request_body(&request, false).unwrap();
+        assert_eq!(
+            body["thinking"],
+            serde_json::json!({"type": "enabled", "budget_tokens": 8000})
+        );
+        let first = &body["messages"][1]["content"][0];
+        assert_eq!(first["type"], "thinking");
+        assert_eq!(first["signature"], "sig");
+        assert_eq!(
+            body["messages"][1]["content"][1],
+            serde_json::json!({"type": "redacted_thinking", "data": "opaque"})
+        );
+        assert_eq!(body["messages"][1]["content"][2]["type"], "tool_use");
+
+        request.temperature = Some(0.2);
+        request.top_p = Some(0.9);
+        let body = provider.request_body(&request, false).unwrap();
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("top_p").is_none());
+
+        request.max_tokens = 8000;
+        let err = provider.request_body(&request, false).unwrap_err();
+        assert!(err.to_string().contains("must be less than max_tokens"));
+    }
+
+    #[test]
+    fn test_cache_control_on_system_and_latest_large_tool_result() {
+        let provider = AnthropicProvider::new("sk-ant".to_string(), None);
+        let call = |id: &str| {
+            Message::assistant_with_tool_calls(
+                "",
+                vec![ToolCall {
+                    id: id.to_string(),
+                    name: "read_file".to_string(),
+                    arguments: "{}".to_string(),
+                }],
+            )
+        };
+        let mut request = ChatRequest {
+            model: "claude-sonnet".to_string(),
+            messages: vec![
+                Message::system("sys"),
+                Message::user("read both"),
+                call("toolu_1"),
+                Message::tool_result("toolu_1", "x".repeat(CACHE_MIN_TOOL_RESULT_CHARS)),
+                call("toolu_2"),
+                // Over the threshold in bytes, not in chars
+                Message::tool_result("toolu_2", "é".repeat(CACHE_MIN_TOOL_RESULT_CHARS - 1)),
+            ],
+            tools: vec![],
+            max_tokens: 1024,
+            enable_search: None,
+            reasoning_effort: None,
+            user: None,
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: None,
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: None,
+        };
+        let body = serde_json::to_value(provider.build_api_request(&request)).unwrap();
+        assert_eq!(body["system"], "sys");
+        assert!(!body.to_string().contains("cache_control"));
+
+        request.prompt_caching = true;
+        let body = serde_json::to_value(provider.build_api_request(&request)).unwrap();
+        let ephemeral = serde_json::json!({"type": "ephemeral"});
+        assert_eq!(body["system"][0]["text"], "sys");
+        assert_eq!(body["system"][0]["cache_control"], ephemeral);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "toolu_1");
+        assert_eq!(messages[2]["content"][0]["cache_control"], ephemeral);
+        assert_eq!(messages[4]["content"][0]["tool_use_id"], "toolu_2");
+        assert!(messages[4]["content"][0].get("cache_control").is_none());
+        assert_eq!(body.to_string().matches("cache_control").count(), 2);
+    }
+
+    #[test]
+    fn test_thinking_blocks_parsed() {
+        let provider = AnthropicProvider::new("sk-ant".to_string(), None);
+        let api_response: ApiResponse = serde_json::from_value(serde_json::json!({
+            "content": [
+                {"type": "thinking", "thinking": "2 + 2 = 4", "signature": "abc"},
+                {"type": "redacted_thinking", "data": "opaque"},
+                {"type": "text", "text": "4"}
+            ],
+            "stop_reason": "end_turn"
+        }))
+        .unwrap();
+        let response = provider.parse_response(api_response);
+        assert_eq!(response.content, "4");
+        assert_eq!(response.thinking.len(), 2);
+        assert_eq!(response.thinking[0].thinking, "2 + 2 = 4");
+        assert_eq!(response.thinking[1].redacted.as_deref(), Some("opaque"));
+    }
+}
@@ -12,6 +12,7 @@
 //!   but they all implement the same trait so the rest of the code doesn't care
 
 pub mod anthropic;
+pub mod ollama;
 pub mod openai_compatible;
 
 use anyhow::Result;
@@ -20,6 +21,13 @@ use tokio::sync::mpsc;
 
 use crate::types::{ChatRequest, ChatResponse, StreamChunk};
 
+/// Provider names accepted in `provider` / `api` config fields.
+pub const SUPPORTED_PROVIDERS: &[&str] = &["anthropic", "openai_compatible", "ollama"];
+
+/// Capacity of the `StreamChunk` channel; a slow consumer pauses the stream
+/// read instead of buffering deltas without bound.
+pub const STREAM_CHANNEL_CAPACITY: usize = 256;
+
 /// Trait that all LLM providers must implement.
 ///
 /// This is the core abstraction that allows swapping between
@@ -41,16 +49,424 @@ pub trait LlmProvider: Send + Sync {
     async fn chat_completion_stream(
         &self,
         request: &ChatRequest,
-        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+        chunk_tx: mpsc::Sender<StreamChunk>,
     ) -> Result<ChatResponse> {
         let response = self.chat_completion(request).await?;
         if !response.content.is_empty() {
-            let _ = chunk_tx.send(StreamChunk::TextDelta(response.content.clone()));
+            let _ = chunk_tx
+                .send(StreamChunk::TextDelta(response.content.clone()))
+                .await;
         }
-        let _ = chunk_tx.send(StreamChunk::Done);
+        let _ = chunk_tx.send(StreamChunk::Done).await;
         Ok(response)
     }
 
+    /// Check that the provider's endpoint is reachable, without spending tokens.
+    /// Default implementation assumes reachable (no cheap probe available).
+    async fn health_check(&self) -> Result<()> {
+        Ok(())
+    }
+
     /// Return the provider's display name (for logging).
+    #[allow(dead_code)]
     fn name(&self) -> &str;
 }
+
+/// Timeout for [`probe_endpoint`].
+const HEALTH_CHECK_TIMEOUT_SECS: u64 = 5;
+
+/// `HEAD` the API base URL. Any HTTP response (even 401/404) means the host is
+/// reachable; only connection failures and timeouts are errors.
+pub async fn probe_endpoint(client: &reqwest::Client, url: &str) -> Result<()> {
+    client
+        .head(url)
+        .timeout(std::time::Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS))
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("{} unreachable: {}", url, e))
+}
+
+/// Merge a model's `extra_body` into a serialized request body. Fields already
+/// in `body` win, so extras can add provider knobs but never replace core fields.
+pub fn merge_extra_body(body: &mut serde_json::Value, extra: Option<&serde_json::Value>) {
+    let (Some(body), Some(extra)) = (body.as_object_mut(), extra.and_then(|e| e.as_object()))
+    else {
+        return;
+    };
+    for (key, value) in extra {
+        body.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+/// Upper bound on the TCP/TLS connect phase, whatever the request timeout is.
+const CONNECT_TIMEOUT_SECS: u64 = 30;
+
+/// HTTP client for LLM calls. With a `timeout`, connecting and every read
+/// (waiting for headers, or the next chunk of a stream) fail once it elapses.
+pub fn http_client(timeout: Option<std::time::Duration>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder();
+    if let Some(timeout) = timeout {
+        builder = builder
+            .connect_timeout(timeout.min(std::time::Duration::from_secs(CONNECT_TIMEOUT_SECS)))
+            .read_timeout(timeout);
+    }
+    builder.build().unwrap_or_default()
+}
+
+/// Whether an LLM call failed because the request or stream timed out.
+pub fn is_timeout_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_timeout())
+    })
+}
+
+/// Bounds the text a streamed response accumulates, across content and tool-call
+/// arguments (`ChatRequest::max_stream_bytes`).
+pub struct StreamCap {
+    /// Bytes still allowed; None = unlimited.
+    remaining: Option<usize>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl StreamCap {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            remaining: limit,
+            limit: limit.unwrap_or(0),
+            truncated: false,
+        }
+    }
+
+    /// Append as much of `delta` to `buf` as the cap allows and return that part.
+    /// The first cut sends a `StreamChunk::Warning`; later deltas are dropped.
+    pub async fn append<'a>(
+        &mut self,
+        buf: &mut String,
+        delta: &'a str,
+        chunk_tx: &mpsc::Sender<StreamChunk>,
+    ) -> &'a str {
+        let kept = match self.remaining.as_mut() {
+            None => delta,
+            Some(remaining) if delta.len() <= *remaining => {
+                *remaining -= delta.len();
+                delta
+            }
+            Some(remaining) => {
+                let kept = &delta[..delta.floor_char_boundary(*remaining)];
+                *remaining = 0;
+                if !self.truncated {
+                    self.truncated = true;
+                    let _ = chunk_tx
+                        .send(StreamChunk::Warning(format!(
+                            "Response exceeded llm.max_stream_bytes ({} bytes); the rest was dropped",
+                            self.limit
+                        )))
+                        .await;
+                }
+                kept
+            }
+        };
+        buf.push_str(kept);
+        kept
+    }
+}
+
+/// HTTP statuses treated as transient (rate limit / overload / gateway errors).
+const RETRYABLE_STATUSES: &[u16] = &[408, 429, 500, 502, 503, 504, 529];
+
+/// First retry delay; doubles on each further attempt.
+const RETRY_BASE_DELAY_MS: u64 = 500;
+/// Upper bound for both the computed backoff and a server's `Retry-After`.
+const RETRY_MAX_DELAY_MS: u64 = 30_000;
+
+/// Send a request, retrying transient failures (statuses in [`RETRYABLE_STATUSES`]
+/// and connection errors) up to `max_retries` times. Waits `Retry-After` when the
+/// server sends it, else exponential backoff with jitter. The last response is
+/// returned as-is so callers still report non-success statuses. A read timeout is
+/// not retried: the caller reports `llm.request_timeout_secs` as the time waited.
+pub async fn send_with_retry(
+    request: reqwest::RequestBuilder,
+    max_retries: u32,
+) -> reqwest::Result<reqwest::Response> {
+    let mut attempt = 0;
+    loop {
+        // Bodies built with `.json()` are always cloneable
+        let Some(this_try) = request.try_clone().filter(|_| attempt < max_retries) else {
+            return request.send().await;
+        };
+        let delay = match this_try.send().await {
+            Ok(resp) if RETRYABLE_STATUSES.contains(&resp.status().as_u16()) => {
+                retry_after(resp.headers()).unwrap_or_else(|| backoff_delay(attempt))
+            }
+            Err(e) if e.is_connect() => backoff_delay(attempt),
+            other => return other,
+        };
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// `RETRY_BASE_DELAY_MS * 2^attempt` plus up to 50% jitter, capped at `RETRY_MAX_DELAY_MS`.
+fn backoff_delay(attempt: u32) -> std::time::Duration {
+    let base = RETRY_BASE_DELAY_MS.saturating_mul(1 << attempt.min(16));
+    // Sub-second clock noise is plenty to spread out clients retrying together
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    let jitter = nanos % (base / 2 + 1);
+    std::time::Duration::from_millis((base + jitter).min(RETRY_MAX_DELAY_MS))
+}
+
+/// Delay from a `Retry-After: <seconds>` header (the HTTP-date form is ignored).
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    let secs: u64 = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(std::time::Duration::from_millis(
+        secs.saturating_mul(1000).min(RETRY_MAX_DELAY_MS),
+    ))
+}
+
+/// Whether an LLM call error is transient (rate limited, overloaded, network failure),
+/// i.e. worth retrying or failing over to another model.
+pub fn is_retryable_error(err: &anyhow::Error) -> bool {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() || e.is_connect() {
+                return true;
+            }
+        }
+        let msg = cause.to_string();
+        // Providers format HTTP failures as "... API error (<status> <reason>): <body>"
+        if let Some(rest) = msg.split("API error (").nth(1) {
+            let code: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(code) = code.parse::<u16>() {
+                return RETRYABLE_STATUSES.contains(&code);
+            }
+        }
+        if msg.to_lowercase().contains("overloaded") {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    /// Serve one canned HTTP response per connection, in order; returns the base URL.
+    fn serve(responses: Vec<&'static str>) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn test_send_with_retry_retries_transient_status() {
+        let busy = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let ok = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let client = reqwest::Client::new();
+
+        let url = serve(vec![busy, busy, ok]);
+        let resp = rt
+            .block_on(send_with_retry(client.post(&url).json(&"{}"), 3))
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+
+        // Out of retries: the failing response is handed back to the caller
+        let url = serve(vec![busy, busy]);
+        let resp = rt
+            .block_on(send_with_retry(client.post(&url).json(&"{}"), 1))
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 503);
+    }
+
+    #[test]
+    fn test_send_with_retry_does_not_retry_read_timeout() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let accepted = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = accepted.clone();
+        std::thread::spawn(move || {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept() {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                // Never answer
+                held.push(stream);
+            }
+        });
+        let client = http_client(Some(std::time::Duration::from_millis(200)));
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let err = rt
+            .block_on(send_with_retry(client.post(&url).json(&"{}"), 3))
+            .unwrap_err();
+        assert!(err.is_timeout(), "{}", err);
+        assert_eq!(accepted.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_oversized_stream_truncated_at_cap_with_warning() {
+        let mut sse = String::from(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        );
+        for _ in 0..10 {
+            sse.push_str("data: {\"choices\":[{\"delta\":{\"content\":\"0123456789\"}}]}\n\n");
+        }
+        sse.push_str("data: [DONE]\n\n");
+        let url = serve(vec![sse.leak()]);
+        let provider = openai_compatible::OpenAiCompatibleProvider::new("k".into(), Some(url));
+        let request = ChatRequest {
+            model: "m".into(),
+            messages: vec![],
+            tools: vec![],
+            max_tokens: 16,
+            enable_search: None,
+            reasoning_effort: None,
+            user: None,
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: None,
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: Some(25),
+        };
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (tx, mut rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        let response = rt
+            .block_on(provider.chat_completion_stream(&request, tx))
+            .unwrap();
+        assert_eq!(response.content, "0123456789012345678901234");
+
+        let (mut streamed, mut warnings) = (String::new(), Vec::new());
+        while let Ok(chunk) = rx.try_recv() {
+            match chunk {
+                StreamChunk::TextDelta(delta) => streamed.push_str(&delta),
+                StreamChunk::Warning(msg) => warnings.push(msg),
+                StreamChunk::Done => {}
+            }
+        }
+        assert_eq!(streamed, response.content);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("max_stream_bytes (25 bytes)"));
+    }
+
+    #[test]
+    fn test_stalled_stream_times_out() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n\
+                      data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+                )
+                .unwrap();
+            // Keep the connection open without sending anything else
+            std::thread::sleep(std::time::Duration::from_secs(3));
+        });
+        let provider = openai_compatible::OpenAiCompatibleProvider::new("k".into(), Some(url))
+            .with_timeout(Some(std::time::Duration::from_millis(300)));
+        let request = ChatRequest {
+            model: "m".into(),
+            messages: vec![],
+            tools: vec![],
+            max_tokens: 16,
+            enable_search: None,
+            reasoning_effort: None,
+            user: None,
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: None,
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: None,
+        };
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (tx, _rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        let started = std::time::Instant::now();
+        let err = rt
+            .block_on(provider.chat_completion_stream(&request, tx))
+            .unwrap_err();
+        assert!(is_timeout_error(&err), "{:#}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        assert!(!is_timeout_error(&anyhow::anyhow!("API error (400): bad")));
+    }
+
+    #[test]
+    fn test_retry_delays() {
+        for attempt in 0..3 {
+            let base = RETRY_BASE_DELAY_MS << attempt;
+            let ms = backoff_delay(attempt).as_millis() as u64;
+            assert!(
+                (base..=base + base / 2).contains(&ms),
+                "{} -> {}",
+                attempt,
+                ms
+            );
+        }
+        assert_eq!(backoff_delay(30).as_millis() as u64, RETRY_MAX_DELAY_MS);
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(
+            retry_after(&headers),
+            Some(std::time::Duration::from_secs(2))
+        );
+        headers.insert(reqwest::header::RETRY_AFTER, "3600".parse().unwrap());
+        assert_eq!(
+            retry_after(&headers),
+            Some(std::time::Duration::from_millis(RETRY_MAX_DELAY_MS))
+        );
+    }
+
+    #[test]
+    fn test_is_retryable_error() {
+        let retryable = [
+            "API error (429 Too Many Requests): slow down",
+            "Anthropic API error (529 <unknown status code>): {\"type\":\"overloaded_error\"}",
+            "API error (503 Service Unavailable): ",
+        ];
+        for msg in retryable {
+            assert!(is_retryable_error(&anyhow::anyhow!(msg)), "{}", msg);
+        }
+        let wrapped = anyhow::anyhow!("API error (502 Bad Gateway): ").context("LLM call failed");
+        assert!(is_retryable_error(&wrapped));
+
+        let fatal = [
+            "API error (401 Unauthorized): bad key",
+            "API error (400 Bad Request): invalid model",
+            "Failed to parse API response",
+        ];
+        for msg in fatal {
+            assert!(!is_retryable_error(&anyhow::anyhow!(msg)), "{}", msg);
+        }
+    }
+}
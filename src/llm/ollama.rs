@@ -0,0 +1,493 @@
+//! Ollama-native LLM provider (`/api/chat`) for local models.
+//!
+//! Unlike the OpenAI `/v1` shim, the native API streams newline-delimited
+//! JSON objects, returns tool calls whole (arguments as a JSON object, no ids)
+//! and identifies tool results by tool name.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+use super::LlmProvider;
+use crate::types::{ChatRequest, ChatResponse, Role, StreamChunk, TokenUsage, ToolCall};
+
+/// Where `ollama serve` listens by default.
+pub const DEFAULT_API_BASE: &str = "http://localhost:11434";
+
+pub struct OllamaProvider {
+    /// Sent as a Bearer token when non-empty (e.g. behind an authenticating proxy).
+    api_key: String,
+    api_base: String,
+    client: reqwest::Client,
+    /// Retries for transient HTTP failures (`llm.max_retries`).
+    max_retries: u32,
+}
+
+// --- API Request Types (Ollama format) ---
+
+#[derive(Serialize)]
+struct ApiRequest {
+    model: String,
+    messages: Vec<ApiMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ApiTool>,
+    stream: bool,
+    options: ApiOptions,
+}
+
+#[derive(Serialize)]
+struct ApiOptions {
+    /// Max tokens to generate.
+    num_predict: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct ApiMessage {
+    role: &'static str,
+    content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ApiToolCall>,
+    /// Name of the tool whose result this is (role "tool").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ApiTool {
+    r#type: &'static str,
+    function: ApiFunction,
+}
+
+#[derive(Serialize)]
+struct ApiFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ApiToolCall {
+    function: ApiToolCallFunction,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ApiToolCallFunction {
+    name: String,
+    /// A JSON object, not a string as in the OpenAI format.
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+// --- API Response Types (one per line when streaming) ---
+
+#[derive(Deserialize, Debug)]
+struct ApiResponse {
+    #[serde(default)]
+    message: Option<ApiResponseMessage>,
+    #[serde(default)]
+    done: bool,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiResponseMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<ApiToolCall>,
+}
+
+// --- Implementation ---
+
+impl OllamaProvider {
+    pub fn new(api_key: String, api_base: Option<String>) -> Self {
+        Self {
+            api_key,
+            api_base: api_base.unwrap_or_else(|| DEFAULT_API_BASE.to_string()),
+            client: super::http_client(None),
+            max_retries: 0,
+        }
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Fail calls that get no response, or whose stream stalls, for `timeout`
+    /// (`llm.request_timeout_secs`).
+    pub fn with_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+        self.client = super::http_client(timeout);
+        self
+    }
+
+    fn endpoint_url(&self) -> String {
+        format!("{}/api/chat", self.api_base.trim_end_matches('/'))
+    }
+
+    fn post(&self, url: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json");
+        if self.api_key.is_empty() {
+            builder
+        } else {
+            builder.header("Authorization", format!("Bearer {}", self.api_key))
+        }
+    }
+
+    fn build_api_request(&self, request: &ChatRequest, stream: bool) -> ApiRequest {
+        // Tool results carry our call id; Ollama wants the tool's name instead
+        let mut tool_names: HashMap<&str, &str> = HashMap::new();
+        let mut messages = Vec::new();
+
+        for msg in &request.messages {
+            let api_message = match msg.role {
+                Role::System | Role::User => ApiMessage {
+                    role: if msg.role == Role::System {
+                        "system"
+                    } else {
+                        "user"
+                    },
+                    content: msg.content.clone(),
+                    tool_calls: vec![],
+                    tool_name: None,
+                },
+                Role::Assistant => {
+                    for tc in &msg.tool_calls {
+                        tool_names.insert(&tc.id, &tc.name);
+                    }
+                    ApiMessage {
+                        role: "assistant",
+                        content: msg.content.clone(),
+                        tool_calls: msg
+                            .tool_calls
+                            .iter()
+                            .map(|tc| ApiToolCall {
+                                function: ApiToolCallFunction {
+                                    name: tc.name.clone(),
+                                    arguments: serde_json::from_str(&tc.arguments)
+                                        .unwrap_or_else(|_| serde_json::json!({})),
+                                },
+                            })
+                            .collect(),
+                        tool_name: None,
+                    }
+                }
+                Role::Tool => ApiMessage {
+                    role: "tool",
+                    content: msg.content.clone(),
+                    tool_calls: vec![],
+                    tool_name: msg
+                        .tool_call_id
+                        .as_deref()
+                        .and_then(|id| tool_names.get(id))
+                        .map(|name| name.to_string()),
+                },
+            };
+            messages.push(api_message);
+        }
+
+        let tools = request
+            .tools
+            .iter()
+            .map(|t| ApiTool {
+                r#type: "function",
+                function: ApiFunction {
+                    name: t.name.clone(),
+                    description: t.description.clone(),
+                    parameters: t.input_schema.clone(),
+                },
+            })
+            .collect();
+
+        ApiRequest {
+            model: request.model.clone(),
+            messages,
+            tools,
+            stream,
+            options: ApiOptions {
+                num_predict: request.max_tokens,
+                temperature: request.temperature,
+                top_p: request.top_p,
+            },
+        }
+    }
+
+    /// Final JSON body: the standard request, then `extra_body`.
+    fn request_body(&self, request: &ChatRequest, stream: bool) -> Result<serde_json::Value> {
+        let mut body = serde_json::to_value(self.build_api_request(request, stream))
+            .context("Failed to serialize request")?;
+        super::merge_extra_body(&mut body, request.extra_body.as_ref());
+        Ok(body)
+    }
+
+    async fn send(&self, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let url = self.endpoint_url();
+        let request = self.post(&url).json(body);
+        let response = super::send_with_retry(request, self.max_retries)
+            .await
+            .with_context(|| format!("Failed to send request to {}", url))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_body = response.text().await.unwrap_or_default();
+            anyhow::bail!("API error ({}): {}", status, error_body);
+        }
+        Ok(response)
+    }
+}
+
+/// Our tool call for one of Ollama's; it has no id, so one is made from the position.
+fn to_tool_call(tc: ApiToolCall, index: usize) -> ToolCall {
+    let arguments = match tc.function.arguments {
+        serde_json::Value::Null => "{}".to_string(),
+        // Some models emit the arguments as an already-encoded string
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    };
+    ToolCall {
+        id: format!("call_{}", index),
+        name: tc.function.name,
+        arguments,
+    }
+}
+
+fn usage_of(response: &ApiResponse) -> Option<TokenUsage> {
+    (response.prompt_eval_count.is_some() || response.eval_count.is_some()).then(|| TokenUsage {
+        input_tokens: response.prompt_eval_count.unwrap_or(0),
+        output_tokens: response.eval_count.unwrap_or(0),
+    })
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    async fn chat_completion(&self, request: &ChatRequest) -> Result<ChatResponse> {
+        let body = self.request_body(request, false)?;
+        let api_response: ApiResponse = self
+            .send(&body)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse API response")?;
+        if let Some(error) = api_response.error {
+            anyhow::bail!("Ollama error: {}", error);
+        }
+        let usage = usage_of(&api_response);
+        let message = api_response
+            .message
+            .context("Empty response from Ollama: no message returned")?;
+        Ok(ChatResponse {
+            content: message.content,
+            tool_calls: message
+                .tool_calls
+                .into_iter()
+                .enumerate()
+                .map(|(i, tc)| to_tool_call(tc, i))
+                .collect(),
+            usage,
+            thinking: vec![],
+        })
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: &ChatRequest,
+        chunk_tx: mpsc::Sender<StreamChunk>,
+    ) -> Result<ChatResponse> {
+        let body = self.request_body(request, true)?;
+        let mut cap = super::StreamCap::new(request.max_stream_bytes);
+        let mut byte_stream = self.send(&body).await?.bytes_stream();
+        let mut buffer = String::new();
+        let mut content = String::new();
+        let mut tool_calls: Vec<ToolCall> = Vec::new();
+        let mut usage: Option<TokenUsage> = None;
+
+        'read: while let Some(chunk_result) = byte_stream.next().await {
+            let chunk_bytes = chunk_result.context("Stream read error")?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk_bytes));
+
+            while let Some(newline_pos) = buffer.find('\n') {
+                let line = buffer[..newline_pos].trim().to_string();
+                buffer = buffer[newline_pos + 1..].to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                let Ok(chunk) = serde_json::from_str::<ApiResponse>(&line) else {
+                    continue;
+                };
+                if let Some(error) = chunk.error {
+                    anyhow::bail!("Ollama error: {}", error);
+                }
+                if let Some(message) = &chunk.message {
+                    let kept = cap.append(&mut content, &message.content, &chunk_tx).await;
+                    if !kept.is_empty() {
+                        let _ = chunk_tx
+                            .send(StreamChunk::TextDelta(kept.to_string()))
+                            .await;
+                    }
+                }
+                if chunk.done {
+                    usage = usage_of(&chunk);
+                }
+                for tc in chunk.message.map(|m| m.tool_calls).unwrap_or_default() {
+                    let mut call = to_tool_call(tc, tool_calls.len());
+                    let mut arguments = String::new();
+                    cap.append(&mut arguments, &call.arguments, &chunk_tx).await;
+                    call.arguments = arguments;
+                    tool_calls.push(call);
+                }
+                if chunk.done {
+                    break 'read;
+                }
+            }
+        }
+
+        let _ = chunk_tx.send(StreamChunk::Done).await;
+        Ok(ChatResponse {
+            content,
+            tool_calls,
+            usage,
+            thinking: vec![],
+        })
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        super::probe_endpoint(&self.client, &self.api_base).await
+    }
+
+    fn name(&self) -> &str {
+        "Ollama"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Message, ToolDefinition};
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    fn request(messages: Vec<Message>) -> ChatRequest {
+        ChatRequest {
+            model: "llama3.1".to_string(),
+            messages,
+            tools: vec![ToolDefinition {
+                name: "read_file".to_string(),
+                description: "Read a file".to_string(),
+                input_schema: serde_json::json!({"type": "object"}),
+            }],
+            max_tokens: 64,
+            enable_search: None,
+            reasoning_effort: None,
+            user: None,
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: Some(0.2),
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_request_maps_tool_calls_and_results() {
+        let mut assistant = Message::assistant("");
+        assistant.tool_calls = vec![ToolCall {
+            id: "call_0".to_string(),
+            name: "read_file".to_string(),
+            arguments: r#"{"path":"a.txt"}"#.to_string(),
+        }];
+        let req = request(vec![
+            Message::system("be brief"),
+            Message::user("read a.txt"),
+            assistant,
+            Message::tool_result("call_0", "hello"),
+        ]);
+        let provider = OllamaProvider::new(String::new(), None);
+        assert_eq!(provider.endpoint_url(), "http://localhost:11434/api/chat");
+        assert!(provider
+            .post(&provider.endpoint_url())
+            .build()
+            .unwrap()
+            .headers()
+            .get("authorization")
+            .is_none());
+
+        let body = provider.request_body(&req, true).unwrap();
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["options"]["num_predict"], 64);
+        assert_eq!(body["options"]["temperature"], 0.2);
+        assert_eq!(body["tools"][0]["function"]["name"], "read_file");
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(
+            messages[2]["tool_calls"][0]["function"]["arguments"]["path"],
+            "a.txt"
+        );
+        assert_eq!(messages[3]["role"], "tool");
+        assert_eq!(messages[3]["tool_name"], "read_file");
+    }
+
+    #[test]
+    fn test_stream_parses_text_and_tool_calls() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        rt().block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 65536];
+                let _ = socket.read(&mut buf).await;
+                let body = concat!(
+                    "{\"message\":{\"role\":\"assistant\",\"content\":\"Let me \"},\"done\":false}\n",
+                    "{\"message\":{\"role\":\"assistant\",\"content\":\"look.\"},\"done\":false}\n",
+                    "{\"message\":{\"role\":\"assistant\",\"content\":\"\",\"tool_calls\":[",
+                    "{\"function\":{\"name\":\"read_file\",\"arguments\":{\"path\":\"a.txt\"}}}]},\"done\":false}\n",
+                    "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,",
+                    "\"prompt_eval_count\":12,\"eval_count\":5}\n",
+                );
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+
+            let provider = OllamaProvider::new(String::new(), Some(format!("http://{}", addr)));
+            let (tx, mut rx) = mpsc::channel(super::super::STREAM_CHANNEL_CAPACITY);
+            let response = provider
+                .chat_completion_stream(&request(vec![Message::user("hi")]), tx)
+                .await
+                .unwrap();
+            assert_eq!(response.content, "Let me look.");
+            assert_eq!(response.tool_calls.len(), 1);
+            assert_eq!(response.tool_calls[0].id, "call_0");
+            assert_eq!(response.tool_calls[0].name, "read_file");
+            assert_eq!(response.tool_calls[0].arguments, r#"{"path":"a.txt"}"#);
+            let usage = response.usage.unwrap();
+            assert_eq!((usage.input_tokens, usage.output_tokens), (12, 5));
+
+            let mut deltas = String::new();
+            while let Some(chunk) = rx.recv().await {
+                if let StreamChunk::TextDelta(t) = chunk {
+                    deltas.push_str(&t);
+                }
+            }
+            assert_eq!(deltas, "Let me look.");
+        });
+    }
+}
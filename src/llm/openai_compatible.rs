@@ -7,26 +7,70 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
 use super::LlmProvider;
-use crate::types::{ChatRequest, ChatResponse, Role, StreamChunk, TokenUsage, ToolCall};
+use crate::types::{
+    ChatRequest, ChatResponse, Role, StreamChunk, TokenUsage, ToolCall, ToolChoice,
+};
 
 pub struct OpenAiCompatibleProvider {
     api_key: String,
     api_base: String,
     client: reqwest::Client,
+    /// URL template with `{base}`/`{model}` placeholders (Azure-style deployments).
+    url_template: Option<String>,
+    auth_style: AuthStyle,
+    /// Retries for transient HTTP failures (`llm.max_retries`).
+    max_retries: u32,
+}
+
+/// How the API key is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AuthStyle {
+    /// `Authorization: Bearer <key>` (OpenAI and most gateways).
+    #[default]
+    Bearer,
+    /// `api-key: <key>` (Azure OpenAI).
+    ApiKey,
+}
+
+impl AuthStyle {
+    /// Parse the `auth_header` config value ("bearer" / "api-key"); None means Bearer.
+    pub fn from_config(value: Option<&str>) -> Result<Self> {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("bearer") => Ok(Self::Bearer),
+            Some("api-key") | Some("api_key") => Ok(Self::ApiKey),
+            Some(other) => anyhow::bail!(
+                "Unknown auth_header '{}'. Supported: bearer, api-key",
+                other
+            ),
+        }
+    }
 }
 
 // --- API Request Types (OpenAI format) ---
 
 #[derive(Serialize)]
 struct ApiRequest {
-    model: String,
+    /// Omitted when the model is part of the URL (see `url_template`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
     messages: Vec<ApiMessage>,
     max_tokens: u32,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tools: Vec<ApiTool>,
+    /// "auto" / "none"; only sent together with tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'static str>,
     /// DashScope/百炼 联网搜索: enable_search=true. See https://help.aliyun.com/zh/model-studio/web-search
     #[serde(skip_serializing_if = "Option::is_none")]
     enable_search: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
 }
 
 #[derive(Serialize)]
@@ -137,7 +181,62 @@ impl OpenAiCompatibleProvider {
         Self {
             api_key,
             api_base: api_base.unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
-            client: reqwest::Client::new(),
+            client: super::http_client(None),
+            url_template: None,
+            auth_style: AuthStyle::Bearer,
+            max_retries: 0,
+        }
+    }
+
+    /// Build request URLs from a template instead of `{api_base}/chat/completions`.
+    pub fn with_url_template(mut self, template: Option<String>) -> Self {
+        self.url_template = template.filter(|t| !t.trim().is_empty());
+        self
+    }
+
+    pub fn with_auth_style(mut self, auth_style: AuthStyle) -> Self {
+        self.auth_style = auth_style;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Fail calls that get no response, or whose stream stalls, for `timeout`
+    /// (`llm.request_timeout_secs`).
+    pub fn with_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+        self.client = super::http_client(timeout);
+        self
+    }
+
+    /// Whether the model is carried in the URL, so the body `model` field is dropped.
+    fn model_in_url(&self) -> bool {
+        self.url_template
+            .as_deref()
+            .is_some_and(|t| t.contains("{model}"))
+    }
+
+    fn endpoint_url(&self, model: &str) -> String {
+        let base = self.api_base.trim_end_matches('/');
+        match &self.url_template {
+            Some(template) => template.replace("{base}", base).replace("{model}", model),
+            None => format!("{}/chat/completions", base),
+        }
+    }
+
+    /// Start a POST to `url` with the configured auth header.
+    fn post(&self, url: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json");
+        match self.auth_style {
+            AuthStyle::Bearer => {
+                builder.header("Authorization", format!("Bearer {}", self.api_key))
+            }
+            AuthStyle::ApiKey => builder.header("api-key", &self.api_key),
         }
     }
 
@@ -216,12 +315,33 @@ impl OpenAiCompatibleProvider {
             .collect();
 
         ApiRequest {
-            model: request.model.clone(),
+            model: (!self.model_in_url()).then(|| request.model.clone()),
             messages: api_messages,
             max_tokens: request.max_tokens,
+            tool_choice: request.tool_choice.filter(|_| !tools.is_empty()).map(
+                |choice| match choice {
+                    ToolChoice::None => "none",
+                },
+            ),
             tools,
             enable_search: request.enable_search,
+            reasoning_effort: request.reasoning_effort.clone(),
+            user: request.user.clone(),
+            temperature: request.temperature,
+            top_p: request.top_p,
+        }
+    }
+
+    /// Final JSON body: the standard request, stream flags, then `extra_body`.
+    fn request_body(&self, request: &ChatRequest, stream: bool) -> Result<serde_json::Value> {
+        let mut body = serde_json::to_value(self.build_api_request(request))
+            .context("Failed to serialize request")?;
+        if stream {
+            body["stream"] = serde_json::json!(true);
+            body["stream_options"] = serde_json::json!({"include_usage": true});
         }
+        super::merge_extra_body(&mut body, request.extra_body.as_ref());
+        Ok(body)
     }
 
     fn parse_response(&self, api_response: ApiResponse) -> Result<ChatResponse> {
@@ -253,6 +373,7 @@ impl OpenAiCompatibleProvider {
             content,
             tool_calls,
             usage,
+            thinking: vec![],
         })
     }
 }
@@ -260,16 +381,11 @@ impl OpenAiCompatibleProvider {
 #[async_trait]
 impl LlmProvider for OpenAiCompatibleProvider {
     async fn chat_completion(&self, request: &ChatRequest) -> Result<ChatResponse> {
-        let api_request = self.build_api_request(request);
-        let url = format!("{}/chat/completions", self.api_base.trim_end_matches('/'));
+        let body = self.request_body(request, false)?;
+        let url = self.endpoint_url(&request.model);
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&api_request)
-            .send()
+        let request = self.post(&url).json(&body);
+        let response = super::send_with_retry(request, self.max_retries)
             .await
             .with_context(|| format!("Failed to send request to {}", url))?;
 
@@ -290,22 +406,14 @@ impl LlmProvider for OpenAiCompatibleProvider {
     async fn chat_completion_stream(
         &self,
         request: &ChatRequest,
-        chunk_tx: mpsc::UnboundedSender<StreamChunk>,
+        chunk_tx: mpsc::Sender<StreamChunk>,
     ) -> Result<ChatResponse> {
-        let api_request = self.build_api_request(request);
-        let url = format!("{}/chat/completions", self.api_base.trim_end_matches('/'));
-
-        let mut body = serde_json::to_value(&api_request).context("Failed to serialize request")?;
-        body["stream"] = serde_json::json!(true);
-        body["stream_options"] = serde_json::json!({"include_usage": true});
+        let body = self.request_body(request, true)?;
+        let url = self.endpoint_url(&request.model);
+        let mut cap = super::StreamCap::new(request.max_stream_bytes);
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
+        let request = self.post(&url).json(&body);
+        let response = super::send_with_retry(request, self.max_retries)
             .await
             .with_context(|| format!("Failed to send streaming request to {}", url))?;
 
@@ -339,7 +447,7 @@ impl LlmProvider for OpenAiCompatibleProvider {
                 };
 
                 if data.trim() == "[DONE]" {
-                    let _ = chunk_tx.send(StreamChunk::Done);
+                    let _ = chunk_tx.send(StreamChunk::Done).await;
                     let final_tool_calls = tool_calls
                         .into_iter()
                         .map(|tc| ToolCall {
@@ -352,15 +460,18 @@ impl LlmProvider for OpenAiCompatibleProvider {
                         content,
                         tool_calls: final_tool_calls,
                         usage,
+                        thinking: vec![],
                     });
                 }
 
                 if let Ok(chunk_resp) = serde_json::from_str::<StreamResponseChunk>(data) {
                     if let Some(choice) = chunk_resp.choices.first() {
                         if let Some(ref text) = choice.delta.content {
-                            if !text.is_empty() {
-                                content.push_str(text);
-                                let _ = chunk_tx.send(StreamChunk::TextDelta(text.clone()));
+                            let kept = cap.append(&mut content, text, &chunk_tx).await;
+                            if !kept.is_empty() {
+                                let _ = chunk_tx
+                                    .send(StreamChunk::TextDelta(kept.to_string()))
+                                    .await;
                             }
                         }
                         if let Some(ref tcs) = choice.delta.tool_calls {
@@ -377,7 +488,7 @@ impl LlmProvider for OpenAiCompatibleProvider {
                                         acc.name.push_str(name);
                                     }
                                     if let Some(ref args) = func.arguments {
-                                        acc.arguments.push_str(args);
+                                        cap.append(&mut acc.arguments, args, &chunk_tx).await;
                                     }
                                 }
                             }
@@ -393,7 +504,7 @@ impl LlmProvider for OpenAiCompatibleProvider {
             }
         }
 
-        let _ = chunk_tx.send(StreamChunk::Done);
+        let _ = chunk_tx.send(StreamChunk::Done).await;
         let final_tool_calls = tool_calls
             .into_iter()
             .map(|tc| ToolCall {
@@ -406,10 +517,155 @@ impl LlmProvider for OpenAiCompatibleProvider {
             content,
             tool_calls: final_tool_calls,
             usage,
+            thinking: vec![],
         })
     }
 
+    async fn health_check(&self) -> Result<()> {
+        super::probe_endpoint(&self.client, &self.api_base).await
+    }
+
     fn name(&self) -> &str {
         "OpenAI-Compatible"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str) -> ChatRequest {
+        ChatRequest {
+            model: model.to_string(),
+            messages: vec![],
+            tools: vec![],
+            max_tokens: 16,
+            enable_search: None,
+            reasoning_effort: None,
+            user: None,
+            extra_body: None,
+            thinking_budget: None,
+            tool_choice: None,
+            temperature: None,
+            top_p: None,
+            prompt_caching: false,
+            max_stream_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_default_url_and_bearer_auth() {
+        let provider =
+            OpenAiCompatibleProvider::new("sk-test".to_string(), Some("https://x.test/v1/".into()));
+        assert_eq!(
+            provider.endpoint_url("gpt-4o"),
+            "https://x.test/v1/chat/completions"
+        );
+        let req = provider.post("https://x.test/v1").build().unwrap();
+        assert_eq!(req.headers()["authorization"], "Bearer sk-test");
+        assert!(req.headers().get("api-key").is_none());
+
+        let body = serde_json::to_value(provider.build_api_request(&request("gpt-4o"))).unwrap();
+        assert_eq!(body["model"], "gpt-4o");
+    }
+
+    #[test]
+    fn test_url_template_and_api_key_auth() {
+        let provider = OpenAiCompatibleProvider::new(
+            "az-key".to_string(),
+            Some("https://res.openai.azure.com/openai".into()),
+        )
+        .with_url_template(Some(
+            "{base}/deployments/{model}/chat/completions?api-version=2024-06-01".into(),
+        ))
+        .with_auth_style(AuthStyle::from_config(Some("api-key")).unwrap());
+
+        assert_eq!(
+            provider.endpoint_url("gpt4o-prod"),
+            "https://res.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-06-01"
+        );
+        let req = provider.post(&provider.endpoint_url("m")).build().unwrap();
+        assert_eq!(req.headers()["api-key"], "az-key");
+        assert!(req.headers().get("authorization").is_none());
+
+        // Model is in the URL, so it's dropped from the body
+        let body =
+            serde_json::to_value(provider.build_api_request(&request("gpt4o-prod"))).unwrap();
+        assert!(body.get("model").is_none());
+    }
+
+    #[test]
+    fn test_reasoning_effort_serialized_only_when_set() {
+        let provider = OpenAiCompatibleProvider::new("sk-test".to_string(), None);
+        let body = serde_json::to_value(provider.build_api_request(&request("o3-mini"))).unwrap();
+        assert!(body.get("reasoning_effort").is_none());
+
+        let mut req = request("o3-mini");
+        req.reasoning_effort = Some("high".to_string());
+        let body = serde_json::to_value(provider.build_api_request(&req)).unwrap();
+        assert_eq!(body["reasoning_effort"], "high");
+    }
+
+    #[test]
+    fn test_user_field_serialized_only_when_set() {
+        let provider = OpenAiCompatibleProvider::new("sk-test".to_string(), None);
+        let body = serde_json::to_value(provider.build_api_request(&request("gpt-4o"))).unwrap();
+        assert!(body.get("user").is_none());
+
+        let mut req = request("gpt-4o");
+        req.user = Some("team-alpha".to_string());
+        let body = serde_json::to_value(provider.build_api_request(&req)).unwrap();
+        assert_eq!(body["user"], "team-alpha");
+    }
+
+    #[test]
+    fn test_sampling_params_serialized_only_when_set() {
+        let provider = OpenAiCompatibleProvider::new("sk-test".to_string(), None);
+        let body = serde_json::to_value(provider.build_api_request(&request("gpt-4o"))).unwrap();
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("top_p").is_none());
+
+        let mut req = request("gpt-4o");
+        req.temperature = Some(0.0);
+        req.top_p = Some(0.9);
+        let body = serde_json::to_value(provider.build_api_request(&req)).unwrap();
+        assert_eq!(body["temperature"], 0.0);
+        assert_eq!(body["top_p"], 0.9);
+    }
+
+    #[test]
+    fn test_extra_body_merged_without_clobbering() {
+        let provider = OpenAiCompatibleProvider::new("sk-test".to_string(), None);
+        let mut req = request("qwen3-plus");
+        req.extra_body = Some(serde_json::json!({
+            "top_k": 20,
+            "enable_thinking": false,
+            "model": "other-model",
+            "stream": false,
+        }));
+        let body = provider.request_body(&req, true).unwrap();
+        assert_eq!(body["top_k"], 20);
+        assert_eq!(body["enable_thinking"], false);
+        assert_eq!(body["model"], "qwen3-plus");
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["max_tokens"], 16);
+
+        let body = provider.request_body(&request("gpt-4o"), false).unwrap();
+        assert!(body.get("top_k").is_none());
+        assert!(body.get("stream").is_none());
+    }
+
+    #[test]
+    fn test_auth_style_from_config() {
+        assert_eq!(AuthStyle::from_config(None).unwrap(), AuthStyle::Bearer);
+        assert_eq!(
+            AuthStyle::from_config(Some("Bearer")).unwrap(),
+            AuthStyle::Bearer
+        );
+        assert_eq!(
+            AuthStyle::from_config(Some("api-key")).unwrap(),
+            AuthStyle::ApiKey
+        );
+        assert!(AuthStyle::from_config(Some("basic")).is_err());
+    }
+}
@@ -1,8 +1,12 @@
 mod agent;
+mod build_info;
 mod config;
+mod input_history;
 mod llm;
+mod paths;
 mod rules;
 mod session;
+mod snippets;
 mod tools;
 mod transport;
 mod trusted_workspaces;
@@ -17,26 +21,44 @@ use transport::{resolve_mode, Args};
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    let config_path = AppConfig::config_path()?;
+    if args.version {
+        println!("{}", build_info::long_version());
+        return Ok(());
+    }
+    if let Some(dir) = &args.data_dir {
+        paths::set_data_dir_override(dir.clone());
+    }
+    let mode = resolve_mode(&args);
+    let config_path = match &args.config {
+        Some(path) => {
+            paths::set_config_path_override(path.clone());
+            path.clone()
+        }
+        None => AppConfig::config_path()?,
+    };
+    if let transport::ResolvedMode::Init(init_args) = &mode {
+        return transport::init::run_init(&config_path, init_args);
+    }
     if !config_path.exists() {
-        let path = AppConfig::save_default()?;
-        eprintln!("[Config] Created default config: {}", path.display());
+        AppConfig::default().save_to(&config_path)?;
+        eprintln!("[Config] Created default config: {}", config_path.display());
         eprintln!("[Config] Edit it to set your api_key, model, etc.");
     }
 
-    let config = AppConfig::load()?;
-    let mode = resolve_mode(&args);
+    let config = AppConfig::load_from(Some(&config_path))?;
 
     match mode {
         transport::ResolvedMode::Tui => {
             let project_root = std::env::current_dir().unwrap_or_default();
             let agent = agent::Agent::create(&config, &project_root)?;
-            let tui = ui::ratatui_ui::RatatuiUi::new(config.clone(), project_root);
+            let tui = ui::ratatui_ui::RatatuiUi::new(config.clone(), project_root)
+                .with_config_path(config_path);
             let _exit = tui.run(agent).await?;
         }
         transport::ResolvedMode::Cli(cli_args) => {
             transport::cli::run_cli(cli_args, config).await?;
         }
+        transport::ResolvedMode::Init(_) => unreachable!("handled before loading config"),
         #[cfg(feature = "telegram")]
         transport::ResolvedMode::Telegram(tg_args) => {
             transport::telegram::run_telegram(tg_args, config).await?;
@@ -0,0 +1,277 @@
+//! Centralized resolution of miniclaw's on-disk state locations.
+//!
+//! All state (config, sessions, logs, usage, trusted workspaces, ...) lives
+//! under a single data directory. Resolution order:
+//! 1. `--data-dir <path>` (set once at startup via [`set_data_dir_override`])
+//! 2. `$XDG_DATA_HOME/miniclaw` when `XDG_DATA_HOME` is set, unless only an
+//!    existing `~/.miniclaw` is found (kept so setting XDG does not hide old state)
+//! 3. `~/.miniclaw` (legacy default)
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set the data directory override (from `--data-dir`). Only the first call takes effect.
+pub fn set_data_dir_override(dir: PathBuf) {
+    let _ = DATA_DIR_OVERRIDE.set(dir);
+}
+
+/// Record the config file chosen with `--config` so tools can't modify it either.
+/// Only the first call takes effect.
+pub fn set_config_path_override(path: PathBuf) {
+    let _ = CONFIG_PATH_OVERRIDE.set(path);
+}
+
+/// Returns the data directory override, if one was set.
+///
+/// Test builds always have one: a per-process temp dir, so no test touches the
+/// real data dir whatever order the tests run in.
+pub fn data_dir_override() -> Option<&'static Path> {
+    #[cfg(test)]
+    let dir = Some(DATA_DIR_OVERRIDE.get_or_init(|| tempfile::tempdir().unwrap().keep()));
+    #[cfg(not(test))]
+    let dir = DATA_DIR_OVERRIDE.get();
+    dir.map(|p| p.as_path())
+}
+
+/// Resolve the data directory from an explicit override, `XDG_DATA_HOME` and the home dir.
+fn resolve_data_dir(
+    override_dir: Option<&Path>,
+    xdg_data_home: Option<PathBuf>,
+    home: Option<PathBuf>,
+) -> Result<PathBuf> {
+    if let Some(dir) = override_dir {
+        return Ok(dir.to_path_buf());
+    }
+    let legacy = home.map(|h| h.join(".miniclaw"));
+    if let Some(xdg) = xdg_data_home.filter(|p| p.is_absolute()) {
+        let xdg_dir = xdg.join("miniclaw");
+        match legacy {
+            Some(legacy) if legacy.is_dir() && !xdg_dir.exists() => return Ok(legacy),
+            _ => return Ok(xdg_dir),
+        }
+    }
+    legacy.context("Could not determine home directory")
+}
+
+/// Root directory for all miniclaw state.
+pub fn data_dir() -> Result<PathBuf> {
+    resolve_data_dir(
+        data_dir_override(),
+        std::env::var_os("XDG_DATA_HOME").map(PathBuf::from),
+        dirs::home_dir(),
+    )
+}
+
+/// Path to `config.toml`.
+pub fn config_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("config.toml"))
+}
+
+/// Directory holding saved sessions (`<id>.json`).
+pub fn sessions_dir() -> Result<PathBuf> {
+    Ok(data_dir()?.join("sessions"))
+}
+
+/// Directory for log files.
+#[allow(dead_code)]
+pub fn logs_dir() -> Result<PathBuf> {
+    Ok(data_dir()?.join("logs"))
+}
+
+/// Path to `usage.json` (first-use date for the stats panel).
+pub fn usage_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("usage.json"))
+}
+
+/// Path to `trusted_workspaces.json`.
+pub fn trusted_workspaces_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("trusted_workspaces.json"))
+}
+
+/// Path to `telegram_state.json`.
+#[allow(dead_code)]
+pub fn telegram_state_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("telegram_state.json"))
+}
+
+/// Default user-level instructions file, injected before project rules.
+pub fn instructions_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("instructions.md"))
+}
+
+/// Path to `input_history` (submitted user inputs, one JSON string per line).
+pub fn input_history_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("input_history"))
+}
+
+/// Directory of `/snippet` files.
+pub fn snippets_dir() -> Result<PathBuf> {
+    Ok(data_dir()?.join("snippets"))
+}
+
+/// Directory of oversized tool results (`tools.spill_large_results`).
+pub fn tool_output_dir() -> Result<PathBuf> {
+    Ok(data_dir()?.join("tool-output"))
+}
+
+/// Expand a leading `~/` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Path to the Telegram daemon PID file.
+#[allow(dead_code)]
+pub fn telegram_pid_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("miniclaw-telegram.pid"))
+}
+
+/// miniclaw's own state that tools must not modify: config (default, `--config`
+/// and the current project's `.miniclaw/config.toml`), sessions, usage, trusted
+/// workspaces and Telegram state.
+pub fn protected_paths() -> Result<Vec<PathBuf>> {
+    let mut paths = vec![
+        config_path()?,
+        sessions_dir()?,
+        usage_path()?,
+        trusted_workspaces_path()?,
+        telegram_state_path()?,
+        telegram_pid_path()?,
+    ];
+    paths.extend(CONFIG_PATH_OVERRIDE.get().cloned());
+    // A project overlay written by the model would apply on the next start
+    if let Ok(cwd) = std::env::current_dir() {
+        paths.push(cwd.join(".miniclaw").join("config.toml"));
+    }
+    Ok(paths)
+}
+
+/// Whether `path` is (or is inside) one of the [`protected_paths`].
+/// Relative paths are resolved against the current directory.
+pub fn is_protected(path: &Path) -> bool {
+    let Ok(protected) = protected_paths() else {
+        return false;
+    };
+    let target = normalize(path);
+    protected.iter().any(|p| target.starts_with(normalize(p)))
+}
+
+/// Absolute, symlink-resolved form of `path`, even if it does not exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    let abs = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|d| d.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    if let Ok(canonical) = abs.canonicalize() {
+        return canonical;
+    }
+    // Resolve the parent so "<dir>/../config.toml" and symlinked dirs still match
+    let mut lexical = PathBuf::new();
+    for component in abs.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                lexical.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => lexical.push(other),
+        }
+    }
+    match (lexical.parent(), lexical.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|p| p.join(name))
+            .unwrap_or(lexical),
+        _ => lexical,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_wins() {
+        let dir = resolve_data_dir(
+            Some(Path::new("/srv/miniclaw")),
+            Some(PathBuf::from("/xdg")),
+            Some(PathBuf::from("/home/u")),
+        )
+        .unwrap();
+        assert_eq!(dir, PathBuf::from("/srv/miniclaw"));
+    }
+
+    #[test]
+    fn test_xdg_data_home() {
+        let dir = resolve_data_dir(
+            None,
+            Some(PathBuf::from("/xdg")),
+            Some(PathBuf::from("/home/u")),
+        )
+        .unwrap();
+        assert_eq!(dir, PathBuf::from("/xdg/miniclaw"));
+
+        // Relative XDG_DATA_HOME is invalid per spec and ignored
+        let dir = resolve_data_dir(
+            None,
+            Some(PathBuf::from("xdg")),
+            Some(PathBuf::from("/home/u")),
+        )
+        .unwrap();
+        assert_eq!(dir, PathBuf::from("/home/u/.miniclaw"));
+    }
+
+    #[test]
+    fn test_existing_legacy_dir_beats_new_xdg_dir() {
+        let home = tempfile::tempdir().unwrap();
+        let xdg = tempfile::tempdir().unwrap();
+        std::fs::create_dir(home.path().join(".miniclaw")).unwrap();
+        let resolve = || {
+            resolve_data_dir(
+                None,
+                Some(xdg.path().to_path_buf()),
+                Some(home.path().to_path_buf()),
+            )
+            .unwrap()
+        };
+        assert_eq!(resolve(), home.path().join(".miniclaw"));
+
+        // Once the XDG dir exists it is used
+        std::fs::create_dir(xdg.path().join("miniclaw")).unwrap();
+        assert_eq!(resolve(), xdg.path().join("miniclaw"));
+    }
+
+    #[test]
+    fn test_home_fallback() {
+        let dir = resolve_data_dir(None, None, Some(PathBuf::from("/home/u"))).unwrap();
+        assert_eq!(dir, PathBuf::from("/home/u/.miniclaw"));
+        assert!(resolve_data_dir(None, None, None).is_err());
+    }
+
+    #[test]
+    fn test_derived_paths_stay_under_test_data_dir() {
+        let root = data_dir().unwrap();
+        assert_eq!(Some(root.as_path()), data_dir_override());
+        assert!(root.starts_with(std::env::temp_dir()), "{}", root.display());
+        for path in [
+            config_path().unwrap(),
+            sessions_dir().unwrap(),
+            logs_dir().unwrap(),
+            usage_path().unwrap(),
+            trusted_workspaces_path().unwrap(),
+            telegram_state_path().unwrap(),
+            telegram_pid_path().unwrap(),
+            instructions_path().unwrap(),
+        ] {
+            assert!(path.starts_with(&root), "{} not under root", path.display());
+        }
+    }
+}
@@ -7,7 +7,14 @@
 //! Discovered content is concatenated (ancestors first, then project root)
 //! and returned as a string for injection into the system prompt.
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::AgentConfig;
+
+/// Default per-file header; `{path}` is replaced with the rule file's path.
+pub const DEFAULT_RULES_HEADER: &str = "# Rules from {path}";
+/// Default text placed between rule files.
+pub const DEFAULT_RULES_SEPARATOR: &str = "\n\n---\n\n";
 
 /// A single rule file discovered on disk.
 #[derive(Debug, Clone)]
@@ -39,21 +46,70 @@ pub fn load_rules(project_root: &Path) -> Vec<RuleFile> {
     rules
 }
 
-/// Build a combined rules string ready for system prompt injection.
+/// Build a combined rules string ready for system prompt injection, framed per
+/// `agent.rules_header`, `agent.rules_separator` and `agent.rules_relative_paths`.
 /// Returns `None` if no rule files were found.
-pub fn build_rules_context(project_root: &Path) -> Option<String> {
+pub fn build_rules_context(project_root: &Path, agent: &AgentConfig) -> Option<String> {
     let rules = load_rules(project_root);
     if rules.is_empty() {
         return None;
     }
+    let root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    let header = agent
+        .rules_header
+        .as_deref()
+        .unwrap_or(DEFAULT_RULES_HEADER);
+    let separator = agent
+        .rules_separator
+        .as_deref()
+        .unwrap_or(DEFAULT_RULES_SEPARATOR);
 
     let mut parts: Vec<String> = Vec::with_capacity(rules.len());
     for rule in &rules {
-        let header = format!("# Rules from {}", rule.path.display());
-        parts.push(format!("{}\n\n{}", header, rule.content.trim()));
+        let content = rule.content.trim();
+        if header.is_empty() {
+            parts.push(content.to_string());
+            continue;
+        }
+        let path = if agent.rules_relative_paths {
+            relative_to(&rule.path, &root)
+        } else {
+            rule.path.clone()
+        };
+        let header = header.replace("{path}", &path.display().to_string());
+        parts.push(format!("{}\n\n{}", header, content));
     }
 
-    Some(parts.join("\n\n---\n\n"))
+    Some(parts.join(separator))
+}
+
+/// `path` relative to `base` (both absolute), using `..` for ancestors of `base`.
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path_parts: Vec<Component> = path.components().collect();
+    let base_parts: Vec<Component> = base.components().collect();
+    let common = path_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut rel = PathBuf::new();
+    for _ in common..base_parts.len() {
+        rel.push("..");
+    }
+    for part in &path_parts[common..] {
+        rel.push(part);
+    }
+    rel
+}
+
+/// Load the user-level instructions file (applies to every project).
+/// Returns `None` if it is missing or empty.
+pub fn load_user_instructions(path: &Path) -> Option<RuleFile> {
+    let mut found = Vec::new();
+    try_load(path, &mut found);
+    found.pop()
 }
 
 fn collect_ancestor_rules(project_root: &Path) -> Vec<RuleFile> {
@@ -79,3 +135,36 @@ fn try_load(path: &Path, out: &mut Vec<RuleFile>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_rule_paths_and_custom_framing() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("work").join("app");
+        std::fs::create_dir_all(project.join(".claude")).unwrap();
+        std::fs::write(dir.path().join("work").join("CLAUDE.md"), "team rule").unwrap();
+        std::fs::write(project.join("CLAUDE.md"), "project rule").unwrap();
+        std::fs::write(project.join(".claude").join("CLAUDE.md"), "local rule").unwrap();
+
+        let mut agent = AgentConfig {
+            rules_relative_paths: true,
+            ..AgentConfig::default()
+        };
+        let ctx = build_rules_context(&project, &agent).unwrap();
+        let team = Path::new("..").join("CLAUDE.md");
+        let local = Path::new(".claude").join("CLAUDE.md");
+        assert!(ctx.contains(&format!("# Rules from {}\n\nteam rule", team.display())));
+        assert!(ctx.contains("# Rules from CLAUDE.md\n\nproject rule"));
+        assert!(ctx.contains(&format!("# Rules from {}\n\nlocal rule", local.display())));
+        let root = dir.path().canonicalize().unwrap();
+        assert!(!ctx.contains(root.to_str().unwrap()));
+
+        agent.rules_header = Some(String::new());
+        agent.rules_separator = Some("\n\n".to_string());
+        let ctx = build_rules_context(&project, &agent).unwrap();
+        assert!(ctx.ends_with("team rule\n\nproject rule\n\nlocal rule"));
+    }
+}
@@ -1,11 +1,13 @@
 //! Session persistence and multi-session management.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use crate::agent::SessionStats;
-use crate::types::Message;
+use crate::paths;
+use crate::types::{Message, Role};
 
 /// Persistent session data saved to disk.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,10 +17,22 @@ pub struct SessionData {
     pub created_at: String,
     pub agent_messages: Vec<Message>,
     pub ui_messages: Vec<String>,
+    /// Local time each UI message was added (parallel to `ui_messages`; None = unknown).
+    #[serde(default)]
+    pub ui_message_times: Vec<Option<String>>,
     pub stats: SessionStatsData,
     /// Current model id for multi-model support. Empty means use config default.
     #[serde(default)]
     pub current_model_id: String,
+    /// User-requested summary of the conversation (`/summarize`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Free-form scratchpad notes (`/notes`); never sent to the model.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Per-session environment for bash commands (`/env set`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -26,6 +40,8 @@ pub struct SessionStatsData {
     pub total_input_tokens: u64,
     pub total_output_tokens: u64,
     pub request_count: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost: Option<f64>,
 }
 
 impl From<&SessionStats> for SessionStatsData {
@@ -34,6 +50,7 @@ impl From<&SessionStats> for SessionStatsData {
             total_input_tokens: stats.total_input_tokens,
             total_output_tokens: stats.total_output_tokens,
             request_count: stats.request_count,
+            total_cost: stats.total_cost,
         }
     }
 }
@@ -44,13 +61,13 @@ impl SessionStatsData {
             total_input_tokens: self.total_input_tokens,
             total_output_tokens: self.total_output_tokens,
             request_count: self.request_count,
+            total_cost: self.total_cost,
         }
     }
 }
 
 fn sessions_dir() -> Result<PathBuf> {
-    let home = dirs::home_dir().context("Could not determine home directory")?;
-    let dir = home.join(".miniclaw").join("sessions");
+    let dir = paths::sessions_dir()?;
     std::fs::create_dir_all(&dir)?;
     Ok(dir)
 }
@@ -72,6 +89,15 @@ pub fn load_session(id: &str) -> Result<SessionData> {
     Ok(data)
 }
 
+/// Remove the saved session file for `id`.
+pub fn delete_session(id: &str) -> Result<()> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        bail!("Invalid session id '{}'", id);
+    }
+    let path = sessions_dir()?.join(format!("{}.json", id));
+    std::fs::remove_file(&path).with_context(|| format!("Session '{}' not found", id))
+}
+
 pub fn list_sessions() -> Result<Vec<SessionData>> {
     let dir = sessions_dir()?;
     let mut sessions = Vec::new();
@@ -81,7 +107,7 @@ pub fn list_sessions() -> Result<Vec<SessionData>> {
     for entry in std::fs::read_dir(&dir)? {
         let entry = entry?;
         let path = entry.path();
-        if path.extension().map_or(false, |ext| ext == "json") {
+        if path.extension().is_some_and(|ext| ext == "json") {
             if let Ok(content) = std::fs::read_to_string(&path) {
                 if let Ok(data) = serde_json::from_str::<SessionData>(&content) {
                     sessions.push(data);
@@ -106,6 +132,147 @@ pub fn import_session(path: &Path) -> Result<SessionData> {
     Ok(data)
 }
 
+/// Write the session summary as a Markdown note. Errors if the session has no summary.
+pub fn write_summary_note(data: &SessionData, path: &Path) -> Result<()> {
+    let summary = data
+        .summary
+        .as_deref()
+        .context("Session has no summary; run /summarize first")?;
+    let note = format!(
+        "# {}\n\n_Session {} · {}_\n\n{}\n",
+        data.name,
+        data.id,
+        data.created_at,
+        summary.trim()
+    );
+    std::fs::write(path, note).with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(())
+}
+
+/// Raw agent message history (roles, tool calls, tool_call ids) as pretty JSON.
+pub fn history_json(messages: &[Message]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(messages)?)
+}
+
+/// Write the raw agent message history to `path` as pretty JSON.
+pub fn export_history(messages: &[Message], path: &Path) -> Result<()> {
+    std::fs::write(path, history_json(messages)?)
+        .with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(())
+}
+
+/// Whether the session has at least one user message (empty sessions aren't exported).
+pub fn has_conversation(data: &SessionData) -> bool {
+    data.agent_messages.iter().any(|m| m.role == Role::User)
+}
+
+/// The messages the user typed, in order, for re-submitting a session to a fresh
+/// agent (`/replay`). Synthetic notes are left out; replaying re-creates them.
+pub fn user_turns(messages: &[Message]) -> Vec<String> {
+    messages
+        .iter()
+        .filter(|m| m.role == Role::User && !m.synthetic)
+        .map(|m| m.content.clone())
+        .collect()
+}
+
+/// Render the conversation as Markdown: name, creation time and stats up top,
+/// then user and assistant turns, with each tool call listed under the
+/// assistant turn that made it and its output in a code block.
+pub fn session_to_markdown(data: &SessionData) -> String {
+    let mut md = format!(
+        "# {}\n\n_Session {} · {}_\n\n",
+        data.name, data.id, data.created_at
+    );
+    if !data.current_model_id.is_empty() {
+        md.push_str(&format!("- Model: `{}`\n", data.current_model_id));
+    }
+    let stats = &data.stats;
+    md.push_str(&format!(
+        "- Requests: {} · Tokens: {} in / {} out",
+        stats.request_count, stats.total_input_tokens, stats.total_output_tokens
+    ));
+    if let Some(cost) = stats.total_cost {
+        md.push_str(&format!(" · Cost: ${:.4}", cost));
+    }
+    md.push('\n');
+    if let Some(summary) = &data.summary {
+        md.push_str(&format!("\n## Summary\n\n{}\n", summary.trim()));
+    }
+    for msg in &data.agent_messages {
+        match msg.role {
+            Role::System => {}
+            Role::User => md.push_str(&format!("\n## You\n\n{}\n", msg.content.trim())),
+            Role::Assistant => {
+                md.push_str("\n## Assistant\n\n");
+                if !msg.content.trim().is_empty() {
+                    md.push_str(&format!("{}\n", msg.content.trim()));
+                }
+                for call in &msg.tool_calls {
+                    let ticks = backtick_fence(&call.arguments, 1);
+                    md.push_str(&format!(
+                        "- tool `{}`: {} {} {}\n",
+                        call.name, ticks, call.arguments, ticks
+                    ));
+                }
+            }
+            Role::Tool => {
+                let output = msg.content.trim_end();
+                if !output.is_empty() {
+                    let fence = backtick_fence(output, 3);
+                    md.push_str(&format!("\n{}\n{}\n{}\n", fence, output, fence));
+                }
+            }
+        }
+    }
+    md
+}
+
+/// A run of backticks longer than any in `text` (and at least `min` long), so
+/// a code span or block built with it cannot be closed early by the content.
+fn backtick_fence(text: &str, min: usize) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(min.max(longest + 1))
+}
+
+/// Write [`session_to_markdown`] to `path` (`/export-md`).
+pub fn export_session_markdown(data: &SessionData, path: &Path) -> Result<()> {
+    std::fs::write(path, session_to_markdown(data))
+        .with_context(|| format!("Cannot write {}", path.display()))
+}
+
+/// Export the session as Markdown into `dir`, named `<timestamp>-<name>-<id>.md`.
+/// Returns the written path.
+pub fn export_markdown_to_dir(data: &SessionData, dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    let slug: String = data
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug: String = slug.chars().take(40).collect();
+    let slug = if slug.is_empty() {
+        "session".to_string()
+    } else {
+        slug
+    };
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let path = dir.join(format!("{}-{}-{}.md", stamp, slug, data.id));
+    std::fs::write(&path, session_to_markdown(data))
+        .with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(path)
+}
+
 pub fn generate_session_id() -> String {
     uuid::Uuid::new_v4().to_string()[..8].to_string()
 }
@@ -117,6 +284,7 @@ pub fn now_timestamp() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::ToolCall;
 
     #[test]
     fn test_generate_session_id() {
@@ -124,6 +292,20 @@ mod tests {
         assert_eq!(id.len(), 8);
     }
 
+    #[test]
+    fn test_user_turns_in_order() {
+        let messages = vec![
+            Message::system("prompt"),
+            Message::user("first"),
+            Message::assistant("reply"),
+            Message::tool_result("call_1", "output"),
+            Message::note("Output of `ls` (run by the user with /run)"),
+            Message::user("second"),
+            Message::assistant("done"),
+        ];
+        assert_eq!(user_turns(&messages), vec!["first", "second"]);
+    }
+
     #[test]
     fn test_session_data_roundtrip() {
         let data = SessionData {
@@ -132,14 +314,52 @@ mod tests {
             created_at: now_timestamp(),
             agent_messages: vec![],
             ui_messages: vec!["Hello".to_string()],
+            ui_message_times: vec![Some("2026-10-15 09:30:00".to_string())],
             stats: SessionStatsData::default(),
             current_model_id: String::new(),
+            summary: None,
+            notes: String::new(),
+            env: BTreeMap::new(),
         };
         let json = serde_json::to_string(&data).unwrap();
         let loaded: SessionData = serde_json::from_str(&json).unwrap();
         assert_eq!(loaded.id, "test123");
         assert_eq!(loaded.name, "Test Session");
         assert_eq!(loaded.ui_messages.len(), 1);
+        assert_eq!(
+            loaded.ui_message_times,
+            vec![Some("2026-10-15 09:30:00".to_string())]
+        );
+
+        // Sessions saved before timestamps existed still load
+        let legacy = json.replace(r#","ui_message_times":["2026-10-15 09:30:00"]"#, "");
+        let loaded: SessionData = serde_json::from_str(&legacy).unwrap();
+        assert!(loaded.ui_message_times.is_empty());
+    }
+
+    #[test]
+    fn test_export_history_roundtrip() {
+        use crate::types::ToolCall;
+        let messages = vec![
+            Message::system("You are helpful."),
+            Message::user("list files"),
+            Message::assistant_with_tool_calls(
+                "",
+                vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "list_directory".to_string(),
+                    arguments: r#"{"path":"."}"#.to_string(),
+                }],
+            ),
+            Message::tool_result("call_1", "a.txt\nb.txt"),
+            Message::assistant("Two files."),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        export_history(&messages, &path).unwrap();
+        let loaded: Vec<Message> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(loaded, messages);
     }
 
     #[test]
@@ -148,12 +368,14 @@ mod tests {
             total_input_tokens: 100,
             total_output_tokens: 50,
             request_count: 3,
+            total_cost: Some(0.25),
         };
         let data = SessionStatsData::from(&stats);
         assert_eq!(data.total_input_tokens, 100);
         let back = data.to_session_stats();
         assert_eq!(back.total_output_tokens, 50);
         assert_eq!(back.request_count, 3);
+        assert_eq!(back.total_cost, Some(0.25));
     }
 
     #[test]
@@ -166,12 +388,99 @@ mod tests {
             created_at: now_timestamp(),
             agent_messages: vec![],
             ui_messages: vec!["msg1".to_string(), "msg2".to_string()],
+            ui_message_times: vec![],
             stats: SessionStatsData::default(),
             current_model_id: String::new(),
+            summary: None,
+            notes: String::new(),
+            env: BTreeMap::new(),
         };
         export_session(&data, &path).unwrap();
         let loaded = import_session(&path).unwrap();
         assert_eq!(loaded.id, "exp1");
         assert_eq!(loaded.ui_messages.len(), 2);
     }
+
+    #[test]
+    fn test_summary_survives_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("summary_session.json");
+        let mut data = SessionData {
+            id: "sum1".to_string(),
+            name: "Debugging".to_string(),
+            created_at: now_timestamp(),
+            agent_messages: vec![],
+            ui_messages: vec![],
+            ui_message_times: vec![],
+            stats: SessionStatsData::default(),
+            current_model_id: String::new(),
+            summary: None,
+            notes: String::new(),
+            env: BTreeMap::new(),
+        };
+        let note = dir.path().join("note.md");
+        assert!(write_summary_note(&data, &note).is_err());
+
+        data.summary = Some("- Fixed the flaky test".to_string());
+        export_session(&data, &path).unwrap();
+        let loaded = import_session(&path).unwrap();
+        assert_eq!(loaded.summary.as_deref(), Some("- Fixed the flaky test"));
+
+        write_summary_note(&loaded, &note).unwrap();
+        let text = std::fs::read_to_string(&note).unwrap();
+        assert!(text.starts_with("# Debugging\n"));
+        assert!(text.contains("- Fixed the flaky test"));
+    }
+
+    #[test]
+    fn test_export_session_markdown_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcript.md");
+        let call = Message::assistant_with_tool_calls(
+            "Let me look.",
+            vec![ToolCall {
+                id: "c1".to_string(),
+                name: "read_file".to_string(),
+                arguments: r#"{"path":"src/main.rs"}"#.to_string(),
+            }],
+        );
+        let data = SessionData {
+            id: "md1".to_string(),
+            name: "Fix build".to_string(),
+            created_at: "2026-10-16 09:30:00".to_string(),
+            agent_messages: vec![
+                Message::system("sys"),
+                Message::user("why does the build fail?"),
+                call,
+                Message::tool_result("c1", "let s = \"```\";\nfn main() {}"),
+                Message::assistant("A missing **semicolon**."),
+            ],
+            ui_messages: vec![],
+            ui_message_times: vec![],
+            stats: SessionStatsData {
+                total_input_tokens: 1200,
+                total_output_tokens: 80,
+                request_count: 2,
+                total_cost: Some(0.0123),
+            },
+            current_model_id: "qwen-plus".to_string(),
+            summary: None,
+            notes: String::new(),
+            env: BTreeMap::new(),
+        };
+        export_session_markdown(&data, &path).unwrap();
+        let md = std::fs::read_to_string(&path).unwrap();
+        assert!(md.starts_with(
+            "# Fix build\n\n_Session md1 · 2026-10-16 09:30:00_\n\n- Model: `qwen-plus`\n\
+             - Requests: 2 · Tokens: 1200 in / 80 out · Cost: $0.0123\n"
+        ));
+        assert!(md.contains("## You\n\nwhy does the build fail?\n"));
+        assert!(md.contains(
+            "## Assistant\n\nLet me look.\n- tool `read_file`: ` {\"path\":\"src/main.rs\"} `\n"
+        ));
+        // The fence outgrows the backticks inside the output
+        assert!(md.contains("\n````\nlet s = \"```\";\nfn main() {}\n````\n"));
+        assert!(md.contains("## Assistant\n\nA missing **semicolon**.\n"));
+        assert!(!md.contains("sys"));
+    }
 }
@@ -0,0 +1,122 @@
+//! Saved text snippets for `/snippet`.
+//!
+//! Each file in `<data_dir>/snippets` (default ~/.miniclaw/snippets) is one
+//! snippet, named by its file stem: `snippets/axum-handler.rs` is `axum-handler`.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+/// Snippet names in `dir`, sorted; a missing directory has none.
+pub fn list_in(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|e| snippet_name(&e.path()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Content of the snippet called `name` in `dir`.
+pub fn load_in(dir: &Path, name: &str) -> Result<String> {
+    let Some(path) = find(dir, name) else {
+        let similar = search_in(dir, name);
+        if similar.is_empty() {
+            bail!("Snippet '{}' not found in {}", name, dir.display());
+        }
+        bail!(
+            "Snippet '{}' not found. Did you mean: {}",
+            name,
+            similar.join(", ")
+        );
+    };
+    std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read snippet {}", path.display()))
+}
+
+/// Snippet names in `dir` that fuzzily match `query`, best match first.
+pub fn search_in(dir: &Path, query: &str) -> Vec<String> {
+    let mut scored: Vec<(u8, String)> = list_in(dir)
+        .into_iter()
+        .filter_map(|name| Some((fuzzy_rank(query, &name)?, name)))
+        .collect();
+    scored.sort();
+    scored.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Directory holding the user's snippets.
+pub fn dir() -> Result<PathBuf> {
+    paths::snippets_dir()
+}
+
+/// How well `query` matches `name`, lower is better: prefix, substring, then
+/// characters in order (case-insensitive). None if it does not match.
+fn fuzzy_rank(query: &str, name: &str) -> Option<u8> {
+    let query = query.trim().to_lowercase();
+    let name = name.to_lowercase();
+    if name.starts_with(&query) {
+        return Some(0);
+    }
+    if name.contains(&query) {
+        return Some(1);
+    }
+    let mut chars = name.chars();
+    query.chars().all(|q| chars.any(|c| c == q)).then_some(2)
+}
+
+fn find(dir: &Path, name: &str) -> Option<PathBuf> {
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && snippet_name(p).as_deref() == Some(name))
+        .collect();
+    matches.sort();
+    matches.into_iter().next()
+}
+
+/// Snippet name for a file: its stem, skipping hidden files.
+fn snippet_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    (!stem.starts_with('.')).then(|| stem.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_search_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("axum-handler.rs"),
+            "async fn handler() {}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("bug-report.md"), "## Steps\n").unwrap();
+        std::fs::write(dir.path().join(".hidden"), "x").unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+
+        assert_eq!(list_in(dir.path()), vec!["axum-handler", "bug-report"]);
+        assert_eq!(
+            load_in(dir.path(), "axum-handler").unwrap(),
+            "async fn handler() {}\n"
+        );
+        // Substring beats scattered characters
+        assert_eq!(
+            search_in(dir.path(), "er"),
+            vec!["axum-handler", "bug-report"]
+        );
+        assert_eq!(search_in(dir.path(), "ahd"), vec!["axum-handler"]);
+        assert!(search_in(dir.path(), "zzz").is_empty());
+
+        let err = load_in(dir.path(), "bug").unwrap_err().to_string();
+        assert!(err.contains("Did you mean: bug-report"), "{}", err);
+        assert!(list_in(&dir.path().join("missing")).is_empty());
+    }
+}
@@ -1,15 +1,54 @@
 //! Bash tool implementation.
 //!
-//! Executes shell commands via `bash -c`, with timeout control
-//! and output truncation for safety.
+//! Executes shell commands via `bash -c` (or the configured `tools.shell`),
+//! with timeout control and output truncation for safety.
+
+use std::collections::BTreeMap;
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde_json::json;
 
+use super::risk::{self, RiskLevel};
 use super::Tool;
 
-pub struct BashTool;
+pub struct BashTool {
+    /// Extra environment variables set for every command (`tools.bash_env` plus session `/env`).
+    pub env: BTreeMap<String, String>,
+    /// Shell program commands run with (`tools.shell`).
+    shell: String,
+    /// Tool description naming that shell, so the model writes commands for it.
+    description: String,
+}
+
+impl BashTool {
+    /// A tool running commands with `shell` (empty = bash) and `env` set.
+    pub fn new(shell: String, env: BTreeMap<String, String>) -> Self {
+        let shell = if shell.is_empty() {
+            "bash".to_string()
+        } else {
+            shell
+        };
+        let description = format!(
+            "Execute a shell command via {}. Returns stdout and stderr. \
+             Use this for running build commands, searching files (grep/rg/find), \
+             git operations, listing directories, installing packages, etc. \
+             Commands run with a configurable timeout (default 30s).",
+            shell_name(&shell)
+        );
+        Self {
+            env,
+            shell,
+            description,
+        }
+    }
+}
+
+impl Default for BashTool {
+    fn default() -> Self {
+        Self::new(String::new(), BTreeMap::new())
+    }
+}
 
 const DEFAULT_TIMEOUT_SECS: u64 = 30;
 const MAX_OUTPUT_BYTES: usize = 100_000;
@@ -21,10 +60,7 @@ impl Tool for BashTool {
     }
 
     fn description(&self) -> &str {
-        "Execute a shell command via bash. Returns stdout and stderr. \
-         Use this for running build commands, searching files (grep/rg/find), \
-         git operations, listing directories, installing packages, etc. \
-         Commands run with a configurable timeout (default 30s)."
+        &self.description
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -38,12 +74,21 @@ impl Tool for BashTool {
                 "timeout": {
                     "type": "integer",
                     "description": "Timeout in seconds (default: 30, max: 300)"
+                },
+                "retriable": {
+                    "type": "boolean",
+                    "description": "Set true only for commands that are safe to re-run (e.g. network installs, reads). A non-zero exit or timeout is then retried if retries are enabled. Ignored for destructive commands."
                 }
             },
             "required": ["command"]
         })
     }
 
+    fn is_retryable(&self, params: &serde_json::Value) -> bool {
+        retriable_requested(params)
+            && risk::assess_risk("bash", &params.to_string()) != RiskLevel::Dangerous
+    }
+
     async fn execute(&self, params: serde_json::Value) -> Result<String> {
         let command = params
             .get("command")
@@ -56,12 +101,14 @@ impl Tool for BashTool {
             .unwrap_or(DEFAULT_TIMEOUT_SECS)
             .min(300);
 
+        let shell = &self.shell;
         let cmd_clone = command.to_string();
         let result = tokio::time::timeout(
             std::time::Duration::from_secs(timeout_secs),
-            tokio::process::Command::new("bash")
-                .arg("-c")
+            tokio::process::Command::new(shell)
+                .arg(command_flag(shell))
                 .arg(&cmd_clone)
+                .envs(&self.env)
                 .output(),
         )
         .await;
@@ -93,9 +140,17 @@ impl Tool for BashTool {
                     result.push_str(&format!("\n[exit code: {}]", exit_code));
                 }
 
+                // Surface failure as an error so the router can retry the command
+                if exit_code != 0 && retriable_requested(&params) {
+                    anyhow::bail!("{}", result);
+                }
                 Ok(result)
             }
-            Ok(Err(e)) => Err(anyhow::anyhow!("Failed to execute command: {}", e)),
+            Ok(Err(e)) => Err(anyhow::anyhow!(
+                "Failed to execute command with {}: {}",
+                shell,
+                e
+            )),
             Err(_) => Err(anyhow::anyhow!(
                 "Command timed out after {}s: {}",
                 timeout_secs,
@@ -105,7 +160,30 @@ impl Tool for BashTool {
     }
 }
 
-fn truncate_output(output: &str, max_bytes: usize) -> String {
+/// A shell's program name without directory or extension, e.g. "zsh" for `/bin/zsh`.
+pub fn shell_name(shell: &str) -> &str {
+    let file = shell.rsplit(['/', '\\']).next().unwrap_or(shell);
+    file.split('.').next().unwrap_or(file)
+}
+
+/// The "run this command string" flag for a shell, by program name.
+fn command_flag(shell: &str) -> &'static str {
+    match shell_name(shell).to_ascii_lowercase().as_str() {
+        "pwsh" | "powershell" => "-Command",
+        "cmd" => "/C",
+        _ => "-c",
+    }
+}
+
+/// Whether the call marked itself `retriable`.
+fn retriable_requested(params: &serde_json::Value) -> bool {
+    params
+        .get("retriable")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+pub(crate) fn truncate_output(output: &str, max_bytes: usize) -> String {
     if output.len() <= max_bytes {
         return output.to_string();
     }
@@ -130,7 +208,7 @@ mod tests {
 
     #[test]
     fn test_metadata() {
-        let tool = BashTool;
+        let tool = BashTool::default();
         assert_eq!(tool.name(), "bash");
         assert!(!tool.description().is_empty());
         let schema = tool.parameters_schema();
@@ -141,7 +219,7 @@ mod tests {
     fn test_echo_command() {
         let rt = rt();
         rt.block_on(async {
-            let result = BashTool
+            let result = BashTool::default()
                 .execute(json!({ "command": "echo hello" }))
                 .await
                 .unwrap();
@@ -153,7 +231,7 @@ mod tests {
     fn test_exit_code() {
         let rt = rt();
         rt.block_on(async {
-            let result = BashTool
+            let result = BashTool::default()
                 .execute(json!({ "command": "exit 42" }))
                 .await
                 .unwrap();
@@ -161,11 +239,29 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_retriable_commands() {
+        let tool = BashTool::default();
+        assert!(!tool.is_retryable(&json!({ "command": "cargo fetch" })));
+        assert!(tool.is_retryable(&json!({ "command": "cargo fetch", "retriable": true })));
+        // Destructive commands are never retried, even when marked
+        assert!(!tool.is_retryable(&json!({ "command": "rm -rf target", "retriable": true })));
+
+        let rt = rt();
+        rt.block_on(async {
+            let err = tool
+                .execute(json!({ "command": "exit 3", "retriable": true }))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("exit code: 3"));
+        });
+    }
+
     #[test]
     fn test_stderr_capture() {
         let rt = rt();
         rt.block_on(async {
-            let result = BashTool
+            let result = BashTool::default()
                 .execute(json!({ "command": "echo error >&2" }))
                 .await
                 .unwrap();
@@ -174,11 +270,33 @@ mod tests {
         });
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_configured_shell_runs_command() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let shell = dir.path().join("fakesh");
+        std::fs::write(&shell, "#!/bin/sh\necho \"fakesh $1: $2\"\n").unwrap();
+        std::fs::set_permissions(&shell, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let tool = BashTool::new(shell.display().to_string(), BTreeMap::new());
+        assert!(tool.description().contains("via fakesh."));
+        assert!(BashTool::default().description().contains("via bash."));
+        let result = rt()
+            .block_on(tool.execute(json!({ "command": "echo hi" })))
+            .unwrap();
+        assert_eq!(result.trim(), "fakesh -c: echo hi");
+
+        assert_eq!(command_flag("/usr/bin/fish"), "-c");
+        assert_eq!(command_flag("pwsh"), "-Command");
+        assert_eq!(command_flag("C:\\Windows\\System32\\cmd.exe"), "/C");
+    }
+
     #[test]
     fn test_timeout() {
         let rt = rt();
         rt.block_on(async {
-            let result = BashTool
+            let result = BashTool::default()
                 .execute(json!({ "command": "sleep 10", "timeout": 1 }))
                 .await;
             assert!(result.is_err());
@@ -190,7 +308,7 @@ mod tests {
     fn test_missing_command() {
         let rt = rt();
         rt.block_on(async {
-            let result = BashTool.execute(json!({})).await;
+            let result = BashTool::default().execute(json!({})).await;
             assert!(result.is_err());
             assert!(result.unwrap_err().to_string().contains("command"));
         });
@@ -200,7 +318,7 @@ mod tests {
     fn test_multiline_output() {
         let rt = rt();
         rt.block_on(async {
-            let result = BashTool
+            let result = BashTool::default()
                 .execute(json!({ "command": "echo line1; echo line2; echo line3" }))
                 .await
                 .unwrap();
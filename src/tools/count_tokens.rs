@@ -0,0 +1,104 @@
+//! Count tokens tool implementation.
+//!
+//! Estimates how many tokens a piece of text (or a file) takes, using the same
+//! estimator the agent uses for context compaction, plus char and line counts.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::Tool;
+use crate::agent::Agent;
+
+pub struct CountTokensTool;
+
+#[async_trait]
+impl Tool for CountTokensTool {
+    fn name(&self) -> &str {
+        "count_tokens"
+    }
+
+    fn description(&self) -> &str {
+        "Estimate the token count of text or a file, with char and line counts. \
+         Uses the same estimate as the agent's context tracking (about 3 chars per token)."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "Text to measure"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File to measure instead of text"
+                }
+            }
+        })
+    }
+
+    fn is_retryable(&self, _params: &serde_json::Value) -> bool {
+        true
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let text = params.get("text").and_then(|v| v.as_str());
+        let path = params.get("path").and_then(|v| v.as_str());
+        let (source, content) = match (text, path) {
+            (Some(_), Some(_)) => anyhow::bail!("Pass either text or path, not both"),
+            (Some(text), None) => ("text".to_string(), text.to_string()),
+            (None, Some(path)) => {
+                let content = tokio::fs::read_to_string(path)
+                    .await
+                    .with_context(|| format!("Failed to read file: {}", path))?;
+                (path.to_string(), content)
+            }
+            (None, None) => anyhow::bail!("Missing required parameter: text or path"),
+        };
+
+        Ok(format!(
+            "{}: ~{} tokens (estimated), {} chars, {} lines",
+            source,
+            Agent::estimate_tokens(&content),
+            content.chars().count(),
+            content.lines().count()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    #[test]
+    fn test_count_text_and_file() {
+        let text = "The quick brown fox jumps over the lazy dog.\nSecond line.\n";
+        let result = rt()
+            .block_on(CountTokensTool.execute(json!({ "text": text })))
+            .unwrap();
+        assert_eq!(result, "text: ~19 tokens (estimated), 58 chars, 2 lines");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompt.md");
+        std::fs::write(&path, text).unwrap();
+        let path = path.to_str().unwrap();
+        let result = rt()
+            .block_on(CountTokensTool.execute(json!({ "path": path })))
+            .unwrap();
+        assert_eq!(
+            result,
+            format!("{}: ~19 tokens (estimated), 58 chars, 2 lines", path)
+        );
+
+        let err = rt()
+            .block_on(CountTokensTool.execute(json!({})))
+            .unwrap_err();
+        assert!(err.to_string().contains("text or path"));
+    }
+}
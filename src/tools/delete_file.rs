@@ -0,0 +1,168 @@
+//! Delete file tool implementation.
+//!
+//! Removes one file, or a directory tree with `recursive: true`. Always
+//! Dangerous (needs confirmation), and limited to the project root (the
+//! working directory) unless the call sets `allow_outside_project`.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::Path;
+
+use super::Tool;
+
+pub struct DeleteFileTool;
+
+#[async_trait]
+impl Tool for DeleteFileTool {
+    fn name(&self) -> &str {
+        "delete_file"
+    }
+
+    fn description(&self) -> &str {
+        "Delete a file. Directories are only removed with recursive: true. \
+         Paths outside the project are refused unless allow_outside_project is true. \
+         Always asks the user for confirmation; prefer this over bash rm."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The file (or directory, with recursive) to delete"
+                },
+                "recursive": {
+                    "type": "boolean",
+                    "description": "Delete a directory and everything in it (default: false)"
+                },
+                "allow_outside_project": {
+                    "type": "boolean",
+                    "description": "Allow deleting outside the project directory (default: false)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn modified_paths(&self, params: &serde_json::Value) -> Vec<std::path::PathBuf> {
+        params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(|p| vec![std::path::PathBuf::from(p)])
+            .unwrap_or_default()
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: path")?;
+        let recursive = params["recursive"].as_bool().unwrap_or(false);
+        let allow_outside = params["allow_outside_project"].as_bool().unwrap_or(false);
+        let root = std::env::current_dir().context("Failed to get the project directory")?;
+        delete_path(Path::new(path), &root, recursive, allow_outside).await
+    }
+}
+
+/// Delete `path` (relative paths resolve against `root`), refusing anything that
+/// isn't strictly inside `root` unless `allow_outside`.
+async fn delete_path(
+    path: &Path,
+    root: &Path,
+    recursive: bool,
+    allow_outside: bool,
+) -> Result<String> {
+    let full = root.join(path);
+    let resolved = tokio::fs::canonicalize(&full)
+        .await
+        .with_context(|| format!("Failed to find {}", path.display()))?;
+    let root = tokio::fs::canonicalize(root)
+        .await
+        .context("Failed to resolve the project directory")?;
+    if !allow_outside && (resolved == root || !resolved.starts_with(&root)) {
+        bail!(
+            "Refusing to delete {}: it is outside the project directory {} \
+             (pass allow_outside_project: true if this is intended)",
+            path.display(),
+            root.display()
+        );
+    }
+
+    // Check the link itself so a symlink to a directory is removed as a file
+    let meta = tokio::fs::symlink_metadata(&full)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if meta.is_dir() {
+        if !recursive {
+            bail!(
+                "{} is a directory; pass recursive: true to delete it and its contents",
+                path.display()
+            );
+        }
+        tokio::fs::remove_dir_all(&full)
+            .await
+            .with_context(|| format!("Failed to delete directory: {}", path.display()))?;
+        Ok(format!("Deleted directory {}", path.display()))
+    } else {
+        tokio::fs::remove_file(&full)
+            .await
+            .with_context(|| format!("Failed to delete file: {}", path.display()))?;
+        Ok(format!("Deleted {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    #[test]
+    fn test_deletes_files_and_directories_inside_root() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("old.log"), "x").unwrap();
+        std::fs::create_dir_all(root.path().join("build/out")).unwrap();
+        std::fs::write(root.path().join("build/out/a.o"), "x").unwrap();
+
+        let result = rt()
+            .block_on(delete_path(Path::new("old.log"), root.path(), false, false))
+            .unwrap();
+        assert_eq!(result, "Deleted old.log");
+        assert!(!root.path().join("old.log").exists());
+
+        let err = rt()
+            .block_on(delete_path(Path::new("build"), root.path(), false, false))
+            .unwrap_err();
+        assert!(err.to_string().contains("recursive: true"));
+        assert!(root.path().join("build/out/a.o").exists());
+
+        rt().block_on(delete_path(Path::new("build"), root.path(), true, false))
+            .unwrap();
+        assert!(!root.path().join("build").exists());
+    }
+
+    #[test]
+    fn test_refuses_paths_outside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let project = root.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        let outside = root.path().join("keep.txt");
+        std::fs::write(&outside, "x").unwrap();
+
+        for path in [Path::new("../keep.txt"), outside.as_path(), Path::new(".")] {
+            let err = rt()
+                .block_on(delete_path(path, &project, true, false))
+                .unwrap_err();
+            assert!(err.to_string().contains("outside the project"), "{}", err);
+        }
+        assert!(outside.exists());
+
+        rt().block_on(delete_path(&outside, &project, false, true))
+            .unwrap();
+        assert!(!outside.exists());
+    }
+}
@@ -9,7 +9,7 @@ use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use serde_json::json;
 
-use super::Tool;
+use super::{Tool, ToolOutput};
 
 pub struct EditTool;
 
@@ -51,6 +51,23 @@ impl Tool for EditTool {
         })
     }
 
+    fn modified_paths(&self, params: &serde_json::Value) -> Vec<std::path::PathBuf> {
+        params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(|p| vec![std::path::PathBuf::from(p)])
+            .unwrap_or_default()
+    }
+
+    async fn execute_structured(&self, params: serde_json::Value) -> Result<ToolOutput> {
+        let files = self.modified_paths(&params);
+        let text = self.execute(params).await?;
+        Ok(ToolOutput {
+            text,
+            metadata: json!({ "files": files }),
+        })
+    }
+
     async fn execute(&self, params: serde_json::Value) -> Result<String> {
         let path = params
             .get("path")
@@ -0,0 +1,446 @@
+//! Fetch tool implementation.
+//!
+//! GETs an http/https URL and returns the body as text, with HTML reduced to
+//! readable text. Loopback and private-network hosts are refused unless
+//! `tools.fetch_allow_private` is set.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use reqwest::Url;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::bash::truncate_output;
+use super::Tool;
+
+const DEFAULT_MAX_BYTES: usize = 100_000;
+/// Hard cap on how much of a response is downloaded, whatever `max_bytes` says.
+const MAX_DOWNLOAD_BYTES: usize = 5 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Default)]
+pub struct FetchTool {
+    /// Allow loopback/private/link-local hosts (`tools.fetch_allow_private`).
+    pub allow_private: bool,
+}
+
+#[async_trait]
+impl Tool for FetchTool {
+    fn name(&self) -> &str {
+        "fetch"
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a URL with HTTP GET and return the response body as text. \
+         HTML pages are converted to readable text. Only http/https; \
+         localhost and private network addresses are refused. Prefer this over bash curl."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "The http or https URL to fetch"
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": "Maximum bytes of text to return (default: 100000)"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    fn is_retryable(&self, _params: &serde_json::Value) -> bool {
+        true
+    }
+
+    fn uses_network(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let url = params
+            .get("url")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: url")?;
+        let max_bytes = params
+            .get("max_bytes")
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).clamp(1, MAX_DOWNLOAD_BYTES))
+            .unwrap_or(DEFAULT_MAX_BYTES);
+        let url = Url::parse(url).with_context(|| format!("Invalid URL: {}", url))?;
+        check_static(&url, self.allow_private)?;
+        fetch(url, max_bytes, self.allow_private).await
+    }
+}
+
+/// Resolves hosts for the fetch client and drops loopback/private addresses,
+/// so the check happens at connect time for the first request and every
+/// redirect, with no window for the name to re-resolve elsewhere.
+struct PublicOnlyResolver;
+
+impl reqwest::dns::Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|a| is_private_ip(a.ip())) {
+                return Err(format!(
+                    "{} resolves to private network address {} \
+                     (set tools.fetch_allow_private = true to allow)",
+                    host,
+                    addr.ip()
+                )
+                .into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Checks that need no DNS lookup; also applied to every redirect target.
+fn check_static(url: &Url, allow_private: bool) -> Result<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!(
+            "Only http and https URLs can be fetched, got {}",
+            url.scheme()
+        );
+    }
+    let host = url.host_str().context("URL has no host")?;
+    let blocked = !allow_private
+        && match host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+        {
+            Ok(ip) => is_private_ip(ip),
+            Err(_) => {
+                let domain = host.trim_end_matches('.').to_ascii_lowercase();
+                domain == "localhost" || domain.ends_with(".localhost")
+            }
+        };
+    if blocked {
+        bail!(
+            "Refusing to fetch {}: localhost and private network addresses are blocked \
+             (set tools.fetch_allow_private = true to allow)",
+            url
+        );
+    }
+    Ok(())
+}
+
+/// Loopback, private, link-local, CGNAT, unspecified and broadcast addresses.
+fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_private_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+async fn fetch(url: Url, max_bytes: usize, allow_private: bool) -> Result<String> {
+    let redirect = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if let Err(e) = check_static(attempt.url(), allow_private) {
+            attempt.error(e.to_string())
+        } else {
+            attempt.follow()
+        }
+    });
+    let mut builder = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(redirect)
+        .user_agent(concat!("miniclaw/", env!("CARGO_PKG_VERSION")));
+    if !allow_private {
+        builder = builder.dns_resolver(Arc::new(PublicOnlyResolver));
+    }
+    let client = builder.build().context("Failed to build HTTP client")?;
+
+    let mut response = client.get(url.clone()).send().await.map_err(|e| {
+        // Surface the resolver's refusal, which reqwest nests a few sources deep
+        let cause = std::iter::successors(std::error::Error::source(&e), |s| s.source())
+            .last()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| e.to_string());
+        anyhow::anyhow!("Failed to fetch {}: {}", url, cause)
+    })?;
+    let status = response.status();
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.to_ascii_lowercase().contains("text/html"));
+
+    // HTML shrinks when stripped, so download more than will be returned
+    let limit = if is_html {
+        max_bytes.saturating_mul(4).min(MAX_DOWNLOAD_BYTES)
+    } else {
+        max_bytes
+    };
+    let mut body = Vec::new();
+    let mut cut = false;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("Failed to read response from {}", url))?
+    {
+        body.extend_from_slice(&chunk);
+        if body.len() > limit {
+            body.truncate(limit);
+            cut = true;
+            break;
+        }
+    }
+
+    let text = String::from_utf8_lossy(&body);
+    let text = if is_html {
+        html_to_text(&text)
+    } else {
+        text.into_owned()
+    };
+    let mut output = String::new();
+    if !status.is_success() {
+        output.push_str(&format!("HTTP {}\n\n", status));
+    }
+    output.push_str(&truncate_output(&text, max_bytes));
+    if cut {
+        output.push_str(&format!("\n\n... (download stopped after {} bytes)", limit));
+    }
+    Ok(output)
+}
+
+/// Reduce HTML to readable text: drops scripts/styles and tags, puts block
+/// elements on their own lines, decodes common entities and collapses whitespace and blank lines.
+fn html_to_text(html: &str) -> String {
+    const SKIPPED: [&str; 5] = ["script", "style", "noscript", "template", "svg"];
+    const BLOCKS: [&str; 22] = [
+        "p",
+        "div",
+        "br",
+        "li",
+        "ul",
+        "ol",
+        "tr",
+        "table",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "pre",
+        "blockquote",
+        "section",
+        "article",
+        "header",
+        "footer",
+        "title",
+        "hr",
+    ];
+
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if !tag.starts_with('/') && SKIPPED.contains(&name.as_str()) {
+            let close = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(pos) => rest[pos..].find('>').map_or("", |e| &rest[pos + e + 1..]),
+                None => "",
+            };
+            continue;
+        }
+        if BLOCKS.contains(&name.as_str()) {
+            text.push('\n');
+        }
+    }
+    text.push_str(rest);
+
+    decode_entities(&text)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let decoded = entity.and_then(|e| match e {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" | "#39" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let code = match e.strip_prefix("#x").or_else(|| e.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => e.strip_prefix('#').and_then(|d| d.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (entity, decoded) {
+            (Some(e), Some(c)) => {
+                out.push(c);
+                rest = &rest[e.len() + 2..];
+            }
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    /// Serve one canned HTTP response on a loopback port; returns its URL.
+    async fn serve_once(content_type: &'static str, body: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                content_type,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{}/page", addr)
+    }
+
+    #[test]
+    fn test_refuses_other_schemes_and_private_hosts() {
+        let rt = rt();
+        let tool = FetchTool::default();
+        for url in [
+            "file:///etc/passwd",
+            "http://localhost:8080/",
+            "http://127.0.0.1/",
+            "http://10.1.2.3/",
+            "http://192.168.0.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            let err = rt
+                .block_on(tool.execute(json!({ "url": url })))
+                .unwrap_err();
+            let msg = err.to_string();
+            assert!(
+                msg.contains("Only http and https") || msg.contains("private network"),
+                "{}: {}",
+                url,
+                msg
+            );
+        }
+        assert!(!is_private_ip("93.184.216.34".parse().unwrap()));
+        assert!(!is_private_ip("2606:4700::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_resolver_refuses_names_with_private_addresses() {
+        use reqwest::dns::Resolve;
+        let rt = rt();
+        let err = rt
+            .block_on(PublicOnlyResolver.resolve("localhost".parse().unwrap()))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("private network address"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_fetches_html_as_text_when_private_allowed() {
+        let rt = rt();
+        rt.block_on(async {
+            let html = "<html><head><title>Docs</title><style>p { color: red }</style></head>\
+                        <body><h1>Hello &amp; welcome</h1><script>alert(1)</script>\
+                        <p>First&nbsp;para</p><!-- hidden --><ul><li>one</li><li>two</li></ul></body></html>";
+            let url = serve_once("text/html; charset=utf-8", html).await;
+            let text = FetchTool {
+                allow_private: true,
+            }
+            .execute(json!({ "url": url }))
+            .await
+            .unwrap();
+            assert_eq!(text, "Docs\nHello & welcome\nFirst para\none\ntwo");
+
+            let url = serve_once("text/plain", "0123456789").await;
+            let text = FetchTool {
+                allow_private: true,
+            }
+            .execute(json!({ "url": url, "max_bytes": 4 }))
+            .await
+            .unwrap();
+            assert_eq!(text, "0123\n\n... (download stopped after 4 bytes)");
+        });
+    }
+}
@@ -0,0 +1,309 @@
+//! Glob tool implementation.
+//!
+//! Finds files whose path (relative to the search root) matches a glob pattern
+//! and returns them newest first. Supports `*`, `?`, `[...]`, `**` for any number
+//! of directories and `{a,b}` alternation. Hidden entries and build/dependency
+//! directories are skipped like in `grep`.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::grep::SKIP_DIRS;
+use super::Tool;
+
+pub struct GlobTool;
+
+const MAX_ENTRIES: usize = 500;
+
+#[async_trait]
+impl Tool for GlobTool {
+    fn name(&self) -> &str {
+        "glob"
+    }
+
+    fn description(&self) -> &str {
+        "Find files by glob pattern, e.g. **/*.rs or src/{lib,main}.rs. \
+         Returns matching paths sorted by modification time (newest first), one per line. \
+         Faster than listing whole trees with list_directory."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "Glob pattern relative to path; supports *, ?, [abc], ** and {a,b}"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Directory to search from (default: .)"
+                }
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    fn is_retryable(&self, _params: &serde_json::Value) -> bool {
+        true
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let pattern = params
+            .get("pattern")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: pattern")?;
+        let path = params.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+
+        let root = Path::new(path);
+        if !root.is_dir() {
+            anyhow::bail!("Path is not a directory: {}", path);
+        }
+        let patterns = expand_braces(pattern.trim_start_matches("./"));
+
+        let mut found: Vec<(SystemTime, PathBuf)> = Vec::new();
+        collect_matches(root, Path::new(""), &patterns, &mut found)
+            .with_context(|| format!("Failed to read directory: {}", path))?;
+        if found.is_empty() {
+            return Ok(format!("No files matching '{}' in {}", pattern, path));
+        }
+        found.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        let truncated = found.len() > MAX_ENTRIES;
+        found.truncate(MAX_ENTRIES);
+        let mut output = String::new();
+        for (_, rel) in &found {
+            let shown = if path == "." {
+                rel.clone()
+            } else {
+                root.join(rel)
+            };
+            output.push_str(&shown.display().to_string());
+            output.push('\n');
+        }
+        if truncated {
+            output.push_str(&format!("... (truncated at {} entries)\n", MAX_ENTRIES));
+        }
+        Ok(output)
+    }
+}
+
+fn collect_matches(
+    root: &Path,
+    rel: &Path,
+    patterns: &[String],
+    found: &mut Vec<(SystemTime, PathBuf)>,
+) -> Result<()> {
+    for entry in std::fs::read_dir(root.join(rel))?.filter_map(|e| e.ok()) {
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        if name_str.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let child = rel.join(&name);
+        if file_type.is_dir() {
+            if !SKIP_DIRS.contains(&name_str.as_ref()) {
+                collect_matches(root, &child, patterns, found)?;
+            }
+        } else if file_type.is_file() {
+            let child_str = child.to_string_lossy().replace('\\', "/");
+            if patterns.iter().any(|p| glob_match(p, &child_str)) {
+                let modified = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                found.push((modified, child));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Expand `{a,b}` alternation (nesting allowed) into plain glob patterns.
+pub fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    // Find the matching close brace and the top-level commas inside it
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut close = None;
+    for (i, c) in pattern[open..].char_indices() {
+        let i = open + i;
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => commas.push(i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        // Unbalanced: treat the brace literally
+        return vec![pattern.to_string()];
+    };
+    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+    let mut bounds = vec![open];
+    bounds.extend(&commas);
+    bounds.push(close);
+    bounds
+        .windows(2)
+        .flat_map(|w| expand_braces(&format!("{}{}{}", prefix, &pattern[w[0] + 1..w[1]], suffix)))
+        .collect()
+}
+
+/// Match a `/`-separated path against a brace-free glob pattern. `**` as a whole
+/// segment matches any number of directories (including none).
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                segment_match(first, name) && match_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match one path segment against `*`, `?` and `[...]` (with `!`/`^` negation and ranges).
+fn segment_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Pattern position after the last `*` and the name position it now covers up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(p + 1),
+            Some('[') => match_class(&pattern, p, name[n]),
+            Some(&c) if c == name[n] => Some(p + 1),
+            _ => None,
+        };
+        match (step, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                n += 1;
+            }
+            (None, Some((bp, bn))) => {
+                p = bp;
+                n = bn + 1;
+                backtrack = Some((bp, bn + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Match `c` against the class starting at `pattern[start] == '['`; returns the
+/// position after the class on a hit. An unclosed `[` is a literal.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+    let Some(len) = pattern[start + 1..]
+        .iter()
+        .skip(1)
+        .position(|&ch| ch == ']')
+    else {
+        return (c == '[').then_some(start + 1);
+    };
+    let end = start + 2 + len;
+    let mut body = &pattern[start + 1..end];
+    let negated = matches!(body.first(), Some('!' | '^'));
+    if negated {
+        body = &body[1..];
+    }
+    let mut hit = false;
+    let mut i = 0;
+    while i < body.len() {
+        if i + 2 < body.len() && body[i + 1] == '-' {
+            hit |= body[i] <= c && c <= body[i + 2];
+            i += 3;
+        } else {
+            hit |= body[i] == c;
+            i += 1;
+        }
+    }
+    (hit != negated).then_some(end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        expand_braces(pattern).iter().any(|p| glob_match(p, path))
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(matches("**/*.rs", "main.rs"));
+        assert!(matches("**/*.rs", "src/tools/glob.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("src/**/mod.rs", "src/mod.rs"));
+        assert!(matches("src/{lib,main}.rs", "src/main.rs"));
+        assert!(!matches("src/{lib,main}.rs", "src/glob.rs"));
+        assert!(matches("**/*.{toml,{md,txt}}", "docs/ROADMAP.md"));
+        assert!(matches("test_?.py", "test_a.py"));
+        assert!(matches("[a-c]*.txt", "b1.txt"));
+        assert!(!matches("[!a-c]*.txt", "b1.txt"));
+        assert!(matches("*_test*", "foo_test_bar.go"));
+        assert!(!matches("Cargo.*", "cargo.toml"));
+        assert!(matches("a{b", "a{b"));
+    }
+
+    #[test]
+    fn test_glob_tool_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/tools")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("target/build.rs"), "").unwrap();
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+        let old = std::fs::File::create(dir.path().join("src/main.rs")).unwrap();
+        old.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000))
+            .unwrap();
+        std::fs::write(dir.path().join("src/tools/glob.rs"), "").unwrap();
+
+        let root = dir.path().to_str().unwrap();
+        let result = rt()
+            .block_on(GlobTool.execute(json!({"pattern": "**/*.rs", "path": root})))
+            .unwrap();
+        let expected = format!(
+            "{}\n{}\n",
+            dir.path().join("src/tools/glob.rs").display(),
+            dir.path().join("src/main.rs").display()
+        );
+        assert_eq!(result, expected);
+
+        let none = rt()
+            .block_on(GlobTool.execute(json!({"pattern": "*.py", "path": root})))
+            .unwrap();
+        assert!(none.starts_with("No files matching"));
+    }
+}
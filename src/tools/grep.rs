@@ -0,0 +1,231 @@
+//! Grep tool implementation.
+//!
+//! Searches file contents for a regular expression and returns matching lines
+//! as `file:line:content`, so the LLM doesn't depend on `rg`/`grep` being
+//! installed. Hidden entries and build/dependency directories are skipped.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+use super::glob::{expand_braces, glob_match};
+use super::regex::Regex;
+use super::Tool;
+
+pub struct GrepTool;
+
+const MAX_MATCHES: usize = 200;
+/// Matched lines longer than this are cut so minified files don't flood the context.
+const MAX_LINE_CHARS: usize = 300;
+/// Larger files are skipped rather than read into memory.
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+/// Directories never searched (also skipped by `glob`).
+pub const SKIP_DIRS: &[&str] = &["target", "node_modules"];
+
+#[async_trait]
+impl Tool for GrepTool {
+    fn name(&self) -> &str {
+        "grep"
+    }
+
+    fn description(&self) -> &str {
+        "Search file contents with a regular expression. \
+         Walks a directory recursively (skipping hidden, target and node_modules) \
+         and returns matching lines as file:line:content. \
+         Prefer this over running grep/rg via bash."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "Regular expression to search for (prefix with (?i) to ignore case)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to search (default: .)"
+                },
+                "glob": {
+                    "type": "string",
+                    "description": "Only search files whose name matches this glob, e.g. *.rs or *.{ts,tsx}"
+                }
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    fn is_retryable(&self, _params: &serde_json::Value) -> bool {
+        true
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let pattern = params
+            .get("pattern")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: pattern")?;
+        let path = params.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+        let globs = params
+            .get("glob")
+            .and_then(|v| v.as_str())
+            .map(expand_braces);
+
+        let regex = Regex::new(pattern)?;
+        let root = Path::new(path);
+        if !root.exists() {
+            anyhow::bail!("Path does not exist: {}", path);
+        }
+
+        let mut files = Vec::new();
+        if root.is_dir() {
+            collect_files(root, globs.as_deref(), &mut files)
+                .with_context(|| format!("Failed to read directory: {}", path))?;
+            files.sort();
+        } else {
+            files.push(root.to_path_buf());
+        }
+
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        'files: for file in &files {
+            let too_large = std::fs::metadata(file).is_ok_and(|m| m.len() > MAX_FILE_BYTES);
+            if too_large {
+                continue;
+            }
+            // Binary and non-UTF-8 files are skipped
+            let Ok(content) = std::fs::read_to_string(file) else {
+                continue;
+            };
+            if content.contains('\0') {
+                continue;
+            }
+            for (idx, line) in content.lines().enumerate() {
+                if !regex.is_match(line) {
+                    continue;
+                }
+                if matches.len() >= MAX_MATCHES {
+                    truncated = true;
+                    break 'files;
+                }
+                matches.push(format!(
+                    "{}:{}:{}",
+                    file.display(),
+                    idx + 1,
+                    truncate_line(line)
+                ));
+            }
+        }
+
+        if matches.is_empty() {
+            return Ok(format!("No matches for '{}' in {}", pattern, path));
+        }
+        let mut output = matches.join("\n");
+        output.push('\n');
+        if truncated {
+            output.push_str(&format!(
+                "... (truncated at {} matches; narrow the pattern, path or glob)\n",
+                MAX_MATCHES
+            ));
+        }
+        Ok(output)
+    }
+}
+
+fn collect_files(dir: &Path, globs: Option<&[String]>, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !SKIP_DIRS.contains(&name.as_ref()) {
+                collect_files(&entry.path(), globs, files)?;
+            }
+        } else if file_type.is_file()
+            && globs.is_none_or(|globs| globs.iter().any(|g| glob_match(g, &name)))
+        {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+fn truncate_line(line: &str) -> String {
+    let line = line.trim_end();
+    if line.chars().count() <= MAX_LINE_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(MAX_LINE_CHARS).collect();
+    format!("{}…", cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    #[test]
+    fn test_grep_directory_with_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(
+            dir.path().join("src/main.rs"),
+            "fn main() {\n    helper();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("src/notes.txt"), "fn main in prose\n").unwrap();
+        std::fs::write(dir.path().join("target/gen.rs"), "fn main() {}\n").unwrap();
+
+        let root = dir.path().to_str().unwrap();
+        let result = rt()
+            .block_on(GrepTool.execute(json!({
+                "pattern": r"^fn \w+\(",
+                "path": root,
+                "glob": "*.rs"
+            })))
+            .unwrap();
+        let expected = format!(
+            "{}:1:fn main() {{\n",
+            dir.path().join("src/main.rs").display()
+        );
+        assert_eq!(result, expected);
+
+        let none = rt()
+            .block_on(GrepTool.execute(json!({"pattern": "absent", "path": root})))
+            .unwrap();
+        assert!(none.starts_with("No matches"));
+    }
+
+    #[test]
+    fn test_grep_caps_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("many.txt");
+        std::fs::write(&file, "hit\n".repeat(MAX_MATCHES + 10)).unwrap();
+        let result = rt()
+            .block_on(GrepTool.execute(json!({"pattern": "hit", "path": file.to_str().unwrap()})))
+            .unwrap();
+        assert_eq!(
+            result.lines().filter(|l| l.ends_with(":hit")).count(),
+            MAX_MATCHES
+        );
+        assert!(result.contains("truncated at 200 matches"));
+    }
+
+    #[test]
+    fn test_grep_invalid_pattern() {
+        let err = rt()
+            .block_on(GrepTool.execute(json!({"pattern": "(unclosed"})))
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid regex"));
+    }
+}
@@ -0,0 +1,721 @@
+//! Hash tool implementation.
+//!
+//! Computes SHA-256, MD5 or BLAKE3 digests of a file, or of every file under
+//! a directory (up to [`MAX_FILES`]). Files are streamed in fixed-size blocks
+//! so large files are never loaded into memory. The digest algorithms are
+//! implemented here to avoid pulling in a crypto crate for a read-only tool.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+use super::Tool;
+
+pub struct HashTool;
+
+/// Most files hashed for a directory; the rest are counted but skipped.
+const MAX_FILES: usize = 200;
+const READ_BUF_SIZE: usize = 64 * 1024;
+
+#[async_trait]
+impl Tool for HashTool {
+    fn name(&self) -> &str {
+        "hash_file"
+    }
+
+    fn description(&self) -> &str {
+        "Compute the hash of a file (sha256, md5 or blake3). \
+         For a directory, returns one hash per file (sha256sum-style lines). \
+         Use to verify downloads or detect changes."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The file or directory to hash"
+                },
+                "algorithm": {
+                    "type": "string",
+                    "enum": ["sha256", "md5", "blake3"],
+                    "description": "Hash algorithm (default: sha256)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn is_retryable(&self, _params: &serde_json::Value) -> bool {
+        true
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: path")?;
+        let algorithm = match params.get("algorithm").and_then(|v| v.as_str()) {
+            None => Algorithm::Sha256,
+            Some(name) => Algorithm::parse(name)?,
+        };
+
+        let root = Path::new(path);
+        if !root.exists() {
+            anyhow::bail!("Path does not exist: {}", path);
+        }
+        if !root.is_dir() {
+            let digest = hash_file(root, algorithm).await?;
+            return Ok(format!("{}  {}", digest, path));
+        }
+
+        let mut files = Vec::new();
+        collect_files(root, &mut files)
+            .with_context(|| format!("Failed to read directory: {}", path))?;
+        files.sort();
+        if files.is_empty() {
+            return Ok(format!("(no files under {})", path));
+        }
+        let mut lines = Vec::new();
+        for file in files.iter().take(MAX_FILES) {
+            let digest = hash_file(file, algorithm).await?;
+            let rel = file.strip_prefix(root).unwrap_or(file);
+            lines.push(format!("{}  {}", digest, rel.display()));
+        }
+        if files.len() > MAX_FILES {
+            lines.push(format!(
+                "({} files, hashed first {})",
+                files.len(),
+                MAX_FILES
+            ));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Algorithm {
+    Sha256,
+    Md5,
+    Blake3,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(Self::Sha256),
+            "md5" => Ok(Self::Md5),
+            "blake3" => Ok(Self::Blake3),
+            _ => anyhow::bail!(
+                "Unsupported algorithm: {} (expected sha256, md5 or blake3)",
+                name
+            ),
+        }
+    }
+
+    fn hasher(self) -> Box<dyn Digest + Send> {
+        match self {
+            Self::Sha256 => Box::new(Sha256::new()),
+            Self::Md5 => Box::new(Md5::new()),
+            Self::Blake3 => Box::new(Blake3::new()),
+        }
+    }
+}
+
+/// Regular files under `dir`, recursively. Symlinks are not followed.
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), out)?;
+        } else if file_type.is_file() {
+            out.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Stream `path` through the hasher and return the lowercase hex digest.
+async fn hash_file(path: &Path, algorithm: Algorithm) -> Result<String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut hasher = algorithm.hasher();
+    let mut buf = vec![0u8; READ_BUF_SIZE];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Incremental digest over a byte stream.
+trait Digest {
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+}
+
+/// Buffers input into 64-byte blocks for the Merkle–Damgård hashes (SHA-256, MD5).
+struct BlockBuffer {
+    block: [u8; 64],
+    len: usize,
+    total: u64,
+}
+
+impl BlockBuffer {
+    fn new() -> Self {
+        Self {
+            block: [0; 64],
+            len: 0,
+            total: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.total += data.len() as u64;
+        while !data.is_empty() {
+            let take = (64 - self.len).min(data.len());
+            self.block[self.len..self.len + take].copy_from_slice(&data[..take]);
+            self.len += take;
+            data = &data[take..];
+            if self.len == 64 {
+                compress(&self.block);
+                self.len = 0;
+            }
+        }
+    }
+
+    /// Append the 0x80 terminator, zero padding and the bit length.
+    fn pad(&mut self, big_endian: bool, mut compress: impl FnMut(&[u8; 64])) {
+        let bit_len = self.total.wrapping_mul(8);
+        self.block[self.len] = 0x80;
+        self.block[self.len + 1..].fill(0);
+        if self.len >= 56 {
+            compress(&self.block);
+            self.block.fill(0);
+        }
+        let len_bytes = if big_endian {
+            bit_len.to_be_bytes()
+        } else {
+            bit_len.to_le_bytes()
+        };
+        self.block[56..].copy_from_slice(&len_bytes);
+        compress(&self.block);
+    }
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 initial hash value; BLAKE3 reuses it as its IV.
+const SHA256_H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+struct Sha256 {
+    state: [u32; 8],
+    buffer: BlockBuffer,
+}
+
+impl Sha256 {
+    fn new() -> Self {
+        Self {
+            state: SHA256_H0,
+            buffer: BlockBuffer::new(),
+        }
+    }
+
+    fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+}
+
+impl Digest for Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.buffer
+            .update(data, |block| Self::compress(state, block));
+    }
+
+    fn finalize(mut self: Box<Self>) -> Vec<u8> {
+        let state = &mut self.state;
+        self.buffer.pad(true, |block| Self::compress(state, block));
+        self.state.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+}
+
+const MD5_S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+struct Md5 {
+    state: [u32; 4],
+    buffer: BlockBuffer,
+}
+
+impl Md5 {
+    fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            buffer: BlockBuffer::new(),
+        }
+    }
+
+    fn compress(state: &mut [u32; 4], block: &[u8; 64]) {
+        let mut m = [0u32; 16];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            m[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = *state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(MD5_K[i])
+                .wrapping_add(m[g])
+                .rotate_left(MD5_S[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+}
+
+impl Digest for Md5 {
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.buffer
+            .update(data, |block| Self::compress(state, block));
+    }
+
+    fn finalize(mut self: Box<Self>) -> Vec<u8> {
+        let state = &mut self.state;
+        self.buffer.pad(false, |block| Self::compress(state, block));
+        self.state.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+}
+
+const BLAKE3_CHUNK_LEN: usize = 1024;
+const BLAKE3_CHUNK_START: u32 = 1;
+const BLAKE3_CHUNK_END: u32 = 2;
+const BLAKE3_PARENT: u32 = 4;
+const BLAKE3_ROOT: u32 = 8;
+const BLAKE3_MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+fn blake3_g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, mx: u32, my: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(mx);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(my);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn blake3_compress(
+    cv: &[u32; 8],
+    block: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut state = [
+        cv[0],
+        cv[1],
+        cv[2],
+        cv[3],
+        cv[4],
+        cv[5],
+        cv[6],
+        cv[7],
+        SHA256_H0[0],
+        SHA256_H0[1],
+        SHA256_H0[2],
+        SHA256_H0[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+    let mut m = *block;
+    for round in 0..7 {
+        blake3_g(&mut state, 0, 4, 8, 12, m[0], m[1]);
+        blake3_g(&mut state, 1, 5, 9, 13, m[2], m[3]);
+        blake3_g(&mut state, 2, 6, 10, 14, m[4], m[5]);
+        blake3_g(&mut state, 3, 7, 11, 15, m[6], m[7]);
+        blake3_g(&mut state, 0, 5, 10, 15, m[8], m[9]);
+        blake3_g(&mut state, 1, 6, 11, 12, m[10], m[11]);
+        blake3_g(&mut state, 2, 7, 8, 13, m[12], m[13]);
+        blake3_g(&mut state, 3, 4, 9, 14, m[14], m[15]);
+        if round < 6 {
+            m = std::array::from_fn(|i| m[BLAKE3_MSG_PERMUTATION[i]]);
+        }
+    }
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= cv[i];
+    }
+    state
+}
+
+fn first_8_words(words: [u32; 16]) -> [u32; 8] {
+    std::array::from_fn(|i| words[i])
+}
+
+/// Inputs to a pending compression whose flags (ROOT or not) are not yet known.
+struct Blake3Output {
+    cv: [u32; 8],
+    block: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Blake3Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8_words(blake3_compress(
+            &self.cv,
+            &self.block,
+            self.counter,
+            self.block_len,
+            self.flags,
+        ))
+    }
+
+    fn root_hash(&self) -> Vec<u8> {
+        let words = blake3_compress(
+            &self.cv,
+            &self.block,
+            0,
+            self.block_len,
+            self.flags | BLAKE3_ROOT,
+        );
+        words[..8].iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    fn parent(left: [u32; 8], right: [u32; 8]) -> Self {
+        let mut block = [0u32; 16];
+        block[..8].copy_from_slice(&left);
+        block[8..].copy_from_slice(&right);
+        Self {
+            cv: SHA256_H0,
+            block,
+            counter: 0,
+            block_len: 64,
+            flags: BLAKE3_PARENT,
+        }
+    }
+}
+
+fn words_from_le_bytes(bytes: &[u8; 64]) -> [u32; 16] {
+    std::array::from_fn(|i| {
+        u32::from_le_bytes([
+            bytes[4 * i],
+            bytes[4 * i + 1],
+            bytes[4 * i + 2],
+            bytes[4 * i + 3],
+        ])
+    })
+}
+
+struct Blake3ChunkState {
+    cv: [u32; 8],
+    chunk_counter: u64,
+    block: [u8; 64],
+    block_len: usize,
+    blocks_compressed: usize,
+}
+
+impl Blake3ChunkState {
+    fn new(chunk_counter: u64) -> Self {
+        Self {
+            cv: SHA256_H0,
+            chunk_counter,
+            block: [0; 64],
+            block_len: 0,
+            blocks_compressed: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        64 * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            BLAKE3_CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // Only compress a full block once more input arrives: the last
+            // block of a chunk is compressed with CHUNK_END by `output`.
+            if self.block_len == 64 {
+                self.cv = first_8_words(blake3_compress(
+                    &self.cv,
+                    &words_from_le_bytes(&self.block),
+                    self.chunk_counter,
+                    64,
+                    self.start_flag(),
+                ));
+                self.blocks_compressed += 1;
+                self.block = [0; 64];
+                self.block_len = 0;
+            }
+            let take = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&data[..take]);
+            self.block_len += take;
+            data = &data[take..];
+        }
+    }
+
+    fn output(&self) -> Blake3Output {
+        Blake3Output {
+            cv: self.cv,
+            block: words_from_le_bytes(&self.block),
+            counter: self.chunk_counter,
+            block_len: self.block_len as u32,
+            flags: self.start_flag() | BLAKE3_CHUNK_END,
+        }
+    }
+}
+
+/// Unkeyed BLAKE3 with a 32-byte output.
+struct Blake3 {
+    chunk: Blake3ChunkState,
+    /// Chaining values of completed subtrees, merged as chunks complete.
+    cv_stack: Vec<[u32; 8]>,
+}
+
+impl Blake3 {
+    fn new() -> Self {
+        Self {
+            chunk: Blake3ChunkState::new(0),
+            cv_stack: Vec::new(),
+        }
+    }
+
+    fn push_chunk_cv(&mut self, mut cv: [u32; 8], mut total_chunks: u64) {
+        while total_chunks & 1 == 0 {
+            let left = self.cv_stack.pop().expect("subtree stack underflow");
+            cv = Blake3Output::parent(left, cv).chaining_value();
+            total_chunks >>= 1;
+        }
+        self.cv_stack.push(cv);
+    }
+}
+
+impl Digest for Blake3 {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.chunk.len() == BLAKE3_CHUNK_LEN {
+                let cv = self.chunk.output().chaining_value();
+                let total_chunks = self.chunk.chunk_counter + 1;
+                self.push_chunk_cv(cv, total_chunks);
+                self.chunk = Blake3ChunkState::new(total_chunks);
+            }
+            let take = (BLAKE3_CHUNK_LEN - self.chunk.len()).min(data.len());
+            self.chunk.update(&data[..take]);
+            data = &data[take..];
+        }
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        let mut output = self.chunk.output();
+        for left in self.cv_stack.iter().rev() {
+            output = Blake3Output::parent(*left, output.chaining_value());
+        }
+        output.root_hash()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    fn hex(algorithm: Algorithm, data: &[u8]) -> String {
+        let mut hasher = algorithm.hasher();
+        // Feed in uneven pieces to exercise block buffering.
+        for piece in data.chunks(7) {
+            hasher.update(piece);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    #[test]
+    fn test_known_digests() {
+        assert_eq!(
+            hex(Algorithm::Sha256, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha256, b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(
+                Algorithm::Sha256,
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            ),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(hex(Algorithm::Md5, b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            hex(
+                Algorithm::Md5,
+                b"The quick brown fox jumps over the lazy dog"
+            ),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+        assert_eq!(
+            hex(Algorithm::Blake3, b""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+        assert_eq!(
+            hex(Algorithm::Blake3, b"abc"),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+    }
+
+    #[test]
+    fn test_hash_file_sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        std::fs::write(&path, "hello world\n").unwrap();
+        let result = rt()
+            .block_on(HashTool.execute(json!({ "path": path.to_str().unwrap() })))
+            .unwrap();
+        assert_eq!(
+            result,
+            format!(
+                "a948904f2f0f479b8f8197694b30184b0d2ed1c1cd2a1ec0fb85d299a192a447  {}",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_hash_directory_lists_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "").unwrap();
+        std::fs::write(dir.path().join("sub/b.txt"), "").unwrap();
+        let result = rt()
+            .block_on(HashTool.execute(json!({
+                "path": dir.path().to_str().unwrap(),
+                "algorithm": "md5"
+            })))
+            .unwrap();
+        let empty = "d41d8cd98f00b204e9800998ecf8427e";
+        let sub_b = Path::new("sub").join("b.txt");
+        assert_eq!(
+            result,
+            format!("{empty}  a.txt\n{empty}  {}", sub_b.display())
+        );
+    }
+
+    #[test]
+    fn test_missing_path_and_bad_algorithm() {
+        let err = rt()
+            .block_on(HashTool.execute(json!({ "path": "/nonexistent/file.bin" })))
+            .unwrap_err();
+        assert!(err.to_string().contains("Path does not exist"), "{}", err);
+
+        let err = rt()
+            .block_on(HashTool.execute(json!({ "path": ".", "algorithm": "crc32" })))
+            .unwrap_err();
+        assert!(err.to_string().contains("Unsupported algorithm"), "{}", err);
+    }
+}
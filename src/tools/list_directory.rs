@@ -1,13 +1,17 @@
 //! List Directory tool implementation.
 //!
 //! Lists files and subdirectories within a given path, with optional
-//! recursive traversal up to a configurable depth.
+//! recursive traversal up to a configurable depth. `.gitignore` files found
+//! along the way, in parent directories up to the repository root, and the
+//! repository's `.git/info/exclude` prune matching entries unless
+//! `respect_gitignore` is false.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde_json::json;
 use std::path::Path;
 
+use super::glob::glob_match;
 use super::Tool;
 
 pub struct ListDirectoryTool;
@@ -42,12 +46,20 @@ impl Tool for ListDirectoryTool {
                 "max_depth": {
                     "type": "integer",
                     "description": "Maximum recursion depth (default: 3, only used when recursive is true)"
+                },
+                "respect_gitignore": {
+                    "type": "boolean",
+                    "description": "Skip paths matched by .gitignore files (default: true)"
                 }
             },
             "required": ["path"]
         })
     }
 
+    fn is_retryable(&self, _params: &serde_json::Value) -> bool {
+        true
+    }
+
     async fn execute(&self, params: serde_json::Value) -> Result<String> {
         let path = params
             .get("path")
@@ -65,6 +77,11 @@ impl Tool for ListDirectoryTool {
             .map(|v| v as u32)
             .unwrap_or(DEFAULT_MAX_DEPTH);
 
+        let respect_gitignore = params
+            .get("respect_gitignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
         let dir_path = Path::new(path);
         if !dir_path.exists() {
             anyhow::bail!("Path does not exist: {}", path);
@@ -74,7 +91,17 @@ impl Tool for ListDirectoryTool {
         }
 
         let mut entries = Vec::new();
-        collect_entries(dir_path, dir_path, recursive, max_depth, 0, &mut entries)?;
+        let walk = Walk {
+            recursive,
+            max_depth,
+            respect_gitignore,
+        };
+        let inherited = if respect_gitignore {
+            ancestor_rules(dir_path)
+        } else {
+            Vec::new()
+        };
+        walk.collect_entries(dir_path, "", 0, &inherited, &mut entries)?;
 
         if entries.is_empty() {
             return Ok(format!("{} (empty directory)", path));
@@ -98,61 +125,183 @@ impl Tool for ListDirectoryTool {
     }
 }
 
-fn collect_entries(
-    base: &Path,
-    dir: &Path,
+struct Walk {
     recursive: bool,
     max_depth: u32,
-    current_depth: u32,
-    entries: &mut Vec<String>,
-) -> Result<()> {
-    let mut dir_entries: Vec<_> = std::fs::read_dir(dir)
-        .with_context(|| format!("Failed to read directory: {}", dir.display()))?
-        .filter_map(|e| e.ok())
-        .collect();
+    respect_gitignore: bool,
+}
 
-    dir_entries.sort_by_key(|e| e.file_name());
+impl Walk {
+    /// `rel` is `dir` relative to the listing root (`""` or ending in `/`);
+    /// `rules` are the ignore rules inherited from parent directories.
+    fn collect_entries(
+        &self,
+        dir: &Path,
+        rel: &str,
+        current_depth: u32,
+        rules: &[IgnoreRule],
+        entries: &mut Vec<String>,
+    ) -> Result<()> {
+        let mut dir_entries: Vec<_> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read directory: {}", dir.display()))?
+            .filter_map(|e| e.ok())
+            .collect();
+
+        dir_entries.sort_by_key(|e| e.file_name());
+
+        let mut rules = rules.to_vec();
+        if self.respect_gitignore {
+            if let Ok(content) = std::fs::read_to_string(dir.join(".gitignore")) {
+                rules.extend(parse_gitignore(&content, rel, ""));
+            }
+        }
 
-    let indent = "  ".repeat(current_depth as usize);
+        let indent = "  ".repeat(current_depth as usize);
 
-    for entry in dir_entries {
-        if entries.len() >= MAX_ENTRIES {
-            return Ok(());
-        }
+        for entry in dir_entries {
+            if entries.len() >= MAX_ENTRIES {
+                return Ok(());
+            }
 
-        let file_name = entry.file_name();
-        let name = file_name.to_string_lossy();
+            let file_name = entry.file_name();
+            let name = file_name.to_string_lossy();
 
-        // Skip hidden files/dirs at depth 0 to reduce noise
-        if current_depth == 0 && name.starts_with('.') {
-            continue;
-        }
+            // Skip hidden files/dirs at depth 0 to reduce noise
+            if current_depth == 0 && name.starts_with('.') {
+                continue;
+            }
 
-        let metadata = entry.metadata();
-        let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
-
-        if is_dir {
-            entries.push(format!("{}📁 {}/", indent, name));
-            if recursive && current_depth < max_depth {
-                collect_entries(
-                    base,
-                    &entry.path(),
-                    recursive,
-                    max_depth,
-                    current_depth + 1,
-                    entries,
-                )?;
+            let metadata = entry.metadata();
+            let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+            let entry_rel = format!("{}{}", rel, name);
+            if is_ignored(&rules, &entry_rel, is_dir) {
+                continue;
             }
+
+            if is_dir {
+                entries.push(format!("{}📁 {}/", indent, name));
+                if self.recursive && current_depth < self.max_depth {
+                    self.collect_entries(
+                        &entry.path(),
+                        &format!("{}/", entry_rel),
+                        current_depth + 1,
+                        &rules,
+                        entries,
+                    )?;
+                }
+            } else {
+                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+                entries.push(format!("{}  {} ({})", indent, name, format_size(size)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rules from `.git/info/exclude` and the `.gitignore` files between the
+/// repository root and `dir` (exclusive), outermost first so deeper files win.
+/// Empty when `dir` is not inside a git repository.
+fn ancestor_rules(dir: &Path) -> Vec<IgnoreRule> {
+    let Ok(dir) = dir.canonicalize() else {
+        return Vec::new();
+    };
+    let Some(root) = dir.ancestors().find(|a| a.join(".git").exists()) else {
+        return Vec::new();
+    };
+    // Path from `ancestor` down to the listing root, as a rule prefix
+    let prefix = |ancestor: &Path| {
+        let rel = dir.strip_prefix(ancestor).unwrap_or(Path::new(""));
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        if rel.is_empty() {
+            rel
         } else {
-            let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-            entries.push(format!("{}  {} ({})", indent, name, format_size(size)));
+            format!("{}/", rel)
         }
+    };
+    let mut rules = Vec::new();
+    if let Ok(content) = std::fs::read_to_string(root.join(".git/info/exclude")) {
+        rules.extend(parse_gitignore(&content, "", &prefix(root)));
     }
+    let mut ancestors: Vec<&Path> = dir
+        .ancestors()
+        .skip(1)
+        .take_while(|a| a.starts_with(root))
+        .collect();
+    ancestors.reverse();
+    for ancestor in ancestors {
+        if let Ok(content) = std::fs::read_to_string(ancestor.join(".gitignore")) {
+            rules.extend(parse_gitignore(&content, "", &prefix(ancestor)));
+        }
+    }
+    rules
+}
 
-    Ok(())
+/// One `.gitignore` line, with its glob rewritten for `glob_match` relative to `base`.
+#[derive(Clone)]
+struct IgnoreRule {
+    /// For rules from above the listing root: the path from their directory down
+    /// to the listing root (ending in `/`), prepended before matching. Else `""`.
+    prefix: String,
+    /// Directory holding the `.gitignore`, relative to the listing root (`""` or ending in `/`)
+    base: String,
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
 }
 
-fn format_size(bytes: u64) -> String {
+/// Parse a `.gitignore` body: `#` comments, `!` negation, trailing `/` for
+/// directories only, and patterns without an inner `/` matching at any depth.
+fn parse_gitignore(content: &str, base: &str, prefix: &str) -> Vec<IgnoreRule> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            if line.is_empty() {
+                return None;
+            }
+            let pattern = match line.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if line.contains('/') => line.to_string(),
+                None => format!("**/{}", line),
+            };
+            Some(IgnoreRule {
+                prefix: prefix.to_string(),
+                base: base.to_string(),
+                pattern,
+                negated,
+                dir_only,
+            })
+        })
+        .collect()
+}
+
+/// Whether `rel` (relative to the listing root) is ignored; the last matching rule wins.
+fn is_ignored(rules: &[IgnoreRule], rel: &str, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| {
+            (is_dir || !rule.dir_only)
+                && format!("{}{}", rule.prefix, rel)
+                    .strip_prefix(rule.base.as_str())
+                    .is_some_and(|path| glob_match(&rule.pattern, path))
+        })
+        .is_some_and(|rule| !rule.negated)
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
     if bytes >= 1_048_576 {
         format!("{:.1} MB", bytes as f64 / 1_048_576.0)
     } else if bytes >= 1024 {
@@ -264,6 +413,69 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_gitignore_prunes_ignored_paths() {
+        let rt = rt();
+        rt.block_on(async {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+            std::fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+            std::fs::create_dir_all(dir.path().join("src/gen")).unwrap();
+            std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+            std::fs::write(dir.path().join("src/build.log"), "").unwrap();
+            std::fs::write(dir.path().join("src/keep.log"), "").unwrap();
+            std::fs::write(dir.path().join("src/.gitignore"), "/gen\n").unwrap();
+            let path = dir.path().to_str().unwrap();
+
+            let result = ListDirectoryTool
+                .execute(json!({ "path": path, "recursive": true }))
+                .await
+                .unwrap();
+            assert!(!result.contains("target/"), "{}", result);
+            assert!(!result.contains("debug/"), "{}", result);
+            assert!(!result.contains("build.log"), "{}", result);
+            assert!(!result.contains("gen/"), "{}", result);
+            assert!(result.contains("main.rs"));
+            assert!(result.contains("keep.log"));
+
+            let result = ListDirectoryTool
+                .execute(json!({ "path": path, "recursive": true, "respect_gitignore": false }))
+                .await
+                .unwrap();
+            assert!(result.contains("target/"));
+            assert!(result.contains("build.log"));
+            assert!(result.contains("gen/"));
+        });
+    }
+
+    #[test]
+    fn test_gitignore_from_parent_dirs_and_info_exclude() {
+        let rt = rt();
+        rt.block_on(async {
+            let repo = tempfile::tempdir().unwrap();
+            std::fs::create_dir_all(repo.path().join(".git/info")).unwrap();
+            std::fs::write(repo.path().join(".git/info/exclude"), "*.tmp\n").unwrap();
+            std::fs::write(repo.path().join(".gitignore"), "/crates/app/dist/\n*.log\n").unwrap();
+            let app = repo.path().join("crates/app");
+            std::fs::create_dir_all(app.join("dist")).unwrap();
+            std::fs::create_dir_all(app.join("src")).unwrap();
+            std::fs::write(repo.path().join("crates/.gitignore"), "!keep.log\n").unwrap();
+            for file in ["src/lib.rs", "src/debug.log", "keep.log", "scratch.tmp"] {
+                std::fs::write(app.join(file), "").unwrap();
+            }
+
+            let result = ListDirectoryTool
+                .execute(json!({ "path": app.to_str().unwrap(), "recursive": true }))
+                .await
+                .unwrap();
+            assert!(result.contains("lib.rs"), "{}", result);
+            assert!(!result.contains("dist/"), "{}", result);
+            assert!(!result.contains("debug.log"), "{}", result);
+            assert!(!result.contains("scratch.tmp"), "{}", result);
+            assert!(result.contains("keep.log"), "{}", result);
+        });
+    }
+
     #[test]
     fn test_format_size_units() {
         assert_eq!(format_size(0), "0 B");
@@ -0,0 +1,222 @@
+//! Multi-edit tool implementation.
+//!
+//! Applies several `edit`-style replacements to one file in a single call.
+//! Edits run in order against the in-memory content (each sees the result of
+//! the previous ones) and the file is written only if every edit matched, so
+//! a multi-part change is never left half applied.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{Tool, ToolOutput};
+
+pub struct MultiEditTool;
+
+#[async_trait]
+impl Tool for MultiEditTool {
+    fn name(&self) -> &str {
+        "multi_edit"
+    }
+
+    fn description(&self) -> &str {
+        "Apply several exact text replacements to one file at once. Edits are applied \
+         in order, each to the result of the previous one; if any old_text is not found \
+         the file is left unchanged. Prefer this over repeated edit calls on the same file."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The path to the file to edit"
+                },
+                "edits": {
+                    "type": "array",
+                    "description": "Replacements to apply in order",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "old_text": {
+                                "type": "string",
+                                "description": "The exact text to find (must match precisely)"
+                            },
+                            "new_text": {
+                                "type": "string",
+                                "description": "The text to replace old_text with"
+                            },
+                            "replace_all": {
+                                "type": "boolean",
+                                "description": "If true, replace all occurrences (default: false)"
+                            }
+                        },
+                        "required": ["old_text", "new_text"]
+                    }
+                }
+            },
+            "required": ["path", "edits"]
+        })
+    }
+
+    fn modified_paths(&self, params: &serde_json::Value) -> Vec<std::path::PathBuf> {
+        params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(|p| vec![std::path::PathBuf::from(p)])
+            .unwrap_or_default()
+    }
+
+    async fn execute_structured(&self, params: serde_json::Value) -> Result<ToolOutput> {
+        let files = self.modified_paths(&params);
+        let text = self.execute(params).await?;
+        Ok(ToolOutput {
+            text,
+            metadata: json!({ "files": files }),
+        })
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: path")?;
+        let edits = params
+            .get("edits")
+            .and_then(|v| v.as_array())
+            .context("Missing required parameter: edits")?;
+        if edits.is_empty() {
+            bail!("edits must contain at least one edit");
+        }
+
+        let mut content = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read file: {}", path))?;
+
+        let mut replaced = 0;
+        for (i, edit) in edits.iter().enumerate() {
+            let label = format!("Edit {} of {}", i + 1, edits.len());
+            let old_text = edit
+                .get("old_text")
+                .and_then(|v| v.as_str())
+                .with_context(|| format!("{}: missing old_text", label))?;
+            let new_text = edit
+                .get("new_text")
+                .and_then(|v| v.as_str())
+                .with_context(|| format!("{}: missing new_text", label))?;
+            let replace_all = edit
+                .get("replace_all")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            if old_text.is_empty() {
+                bail!(
+                    "{}: old_text must not be empty. No changes were written.",
+                    label
+                );
+            }
+
+            if !content.contains(old_text) {
+                let preview = if old_text.len() > 80 {
+                    format!("{}...", &old_text[..old_text.floor_char_boundary(80)])
+                } else {
+                    old_text.to_string()
+                };
+                bail!(
+                    "{}: old_text not found in {} (after applying the earlier edits). \
+                     No changes were written.\nSearched for: {:?}",
+                    label,
+                    path,
+                    preview
+                );
+            }
+            if replace_all {
+                replaced += content.matches(old_text).count();
+                content = content.replace(old_text, new_text);
+            } else {
+                replaced += 1;
+                content = content.replacen(old_text, new_text, 1);
+            }
+        }
+
+        tokio::fs::write(path, &content)
+            .await
+            .with_context(|| format!("Failed to write file: {}", path))?;
+
+        Ok(format!(
+            "Successfully applied {} edit(s) ({} replacement(s)) to {}",
+            edits.len(),
+            replaced,
+            path
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    #[test]
+    fn test_applies_edits_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("lib.rs");
+        std::fs::write(&file, "fn old() {}\nfn caller() { old(); old(); }\n").unwrap();
+
+        let result = rt()
+            .block_on(MultiEditTool.execute(json!({
+                "path": file.to_str().unwrap(),
+                "edits": [
+                    { "old_text": "fn old()", "new_text": "fn renamed()" },
+                    { "old_text": "old()", "new_text": "renamed()", "replace_all": true },
+                    { "old_text": "fn caller", "new_text": "pub fn caller" }
+                ]
+            })))
+            .unwrap();
+        assert!(
+            result.contains("3 edit(s) (4 replacement(s))"),
+            "{}",
+            result
+        );
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "fn renamed() {}\npub fn caller() { renamed(); renamed(); }\n"
+        );
+    }
+
+    #[test]
+    fn test_failed_edit_leaves_file_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "alpha beta\n").unwrap();
+
+        let err = rt()
+            .block_on(MultiEditTool.execute(json!({
+                "path": file.to_str().unwrap(),
+                "edits": [
+                    { "old_text": "alpha", "new_text": "ALPHA" },
+                    { "old_text": "gamma", "new_text": "GAMMA" }
+                ]
+            })))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("Edit 2 of 2: old_text not found"),
+            "{}",
+            err
+        );
+        assert!(err.contains("No changes were written"));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "alpha beta\n");
+
+        let err = rt()
+            .block_on(MultiEditTool.execute(json!({
+                "path": file.to_str().unwrap(),
+                "edits": []
+            })))
+            .unwrap_err();
+        assert!(err.to_string().contains("at least one edit"));
+    }
+}
@@ -18,6 +18,9 @@ use super::Tool;
 /// Tool that reads the contents of a file.
 pub struct ReadFileTool;
 
+/// Whole-file reads longer than this get a footer nudging the model to page.
+const SOFT_LINE_CAP: usize = 2000;
+
 #[async_trait]
 impl Tool for ReadFileTool {
     fn name(&self) -> &str {
@@ -26,7 +29,8 @@ impl Tool for ReadFileTool {
 
     fn description(&self) -> &str {
         "Read the contents of a file at the given path. \
-         Returns the full text content of the file."
+         Returns the full text content of the file, or a range of lines \
+         when offset and/or limit are given."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -39,24 +43,80 @@ impl Tool for ReadFileTool {
                 "path": {
                     "type": "string",
                     "description": "The path to the file to read"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "First line to return, 1-based (default: 1)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of lines to return (default: all)"
                 }
             },
             "required": ["path"]
         })
     }
 
+    fn is_retryable(&self, _params: &serde_json::Value) -> bool {
+        true
+    }
+
     async fn execute(&self, params: serde_json::Value) -> Result<String> {
         let path = params
             .get("path")
             .and_then(|v| v.as_str())
             .context("Missing required parameter: path")?;
 
+        let offset = params.get("offset").and_then(|v| v.as_u64());
+        let limit = params.get("limit").and_then(|v| v.as_u64());
+
         let content = tokio::fs::read_to_string(path)
             .await
             .with_context(|| format!("Failed to read file: {}", path))?;
 
-        Ok(content)
+        let total = content.lines().count();
+        if offset.is_none() && limit.is_none() {
+            if total > SOFT_LINE_CAP {
+                return Ok(format!(
+                    "{}\n[file has {} lines; use offset and limit to read it in parts]",
+                    content.trim_end_matches('\n'),
+                    total
+                ));
+            }
+            return Ok(content);
+        }
+        slice_lines(&content, total, offset.unwrap_or(1), limit)
+    }
+}
+
+/// Lines `offset..offset + limit` (1-based) of `content`, with a footer when
+/// that isn't the whole file.
+fn slice_lines(content: &str, total: usize, offset: u64, limit: Option<u64>) -> Result<String> {
+    let start = (offset.max(1) - 1) as usize;
+    if start >= total && total > 0 {
+        anyhow::bail!(
+            "offset {} is past the end of the file ({} lines)",
+            offset,
+            total
+        );
+    }
+    let take = limit.map_or(usize::MAX, |l| l as usize);
+    let selected: String = content
+        .split_inclusive('\n')
+        .skip(start)
+        .take(take)
+        .collect();
+    let shown = selected.lines().count();
+    if start == 0 && shown == total {
+        return Ok(selected);
     }
+    Ok(format!(
+        "{}\n[showing lines {}-{} of {}]",
+        selected.trim_end_matches('\n'),
+        start + 1,
+        start + shown,
+        total
+    ))
 }
 
 #[cfg(test)]
@@ -94,6 +154,58 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_read_line_range() {
+        let rt = rt();
+        rt.block_on(async {
+            let mut tmp = tempfile::NamedTempFile::new().unwrap();
+            for i in 1..=10 {
+                writeln!(tmp, "line {}", i).unwrap();
+            }
+            let path = tmp.path().to_str().unwrap();
+
+            let result = ReadFileTool
+                .execute(json!({ "path": path, "offset": 3, "limit": 2 }))
+                .await
+                .unwrap();
+            assert_eq!(result, "line 3\nline 4\n[showing lines 3-4 of 10]");
+
+            let tail = ReadFileTool
+                .execute(json!({ "path": path, "offset": 9 }))
+                .await
+                .unwrap();
+            assert_eq!(tail, "line 9\nline 10\n[showing lines 9-10 of 10]");
+
+            let all = ReadFileTool
+                .execute(json!({ "path": path, "limit": 50 }))
+                .await
+                .unwrap();
+            assert!(all.ends_with("line 10\n"));
+
+            let err = ReadFileTool
+                .execute(json!({ "path": path, "offset": 11 }))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("past the end"));
+        });
+    }
+
+    #[test]
+    fn test_large_file_footer() {
+        let rt = rt();
+        rt.block_on(async {
+            let mut tmp = tempfile::NamedTempFile::new().unwrap();
+            write!(tmp, "{}", "x\n".repeat(SOFT_LINE_CAP + 1)).unwrap();
+            let result = ReadFileTool
+                .execute(json!({ "path": tmp.path().to_str().unwrap() }))
+                .await
+                .unwrap();
+            assert!(
+                result.ends_with("[file has 2001 lines; use offset and limit to read it in parts]")
+            );
+        });
+    }
+
     #[test]
     fn test_read_nonexistent_file() {
         let rt = rt();
@@ -0,0 +1,606 @@
+//! Minimal regular expressions for the `grep` tool.
+//!
+//! Supports the commonly used subset: literals, `.`, character classes
+//! (`[a-z]`, `[^...]`), `\d \w \s` and their negations, anchors `^ $ \b \B`,
+//! groups `(...)` / `(?:...)`, alternation `|`, the quantifiers `* + ? {n} {n,} {n,m}`
+//! (with lazy `?` variants) and a leading `(?i)` for case-insensitive matching.
+//! Backreferences and lookaround are not supported.
+//!
+//! Patterns compile to a small instruction program run by a Pike VM, which tracks
+//! every possible match state at once: time is linear in the line length and no
+//! pattern can backtrack exponentially or exhaust the stack.
+
+use anyhow::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Perl {
+    Digit,
+    Word,
+    Space,
+}
+
+impl Perl {
+    fn matches(self, c: char) -> bool {
+        match self {
+            Perl::Digit => c.is_ascii_digit(),
+            Perl::Word => c.is_alphanumeric() || c == '_',
+            Perl::Space => c.is_whitespace(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ClassItem {
+    Range(char, char),
+    /// `\d`, `\w`, `\s`; `true` for the negated `\D`, `\W`, `\S`.
+    Perl(Perl, bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    /// `\b`; `true` for the negated `\B`.
+    WordBoundary(bool),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    /// Lazy quantifiers parse like greedy ones: only whether a line matches is
+    /// reported, and that doesn't depend on greediness.
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// Zero-width assertions checked while following epsilon transitions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assertion {
+    Start,
+    End,
+    WordBoundary(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Inst {
+    /// Consume one character matching a `Char`, `Any` or `Class` node.
+    Char(Node),
+    Assert(Assertion),
+    /// Continue at both targets.
+    Split(usize, usize),
+    Jmp(usize),
+    Match,
+}
+
+/// Upper bound on compiled instructions; counted repeats like `(a{100}){100}`
+/// expand, so cap them instead of allocating without limit.
+const MAX_PROGRAM_LEN: usize = 50_000;
+/// Deepest group nesting accepted.
+const MAX_GROUP_DEPTH: usize = 64;
+
+/// A compiled pattern.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    ignore_case: bool,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let (pattern, ignore_case) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (rest, true),
+            None => (pattern, false),
+        };
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            depth: 0,
+        };
+        let node = parser.parse_alt()?;
+        if parser.pos < parser.chars.len() {
+            anyhow::bail!("Invalid regex: unmatched ')' at position {}", parser.pos);
+        }
+        let mut program = Vec::new();
+        compile(&node, &mut program)?;
+        program.push(Inst::Match);
+        Ok(Self {
+            program,
+            ignore_case,
+        })
+    }
+
+    /// Whether the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        let input: Vec<char> = text.chars().collect();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        for pos in 0..=input.len() {
+            // Unanchored search: a new attempt starts at every position
+            if self.add_thread(&mut current, 0, &input, pos) {
+                return true;
+            }
+            let Some(&c) = input.get(pos) else {
+                break;
+            };
+            next.clear();
+            for &pc in &current.pcs {
+                if let Inst::Char(node) = &self.program[pc] {
+                    if self.match_char(node, c)
+                        && self.add_thread(&mut next, pc + 1, &input, pos + 1)
+                    {
+                        return true;
+                    }
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+
+    /// Add `pc` and everything reachable from it without consuming input to
+    /// `threads`. Returns true once `Match` is reachable.
+    fn add_thread(&self, threads: &mut Threads, pc: usize, input: &[char], pos: usize) -> bool {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if !threads.insert(pc) {
+                continue;
+            }
+            match &self.program[pc] {
+                Inst::Match => return true,
+                Inst::Char(_) => {}
+                Inst::Jmp(target) => stack.push(*target),
+                Inst::Split(a, b) => {
+                    // Reverse order so `a` is explored first
+                    stack.push(*b);
+                    stack.push(*a);
+                }
+                Inst::Assert(assertion) => {
+                    let holds = match assertion {
+                        Assertion::Start => pos == 0,
+                        Assertion::End => pos == input.len(),
+                        Assertion::WordBoundary(negated) => {
+                            let is_word =
+                                |i: Option<&char>| i.is_some_and(|&c| Perl::Word.matches(c));
+                            let before = pos.checked_sub(1).and_then(|i| input.get(i));
+                            (is_word(before) != is_word(input.get(pos))) != *negated
+                        }
+                    };
+                    if holds {
+                        stack.push(pc + 1);
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn match_char(&self, node: &Node, c: char) -> bool {
+        match node {
+            Node::Char(expected) => {
+                *expected == c || (self.ignore_case && expected.to_lowercase().eq(c.to_lowercase()))
+            }
+            Node::Any => c != '\n',
+            Node::Class { items, negated } => {
+                let hit = items.iter().any(|item| self.class_item_matches(item, c));
+                hit != *negated
+            }
+            _ => false,
+        }
+    }
+
+    fn class_item_matches(&self, item: &ClassItem, c: char) -> bool {
+        match item {
+            ClassItem::Range(lo, hi) => {
+                let in_range = |c: char| *lo <= c && c <= *hi;
+                in_range(c)
+                    || (self.ignore_case
+                        && (c.to_lowercase().any(in_range) || c.to_uppercase().any(in_range)))
+            }
+            ClassItem::Perl(perl, negated) => perl.matches(c) != *negated,
+        }
+    }
+}
+
+/// The set of live program counters at one input position, deduplicated.
+struct Threads {
+    pcs: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Self {
+            pcs: Vec::new(),
+            seen: vec![false; len],
+        }
+    }
+
+    fn insert(&mut self, pc: usize) -> bool {
+        if std::mem::replace(&mut self.seen[pc], true) {
+            return false;
+        }
+        self.pcs.push(pc);
+        true
+    }
+
+    fn clear(&mut self) {
+        for &pc in &self.pcs {
+            self.seen[pc] = false;
+        }
+        self.pcs.clear();
+    }
+}
+
+/// Append the instructions for `node` to `program`.
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Result<()> {
+    if program.len() > MAX_PROGRAM_LEN {
+        anyhow::bail!("Invalid regex: pattern too large (repeat counts expand too far)");
+    }
+    match node {
+        Node::Empty => {}
+        Node::Char(_) | Node::Any | Node::Class { .. } => program.push(Inst::Char(node.clone())),
+        Node::Start => program.push(Inst::Assert(Assertion::Start)),
+        Node::End => program.push(Inst::Assert(Assertion::End)),
+        Node::WordBoundary(negated) => {
+            program.push(Inst::Assert(Assertion::WordBoundary(*negated)))
+        }
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program)?;
+            }
+        }
+        Node::Alt(alternatives) => {
+            // split L1, next; L1: alt; jmp end; next: split ...; last alt; end:
+            let mut jumps = Vec::new();
+            for (i, alt) in alternatives.iter().enumerate() {
+                if i + 1 < alternatives.len() {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(alt, program)?;
+                    jumps.push(program.len());
+                    program.push(Inst::Jmp(0));
+                    program[split] = Inst::Split(split + 1, program.len());
+                } else {
+                    compile(alt, program)?;
+                }
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jmp(end);
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+            match max {
+                // Optional copies: split body, end
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile(node, program)?;
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, end);
+                    }
+                }
+                // loop: split body, end; body; jmp loop
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(0, 0));
+                    compile(node, program)?;
+                    program.push(Inst::Jmp(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Open groups at the cursor; bounded so parsing and compiling can recurse.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn parse_alt(&mut self) -> Result<Node> {
+        let mut alternatives = vec![self.parse_concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.parse_concat()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap_or(Node::Empty)
+        } else {
+            Node::Alt(alternatives)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap_or(Node::Empty),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node> {
+        let start = self.pos;
+        let Some(c) = self.next() else {
+            anyhow::bail!("Invalid regex: unexpected end of pattern");
+        };
+        Ok(match c {
+            '(' => {
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                if self.depth >= MAX_GROUP_DEPTH {
+                    anyhow::bail!(
+                        "Invalid regex: groups nested deeper than {}",
+                        MAX_GROUP_DEPTH
+                    );
+                }
+                self.depth += 1;
+                let inner = self.parse_alt()?;
+                self.depth -= 1;
+                if self.next() != Some(')') {
+                    anyhow::bail!("Invalid regex: unclosed group at position {}", start);
+                }
+                inner
+            }
+            '[' => self.parse_class(start)?,
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '\\' => self.parse_escape()?,
+            '*' | '+' | '?' => {
+                anyhow::bail!("Invalid regex: nothing to repeat at position {}", start)
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    fn parse_escape(&mut self) -> Result<Node> {
+        let Some(c) = self.next() else {
+            anyhow::bail!("Invalid regex: trailing backslash");
+        };
+        let perl = |perl, negated| Node::Class {
+            items: vec![ClassItem::Perl(perl, negated)],
+            negated: false,
+        };
+        Ok(match c {
+            'd' => perl(Perl::Digit, false),
+            'D' => perl(Perl::Digit, true),
+            'w' => perl(Perl::Word, false),
+            'W' => perl(Perl::Word, true),
+            's' => perl(Perl::Space, false),
+            'S' => perl(Perl::Space, true),
+            'b' => Node::WordBoundary(false),
+            'B' => Node::WordBoundary(true),
+            c => Node::Char(unescape(c)),
+        })
+    }
+
+    fn parse_class(&mut self, start: usize) -> Result<Node> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let Some(c) = self.next() else {
+                anyhow::bail!(
+                    "Invalid regex: unclosed character class at position {}",
+                    start
+                );
+            };
+            // A leading ']' is a literal
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = if c == '\\' {
+                let Some(e) = self.next() else {
+                    anyhow::bail!("Invalid regex: trailing backslash");
+                };
+                match e {
+                    'd' | 'D' | 'w' | 'W' | 's' | 'S' => {
+                        let perl = match e.to_ascii_lowercase() {
+                            'd' => Perl::Digit,
+                            'w' => Perl::Word,
+                            _ => Perl::Space,
+                        };
+                        items.push(ClassItem::Perl(perl, e.is_ascii_uppercase()));
+                        continue;
+                    }
+                    e => unescape(e),
+                }
+            } else {
+                c
+            };
+            let is_range =
+                self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&n| n != ']');
+            if is_range {
+                self.pos += 1;
+                let hi = match self.next() {
+                    Some('\\') => self.next().map(unescape).unwrap_or('\\'),
+                    Some(hi) => hi,
+                    None => unreachable!("checked by is_range"),
+                };
+                if hi < lo {
+                    anyhow::bail!("Invalid regex: bad range {}-{} in character class", lo, hi);
+                }
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Range(lo, lo));
+            }
+        }
+        Ok(Node::Class { items, negated })
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node> {
+        let (min, max) = match self.peek() {
+            Some('{') => match self.parse_braces() {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            Some(c @ ('*' | '+' | '?')) => {
+                self.pos += 1;
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => return Ok(atom),
+        };
+        if let Some(max) = max {
+            if max < min {
+                anyhow::bail!(
+                    "Invalid regex: repeat bound {{{},{}}} is reversed",
+                    min,
+                    max
+                );
+            }
+        }
+        // Lazy suffix: accepted, same result for matching
+        if self.peek() == Some('?') {
+            self.pos += 1;
+        }
+        if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_)) {
+            anyhow::bail!("Invalid regex: cannot repeat an anchor");
+        }
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    /// Parse `{n}`, `{n,}` or `{n,m}` at the cursor, consuming it. Anything else
+    /// leaves the cursor alone so the `{` is read as a literal.
+    fn parse_braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let rest: String = self.chars[self.pos..].iter().collect();
+        let end = rest.find('}')?;
+        let body = &rest[1..end];
+        let bounds = match body.split_once(',') {
+            None => {
+                let n = body.parse().ok()?;
+                (n, Some(n))
+            }
+            Some((lo, "")) => (lo.parse().ok()?, None),
+            Some((lo, hi)) => (lo.parse().ok()?, Some(hi.parse().ok()?)),
+        };
+        self.pos += rest[..=end].chars().count();
+        Some(bounds)
+    }
+}
+
+fn unescape(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_regex_matching() {
+        assert!(is_match("fn main", "pub fn main() {"));
+        assert!(is_match(r"^\s*fn \w+\(", "    fn helper(x: u32)"));
+        assert!(!is_match(r"^fn", "  fn helper()"));
+        assert!(is_match(r"\d{3}-\d{4}$", "call 555-1234"));
+        assert!(!is_match(r"\d{3}-\d{4}$", "call 555-12345x"));
+        assert!(is_match("colou?r", "color"));
+        assert!(is_match("(foo|bar)+baz", "xxbarfoobaz"));
+        assert!(is_match("[A-Z][a-z]+Error", "an IoError occurred"));
+        assert!(!is_match("[^a-z]", "abc"));
+        assert!(is_match(r"\bcat\b", "the cat sat"));
+        assert!(!is_match(r"\bcat\b", "concatenate"));
+        assert!(is_match("(?i)todo", "// TODO: fix"));
+        assert!(is_match("a.*?b", "axxb"));
+        assert!(is_match("x{2,}", "axxx"));
+        assert!(is_match("a{", "a{"));
+        assert!(is_match("(a*)*b", "aaab"));
+        assert!(!is_match("(a*)*b", "aaaa"));
+        assert!(is_match(&".".repeat(3), &"z".repeat(100_000)));
+        assert!(is_match("a|b|c", "xc"));
+        assert!(!is_match("^(ab|cd)$", "abcd"));
+        assert!(is_match("^(ab|cd){2}$", "abcd"));
+        assert!(is_match("^a{2,3}$", "aaa"));
+        assert!(!is_match("^a{2,3}$", "aaaa"));
+        assert!(is_match("(a|)*b", "aab"));
+    }
+
+    #[test]
+    fn test_no_catastrophic_backtracking() {
+        // Exponential for a backtracking matcher; must finish quickly here
+        let line = format!("{}!", "a".repeat(50_000));
+        assert!(!is_match("^(a+)+$", &line));
+        assert!(!is_match("^(a|a)*$", &line));
+        assert!(!is_match("(x+x+)+y", &"x".repeat(10_000)));
+        assert!(is_match("(.*)*z", &format!("{}z", "a".repeat(50_000))));
+    }
+
+    #[test]
+    fn test_oversized_pattern_rejected() {
+        let err = Regex::new("((a{1000}){1000}){1000}").unwrap_err();
+        assert!(err.to_string().contains("too large"), "{}", err);
+        let nested = format!("{}a{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(Regex::new(&nested).is_err());
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        for pattern in ["(abc", "abc)", "[a-", "*a", r"a\", "[z-a]", "a{3,1}"] {
+            assert!(
+                Regex::new(pattern).is_err(),
+                "{} should not compile",
+                pattern
+            );
+        }
+    }
+}
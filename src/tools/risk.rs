@@ -13,12 +13,41 @@ pub enum RiskLevel {
     Dangerous,
 }
 
+/// Tools that only read, whatever their arguments: no shell, no writes.
+const READ_ONLY_TOOLS: &[&str] = &[
+    "read_file",
+    "list_directory",
+    "grep",
+    "glob",
+    "hash_file",
+    "count_tokens",
+    "env_info",
+];
+
+/// Whether a tool never has side effects, so its calls may run concurrently.
+/// Unlike `assess_risk`, a "safe" bash command doesn't qualify: `git` or
+/// `cargo` still change the tree.
+pub fn is_read_only_tool(tool_name: &str) -> bool {
+    READ_ONLY_TOOLS.contains(&tool_name)
+}
+
 /// Assess the risk level of a tool call.
 pub fn assess_risk(tool_name: &str, arguments: &str) -> RiskLevel {
     match tool_name {
-        "read_file" | "list_directory" => RiskLevel::Safe,
-        "write_file" | "edit" => RiskLevel::Moderate,
+        "read_file" | "list_directory" | "env_info" | "hash_file" | "grep" | "glob"
+        | "count_tokens" => RiskLevel::Safe,
+        "write_file" | "edit" | "multi_edit" | "fetch" => RiskLevel::Moderate,
+        "delete_file" => RiskLevel::Dangerous,
         "bash" => assess_bash_risk(arguments),
+        "sqlite_query" => {
+            let args: serde_json::Value =
+                serde_json::from_str(arguments).unwrap_or(serde_json::Value::Null);
+            if args["write"].as_bool().unwrap_or(false) {
+                RiskLevel::Dangerous
+            } else {
+                RiskLevel::Safe
+            }
+        }
         _ => RiskLevel::Moderate,
     }
 }
@@ -181,6 +210,22 @@ const SAFE_PATTERNS: &[&str] = &[
     "sleep", // wait - no side effects
 ];
 
+/// Key identifying "the same call" for session-wide "always allow" approvals.
+/// Bash commands compare by whitespace-normalized command; other tools by
+/// tool name plus canonicalized JSON arguments.
+pub fn approval_signature(tool_name: &str, arguments: &str) -> String {
+    let args: serde_json::Value =
+        serde_json::from_str(arguments).unwrap_or(serde_json::Value::Null);
+    match (tool_name, args["command"].as_str()) {
+        ("bash", Some(cmd)) => format!(
+            "bash:{}",
+            cmd.split_whitespace().collect::<Vec<_>>().join(" ")
+        ),
+        _ if args.is_null() => format!("{}:{}", tool_name, arguments.trim()),
+        _ => format!("{}:{}", tool_name, args),
+    }
+}
+
 /// Generate a human-readable description for a tool call confirmation prompt.
 pub fn describe_tool_call(tool_name: &str, arguments: &str) -> String {
     let args: serde_json::Value =
@@ -193,9 +238,13 @@ pub fn describe_tool_call(tool_name: &str, arguments: &str) -> String {
         }
         "write_file" => {
             let path = args["path"].as_str().unwrap_or("?");
-            format!("写入文件: {}", path)
+            if args["append"].as_bool().unwrap_or(false) {
+                format!("追加写入文件: {}", path)
+            } else {
+                format!("写入文件: {}", path)
+            }
         }
-        "edit" => {
+        "edit" | "multi_edit" => {
             let path = args["path"].as_str().unwrap_or("?");
             format!("编辑文件: {}", path)
         }
@@ -203,6 +252,30 @@ pub fn describe_tool_call(tool_name: &str, arguments: &str) -> String {
             let path = args["path"].as_str().unwrap_or("?");
             format!("读取文件: {}", path)
         }
+        "delete_file" => {
+            let path = args["path"].as_str().unwrap_or("?");
+            let mut desc = format!("删除文件: {}", path);
+            if args["recursive"].as_bool().unwrap_or(false) {
+                desc.push_str(" (递归删除目录)");
+            }
+            if args["allow_outside_project"].as_bool().unwrap_or(false) {
+                desc.push_str(" (项目目录外)");
+            }
+            desc
+        }
+        "fetch" => {
+            let url = args["url"].as_str().unwrap_or("?");
+            format!("获取网页: {}", url)
+        }
+        "sqlite_query" => {
+            let db = args["db_path"].as_str().unwrap_or("?");
+            let query = args["query"].as_str().unwrap_or("?");
+            let action = match args["write"].as_bool().unwrap_or(false) {
+                true => "修改数据库",
+                false => "查询数据库",
+            };
+            format!("{} {}: {}", action, db, query)
+        }
         other => format!("调用工具: {}", other),
     }
 }
@@ -211,16 +284,56 @@ pub fn describe_tool_call(tool_name: &str, arguments: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_read_only_tool() {
+        assert!(is_read_only_tool("grep"));
+        assert!(is_read_only_tool("read_file"));
+        assert!(!is_read_only_tool("bash"));
+        assert!(!is_read_only_tool("sqlite_query"));
+        assert!(!is_read_only_tool("write_file"));
+    }
+
+    #[test]
+    fn test_approval_signature() {
+        assert_eq!(
+            approval_signature("bash", r#"{"command": "rm  -rf   build"}"#),
+            approval_signature("bash", r#"{"command":"rm -rf build"}"#)
+        );
+        assert_ne!(
+            approval_signature("bash", r#"{"command": "rm -rf build"}"#),
+            approval_signature("bash", r#"{"command": "rm -rf /"}"#)
+        );
+        assert_eq!(
+            approval_signature("write_file", r#"{"path":"a","content":"x"}"#),
+            approval_signature("write_file", r#"{"content": "x", "path": "a"}"#)
+        );
+    }
+
     #[test]
     fn test_safe_tools() {
         assert_eq!(assess_risk("read_file", "{}"), RiskLevel::Safe);
         assert_eq!(assess_risk("list_directory", "{}"), RiskLevel::Safe);
+        assert_eq!(assess_risk("env_info", "{}"), RiskLevel::Safe);
+        assert_eq!(assess_risk("hash_file", "{}"), RiskLevel::Safe);
+        assert_eq!(assess_risk("grep", "{}"), RiskLevel::Safe);
+        assert_eq!(assess_risk("glob", "{}"), RiskLevel::Safe);
+        assert_eq!(assess_risk("count_tokens", "{}"), RiskLevel::Safe);
+        assert_eq!(
+            assess_risk("sqlite_query", r#"{"query":"SELECT 1"}"#),
+            RiskLevel::Safe
+        );
+        assert_eq!(
+            assess_risk("sqlite_query", r#"{"query":"DELETE FROM t","write":true}"#),
+            RiskLevel::Dangerous
+        );
     }
 
     #[test]
     fn test_moderate_tools() {
         assert_eq!(assess_risk("write_file", "{}"), RiskLevel::Moderate);
         assert_eq!(assess_risk("edit", "{}"), RiskLevel::Moderate);
+        assert_eq!(assess_risk("multi_edit", "{}"), RiskLevel::Moderate);
+        assert_eq!(assess_risk("fetch", "{}"), RiskLevel::Moderate);
     }
 
     #[test]
@@ -339,5 +452,24 @@ mod tests {
 
         let desc = describe_tool_call("edit", r#"{"path": "src/main.rs"}"#);
         assert!(desc.contains("src/main.rs"));
+
+        let args = r#"{"path": "target/tmp.txt"}"#;
+        assert_eq!(assess_risk("delete_file", args), RiskLevel::Dangerous);
+        assert_eq!(
+            describe_tool_call("delete_file", args),
+            "删除文件: target/tmp.txt"
+        );
+
+        assert_eq!(
+            describe_tool_call("sqlite_query", r#"{"db_path":"a.db","query":"SELECT 1"}"#),
+            "查询数据库 a.db: SELECT 1"
+        );
+        assert_eq!(
+            describe_tool_call(
+                "sqlite_query",
+                r#"{"db_path":"a.db","query":"DELETE FROM t","write":true}"#
+            ),
+            "修改数据库 a.db: DELETE FROM t"
+        );
     }
 }
@@ -0,0 +1,334 @@
+//! SQLite query tool (`--features sqlite`).
+//!
+//! Runs a single SQL query against a SQLite database file through the
+//! `sqlite3` command-line shell and returns the rows as a text table.
+//! Queries are read-only (`sqlite3 -readonly`) unless the call sets `write: true`,
+//! which also makes the call Dangerous (see `risk::assess_risk`).
+//! The tool is only registered when `sqlite3` is found on `PATH`.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::Tool;
+
+pub struct SqliteQueryTool;
+
+const QUERY_TIMEOUT_SECS: u64 = 30;
+/// Rows shown in the result table; the total count is still reported.
+const MAX_ROWS: usize = 100;
+const MAX_CELL_CHARS: usize = 200;
+/// `sqlite3 -ascii` separators: unit (field) and record (row).
+const FIELD_SEP: char = '\x1f';
+const ROW_SEP: char = '\x1e';
+
+/// Leading keywords of statements that only read.
+const READ_ONLY_KEYWORDS: &[&str] = &["SELECT", "WITH", "EXPLAIN", "VALUES", "PRAGMA"];
+
+#[async_trait]
+impl Tool for SqliteQueryTool {
+    fn name(&self) -> &str {
+        "sqlite_query"
+    }
+
+    fn description(&self) -> &str {
+        "Run one SQL query against a SQLite database file and return the rows as a table. \
+         Read-only by default; INSERT/UPDATE/DELETE/DDL require \"write\": true. \
+         Prefer this over running sqlite3 via bash."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "db_path": {
+                    "type": "string",
+                    "description": "Path to the SQLite database file"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "The SQL query to run"
+                },
+                "write": {
+                    "type": "boolean",
+                    "description": "Allow statements that modify the database (default: false; requires user confirmation)"
+                }
+            },
+            "required": ["db_path", "query"]
+        })
+    }
+
+    fn modified_paths(&self, params: &serde_json::Value) -> Vec<std::path::PathBuf> {
+        match params.get("db_path").and_then(|v| v.as_str()) {
+            Some(path) if write_requested(params) => vec![std::path::PathBuf::from(path)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn is_retryable(&self, params: &serde_json::Value) -> bool {
+        !write_requested(params)
+    }
+
+    async fn execute(&self, params: serde_json::Value) -> Result<String> {
+        let db_path = params
+            .get("db_path")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: db_path")?;
+        let query = params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .context("Missing required parameter: query")?;
+        let write = write_requested(&params);
+
+        if !write && !is_read_only(query) {
+            anyhow::bail!(
+                "Refusing to run a statement that may modify the database without \"write\": true"
+            );
+        }
+        if !std::path::Path::new(db_path).is_file() && !write {
+            anyhow::bail!("Database file not found: {}", db_path);
+        }
+
+        let mut cmd = tokio::process::Command::new("sqlite3");
+        if !write {
+            cmd.arg("-readonly");
+        }
+        cmd.args(["-bail", "-ascii", "-header", db_path, query])
+            .kill_on_drop(true);
+        let output = tokio::time::timeout(
+            std::time::Duration::from_secs(QUERY_TIMEOUT_SECS),
+            cmd.output(),
+        )
+        .await
+        .with_context(|| format!("Query timed out after {}s", QUERY_TIMEOUT_SECS))?
+        .context("Failed to run sqlite3 (is it installed?)")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("SQLite error: {}", stderr.trim());
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(format_table(&stdout))
+    }
+}
+
+/// Whether a `sqlite3` executable is on `PATH`.
+pub fn sqlite3_available() -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path)
+            .any(|dir| dir.join("sqlite3").is_file() || dir.join("sqlite3.exe").is_file())
+    })
+}
+
+fn write_requested(params: &serde_json::Value) -> bool {
+    params
+        .get("write")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Whether every statement in `query` only reads. PRAGMA assignments count as writes.
+fn is_read_only(query: &str) -> bool {
+    let statements: Vec<String> = strip_comments(query)
+        .split(';')
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    !statements.is_empty()
+        && statements.iter().all(|stmt| {
+            let keyword = stmt
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .next()
+                .unwrap_or("");
+            READ_ONLY_KEYWORDS.contains(&keyword)
+                && !(keyword == "PRAGMA" && stmt.contains('='))
+                // A CTE can front a write: WITH x AS (...) DELETE FROM ...
+                && !(keyword == "WITH"
+                    && ["INSERT", "UPDATE", "DELETE", "REPLACE"]
+                        .iter()
+                        .any(|w| stmt.split_whitespace().any(|t| t == *w)))
+        })
+}
+
+/// Remove `-- line` and `/* block */` comments.
+fn strip_comments(query: &str) -> String {
+    let mut out = String::with_capacity(query.len());
+    let mut rest = query;
+    loop {
+        let line = rest.find("--");
+        let block = rest.find("/*");
+        match (line, block) {
+            (Some(l), b) if b.is_none_or(|b| l < b) => {
+                out.push_str(&rest[..l]);
+                rest = rest[l..].find('\n').map_or("", |n| &rest[l + n..]);
+            }
+            (_, Some(b)) => {
+                out.push_str(&rest[..b]);
+                rest = rest[b..].find("*/").map_or("", |e| &rest[b + e + 2..]);
+            }
+            _ => {
+                out.push_str(rest);
+                return out;
+            }
+        }
+    }
+}
+
+/// Render `sqlite3 -ascii -header` output as an aligned text table, capped at [`MAX_ROWS`].
+fn format_table(raw: &str) -> String {
+    let mut rows: Vec<Vec<String>> = raw
+        .split(ROW_SEP)
+        .filter(|r| !r.is_empty())
+        .map(|r| {
+            r.split(FIELD_SEP)
+                .map(|cell| {
+                    let cell = cell.replace('\n', "\\n");
+                    if cell.chars().count() > MAX_CELL_CHARS {
+                        let cut: String = cell.chars().take(MAX_CELL_CHARS).collect();
+                        format!("{}…", cut)
+                    } else {
+                        cell
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    if rows.is_empty() {
+        return "Query executed (no rows returned)".to_string();
+    }
+    let header = rows.remove(0);
+    let total = rows.len();
+    rows.truncate(MAX_ROWS);
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            if let Some(w) = widths.get_mut(i) {
+                *w = (*w).max(cell.chars().count());
+            }
+        }
+    }
+    let render = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{:<width$}", c, width = w))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+    let mut out = vec![render(&header)];
+    out.push(
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("-+-"),
+    );
+    out.extend(rows.iter().map(|r| render(r)));
+    out.push(if total > MAX_ROWS {
+        format!("({} rows, showing first {})", total, MAX_ROWS)
+    } else {
+        format!("({} rows)", total)
+    });
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rt() -> tokio::runtime::Runtime {
+        tokio::runtime::Runtime::new().unwrap()
+    }
+
+    /// Create a small database with the sqlite3 shell; None when it isn't installed.
+    fn sample_db(dir: &std::path::Path) -> Option<String> {
+        if !sqlite3_available() {
+            eprintln!("sqlite3 not found, skipping");
+            return None;
+        }
+        let path = dir.join("app.db");
+        let status = std::process::Command::new("sqlite3")
+            .arg(&path)
+            .arg(
+                "CREATE TABLE users (id INTEGER, name TEXT); \
+                 INSERT INTO users VALUES (1, 'ada'), (2, 'grace');",
+            )
+            .status()
+            .unwrap();
+        assert!(status.success());
+        Some(path.display().to_string())
+    }
+
+    #[test]
+    fn test_read_only_classification() {
+        assert!(is_read_only("SELECT * FROM t"));
+        assert!(is_read_only("  with x as (select 1) select * from x;"));
+        assert!(is_read_only("-- count\nSELECT count(*) FROM t"));
+        assert!(is_read_only("PRAGMA table_info(t)"));
+        assert!(!is_read_only("PRAGMA journal_mode = WAL"));
+        assert!(!is_read_only("UPDATE t SET a = 1"));
+        assert!(!is_read_only("SELECT 1; DROP TABLE t"));
+        assert!(!is_read_only("WITH x AS (SELECT 1) DELETE FROM t"));
+        assert!(!is_read_only("/* SELECT */ DELETE FROM t"));
+        assert!(!is_read_only(""));
+    }
+
+    #[test]
+    fn test_select_returns_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(db) = sample_db(dir.path()) else {
+            return;
+        };
+        let result = rt()
+            .block_on(SqliteQueryTool.execute(json!({
+                "db_path": db,
+                "query": "SELECT id, name FROM users ORDER BY id"
+            })))
+            .unwrap();
+        assert_eq!(
+            result,
+            "id | name\n---+------\n1  | ada\n2  | grace\n(2 rows)"
+        );
+    }
+
+    #[test]
+    fn test_update_blocked_without_write_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some(db) = sample_db(dir.path()) else {
+            return;
+        };
+        let update = |write: bool| {
+            rt().block_on(SqliteQueryTool.execute(json!({
+                "db_path": db,
+                "query": "UPDATE users SET name = 'x'",
+                "write": write,
+            })))
+        };
+        let err = update(false).unwrap_err();
+        assert!(err.to_string().contains("\"write\": true"), "{}", err);
+
+        update(true).unwrap();
+        let names = rt()
+            .block_on(SqliteQueryTool.execute(json!({
+                "db_path": db,
+                "query": "SELECT DISTINCT name FROM users"
+            })))
+            .unwrap();
+        assert!(names.contains("x\n(1 rows)"), "{}", names);
+    }
+
+    #[test]
+    fn test_row_cap() {
+        let mut raw = format!("n{}", ROW_SEP);
+        for i in 0..MAX_ROWS + 5 {
+            raw.push_str(&format!("{}{}", i, ROW_SEP));
+        }
+        let table = format_table(&raw);
+        assert!(table.ends_with(&format!("(105 rows, showing first {})", MAX_ROWS)));
+        assert_eq!(table.lines().count(), MAX_ROWS + 3);
+    }
+}
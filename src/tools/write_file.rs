@@ -1,15 +1,26 @@
 //! Write File tool implementation.
 //!
 //! This tool allows the AI assistant to write content to a file.
+//!
+//! Large files can be built across several calls: the first writes (truncates)
+//! the file, later ones pass `append: true` to add the next chunk.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
 
-use super::Tool;
+use super::{Tool, ToolOutput};
 
 /// Tool that writes content to a file.
-pub struct WriteFileTool;
+#[derive(Default)]
+pub struct WriteFileTool {
+    /// File most recently written or appended, to catch appends that switch
+    /// files in the middle of building one.
+    last_path: Mutex<Option<PathBuf>>,
+}
 
 #[async_trait]
 impl Tool for WriteFileTool {
@@ -19,7 +30,9 @@ impl Tool for WriteFileTool {
 
     fn description(&self) -> &str {
         "Write content to a file at the given path. \
-         Creates the file if it doesn't exist, overwrites if it does."
+         Creates the file if it doesn't exist, overwrites if it does. \
+         For very large files, write the first chunk normally and add the rest \
+         with append set to true in follow-up calls."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -34,12 +47,41 @@ impl Tool for WriteFileTool {
                 "content": {
                     "type": "string",
                     "description": "The content to write to the file"
+                },
+                "append": {
+                    "type": "boolean",
+                    "description": "Append content to the end of the file instead of overwriting it (default: false)"
+                },
+                "allow_secrets": {
+                    "type": "boolean",
+                    "description": "Set only when writing a credential is intended (default: false)"
                 }
             },
             "required": ["path", "content"]
         })
     }
 
+    fn modified_paths(&self, params: &serde_json::Value) -> Vec<std::path::PathBuf> {
+        params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(|p| vec![std::path::PathBuf::from(p)])
+            .unwrap_or_default()
+    }
+
+    fn written_content<'a>(&self, params: &'a serde_json::Value) -> Option<&'a str> {
+        params.get("content").and_then(|v| v.as_str())
+    }
+
+    async fn execute_structured(&self, params: serde_json::Value) -> Result<ToolOutput> {
+        let files = self.modified_paths(&params);
+        let text = self.execute(params).await?;
+        Ok(ToolOutput {
+            text,
+            metadata: json!({ "files": files }),
+        })
+    }
+
     async fn execute(&self, params: serde_json::Value) -> Result<String> {
         let path = params
             .get("path")
@@ -58,16 +100,54 @@ impl Tool for WriteFileTool {
                 .with_context(|| format!("Failed to create directory for: {}", path))?;
         }
 
-        // Write the file
-        tokio::fs::write(path, content)
+        let append = params
+            .get("append")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let previous = self.last_path.lock().unwrap().replace(PathBuf::from(path));
+
+        if !append {
+            tokio::fs::write(path, content)
+                .await
+                .with_context(|| format!("Failed to write file: {}", path))?;
+            return Ok(format!(
+                "Successfully wrote {} characters to file: {}",
+                content.len(),
+                path
+            ));
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
             .await
-            .with_context(|| format!("Failed to write file: {}", path))?;
+            .with_context(|| format!("Failed to open file for appending: {}", path))?;
+        file.write_all(content.as_bytes())
+            .await
+            .with_context(|| format!("Failed to append to file: {}", path))?;
+        file.flush().await?;
+        let total = file.metadata().await.map(|m| m.len()).unwrap_or(0);
 
-        Ok(format!(
-            "Successfully wrote {} characters to file: {}",
+        let mut result = format!(
+            "Successfully appended {} characters to file: {} (now {} bytes)",
             content.len(),
-            path
-        ))
+            path,
+            total
+        );
+        match previous {
+            Some(prev) if prev.as_path() == std::path::Path::new(path) => {}
+            Some(prev) => result.push_str(&format!(
+                "\nWarning: the previous write_file call was for {}; check that chunks \
+                 of different files are not being mixed.",
+                prev.display()
+            )),
+            None => result.push_str(
+                "\nWarning: this file was not started with write_file in this session; \
+                 the chunk was added after its existing content.",
+            ),
+        }
+        Ok(result)
     }
 }
 
@@ -82,7 +162,7 @@ mod tests {
 
     #[test]
     fn test_metadata() {
-        let tool = WriteFileTool;
+        let tool = WriteFileTool::default();
         assert_eq!(tool.name(), "write_file");
         assert!(!tool.description().is_empty());
         let schema = tool.parameters_schema();
@@ -98,7 +178,7 @@ mod tests {
             let dir = tempfile::tempdir().unwrap();
             let file_path = dir.path().join("test.txt");
 
-            let result = WriteFileTool
+            let result = WriteFileTool::default()
                 .execute(json!({
                     "path": file_path.to_str().unwrap(),
                     "content": "hello world"
@@ -118,7 +198,7 @@ mod tests {
             let dir = tempfile::tempdir().unwrap();
             let file_path = dir.path().join("sub").join("deep").join("file.txt");
 
-            WriteFileTool
+            WriteFileTool::default()
                 .execute(json!({
                     "path": file_path.to_str().unwrap(),
                     "content": "nested"
@@ -138,7 +218,7 @@ mod tests {
             let file_path = dir.path().join("overwrite.txt");
             std::fs::write(&file_path, "old content").unwrap();
 
-            WriteFileTool
+            WriteFileTool::default()
                 .execute(json!({
                     "path": file_path.to_str().unwrap(),
                     "content": "new content"
@@ -154,11 +234,60 @@ mod tests {
     fn test_missing_params() {
         let rt = rt();
         rt.block_on(async {
-            let r1 = WriteFileTool.execute(json!({ "content": "x" })).await;
+            let r1 = WriteFileTool::default()
+                .execute(json!({ "content": "x" }))
+                .await;
             assert!(r1.is_err());
 
-            let r2 = WriteFileTool.execute(json!({ "path": "/tmp/x" })).await;
+            let r2 = WriteFileTool::default()
+                .execute(json!({ "path": "/tmp/x" }))
+                .await;
             assert!(r2.is_err());
         });
     }
+
+    #[test]
+    fn test_truncate_then_append_builds_file() {
+        let rt = rt();
+        rt.block_on(async {
+            let dir = tempfile::tempdir().unwrap();
+            let big = dir.path().join("big.txt");
+            let other = dir.path().join("other.txt");
+            std::fs::write(&big, "stale content\n").unwrap();
+            let tool = WriteFileTool::default();
+            let write = |path: &std::path::Path, content: &str, append: bool| {
+                tool.execute(json!({
+                    "path": path.to_str().unwrap(),
+                    "content": content,
+                    "append": append
+                }))
+            };
+
+            write(&big, "part 1\n", false).await.unwrap();
+            let second = write(&big, "part 2\n", true).await.unwrap();
+            assert!(second.contains("appended 7 characters"));
+            assert!(!second.contains("Warning"));
+            write(&big, "part 3\n", true).await.unwrap();
+            assert_eq!(
+                std::fs::read_to_string(&big).unwrap(),
+                "part 1\npart 2\npart 3\n"
+            );
+
+            // Switching files mid-build is flagged but still appended
+            let mixed = write(&other, "oops\n", true).await.unwrap();
+            assert!(mixed.contains("Warning") && mixed.contains("big.txt"));
+            assert_eq!(std::fs::read_to_string(&other).unwrap(), "oops\n");
+
+            let fresh = WriteFileTool::default();
+            let r = fresh
+                .execute(json!({
+                    "path": big.to_str().unwrap(),
+                    "content": "more",
+                    "append": true
+                }))
+                .await
+                .unwrap();
+            assert!(r.contains("not started with write_file"));
+        });
+    }
 }
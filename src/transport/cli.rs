@@ -8,7 +8,8 @@ use clap::Args;
 use std::io::{self, BufRead, Write};
 
 use crate::agent::Agent;
-use crate::config::AppConfig;
+use crate::config::{AppConfig, UiConfig};
+use crate::input_history::InputHistory;
 
 #[derive(Args, Debug, Clone)]
 pub struct CliArgs {
@@ -30,7 +31,20 @@ pub async fn run_cli(args: CliArgs, config: AppConfig) -> Result<()> {
         return Ok(());
     }
 
-    run_interactive(&mut agent).await
+    run_interactive(&mut agent, &config.ui).await
+}
+
+/// Prompt printed before reading each line (`ui.prompt_user`, default "> ").
+fn user_prompt(ui: &UiConfig) -> &str {
+    ui.prompt_user.as_deref().unwrap_or("> ")
+}
+
+/// Printed before each reply: `ui.prompt_assistant` plus a space, or nothing.
+fn assistant_prefix(ui: &UiConfig) -> String {
+    ui.prompt_assistant
+        .as_deref()
+        .map(|p| format!("{} ", p.trim_end()))
+        .unwrap_or_default()
 }
 
 async fn run_one_shot(agent: &mut Agent, message: &str) -> Result<()> {
@@ -39,16 +53,21 @@ async fn run_one_shot(agent: &mut Agent, message: &str) -> Result<()> {
     Ok(())
 }
 
-async fn run_interactive(agent: &mut Agent) -> Result<()> {
+async fn run_interactive(agent: &mut Agent, ui: &UiConfig) -> Result<()> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut lines = stdin.lock().lines();
+    // Plain stdin has no line editing; inputs are still recorded for the TUI's ↑ recall.
+    let mut history = InputHistory::load_default();
 
-    eprintln!("miniclaw CLI (interactive). Type your message and press Enter. Ctrl+D to exit.");
+    eprintln!(
+        "miniclaw v{} CLI (interactive). Type your message and press Enter. Ctrl+D to exit.",
+        crate::build_info::VERSION
+    );
     eprintln!();
 
     loop {
-        write!(stdout, "> ")?;
+        write!(stdout, "{}", user_prompt(ui))?;
         stdout.flush()?;
 
         let Some(Ok(line)) = lines.next() else {
@@ -58,11 +77,29 @@ async fn run_interactive(agent: &mut Agent) -> Result<()> {
         if line.is_empty() {
             continue;
         }
+        history.push(line);
 
         let result = agent.process_message(line, None, None).await?;
-        println!("{}", result);
+        println!("{}{}", assistant_prefix(ui), result);
         println!();
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_prompts() {
+        let mut ui = UiConfig::default();
+        assert_eq!(user_prompt(&ui), "> ");
+        assert_eq!(assistant_prefix(&ui), "");
+
+        ui.prompt_user = Some("You > ".to_string());
+        ui.prompt_assistant = Some("Assistant >".to_string());
+        assert_eq!(user_prompt(&ui), "You > ");
+        assert_eq!(assistant_prefix(&ui), "Assistant > ");
+    }
+}
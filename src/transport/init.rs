@@ -0,0 +1,252 @@
+//! `miniclaw init`: interactive first-run wizard that writes `config.toml`.
+//!
+//! Asks for provider, base URL, model and API key on stdin (the key without
+//! echo). When stdin is not a terminal it writes the default config instead.
+//! An existing config is only replaced after confirmation or with `--force`.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use crate::config::{AppConfig, LlmConfig};
+use crate::llm::SUPPORTED_PROVIDERS;
+
+/// What the wizard asks for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitAnswers {
+    pub provider: String,
+    pub base_url: String,
+    pub model: String,
+    /// Empty means "read the key from the `api_key_env` variable".
+    pub api_key: String,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct InitArgs {
+    /// Overwrite an existing config without asking
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+}
+
+pub fn run_init(config_path: &Path, args: &InitArgs) -> Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    if !stdin.is_terminal() {
+        return write_default_config(config_path, args.force);
+    }
+
+    let mut input = stdin.lock();
+    if config_path.exists() && !args.force {
+        let answer = ask(
+            &mut input,
+            &mut stdout,
+            &format!("{} exists. Overwrite?", config_path.display()),
+            "n",
+        )?;
+        if !answer.eq_ignore_ascii_case("y") {
+            println!("Left the existing config unchanged.");
+            return Ok(());
+        }
+    }
+    let answers = prompt_answers(&mut input, &mut stdout, true)?;
+    build_config(&answers).save_to(config_path)?;
+    println!("[Config] Wrote {}", config_path.display());
+    if answers.api_key.is_empty() {
+        println!(
+            "[Config] No API key saved; export {}=your-key before running.",
+            LlmConfig::default().api_key_env
+        );
+    }
+    Ok(())
+}
+
+/// Non-interactive init: write the default config unless one exists and `force` is off.
+fn write_default_config(config_path: &Path, force: bool) -> Result<()> {
+    if config_path.exists() && !force {
+        println!(
+            "[Config] Not a terminal and {} exists; left unchanged (use --force to overwrite).",
+            config_path.display()
+        );
+        return Ok(());
+    }
+    AppConfig::default().save_to(config_path)?;
+    println!(
+        "[Config] Not a terminal; wrote default config: {}",
+        config_path.display()
+    );
+    Ok(())
+}
+
+/// Ask the wizard questions, re-asking on invalid answers. With `hide_key` the
+/// API key is read from the terminal without echo instead of from `input`.
+pub fn prompt_answers(
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+    hide_key: bool,
+) -> Result<InitAnswers> {
+    let defaults = LlmConfig::default();
+    let provider = loop {
+        let answer = ask(
+            input,
+            out,
+            &format!("Provider ({})", SUPPORTED_PROVIDERS.join(" / ")),
+            &defaults.provider,
+        )?;
+        if SUPPORTED_PROVIDERS.contains(&answer.as_str()) {
+            break answer;
+        }
+        writeln!(out, "Unknown provider '{}'.", answer)?;
+    };
+    let (default_url, default_model) = match provider.as_str() {
+        "anthropic" => ("https://api.anthropic.com".to_string(), String::new()),
+        "ollama" => (
+            crate::llm::ollama::DEFAULT_API_BASE.to_string(),
+            String::new(),
+        ),
+        _ => (defaults.api_base.unwrap_or_default(), defaults.model),
+    };
+    let base_url = ask(input, out, "Base URL", &default_url)?;
+    let model = loop {
+        let answer = ask(input, out, "Model", &default_model)?;
+        if !answer.is_empty() {
+            break answer;
+        }
+        writeln!(out, "A model name is required.")?;
+    };
+    let key_question = format!("API key (empty = read ${})", defaults.api_key_env);
+    let api_key = if hide_key {
+        ask_hidden(out, &key_question)?
+    } else {
+        ask(input, out, &key_question, "")?
+    };
+    Ok(InitAnswers {
+        provider,
+        base_url,
+        model,
+        api_key,
+    })
+}
+
+/// Default config with the wizard's answers filled into `[llm]`.
+pub fn build_config(answers: &InitAnswers) -> AppConfig {
+    let mut config = AppConfig::default();
+    config.llm.provider = answers.provider.clone();
+    config.llm.model = answers.model.clone();
+    config.llm.api_base = Some(answers.base_url.clone()).filter(|u| !u.is_empty());
+    config.llm.api_key = Some(answers.api_key.clone()).filter(|k| !k.is_empty());
+    config
+}
+
+/// Print `question [default]: ` and read one trimmed line; empty input picks the default.
+fn ask(
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+    question: &str,
+    default: &str,
+) -> Result<String> {
+    if default.is_empty() {
+        write!(out, "{}: ", question)?;
+    } else {
+        write!(out, "{} [{}]: ", question, default)?;
+    }
+    out.flush()?;
+    let mut line = String::new();
+    let read = input
+        .read_line(&mut line)
+        .context("Failed to read answer")?;
+    if read == 0 {
+        anyhow::bail!("Input ended before the wizard finished");
+    }
+    let line = line.trim();
+    Ok(if line.is_empty() { default } else { line }.to_string())
+}
+
+/// Like [`ask`] without a default, but read keystrokes in raw mode so the answer
+/// is not echoed.
+fn ask_hidden(out: &mut impl Write, question: &str) -> Result<String> {
+    write!(out, "{}: ", question)?;
+    out.flush()?;
+    crossterm::terminal::enable_raw_mode().context("Failed to read answer")?;
+    let answer = read_hidden_line();
+    let _ = crossterm::terminal::disable_raw_mode();
+    writeln!(out)?;
+    Ok(answer?.trim().to_string())
+}
+
+fn read_hidden_line() -> Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read().context("Failed to read answer")? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                anyhow::bail!("Input ended before the wizard finished")
+            }
+            KeyCode::Char(c) => line.push(c),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_answers_build_valid_config() {
+        let mut input = io::Cursor::new("bogus\nanthropic\n\n\nclaude-sonnet\nsk-ant-123\n");
+        let mut out = Vec::new();
+        let answers = prompt_answers(&mut input, &mut out, false).unwrap();
+        assert_eq!(
+            answers,
+            InitAnswers {
+                provider: "anthropic".to_string(),
+                base_url: "https://api.anthropic.com".to_string(),
+                model: "claude-sonnet".to_string(),
+                api_key: "sk-ant-123".to_string(),
+            }
+        );
+        let transcript = String::from_utf8(out).unwrap();
+        assert!(transcript.contains("Unknown provider 'bogus'."));
+        assert!(transcript.contains("A model name is required."));
+
+        let config = build_config(&answers);
+        let toml = toml::to_string_pretty(&config).unwrap();
+        let parsed: AppConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.llm.provider, "anthropic");
+        assert_eq!(parsed.llm.model, "claude-sonnet");
+        assert_eq!(
+            parsed.llm.api_base.as_deref(),
+            Some("https://api.anthropic.com")
+        );
+        assert_eq!(parsed.llm.api_key.as_deref(), Some("sk-ant-123"));
+        assert_eq!(parsed.llm.max_tokens, config.llm.max_tokens);
+
+        let mut input = io::Cursor::new("\n");
+        assert!(prompt_answers(&mut input, &mut Vec::new(), false).is_err());
+    }
+
+    #[test]
+    fn test_non_interactive_init_keeps_existing_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "# hand-written\n").unwrap();
+
+        write_default_config(&path, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# hand-written\n");
+
+        write_default_config(&path, true).unwrap();
+        let written: AppConfig = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written.llm.model, AppConfig::default().llm.model);
+    }
+}
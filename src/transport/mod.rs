@@ -4,6 +4,7 @@
 //! the user invokes miniclaw.
 
 pub mod cli;
+pub mod init;
 
 #[cfg(feature = "telegram")]
 pub mod telegram;
@@ -11,6 +12,7 @@ pub mod telegram;
 mod telegram_state;
 
 use clap::Parser;
+use std::path::PathBuf;
 
 /// Miniclaw - A minimal AI assistant inspired by OpenClaw
 #[derive(Parser, Debug)]
@@ -24,16 +26,32 @@ pub struct Args {
     /// Legacy: pass message directly for one-shot CLI (same as `cli --message "..."`)
     #[arg(short, long)]
     pub message: Option<String>,
+
+    /// Directory for all miniclaw state (config, sessions, logs). Default: $XDG_DATA_HOME/miniclaw or ~/.miniclaw
+    #[arg(long, global = true, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Config file to use instead of <data dir>/config.toml (created with defaults if missing)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Print version, git commit, and compiled-in providers/tools, then exit
+    #[arg(short = 'V', long)]
+    pub version: bool,
 }
 
-#[derive(Parser, Debug, Clone)]
+#[derive(Parser, Debug, Clone, Default)]
 pub enum Subcommand {
     /// Interactive TUI (default)
+    #[default]
     Tui,
 
     /// CLI mode: one-shot or interactive stdin
     Cli(cli::CliArgs),
 
+    /// Interactive setup wizard that writes config.toml
+    Init(init::InitArgs),
+
     /// Run as Telegram bot (requires: cargo build --features telegram)
     #[cfg(feature = "telegram")]
     Telegram(telegram::TelegramArgs),
@@ -47,12 +65,6 @@ pub enum Subcommand {
 #[derive(Parser, Debug, Clone)]
 pub struct TelegramStubArgs {}
 
-impl Default for Subcommand {
-    fn default() -> Self {
-        Subcommand::Tui
-    }
-}
-
 /// Resolve which mode to run. Handles legacy `--message` flag.
 pub fn resolve_mode(args: &Args) -> ResolvedMode {
     if let Some(msg) = &args.message {
@@ -65,6 +77,7 @@ pub fn resolve_mode(args: &Args) -> ResolvedMode {
         None => ResolvedMode::Tui,
         Some(Subcommand::Tui) => ResolvedMode::Tui,
         Some(Subcommand::Cli(c)) => ResolvedMode::Cli(c.clone()),
+        Some(Subcommand::Init(i)) => ResolvedMode::Init(i.clone()),
         #[cfg(feature = "telegram")]
         Some(Subcommand::Telegram(t)) => ResolvedMode::Telegram(t.clone()),
         #[cfg(not(feature = "telegram"))]
@@ -76,6 +89,7 @@ pub fn resolve_mode(args: &Args) -> ResolvedMode {
 pub enum ResolvedMode {
     Tui,
     Cli(cli::CliArgs),
+    Init(init::InitArgs),
     #[cfg(feature = "telegram")]
     Telegram(telegram::TelegramArgs),
     #[cfg(not(feature = "telegram"))]
@@ -91,6 +105,9 @@ mod tests {
         let args = Args {
             subcommand: None,
             message: Some("hello".to_string()),
+            data_dir: None,
+            config: None,
+            version: false,
         };
         let mode = resolve_mode(&args);
         match &mode {
@@ -107,6 +124,9 @@ mod tests {
         let args = Args {
             subcommand: None,
             message: None,
+            data_dir: None,
+            config: None,
+            version: false,
         };
         let mode = resolve_mode(&args);
         match &mode {
@@ -123,6 +143,9 @@ mod tests {
                 interactive: true,
             })),
             message: None,
+            data_dir: None,
+            config: None,
+            version: false,
         };
         let mode = resolve_mode(&args);
         match &mode {
@@ -30,8 +30,7 @@ pub struct TelegramArgs {
 }
 
 fn pid_file_path() -> Result<std::path::PathBuf> {
-    let home = dirs::home_dir().context("Could not determine home directory")?;
-    Ok(home.join(".miniclaw").join("miniclaw-telegram.pid"))
+    crate::paths::telegram_pid_path()
 }
 
 fn run_daemon_stop() -> Result<()> {
@@ -75,7 +74,11 @@ pub async fn run_telegram(args: TelegramArgs, config: AppConfig) -> Result<()> {
 
     if args.daemon {
         let exe = std::env::current_exe().context("Could not get executable path")?;
-        let child: Child = Command::new(&exe)
+        let mut cmd = Command::new(&exe);
+        if let Some(dir) = crate::paths::data_dir_override() {
+            cmd.arg("--data-dir").arg(dir);
+        }
+        let child: Child = cmd
             .arg("telegram")
             .stdin(Stdio::null())
             .stdout(Stdio::null())
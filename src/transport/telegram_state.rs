@@ -1,4 +1,4 @@
-//! Persist Telegram chat state (e.g. model per chat) to ~/.miniclaw/telegram_state.json
+//! Persist Telegram chat state (e.g. model per chat) to `<data_dir>/telegram_state.json`
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -15,8 +15,7 @@ pub struct TelegramState {
 
 impl TelegramState {
     fn path() -> Result<PathBuf> {
-        let home = dirs::home_dir().context("Could not determine home directory")?;
-        Ok(home.join(".miniclaw").join("telegram_state.json"))
+        crate::paths::telegram_state_path()
     }
 
     pub fn load() -> Result<Self> {
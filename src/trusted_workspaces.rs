@@ -1,13 +1,13 @@
 //! Trusted workspace persistence for auto-approving tool confirmations.
 //!
 //! When a workspace is trusted, dangerous tool calls are auto-approved.
-//! Stored in ~/.miniclaw/trusted_workspaces.json.
+//! Stored in `<data_dir>/trusted_workspaces.json` (default ~/.miniclaw).
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
-const TRUSTED_WORKSPACES_FILE: &str = "trusted_workspaces.json";
+use crate::paths;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TrustedWorkspacesData {
@@ -15,11 +15,12 @@ struct TrustedWorkspacesData {
 }
 
 fn trusted_workspaces_path() -> Result<PathBuf> {
-    let dir = dirs::home_dir()
-        .context("Cannot determine home directory")?
-        .join(".miniclaw");
-    std::fs::create_dir_all(&dir).context("Failed to create ~/.miniclaw")?;
-    Ok(dir.join(TRUSTED_WORKSPACES_FILE))
+    let path = paths::trusted_workspaces_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    Ok(path)
 }
 
 fn load_data() -> Result<TrustedWorkspacesData> {
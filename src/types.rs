@@ -15,7 +15,7 @@ pub enum Role {
 
 // --- Tool Call ---
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ToolCall {
     pub id: String,
     pub name: String,
@@ -31,9 +31,23 @@ pub struct ToolDefinition {
     pub input_schema: serde_json::Value,
 }
 
+// --- Extended Thinking ---
+
+/// A reasoning block from extended thinking (Anthropic `thinking`). Kept on the
+/// assistant message because the API requires it to be replayed with its tool calls.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThinkingBlock {
+    pub thinking: String,
+    pub signature: String,
+    /// Encrypted `redacted_thinking` payload; when set, `thinking` and `signature`
+    /// are empty and the block is replayed as-is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted: Option<String>,
+}
+
 // --- Messages ---
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Message {
     pub role: Role,
     pub content: String,
@@ -41,6 +55,12 @@ pub struct Message {
     pub tool_calls: Vec<ToolCall>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thinking: Vec<ThinkingBlock>,
+    /// A user-role message miniclaw added itself (plan approval, `/run` output,
+    /// compaction summary) rather than one the user typed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub synthetic: bool,
 }
 
 impl Message {
@@ -50,6 +70,8 @@ impl Message {
             content: content.into(),
             tool_calls: vec![],
             tool_call_id: None,
+            thinking: vec![],
+            synthetic: false,
         }
     }
     pub fn user(content: impl Into<String>) -> Self {
@@ -58,6 +80,15 @@ impl Message {
             content: content.into(),
             tool_calls: vec![],
             tool_call_id: None,
+            thinking: vec![],
+            synthetic: false,
+        }
+    }
+    /// A user-role message that the user didn't type (see [`Message::synthetic`]).
+    pub fn note(content: impl Into<String>) -> Self {
+        Self {
+            synthetic: true,
+            ..Self::user(content)
         }
     }
     pub fn assistant(content: impl Into<String>) -> Self {
@@ -66,6 +97,8 @@ impl Message {
             content: content.into(),
             tool_calls: vec![],
             tool_call_id: None,
+            thinking: vec![],
+            synthetic: false,
         }
     }
     pub fn assistant_with_tool_calls(
@@ -77,6 +110,8 @@ impl Message {
             content: content.into(),
             tool_calls,
             tool_call_id: None,
+            thinking: vec![],
+            synthetic: false,
         }
     }
     pub fn tool_result(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
@@ -85,6 +120,8 @@ impl Message {
             content: content.into(),
             tool_calls: vec![],
             tool_call_id: Some(tool_call_id.into()),
+            thinking: vec![],
+            synthetic: false,
         }
     }
 }
@@ -107,6 +144,31 @@ pub struct ChatRequest {
     pub max_tokens: u32,
     /// Enable web search (DashScope/百炼: extra_body.enable_search). Used by qwen3.5-plus etc.
     pub enable_search: Option<bool>,
+    /// Reasoning effort for OpenAI reasoning models ("low" / "medium" / "high").
+    pub reasoning_effort: Option<String>,
+    /// End-user id for provider abuse monitoring (`llm.user_id`).
+    pub user: Option<String>,
+    /// Provider-specific fields merged into the request body (model `extra_body`).
+    pub extra_body: Option<serde_json::Value>,
+    /// Anthropic extended-thinking `budget_tokens`; must be below `max_tokens`.
+    pub thinking_budget: Option<u32>,
+    /// Whether the model may call `tools`; `None` leaves it to the provider default.
+    pub tool_choice: Option<ToolChoice>,
+    /// Sampling temperature; omitted from the request body when `None`.
+    pub temperature: Option<f64>,
+    /// Nucleus sampling `top_p`; omitted from the request body when `None`.
+    pub top_p: Option<f64>,
+    /// Add Anthropic `cache_control` breakpoints (model `prompt_caching`).
+    pub prompt_caching: bool,
+    /// Cap on streamed text kept per response (`llm.max_stream_bytes`); None = unlimited.
+    pub max_stream_bytes: Option<usize>,
+}
+
+/// `tool_choice` control sent with a request that has tools.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToolChoice {
+    /// The model must answer in text only, e.g. to write a plan first.
+    None,
 }
 
 #[derive(Debug, Clone)]
@@ -114,6 +176,8 @@ pub struct ChatResponse {
     pub content: String,
     pub tool_calls: Vec<ToolCall>,
     pub usage: Option<TokenUsage>,
+    /// Extended-thinking blocks (the model's reasoning), in order.
+    pub thinking: Vec<ThinkingBlock>,
 }
 
 impl ChatResponse {
@@ -127,5 +191,7 @@ impl ChatResponse {
 #[derive(Debug, Clone)]
 pub enum StreamChunk {
     TextDelta(String),
+    /// Something the user should know about the stream, e.g. it was truncated.
+    Warning(String),
     Done,
 }
@@ -0,0 +1,134 @@
+//! System clipboard access for copying conversation text.
+//!
+//! Tries the platform clipboard command (pbcopy, wl-copy, xclip, xsel, clip)
+//! and falls back to the OSC 52 terminal escape sequence, which most modern
+//! terminals (including over SSH) accept. When neither is possible (headless,
+//! output not a terminal) copying fails with [`ClipboardError::Unavailable`].
+
+use anyhow::Result;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Why text could not be copied.
+#[derive(Debug, thiserror::Error)]
+pub enum ClipboardError {
+    /// No clipboard command worked and the terminal cannot take OSC 52.
+    #[error("clipboard unavailable")]
+    Unavailable,
+    #[error("failed to send OSC 52 sequence: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Clipboard commands to try, in order, for the current platform.
+fn clipboard_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", &[])]
+    } else if cfg!(target_os = "windows") {
+        vec![("clip", &[])]
+    } else {
+        let mut cmds: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            cmds.push(("wl-copy", &[]));
+        }
+        cmds.push(("xclip", &["-selection", "clipboard"]));
+        cmds.push(("xsel", &["--clipboard", "--input"]));
+        cmds
+    }
+}
+
+fn copy_with_command(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Copy `text` to the clipboard. Returns the mechanism used (e.g. "xclip", "OSC 52").
+pub fn copy_text(text: &str) -> Result<&'static str, ClipboardError> {
+    for (program, args) in clipboard_commands() {
+        if copy_with_command(program, args, text).is_ok() {
+            return Ok(program);
+        }
+    }
+    let term = std::env::var("TERM").ok();
+    if !osc52_usable(std::io::stdout().is_terminal(), term.as_deref()) {
+        return Err(ClipboardError::Unavailable);
+    }
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()?;
+    Ok("OSC 52")
+}
+
+/// Whether the OSC 52 fallback can reach a terminal: stdout must be a TTY
+/// that is not a `dumb` terminal.
+fn osc52_usable(stdout_is_tty: bool, term: Option<&str>) -> bool {
+    stdout_is_tty && term != Some("dumb")
+}
+
+/// OSC 52 "set clipboard" escape sequence for `text`.
+fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode("你好".as_bytes()), "5L2g5aW9");
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("hi"), "\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            osc52_sequence("cargo test\n"),
+            "\x1b]52;c;Y2FyZ28gdGVzdAo=\x07"
+        );
+    }
+
+    #[test]
+    fn test_osc52_needs_terminal() {
+        assert!(osc52_usable(true, Some("xterm-256color")));
+        assert!(osc52_usable(true, None));
+        assert!(!osc52_usable(false, Some("xterm-256color")));
+        assert!(!osc52_usable(true, Some("dumb")));
+    }
+}
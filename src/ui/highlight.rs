@@ -0,0 +1,338 @@
+//! Lightweight syntax highlighting for fenced code blocks.
+//!
+//! A small per-line tokenizer (keywords, strings, comments, numbers) for a
+//! handful of common languages. Block comments and Python triple-quoted
+//! strings carry over between lines; anything fancier is left plain.
+
+use ratatui::{
+    style::{Color, Style},
+    text::Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    Rust,
+    Python,
+    JavaScript,
+    Json,
+    Shell,
+}
+
+impl Lang {
+    /// Language from a fence info string (` ```rust `, ` ```py title="x" `); None if unsupported.
+    pub fn from_hint(hint: &str) -> Option<Self> {
+        let name = hint
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+            .next()?
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "python" | "py" | "python3" => Some(Self::Python),
+            "javascript" | "js" | "jsx" | "mjs" | "typescript" | "ts" | "tsx" => {
+                Some(Self::JavaScript)
+            }
+            "json" | "jsonc" | "json5" => Some(Self::Json),
+            "sh" | "bash" | "shell" | "zsh" | "console" => Some(Self::Shell),
+            _ => None,
+        }
+    }
+
+    /// Space-separated keywords (and a few common builtins).
+    fn keywords(self) -> &'static str {
+        match self {
+            Self::Rust => {
+                "as async await break const continue crate dyn else enum extern false fn for \
+                 if impl in let loop match mod move mut pub ref return self Self static struct \
+                 super trait true type unsafe use where while"
+            }
+            Self::Python => {
+                "False None True and as assert async await break class continue def del elif \
+                 else except finally for from global if import in is lambda nonlocal not or \
+                 pass raise return self try while with yield"
+            }
+            Self::JavaScript => {
+                "async await break case catch class const continue default delete do else \
+                 enum export extends false finally for from function if implements import in \
+                 instanceof interface let new null of return static super switch this throw \
+                 true try type typeof undefined var void while yield"
+            }
+            Self::Json => "true false null",
+            Self::Shell => {
+                "case cd do done echo elif else esac exit export fi for function if in local \
+                 readonly return set source then unset until while"
+            }
+        }
+    }
+
+    fn is_keyword(self, word: &str) -> bool {
+        self.keywords().split_whitespace().any(|k| k == word)
+    }
+
+    fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Self::Rust | Self::JavaScript => Some("//"),
+            Self::Python | Self::Shell => Some("#"),
+            Self::Json => None,
+        }
+    }
+}
+
+const KEYWORD: Style = Style::new().fg(Color::Magenta);
+const STRING: Style = Style::new().fg(Color::Green);
+const COMMENT: Style = Style::new().fg(Color::DarkGray);
+const NUMBER: Style = Style::new().fg(Color::Cyan);
+/// Types, macros and lifetimes
+const TYPE: Style = Style::new().fg(Color::Yellow);
+/// JSON keys and shell variables
+const NAME: Style = Style::new().fg(Color::Blue);
+const PLAIN: Style = Style::new();
+
+/// Highlights a code block one line at a time.
+pub struct Highlighter {
+    lang: Lang,
+    /// Closing delimiter and style of a block comment / string still open from an earlier line.
+    open_block: Option<(&'static str, Style)>,
+}
+
+impl Highlighter {
+    pub fn new(lang: Lang) -> Self {
+        Self {
+            lang,
+            open_block: None,
+        }
+    }
+
+    /// Styled spans for one line (without its newline).
+    pub fn highlight_line(&mut self, line: &str) -> Vec<Span<'static>> {
+        let mut out = Spans::default();
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some((close, style)) = self.open_block {
+                match rest.find(close) {
+                    Some(end) => {
+                        out.push(&rest[..end + close.len()], style);
+                        rest = &rest[end + close.len()..];
+                        self.open_block = None;
+                    }
+                    None => {
+                        out.push(rest, style);
+                        break;
+                    }
+                }
+                continue;
+            }
+            let (len, style) = self.next_token(rest, &line[..line.len() - rest.len()]);
+            out.push(&rest[..len], style);
+            rest = &rest[len..];
+        }
+        out.spans
+    }
+
+    /// Length and style of the token at the start of `rest`; `before` is the
+    /// text already consumed on this line.
+    fn next_token(&mut self, rest: &str, before: &str) -> (usize, Style) {
+        let lang = self.lang;
+        let first = rest.chars().next().unwrap_or_default();
+        let prev = before.chars().next_back();
+
+        if let Some(prefix) = lang.line_comment() {
+            // `#` inside a shell word (`${#x}`, `a#b`) is not a comment
+            let at_word_start = lang != Lang::Shell || prev.is_none_or(char::is_whitespace);
+            if rest.starts_with(prefix) && at_word_start {
+                return (rest.len(), COMMENT);
+            }
+        }
+        if matches!(lang, Lang::Rust | Lang::JavaScript) && rest.starts_with("/*") {
+            self.open_block = Some(("*/", COMMENT));
+            return (2, COMMENT);
+        }
+        if lang == Lang::Python {
+            for quote in ["\"\"\"", "'''"] {
+                if rest.starts_with(quote) {
+                    self.open_block = Some((quote, STRING));
+                    return (3, STRING);
+                }
+            }
+        }
+
+        if first == '"'
+            || (first == '\'' && lang != Lang::Rust)
+            || (first == '`' && lang == Lang::JavaScript)
+        {
+            let len = quoted_len(rest, first);
+            let is_key = lang == Lang::Json && rest[len..].trim_start().starts_with(':');
+            return (len, if is_key { NAME } else { STRING });
+        }
+        if first == '\'' {
+            // Rust: char literal ('a', '\n') or lifetime ('a)
+            let mut chars = rest.char_indices().skip(1);
+            return match (chars.next(), chars.next()) {
+                (Some((_, '\\')), _) => (quoted_len(rest, '\''), STRING),
+                (Some(_), Some((end, '\''))) => (end + 1, STRING),
+                _ => (1 + ident_len(&rest[1..]), TYPE),
+            };
+        }
+        if lang == Lang::Shell && first == '$' {
+            let name = &rest[1..];
+            let len = if name.starts_with('{') {
+                name.find('}').map_or(name.len(), |end| end + 1)
+            } else {
+                ident_len(name).max(name.chars().next().map_or(0, |c| {
+                    if c.is_ascii_digit() || "@#?$!*-".contains(c) {
+                        1
+                    } else {
+                        0
+                    }
+                }))
+            };
+            return (1 + len, NAME);
+        }
+
+        let after_word = prev.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if first.is_ascii_digit() && !after_word {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            return (len, NUMBER);
+        }
+        if first.is_alphabetic() || first == '_' {
+            let len = ident_len(rest);
+            let word = &rest[..len];
+            if lang.is_keyword(word) {
+                return (len, KEYWORD);
+            }
+            if lang == Lang::Rust && rest[len..].starts_with('!') {
+                return (len + 1, TYPE);
+            }
+            if matches!(lang, Lang::Rust | Lang::JavaScript) && first.is_uppercase() && !after_word
+            {
+                return (len, TYPE);
+            }
+            return (len, PLAIN);
+        }
+        (first.len_utf8(), PLAIN)
+    }
+}
+
+/// Length of an identifier (letters, digits, `_`) at the start of `s`.
+fn ident_len(s: &str) -> usize {
+    s.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(s.len())
+}
+
+/// Length of a string starting with `quote`, through the closing quote or end of line.
+fn quoted_len(s: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return i + c.len_utf8(),
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Builds spans, merging neighbours of the same style.
+#[derive(Default)]
+struct Spans {
+    spans: Vec<Span<'static>>,
+}
+
+impl Spans {
+    fn push(&mut self, text: &str, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            Some(last) if last.style == style => last.content.to_mut().push_str(text),
+            _ => self.spans.push(Span::styled(text.to_string(), style)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(lang: Lang, lines: &[&str]) -> Vec<Vec<(String, Style)>> {
+        let mut h = Highlighter::new(lang);
+        lines
+            .iter()
+            .map(|line| {
+                h.highlight_line(line)
+                    .into_iter()
+                    .map(|s| (s.content.into_owned(), s.style))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn style_of(spans: &[(String, Style)], text: &str) -> Style {
+        spans
+            .iter()
+            .find(|(t, _)| t == text)
+            .unwrap_or_else(|| panic!("no span {:?} in {:?}", text, spans))
+            .1
+    }
+
+    #[test]
+    fn test_tokens_per_language() {
+        let rust = styled(
+            Lang::Rust,
+            &[
+                "fn main<'a>(x: &'a str) -> Vec<u8> { println!(\"hi {}\", 42); } // done",
+                "/* open",
+                "still */ let c = '\\n';",
+            ],
+        );
+        assert_eq!(style_of(&rust[0], "fn"), KEYWORD);
+        assert_eq!(style_of(&rust[0], "'a"), TYPE);
+        assert_eq!(style_of(&rust[0], "Vec"), TYPE);
+        assert_eq!(style_of(&rust[0], "println!"), TYPE);
+        assert_eq!(style_of(&rust[0], "\"hi {}\""), STRING);
+        assert_eq!(style_of(&rust[0], "42"), NUMBER);
+        assert_eq!(style_of(&rust[0], "// done"), COMMENT);
+        assert_eq!(style_of(&rust[1], "/* open"), COMMENT);
+        assert_eq!(style_of(&rust[2], "still */"), COMMENT);
+        assert_eq!(style_of(&rust[2], "'\\n'"), STRING);
+
+        let py = styled(
+            Lang::Python,
+            &["def f(): return None  # x", "s = \"\"\"a", "b\"\"\""],
+        );
+        assert_eq!(style_of(&py[0], "def"), KEYWORD);
+        assert_eq!(style_of(&py[0], "# x"), COMMENT);
+        assert_eq!(style_of(&py[1], "\"\"\"a"), STRING);
+        assert_eq!(style_of(&py[2], "b\"\"\""), STRING);
+
+        let json = styled(
+            Lang::Json,
+            &["{\"key\": \"value\", \"n\": 1.5, \"ok\": true}"],
+        );
+        assert_eq!(style_of(&json[0], "\"key\""), NAME);
+        assert_eq!(style_of(&json[0], "\"value\""), STRING);
+        assert_eq!(style_of(&json[0], "1.5"), NUMBER);
+        assert_eq!(style_of(&json[0], "true"), KEYWORD);
+
+        let sh = styled(Lang::Shell, &["echo \"$HOME\" ${#arr} $1 # note"]);
+        assert_eq!(style_of(&sh[0], "echo"), KEYWORD);
+        assert_eq!(style_of(&sh[0], "${#arr}"), NAME);
+        assert_eq!(style_of(&sh[0], "$1"), NAME);
+        assert_eq!(style_of(&sh[0], "# note"), COMMENT);
+
+        // Highlighting never adds or drops text
+        let line = "const x = `t ${a}` + 0x1F; // ünïcode";
+        let js = styled(Lang::JavaScript, &[line]);
+        assert_eq!(
+            js[0].iter().map(|(t, _)| t.as_str()).collect::<String>(),
+            line
+        );
+
+        assert_eq!(Lang::from_hint("py title=\"a.py\""), Some(Lang::Python));
+        assert_eq!(Lang::from_hint("TypeScript"), Some(Lang::JavaScript));
+        assert_eq!(Lang::from_hint("haskell"), None);
+        assert_eq!(Lang::from_hint(""), None);
+    }
+}
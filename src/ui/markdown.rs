@@ -1,14 +1,17 @@
 //! Markdown to ratatui styled text converter.
 //!
 //! Uses pulldown-cmark to parse Markdown and produces `Vec<Line>` with
-//! appropriate colors and modifiers for terminal rendering.
+//! appropriate colors and modifiers for terminal rendering. Fenced code
+//! blocks in a known language are syntax highlighted (see [`super::highlight`]).
 
-use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
 };
 
+use super::highlight::{Highlighter, Lang};
+
 pub fn markdown_to_lines(md: &str) -> Vec<Line<'static>> {
     let opts = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
     let parser = Parser::new_ext(md, opts);
@@ -29,6 +32,8 @@ struct MdRenderer {
     italic: bool,
     in_code_span: bool,
     in_code_block: bool,
+    /// Set for fenced code blocks in a supported language.
+    highlighter: Option<Highlighter>,
     in_heading: u8,
 
     list_stack: Vec<ListKind>,
@@ -49,6 +54,7 @@ impl MdRenderer {
             italic: false,
             in_code_span: false,
             in_code_block: false,
+            highlighter: None,
             in_heading: 0,
             list_stack: Vec::new(),
         }
@@ -87,14 +93,21 @@ impl MdRenderer {
             for (i, code_line) in text.split('\n').enumerate() {
                 if i > 0 {
                     self.flush_line();
+                }
+                if code_line.is_empty() {
+                    continue;
+                }
+                if self.current_spans.is_empty() {
                     self.current_spans
                         .push(Span::styled("  ".to_string(), Style::default()));
                 }
-                if !code_line.is_empty() {
-                    self.current_spans.push(Span::styled(
-                        format!("  {}", code_line),
-                        self.current_style(),
-                    ));
+                match self.highlighter.as_mut() {
+                    Some(h) => self.current_spans.extend(h.highlight_line(code_line)),
+                    None => {
+                        let style = self.current_style();
+                        self.current_spans
+                            .push(Span::styled(code_line.to_string(), style));
+                    }
                 }
             }
             return;
@@ -163,12 +176,17 @@ impl MdRenderer {
             Tag::Emphasis => {
                 self.italic = true;
             }
-            Tag::CodeBlock(_) => {
+            Tag::CodeBlock(kind) => {
                 self.flush_line();
                 self.in_code_block = true;
+                self.highlighter = match kind {
+                    CodeBlockKind::Fenced(info) => Lang::from_hint(&info).map(Highlighter::new),
+                    CodeBlockKind::Indented => None,
+                };
             }
             Tag::List(start) => {
-                if self.list_stack.is_empty() && !self.current_spans.is_empty() {
+                // Nested lists start inside an item: end the parent item's line first
+                if !self.current_spans.is_empty() {
                     self.flush_line();
                 }
                 let kind = match start {
@@ -222,6 +240,7 @@ impl MdRenderer {
             }
             TagEnd::CodeBlock => {
                 self.in_code_block = false;
+                self.highlighter = None;
                 self.flush_line();
                 self.lines.push(Line::from(""));
             }
@@ -231,7 +250,8 @@ impl MdRenderer {
                     self.lines.push(Line::from(""));
                 }
             }
-            TagEnd::Item => {
+            // Already flushed when the item ended with a nested list
+            TagEnd::Item if !self.current_spans.is_empty() => {
                 self.flush_line();
             }
             _ => {}
@@ -328,18 +348,72 @@ mod tests {
         assert!(text.contains("one"));
     }
 
+    #[test]
+    fn test_nested_list_indentation() {
+        let leading = |lines: &[Line], needle: &str| {
+            let line = lines_to_plain(lines)
+                .lines()
+                .find(|l| l.contains(needle))
+                .unwrap()
+                .to_string();
+            line.len() - line.trim_start().len()
+        };
+        for md in [
+            "- outer\n  - inner\n- next",
+            "1. outer\n   1. inner\n2. next",
+            "- outer\n  1. inner\n- next",
+        ] {
+            let lines = markdown_to_lines(md);
+            let text = lines_to_plain(&lines);
+            // Each item on its own line, no blank line after the sublist
+            assert!(!text.contains("outer  "), "{:?}", text);
+            assert!(
+                leading(&lines, "inner") > leading(&lines, "outer"),
+                "{:?}",
+                text
+            );
+            assert_eq!(leading(&lines, "next"), leading(&lines, "outer"));
+            assert!(!text.contains("inner\n\n"), "{:?}", text);
+        }
+    }
+
     #[test]
     fn test_code_block() {
-        let md = "```rust\nfn main() {}\n```";
+        for md in ["```\nfn main() {}\n```", "```haskell\nfn main() {}\n```"] {
+            let lines = markdown_to_lines(md);
+            let text = lines_to_plain(&lines);
+            assert!(text.contains("  fn main()"));
+            let code_span = lines
+                .iter()
+                .flat_map(|l| &l.spans)
+                .find(|s| s.content.contains("fn main()"))
+                .expect("code block span");
+            assert_eq!(code_span.style.fg, Some(Color::Green));
+        }
+    }
+
+    #[test]
+    fn test_fenced_code_block_highlighted() {
+        let md = "```rust\nfn main() {\n    let s = \"hi\";\n}\n```";
         let lines = markdown_to_lines(md);
         let text = lines_to_plain(&lines);
-        assert!(text.contains("fn main()"));
-        let code_span = lines
-            .iter()
-            .flat_map(|l| &l.spans)
-            .find(|s| s.content.contains("fn main()"))
-            .expect("code block span");
-        assert_eq!(code_span.style.fg, Some(Color::Green));
+        // Every code line keeps the same two-space indent
+        assert!(
+            text.contains("\n  fn main() {\n      let s = \"hi\";\n  }\n"),
+            "{:?}",
+            text
+        );
+        let fg = |needle: &str| {
+            lines
+                .iter()
+                .flat_map(|l| &l.spans)
+                .find(|s| s.content == needle)
+                .unwrap_or_else(|| panic!("no span {:?}", needle))
+                .style
+                .fg
+        };
+        assert_eq!(fg("fn"), Some(Color::Magenta));
+        assert_eq!(fg("\"hi\""), Some(Color::Green));
     }
 
     #[test]
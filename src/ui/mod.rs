@@ -2,7 +2,7 @@
 
 use ratatui::Frame;
 
-use crate::agent::SessionStats;
+use crate::agent::{ApiStatus, SessionStats};
 
 /// What should happen when the UI exits its run loop.
 #[derive(Debug, Clone)]
@@ -11,11 +11,13 @@ pub enum UiExitAction {
 }
 
 /// Context passed to header widgets each render frame.
+#[allow(dead_code)]
 pub struct WidgetContext<'a> {
     pub stats: &'a SessionStats,
     pub messages: &'a [String],
     pub processing: bool,
-    pub anim_tick: u32,
+    /// Animation clock (wall time since start, scaled by `ui.pet_animation_speed`).
+    pub anim_elapsed: std::time::Duration,
     pub pet_state: PetState,
     pub pet_name: &'a str,
     pub idle_ticks: u32,
@@ -25,6 +27,8 @@ pub struct WidgetContext<'a> {
     pub context_limit: u64,
     /// Current model id (e.g. "coding_plan/qwen3.5-plus")
     pub current_model_id: &'a str,
+    /// Result of the last API health check.
+    pub api_status: ApiStatus,
 }
 
 /// Pluggable header widget trait.
@@ -36,5 +40,7 @@ pub trait HeaderWidget {
 
 pub use ratatui_ui::PetState;
 
+pub mod clipboard;
+pub mod highlight;
 pub mod markdown;
 pub mod ratatui_ui;
@@ -1,7 +1,8 @@
 //! Modern TUI implementation using ratatui with pluggable header widgets
 //! and multi-session tab support.
 
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::io::Write;
 use std::path::PathBuf;
 
 use anyhow::Result;
@@ -16,10 +17,16 @@ use ratatui::{
     Frame,
 };
 
-use crate::agent::{Agent, AgentEvent, SessionStats};
-use crate::config::{AppConfig, ModelEntry};
+use crate::agent::{Agent, AgentEvent, ApiStatus, ConfirmDecision, SessionStats};
+use crate::config::{AppConfig, ContentAlign, ModelEntry, TabLayout, UiConfig};
+use crate::input_history::InputHistory;
+use crate::rules;
 use crate::session::{self, SessionData, SessionStatsData};
+use crate::tools::bash::BashTool;
+use crate::tools::risk::{self, RiskLevel};
+use crate::tools::secrets;
 use crate::trusted_workspaces;
+use crate::types::Message;
 use crate::ui::{HeaderWidget, UiExitAction, WidgetContext};
 
 // ── Slash Command Definitions ───────────────────────────────
@@ -50,6 +57,14 @@ const SLASH_COMMANDS: &[SlashCommand] = &[
         name: "/rename",
         description: "Rename current session (/rename <name>)",
     },
+    SlashCommand {
+        name: "/title",
+        description: "Regenerate the session title (/title <name> to set it)",
+    },
+    SlashCommand {
+        name: "/delete",
+        description: "Delete a saved session (/delete <id>)",
+    },
     SlashCommand {
         name: "/sessions",
         description: "List saved sessions",
@@ -66,10 +81,95 @@ const SLASH_COMMANDS: &[SlashCommand] = &[
         name: "/export",
         description: "Export session to file (/export <path>)",
     },
+    SlashCommand {
+        name: "/export-md",
+        description: "Export a readable Markdown transcript (/export-md <path>)",
+    },
     SlashCommand {
         name: "/import",
         description: "Import session from file (/import <path>)",
     },
+    SlashCommand {
+        name: "/history",
+        description: "Show raw message history as JSON (/history [path])",
+    },
+    SlashCommand {
+        name: "/summarize",
+        description: "Summarize session into notes (/summarize [note.md])",
+    },
+    SlashCommand {
+        name: "/iter",
+        description: "Show or set the agent's max tool iterations (/iter [n])",
+    },
+    SlashCommand {
+        name: "/queue",
+        description: "List or clear queued messages (/queue [clear])",
+    },
+    SlashCommand {
+        name: "/stream",
+        description: "Show or toggle streaming responses (/stream [on|off])",
+    },
+    SlashCommand {
+        name: "/stream-to",
+        description: "Also write streamed replies to a file (/stream-to <path>)",
+    },
+    SlashCommand {
+        name: "/replay",
+        description: "Re-run a saved session's user messages in a new tab (/replay <id> [model])",
+    },
+    SlashCommand {
+        name: "/whoami",
+        description: "Show project, model, API host, config, rules and tool count",
+    },
+    SlashCommand {
+        name: "/all",
+        description: "Toggle rendering older messages hidden by ui.render_window",
+    },
+    SlashCommand {
+        name: "/dryrun",
+        description: "Toggle dry run: describe changes without making them (/dryrun [on|off])",
+    },
+    SlashCommand {
+        name: "/verbose",
+        description: "Cycle tool status detail: minimal, args, full (/verbose [level])",
+    },
+    SlashCommand {
+        name: "/open",
+        description: "Open a file in $EDITOR (/open [path], default: last modified)",
+    },
+    SlashCommand {
+        name: "/rerun",
+        description: "Run the agent's last bash command again",
+    },
+    SlashCommand {
+        name: "/run",
+        description: "Run a shell command and add its output as context (/run <cmd>)",
+    },
+    SlashCommand {
+        name: "/sendto",
+        description: "Send your last message to another tab (/sendto <tab number|name>)",
+    },
+    SlashCommand {
+        name: "/compare",
+        description:
+            "Ask two models the same prompt in side-by-side tabs (/compare <a> <b> <prompt>)",
+    },
+    SlashCommand {
+        name: "/search",
+        description: "Find text in this conversation (/search <text>; Ctrl+N/Ctrl+P to cycle)",
+    },
+    SlashCommand {
+        name: "/snippet",
+        description: "Insert a saved snippet into the input (/snippet [name])",
+    },
+    SlashCommand {
+        name: "/env",
+        description: "Show or change this session's bash environment (/env set|unset)",
+    },
+    SlashCommand {
+        name: "/notes",
+        description: "Toggle this tab's private notes (/notes [clear])",
+    },
     SlashCommand {
         name: "/stats",
         description: "Toggle stats panel",
@@ -78,13 +178,17 @@ const SLASH_COMMANDS: &[SlashCommand] = &[
         name: "/pet",
         description: "Toggle pet panel",
     },
+    SlashCommand {
+        name: "/timestamps",
+        description: "Toggle message timestamps",
+    },
     SlashCommand {
         name: "/petname",
         description: "Set pet name (/petname <name>)",
     },
     SlashCommand {
         name: "/model",
-        description: "List or switch model (/model [id])",
+        description: "List or switch model (/model [id|alias], /model info [id])",
     },
     SlashCommand {
         name: "/stop",
@@ -126,6 +230,8 @@ struct SlashAutocomplete {
     visible: bool,
     selected: usize,
     filtered: Vec<usize>,
+    /// Snippet names matching `/snippet <query>`, shown instead of commands.
+    snippets: Vec<String>,
 }
 
 impl SlashAutocomplete {
@@ -134,10 +240,21 @@ impl SlashAutocomplete {
             visible: false,
             selected: 0,
             filtered: Vec::new(),
+            snippets: Vec::new(),
         }
     }
 
     fn update_filter(&mut self, input: &str) {
+        self.snippets.clear();
+        if let Some(query) = input.strip_prefix("/snippet ") {
+            self.filtered.clear();
+            self.snippets = crate::snippets::dir()
+                .map(|dir| crate::snippets::search_in(&dir, query))
+                .unwrap_or_default();
+            self.visible = !self.snippets.is_empty();
+            self.selected = self.selected.min(self.len().saturating_sub(1));
+            return;
+        }
         let cmd_part = input.split_whitespace().next().unwrap_or(input);
         if !is_slash_command(cmd_part) && cmd_part != "/" {
             self.visible = false;
@@ -166,35 +283,79 @@ impl SlashAutocomplete {
         }
     }
 
+    fn len(&self) -> usize {
+        if self.snippets.is_empty() {
+            self.filtered.len()
+        } else {
+            self.snippets.len()
+        }
+    }
+
     fn move_up(&mut self) {
         if self.selected > 0 {
             self.selected -= 1;
         } else {
-            self.selected = self.filtered.len().saturating_sub(1);
+            self.selected = self.len().saturating_sub(1);
         }
     }
 
     fn move_down(&mut self) {
-        if self.selected + 1 < self.filtered.len() {
+        if self.selected + 1 < self.len() {
             self.selected += 1;
         } else {
             self.selected = 0;
         }
     }
 
-    fn selected_command(&self) -> Option<&'static str> {
+    /// Input text for the highlighted entry: a command name or `/snippet <name>`.
+    fn selected_command(&self) -> Option<String> {
+        if !self.snippets.is_empty() {
+            return self
+                .snippets
+                .get(self.selected)
+                .map(|name| format!("/snippet {}", name));
+        }
         self.filtered
             .get(self.selected)
-            .map(|&i| SLASH_COMMANDS[i].name)
+            .map(|&i| SLASH_COMMANDS[i].name.to_string())
+    }
+
+    /// (name, description) of each popup entry.
+    fn items(&self) -> Vec<(&str, &str)> {
+        if !self.snippets.is_empty() {
+            return self
+                .snippets
+                .iter()
+                .map(|name| (name.as_str(), "snippet"))
+                .collect();
+        }
+        self.filtered
+            .iter()
+            .map(|&i| (SLASH_COMMANDS[i].name, SLASH_COMMANDS[i].description))
+            .collect()
     }
 
     fn dismiss(&mut self) {
         self.visible = false;
         self.filtered.clear();
+        self.snippets.clear();
         self.selected = 0;
     }
 }
 
+/// Ask the terminal to report modifier keys unambiguously (e.g. Shift+Enter).
+/// Returns false when the terminal does not support it.
+fn push_keyboard_enhancement() -> bool {
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::event::PushKeyboardEnhancementFlags(
+            crossterm::event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                | crossterm::event::KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+        )
+    )
+    .is_ok()
+}
+
 struct TerminalGuard {
     keyboard_enhanced: bool,
 }
@@ -222,6 +383,8 @@ pub enum PetState {
     Happy,
     Error,
     Sleeping,
+    /// Idle, but the context window is nearly full (`ui.pet_worried_percent`).
+    Worried,
 }
 
 type ArtFrame = &'static [&'static str];
@@ -418,18 +581,42 @@ impl PetState {
                     r"   zZzZ   ",
                 ],
             ],
+            PetState::Worried => &[
+                &[
+                    r"   /\_/\ '",
+                    r"  ( O.O;) ",
+                    r"   > n <  ",
+                    r"  /|   |\ ",
+                    r"  (___)   ",
+                ],
+                &[
+                    r"   /\_/\  ",
+                    r"  (;O.O ) ",
+                    r"   > n <  ",
+                    r"  /|   |\ ",
+                    r"   (___)  ",
+                ],
+                &[
+                    r"  '/\_/\  ",
+                    r"  ( o.o;) ",
+                    r"   > ~ <  ",
+                    r"  /|   |\ ",
+                    r"  /compact",
+                ],
+            ],
         }
     }
 
-    fn ticks_per_frame(&self) -> u32 {
+    fn ms_per_frame(&self) -> u64 {
         match self {
-            PetState::Idle => 8,
-            PetState::Typing => 4,
-            PetState::TypingFast => 2,
-            PetState::Thinking => 5,
-            PetState::Happy => 3,
-            PetState::Error => 6,
-            PetState::Sleeping => 10,
+            PetState::Idle => 800,
+            PetState::Typing => 400,
+            PetState::TypingFast => 200,
+            PetState::Thinking => 500,
+            PetState::Happy => 300,
+            PetState::Error => 600,
+            PetState::Sleeping => 1000,
+            PetState::Worried => 600,
         }
     }
 
@@ -442,6 +629,7 @@ impl PetState {
             PetState::Happy => "Happy!",
             PetState::Error => "Oh no...",
             PetState::Sleeping => "zzZ...",
+            PetState::Worried => "So full...",
         }
     }
 
@@ -454,13 +642,49 @@ impl PetState {
             PetState::Happy => Color::Green,
             PetState::Error => Color::Red,
             PetState::Sleeping => Color::DarkGray,
+            PetState::Worried => Color::LightRed,
         }
     }
 
-    fn current_frame(&self, tick: u32) -> ArtFrame {
+    /// Frame shown after `elapsed` of animation time, independent of how often we redraw.
+    fn current_frame(&self, elapsed: std::time::Duration) -> ArtFrame {
         let frames = self.frames();
-        let idx = (tick / self.ticks_per_frame()) as usize % frames.len();
-        frames[idx]
+        let step = elapsed.as_millis() / u128::from(self.ms_per_frame());
+        frames[(step % frames.len() as u128) as usize]
+    }
+
+    /// Next state of an idle tab's pet (a processing tab keeps its state).
+    /// `worried` (high context pressure) replaces Idle, but typing and sleep win over it.
+    fn next(
+        self,
+        processing: bool,
+        typing_intensity: u32,
+        idle_ticks: u32,
+        input_empty: bool,
+        worried: bool,
+    ) -> PetState {
+        if processing {
+            return self;
+        }
+        let resting = if worried {
+            PetState::Worried
+        } else {
+            PetState::Idle
+        };
+        if typing_intensity > TYPING_FAST_THRESHOLD {
+            PetState::TypingFast
+        } else if typing_intensity > 0 && !input_empty {
+            PetState::Typing
+        } else if idle_ticks > 300 {
+            PetState::Sleeping
+        } else if matches!(self, PetState::Idle | PetState::Worried)
+            || (matches!(self, PetState::Happy | PetState::Error) && idle_ticks > 50)
+            || (matches!(self, PetState::Typing | PetState::TypingFast) && typing_intensity == 0)
+        {
+            resting
+        } else {
+            self
+        }
     }
 }
 
@@ -478,7 +702,7 @@ impl HeaderWidget for StatsWidget {
 
     fn render(&self, f: &mut Frame, area: Rect, ctx: &WidgetContext) {
         let stats = ctx.stats;
-        let status_line = if ctx.processing {
+        let mut status_line = if ctx.processing {
             Line::from(vec![
                 Span::raw("  "),
                 Span::styled(
@@ -496,6 +720,18 @@ impl HeaderWidget for StatsWidget {
             ])
         };
 
+        match ctx.api_status {
+            ApiStatus::Online => status_line.spans.push(Span::styled(
+                "  API: online",
+                Style::default().fg(Color::Green),
+            )),
+            ApiStatus::Offline => status_line.spans.push(Span::styled(
+                "  API: offline",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )),
+            ApiStatus::Unknown => {}
+        }
+
         let usage_days = ctx.first_use_date.map_or(1i64, |first| {
             let today = chrono::Local::now().date_naive();
             (today - first).num_days().max(0) + 1
@@ -525,7 +761,7 @@ impl HeaderWidget for StatsWidget {
         } else {
             ctx.current_model_id.to_string()
         };
-        let lines = vec![
+        let mut lines = vec![
             status_line,
             Line::from(vec![
                 Span::styled("  Model: ", Style::default().fg(Color::DarkGray)),
@@ -575,6 +811,15 @@ impl HeaderWidget for StatsWidget {
                 Span::styled("cmds", Style::default().fg(Color::DarkGray)),
             ]),
         ];
+        if let Some(cost) = stats.total_cost {
+            lines.insert(
+                4,
+                Line::from(vec![
+                    Span::styled("  Cost: ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(format_cost(cost), Style::default().fg(Color::Green)),
+                ]),
+            );
+        }
 
         let widget = Paragraph::new(lines).block(
             Block::default()
@@ -599,7 +844,7 @@ impl HeaderWidget for PetWidget {
     fn render(&self, f: &mut Frame, area: Rect, ctx: &WidgetContext) {
         let state = &ctx.pet_state;
         let art_color = state.color();
-        let frame = state.current_frame(ctx.anim_tick);
+        let frame = state.current_frame(ctx.anim_elapsed);
 
         let mut lines: Vec<Line> = Vec::new();
         for art_line in frame {
@@ -637,10 +882,19 @@ fn format_token_count(n: u64) -> String {
     }
 }
 
+/// `$0.0123` below a dollar, `$12.34` above.
+fn format_cost(usd: f64) -> String {
+    if usd < 1.0 {
+        format!("${:.4}", usd)
+    } else {
+        format!("${:.2}", usd)
+    }
+}
+
 // ── Persistence helpers ─────────────────────────────────────
 
 fn usage_data_path() -> Option<std::path::PathBuf> {
-    dirs::home_dir().map(|h| h.join(".miniclaw").join("usage.json"))
+    crate::paths::usage_path().ok()
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Default)]
@@ -684,28 +938,135 @@ struct SessionTab {
     id: String,
     name: String,
     messages: Vec<String>,
+    /// When each message was added, parallel to `messages` (see `stamp_messages`).
+    message_times: Vec<Option<String>>,
+    /// Highlighted message index while in message-select mode (Alt+Up), for copying.
+    selected_message: Option<usize>,
     scroll_offset: usize,
+    /// Active `/search`; matches are highlighted and Ctrl+N/Ctrl+P cycle through them.
+    search: Option<ConversationSearch>,
     follow_tail: bool,
     processing: bool,
     pet_state: PetState,
     streaming_message_idx: Option<usize>,
-    tool_progress_idx: Option<usize>,
+    /// Status lines of running tools, oldest first; parallel calls end in call order.
+    tool_progress_idx: VecDeque<usize>,
     cached_stats: SessionStats,
     agent: Option<Agent>,
     event_rx: Option<tokio::sync::mpsc::UnboundedReceiver<AgentEvent>>,
     agent_handle: Option<tokio::task::JoinHandle<Result<Agent>>>,
+    /// Agent history at the start of the running turn (the agent itself is moved into
+    /// the task); used for auto-save and to rebuild the agent if the task fails or panics.
+    history_snapshot: Vec<Message>,
     input: String,
     cursor_position: usize,
     pending_messages: VecDeque<String>,
     user_message_count: u32,
     title_task: Option<tokio::task::JoinHandle<Option<String>>>,
-    confirm_tx: Option<tokio::sync::mpsc::UnboundedSender<bool>>,
+    /// Conversation summary from `/summarize`, persisted with the session.
+    summary: Option<String>,
+    /// Pending `/summarize` request and the optional `.md` note path to write.
+    summary_task: Option<(
+        tokio::task::JoinHandle<Result<String>>,
+        Option<std::path::PathBuf>,
+    )>,
+    /// Path of the last file the agent wrote or edited, for `/open`.
+    last_modified_path: Option<String>,
+    /// Arguments of the agent's most recent `bash` call, for `/rerun`.
+    last_bash_args: Option<String>,
+    /// Running `/rerun` and the command it executes.
+    rerun_task: Option<(tokio::task::JoinHandle<Result<String>>, String)>,
+    /// Running `/run` and its command; the output becomes context for the next turn.
+    run_task: Option<(tokio::task::JoinHandle<Result<String>>, String)>,
+    /// `/run` command waiting for the Y/N risk confirmation.
+    pending_run: Option<String>,
+    /// Scratchpad text (`/notes`), saved with the session but never sent to the model.
+    notes: String,
+    /// Notes overlay is open and receives typed keys instead of the chat input.
+    notes_open: bool,
+    /// `/all`: render every message, ignoring `ui.render_window`.
+    show_all: bool,
+    /// Environment for this session's bash commands (`/env`), layered over `tools.bash_env`.
+    env: BTreeMap<String, String>,
+    /// `env` keys whose values look like secrets and are kept out of the saved session.
+    env_unsaved: HashSet<String>,
+    /// `/stream-to` target: streamed deltas are appended here until the turn ends.
+    stream_file: Option<(std::fs::File, std::path::PathBuf)>,
+    /// Tool status detail set by `/verbose`; `None` follows the `[ui]` options.
+    tool_verbosity: Option<ToolVerbosity>,
+    confirm_tx: Option<tokio::sync::mpsc::UnboundedSender<ConfirmDecision>>,
     pending_confirm: Option<String>,
     context_used: u64,
     context_limit: u64,
     current_model_id: String,
+    /// Last user input or agent event, for `ui.unload_idle_tabs_after_mins`.
+    last_activity: std::time::Instant,
+    /// Agent and messages dropped after inactivity; reloaded from the saved session.
+    unloaded: bool,
+}
+
+/// How much of each tool call the conversation shows (`/verbose`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ToolVerbosity {
+    /// Status line only.
+    Minimal,
+    /// Status line plus an argument preview for file-modifying tools.
+    Args,
+    /// Arguments plus a preview of the tool output.
+    Full,
+}
+
+impl ToolVerbosity {
+    /// Level implied by `ui.show_tool_output` / `ui.preview_tool_args`.
+    fn from_ui(ui: &UiConfig) -> Self {
+        if ui.show_tool_output {
+            Self::Full
+        } else if ui.preview_tool_args {
+            Self::Args
+        } else {
+            Self::Minimal
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "minimal" => Some(Self::Minimal),
+            "args" => Some(Self::Args),
+            "full" => Some(Self::Full),
+            _ => None,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Minimal => Self::Args,
+            Self::Args => Self::Full,
+            Self::Full => Self::Minimal,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal (status only)",
+            Self::Args => "args (status + arguments)",
+            Self::Full => "full (status + arguments + output preview)",
+        }
+    }
+}
+
+/// Whether an `/env` value should stay out of the saved session: a known token
+/// format, or a variable named like a credential.
+fn env_looks_secret(key: &str, value: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    !secrets::detect_secrets(value).is_empty()
+        || ["TOKEN", "SECRET", "PASSWORD", "API_KEY", "APIKEY"]
+            .iter()
+            .any(|word| key.contains(word))
 }
 
+/// Placeholder shown in an unloaded tab until it is restored.
+const UNLOADED_TAB_MESSAGE: &str = "[Unloaded after inactivity; select this tab to restore it]";
+
 impl SessionTab {
     fn new(id: String, name: String, agent: Agent) -> Self {
         let stats = agent.stats.clone();
@@ -715,28 +1076,162 @@ impl SessionTab {
         Self {
             id,
             name,
-            messages: vec!["Welcome to miniclaw! Type your message or /help for commands.".into()],
+            messages: vec![format!(
+                "Welcome to miniclaw v{}! Type your message or /help for commands.",
+                crate::build_info::VERSION
+            )],
+            message_times: vec![Some(session::now_timestamp())],
+            selected_message: None,
             scroll_offset: 0,
+            search: None,
             follow_tail: true,
             processing: false,
             pet_state: PetState::Idle,
             streaming_message_idx: None,
-            tool_progress_idx: None,
+            tool_progress_idx: VecDeque::new(),
             cached_stats: stats,
             agent: Some(agent),
             event_rx: None,
             agent_handle: None,
+            history_snapshot: Vec::new(),
             input: String::new(),
             cursor_position: 0,
             pending_messages: VecDeque::new(),
             user_message_count: 0,
             title_task: None,
+            summary: None,
+            summary_task: None,
+            last_modified_path: None,
+            last_bash_args: None,
+            rerun_task: None,
+            run_task: None,
+            pending_run: None,
+            notes: String::new(),
+            notes_open: false,
+            show_all: false,
+            env: BTreeMap::new(),
+            env_unsaved: HashSet::new(),
+            stream_file: None,
+            tool_verbosity: None,
             confirm_tx: None,
             pending_confirm: None,
             context_used: ctx_used,
             context_limit: ctx_limit,
             current_model_id,
+            last_activity: std::time::Instant::now(),
+            unloaded: false,
+        }
+    }
+
+    /// Whether the tab has been inactive for `idle` with nothing in flight,
+    /// so it can be unloaded safely.
+    fn is_idle(&self, now: std::time::Instant, idle: std::time::Duration) -> bool {
+        !self.unloaded
+            && !self.processing
+            && self.agent.is_some()
+            && self.pending_messages.is_empty()
+            && self.pending_confirm.is_none()
+            && self.title_task.is_none()
+            && self.summary_task.is_none()
+            && self.rerun_task.is_none()
+            && self.run_task.is_none()
+            && !self.notes_open
+            && now.saturating_duration_since(self.last_activity) >= idle
+    }
+
+    /// Replace the session environment and pass it to the agent.
+    fn set_env(&mut self, env: BTreeMap<String, String>, unsaved: HashSet<String>) {
+        if let Some(agent) = self.agent.as_mut() {
+            agent.set_session_env(env.clone());
+        }
+        self.env = env;
+        self.env_unsaved = unsaved;
+    }
+
+    /// `/env`: list, set or unset this session's bash environment variables.
+    fn env_command(&mut self, arg: &str) {
+        if self.agent.is_none() {
+            self.messages
+                .push("[Cannot change env while processing]".into());
+            return;
+        }
+        let mut env = self.env.clone();
+        let mut unsaved = self.env_unsaved.clone();
+        let (action, rest) = arg.split_once(' ').unwrap_or((arg, ""));
+        match action {
+            "" => {
+                if env.is_empty() {
+                    self.messages
+                        .push("[No session env set; usage: /env set [--persist] KEY=VALUE]".into());
+                }
+                for (key, value) in &env {
+                    let line = if unsaved.contains(key) {
+                        format!("  {}=******** (not saved)", key)
+                    } else {
+                        format!("  {}={}", key, value)
+                    };
+                    self.messages.push(line);
+                }
+                return;
+            }
+            "set" => {
+                let (persist, assignment) = match rest.trim().strip_prefix("--persist") {
+                    Some(rest) => (true, rest.trim()),
+                    None => (false, rest.trim()),
+                };
+                let valid_key = |key: &str| {
+                    key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                };
+                let Some((key, value)) = assignment.split_once('=').filter(|(k, _)| valid_key(k))
+                else {
+                    self.messages
+                        .push("Usage: /env set [--persist] KEY=VALUE".into());
+                    return;
+                };
+                if !persist && env_looks_secret(key, value) {
+                    unsaved.insert(key.to_string());
+                    self.messages.push(format!(
+                        "[Set {} for this session; it looks like a secret so it will not be saved (use --persist to save it)]",
+                        key
+                    ));
+                } else {
+                    unsaved.remove(key);
+                    self.messages.push(format!("[Set {}]", key));
+                }
+                env.insert(key.to_string(), value.to_string());
+            }
+            "unset" if !rest.trim().is_empty() => {
+                let key = rest.trim();
+                unsaved.remove(key);
+                if env.remove(key).is_some() {
+                    self.messages.push(format!("[Unset {}]", key));
+                } else {
+                    self.messages.push(format!("[{} is not set]", key));
+                }
+            }
+            _ => {
+                self.messages
+                    .push("Usage: /env [set [--persist] KEY=VALUE | unset KEY]".into());
+                return;
+            }
         }
+        self.set_env(env, unsaved);
+        let _ = self.auto_save();
+    }
+
+    /// Save the session, then drop the agent and conversation to free memory.
+    /// Nothing is dropped if the save fails.
+    fn unload(&mut self) -> Result<()> {
+        self.auto_save()?;
+        self.agent = None;
+        self.history_snapshot = Vec::new();
+        self.messages = vec![UNLOADED_TAB_MESSAGE.to_string()];
+        self.message_times = vec![None];
+        self.selected_message = None;
+        self.stream_file = None;
+        self.unloaded = true;
+        Ok(())
     }
 
     fn byte_index(&self) -> usize {
@@ -752,12 +1247,14 @@ impl SessionTab {
 
     fn send_next_pending(&mut self) {
         if let Some(msg) = self.pending_messages.pop_front() {
+            self.last_activity = std::time::Instant::now();
             self.messages.push(format!("You: {}", msg));
             self.processing = true;
             self.pet_state = PetState::Thinking;
             self.follow_tail = true;
 
             if let Some(mut moved_agent) = self.agent.take() {
+                self.history_snapshot = moved_agent.history().to_vec();
                 let (evt_tx, evt_rx) = tokio::sync::mpsc::unbounded_channel();
                 let (cfm_tx, mut cfm_rx) = tokio::sync::mpsc::unbounded_channel();
                 self.event_rx = Some(evt_rx);
@@ -769,35 +1266,361 @@ impl SessionTab {
                     result.map(|_| moved_agent)
                 }));
             }
-            self.auto_save();
+            let _ = self.auto_save();
+        }
+    }
+
+    /// The user's most recent message in this tab, for `/sendto`.
+    fn last_user_message(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find_map(|m| m.strip_prefix("You: "))
+    }
+
+    /// Queue `msg`, starting it right away unless a turn is already running.
+    fn enqueue_message(&mut self, msg: String) {
+        self.pending_messages.push_back(msg);
+        if !self.processing {
+            self.send_next_pending();
+        }
+    }
+
+    /// Numbered lines describing the queued messages, for `/queue`.
+    fn queue_lines(&self) -> Vec<String> {
+        if self.pending_messages.is_empty() {
+            return vec!["[No queued messages]".to_string()];
+        }
+        let mut lines = vec![format!("[{} queued]", self.pending_messages.len())];
+        for (i, msg) in self.pending_messages.iter().enumerate() {
+            let first = msg.lines().next().unwrap_or("");
+            let mut shown: String = first.chars().take(100).collect();
+            if msg.chars().count() > shown.chars().count() {
+                shown.push('…');
+            }
+            lines.push(format!("  {}. {}", i + 1, shown));
+        }
+        lines
+    }
+
+    /// Drop all queued messages; returns how many were removed.
+    fn clear_queue(&mut self) -> usize {
+        let n = self.pending_messages.len();
+        self.pending_messages.clear();
+        n
+    }
+
+    /// Remove the most recently queued message.
+    fn pop_last_queued(&mut self) -> Option<String> {
+        self.pending_messages.pop_back()
+    }
+
+    /// Enter message-select mode with the last message highlighted.
+    fn enter_select_mode(&mut self) {
+        if !self.messages.is_empty() {
+            self.selected_message = Some(self.messages.len() - 1);
+            self.follow_tail = false;
+        }
+    }
+
+    /// Move the selection by `delta` messages, clamped to the message list.
+    fn move_selection(&mut self, delta: isize) {
+        if let Some(idx) = self.selected_message {
+            let max = self.messages.len().saturating_sub(1);
+            self.selected_message = Some(idx.saturating_add_signed(delta).min(max));
+        }
+    }
+
+    /// Command of the agent's most recent `bash` call.
+    fn last_bash_command(&self) -> Option<String> {
+        let args: serde_json::Value = serde_json::from_str(self.last_bash_args.as_ref()?).ok()?;
+        args["command"].as_str().map(str::to_string)
+    }
+
+    /// Re-run the agent's last `bash` command directly, without the model (`/rerun`).
+    fn start_rerun(&mut self) {
+        if self.processing || self.rerun_task.is_some() {
+            self.messages
+                .push("[Cannot re-run while processing]".into());
+            return;
+        }
+        let (Some(args), Some(command)) = (self.last_bash_args.clone(), self.last_bash_command())
+        else {
+            self.messages.push("[No bash command to re-run yet]".into());
+            return;
+        };
+        let bash = self.bash_tool();
+        let handle = tokio::spawn(async move {
+            let params: serde_json::Value = serde_json::from_str(&args)?;
+            crate::tools::Tool::execute(&bash, params).await
+        });
+        self.messages.push(format!("[Re-running: {}]", command));
+        self.rerun_task = Some((handle, command));
+        self.follow_tail = true;
+    }
+
+    /// The bash tool with this session's environment.
+    fn bash_tool(&self) -> BashTool {
+        self.agent
+            .as_ref()
+            .map(Agent::bash_tool)
+            .unwrap_or_default()
+    }
+
+    /// Run `command` with the bash tool for `/run` (already confirmed if risky).
+    fn start_run(&mut self, command: String) {
+        let params = serde_json::json!({ "command": command });
+        let bash = self.bash_tool();
+        let handle = tokio::spawn(async move { crate::tools::Tool::execute(&bash, params).await });
+        self.messages.push(format!("[Running: {}]", command));
+        self.run_task = Some((handle, command));
+        self.follow_tail = true;
+    }
+
+    /// Collect a finished `/run` and hand its output to the agent as context.
+    fn poll_run_task(&mut self) {
+        if !self
+            .run_task
+            .as_ref()
+            .is_some_and(|(handle, _)| handle.is_finished())
+        {
+            return;
+        }
+        let Some((task, command)) = self.run_task.take() else {
+            return;
+        };
+        match task.now_or_never() {
+            Some(Ok(Ok(output))) => {
+                self.messages.push(format!("RERUN:{}\n{}", command, output));
+                if let Some(agent) = self.agent.as_mut() {
+                    agent.add_context(format!(
+                        "Output of `{}` (run by the user with /run):\n```\n{}\n```",
+                        command,
+                        output.trim_end()
+                    ));
+                    self.context_used = agent.estimate_context_tokens();
+                    self.messages
+                        .push("[Output added as context for your next message]".into());
+                }
+                let _ = self.auto_save();
+            }
+            Some(Ok(Err(e))) => self.messages.push(format!("Error: {}", e)),
+            _ => self.messages.push("Error: /run failed".into()),
+        }
+        self.follow_tail = true;
+    }
+
+    /// Text of the selected message without its display prefix.
+    fn selected_message_text(&self) -> Option<&str> {
+        let msg = self.messages.get(self.selected_message?)?;
+        let text = [
+            "You: ",
+            "Assistant: ",
+            "SUMMARY:",
+            "HISTORY:",
+            "RERUN:",
+            "TOOL_PROGRESS:",
+            "TOOL_DONE:",
+            "TOOL_ERROR:",
+            "TOOL_OUTPUT:",
+        ]
+        .iter()
+        .find_map(|p| msg.strip_prefix(p))
+        .unwrap_or(msg);
+        Some(text)
+    }
+
+    /// Store a generated summary, show it, save the session and optionally write a note.
+    fn apply_summary(&mut self, summary: String, note_path: Option<&std::path::Path>) {
+        self.messages.push(format!("SUMMARY:{}", summary));
+        self.summary = Some(summary);
+        let _ = self.auto_save();
+        if let Some(path) = note_path {
+            match session::write_summary_note(&self.to_session_data(), path) {
+                Ok(()) => self
+                    .messages
+                    .push(format!("[Summary written to {}]", path.display())),
+                Err(e) => self.messages.push(format!("Error: {}", e)),
+            }
+        }
+    }
+
+    /// Timestamp messages added since the last call; drop times of removed messages.
+    fn stamp_messages(&mut self) {
+        let len = self.messages.len();
+        if self.message_times.len() > len {
+            self.message_times.truncate(len);
+        } else if self.message_times.len() < len {
+            let now = session::now_timestamp();
+            self.message_times.resize(len, Some(now));
         }
     }
 
+    /// Restore UI messages and their timestamps from saved session data.
+    fn restore_messages(&mut self, messages: Vec<String>, mut times: Vec<Option<String>>) {
+        // Older sessions have no timestamps; mark those messages as unknown
+        times.resize(messages.len(), None);
+        self.messages = messages;
+        self.message_times = times;
+    }
+
     fn to_session_data(&self) -> SessionData {
         let (agent_messages, current_model_id) = self
             .agent
             .as_ref()
             .map(|a| (a.history().to_vec(), a.current_model_id().to_string()))
-            .unwrap_or_else(|| (Vec::new(), self.current_model_id.clone()));
+            .unwrap_or_else(|| (self.history_snapshot.clone(), self.current_model_id.clone()));
         SessionData {
             id: self.id.clone(),
             name: self.name.clone(),
             created_at: session::now_timestamp(),
             agent_messages,
             ui_messages: self.messages.clone(),
+            ui_message_times: {
+                let mut times = self.message_times.clone();
+                times.resize(self.messages.len(), Some(session::now_timestamp()));
+                times
+            },
             stats: SessionStatsData::from(&self.cached_stats),
             current_model_id,
+            summary: self.summary.clone(),
+            notes: self.notes.clone(),
+            env: self
+                .env
+                .iter()
+                .filter(|(k, _)| !self.env_unsaved.contains(*k))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
         }
     }
 
-    fn auto_save(&self) {
+    fn auto_save(&self) -> Result<()> {
+        // The saved session is the only copy of an unloaded tab's conversation
+        if self.unloaded {
+            return Ok(());
+        }
         let data = self.to_session_data();
-        let _ = session::save_session(&data);
+        session::save_session(&data).map(|_| ())
+    }
+
+    /// Take back the agent from a finished turn task. If the task failed or panicked,
+    /// the agent is lost with it, so rebuild one to keep the tab usable.
+    fn finish_turn(
+        &mut self,
+        outcome: std::result::Result<Result<Agent>, tokio::task::JoinError>,
+        config: &AppConfig,
+        project_root: &std::path::Path,
+    ) {
+        match outcome {
+            Ok(Ok(mut returned_agent)) => {
+                // `/dryrun` may have been toggled while the turn ran
+                returned_agent.set_dry_run(config.agent.dry_run);
+                self.cached_stats = returned_agent.stats.clone();
+                self.context_used = returned_agent.estimate_context_tokens();
+                self.context_limit = returned_agent.context_window();
+                self.current_model_id = returned_agent.current_model_id().to_string();
+                self.history_snapshot.clear();
+                self.agent = Some(returned_agent);
+                return;
+            }
+            Ok(Err(e)) => {
+                let msg = format!("Error: {}", e);
+                // Already shown if the agent reported it as an `AgentEvent::Error`
+                if self.messages.last() != Some(&msg) {
+                    self.messages.push(msg);
+                }
+            }
+            Err(e) => self.messages.push(format!("Error: task panicked: {}", e)),
+        }
+        self.pet_state = PetState::Error;
+        match self.recover_agent(config, project_root) {
+            Ok(()) => self
+                .messages
+                .push("[Agent restarted; history before the failed turn restored]".to_string()),
+            Err(e) => {
+                self.processing = false;
+                self.messages.push(format!(
+                    "Error restarting agent: {}. Use /new to open a fresh tab.",
+                    e
+                ));
+            }
+        }
+    }
+
+    /// Rebuild the agent after its task failed or panicked, restoring the history
+    /// from before the failed turn so the tab stays usable.
+    fn recover_agent(&mut self, config: &AppConfig, project_root: &std::path::Path) -> Result<()> {
+        let model_id =
+            (!self.current_model_id.is_empty()).then_some(self.current_model_id.as_str());
+        let mut agent = Agent::create_with_model(config, project_root, model_id)?;
+        if !self.history_snapshot.is_empty() {
+            agent.set_messages(std::mem::take(&mut self.history_snapshot));
+        }
+        agent.stats = self.cached_stats.clone();
+        agent.set_session_env(self.env.clone());
+        self.context_used = agent.estimate_context_tokens();
+        self.context_limit = agent.context_window();
+        self.current_model_id = agent.current_model_id().to_string();
+        self.agent = Some(agent);
+        self.event_rx = None;
+        self.confirm_tx = None;
+        self.pending_confirm = None;
+        self.streaming_message_idx = None;
+        self.tool_progress_idx.clear();
+        self.processing = false;
+        Ok(())
     }
 
-    fn handle_agent_event(&mut self, event: AgentEvent) {
+    /// Abort the running turn and drop the queued messages. The agent is rebuilt from
+    /// the history before the turn, so no half-finished tool call is left behind.
+    fn cancel_turn(&mut self, config: &AppConfig, project_root: &std::path::Path) -> Result<()> {
+        if let Some(handle) = self.agent_handle.take() {
+            handle.abort();
+        }
+        self.pending_messages.clear();
+        let recovered = self.recover_agent(config, project_root);
+        self.processing = false;
+        self.pet_state = PetState::Idle;
+        self.finish_stream_to();
+        self.messages.push("[Cancelled]".to_string());
+        self.follow_tail = true;
+        let _ = self.auto_save();
+        recovered
+    }
+
+    /// Start teeing streamed output to `path` (appending) until the current turn ends.
+    fn start_stream_to(&mut self, path: &std::path::Path) -> Result<()> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        self.stream_file = Some((file, path.to_path_buf()));
+        Ok(())
+    }
+
+    /// Close the `/stream-to` file, if any, and note where the output went.
+    fn finish_stream_to(&mut self) {
+        if let Some((_, path)) = self.stream_file.take() {
+            self.messages
+                .push(format!("[Streamed output saved to {}]", path.display()));
+        }
+    }
+
+    fn handle_agent_event(&mut self, event: AgentEvent, ui: &UiConfig) {
+        self.last_activity = std::time::Instant::now();
+        let verbosity = self
+            .tool_verbosity
+            .unwrap_or_else(|| ToolVerbosity::from_ui(ui));
         match event {
             AgentEvent::StreamDelta(delta) => {
+                if let Some((file, path)) = &mut self.stream_file {
+                    if let Err(e) = file.write_all(delta.as_bytes()).and_then(|_| file.flush()) {
+                        let msg = format!("Error writing to {}: {}", path.display(), e);
+                        self.stream_file = None;
+                        self.messages.push(msg);
+                        self.streaming_message_idx = None;
+                    }
+                }
                 if let Some(idx) = self.streaming_message_idx {
                     self.messages[idx].push_str(&delta);
                 } else {
@@ -821,25 +1644,77 @@ impl SessionTab {
             }
             AgentEvent::ToolStart { name, arguments } => {
                 self.streaming_message_idx = None;
-                let text = tool_display_text(&name, &arguments, true);
+                if name == "bash" {
+                    self.last_bash_args = Some(arguments.clone());
+                }
+                let mut text = tool_display_text(&name, &arguments, true);
+                if verbosity != ToolVerbosity::Minimal {
+                    if let Some(preview) = tool_args_preview(&name, &arguments) {
+                        let base = text.trim_end_matches(" ...");
+                        text = format!("{} {} ...", base, preview);
+                    }
+                }
                 self.messages.push(text);
-                self.tool_progress_idx = Some(self.messages.len() - 1);
+                self.tool_progress_idx.push_back(self.messages.len() - 1);
             }
             AgentEvent::ToolEnd {
                 name,
                 arguments,
                 success,
+                result,
+                metadata,
             } => {
+                if let Some(path) = metadata["files"]
+                    .as_array()
+                    .and_then(|files| files.last())
+                    .and_then(|f| f.as_str())
+                {
+                    self.last_modified_path = Some(path.to_string());
+                }
                 let text = if success {
                     tool_display_text(&name, &arguments, false)
                 } else {
                     tool_display_text_error(&name, &arguments)
                 };
-                if let Some(idx) = self.tool_progress_idx.take() {
+                // Skip status lines lost to a cleared conversation
+                let pending = std::iter::from_fn(|| self.tool_progress_idx.pop_front())
+                    .find(|&idx| idx < self.messages.len());
+                let status_idx = if let Some(idx) = pending {
                     self.messages[idx] = text;
+                    idx
                 } else {
                     self.messages.push(text);
+                    self.messages.len() - 1
+                };
+                if verbosity == ToolVerbosity::Full {
+                    let preview = tool_output_preview(&result);
+                    let added = preview.len();
+                    // Keep timestamps aligned with the lines shifted down
+                    self.stamp_messages();
+                    let now = Some(session::now_timestamp());
+                    self.message_times.splice(
+                        status_idx + 1..status_idx + 1,
+                        std::iter::repeat_n(now, added),
+                    );
+                    self.messages
+                        .splice(status_idx + 1..status_idx + 1, preview);
+                    for idx in self
+                        .tool_progress_idx
+                        .iter_mut()
+                        .filter(|i| **i > status_idx)
+                    {
+                        *idx += added;
+                    }
+                }
+            }
+            AgentEvent::PlanReady(plan) => {
+                if self.streaming_message_idx.take().is_none() {
+                    self.messages.push(format!("Assistant: {}", plan));
                 }
+                self.pending_confirm = Some("执行以上计划".to_string());
+                self.messages
+                    .push("📋 计划已生成，按 Y 执行 / N 取消".to_string());
+                self.follow_tail = true;
             }
             AgentEvent::ToolConfirm {
                 name: _,
@@ -848,24 +1723,43 @@ impl SessionTab {
             } => {
                 self.pending_confirm = Some(description.clone());
                 self.messages
-                    .push(format!("⚠️  需要确认: {} [Y/N]", description));
+                    .push(format!("⚠️  需要确认: {} [Y/A/N]", description));
                 self.follow_tail = true;
             }
+            AgentEvent::ModelFallback { from, to, reason } => {
+                self.streaming_message_idx = None;
+                let reason: String = reason
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .take(120)
+                    .collect();
+                self.messages.push(format!(
+                    "[Model {} failed ({}); using fallback {} for this turn]",
+                    from, reason, to
+                ));
+            }
+            AgentEvent::Warning(msg) => {
+                self.messages.push(format!("[Warning: {}]", msg));
+            }
             AgentEvent::Done(response) => {
-                self.tool_progress_idx = None;
+                self.tool_progress_idx.clear();
                 if self.streaming_message_idx.is_some() {
                     self.streaming_message_idx = None;
                 } else if !response.is_empty() {
                     self.messages.push(format!("Assistant: {}", response));
                 }
+                self.finish_stream_to();
                 self.pet_state = PetState::Happy;
                 self.processing = false;
                 self.follow_tail = true;
             }
             AgentEvent::Error(e) => {
                 self.streaming_message_idx = None;
-                self.tool_progress_idx = None;
+                self.tool_progress_idx.clear();
                 self.messages.push(format!("Error: {}", e));
+                self.finish_stream_to();
                 self.pet_state = PetState::Error;
                 self.processing = false;
                 self.follow_tail = true;
@@ -888,10 +1782,12 @@ fn tool_display_text(name: &str, arguments: &str, in_progress: bool) -> String {
         }
         "write_file" => {
             let path = args["path"].as_str().unwrap_or("?");
-            if in_progress {
-                ("写入文件", path.to_string())
-            } else {
-                ("已写入", path.to_string())
+            let append = args["append"].as_bool().unwrap_or(false);
+            match (in_progress, append) {
+                (true, false) => ("写入文件", path.to_string()),
+                (false, false) => ("已写入", path.to_string()),
+                (true, true) => ("追加写入", path.to_string()),
+                (false, true) => ("已追加", path.to_string()),
             }
         }
         "list_directory" => {
@@ -902,12 +1798,77 @@ fn tool_display_text(name: &str, arguments: &str, in_progress: bool) -> String {
                 ("已浏览", path.to_string())
             }
         }
-        "edit" => {
+        "hash_file" => {
             let path = args["path"].as_str().unwrap_or("?");
             if in_progress {
-                ("编辑文件", path.to_string())
+                ("计算哈希", path.to_string())
             } else {
-                ("已编辑", path.to_string())
+                ("已计算哈希", path.to_string())
+            }
+        }
+        "grep" => {
+            let pattern = args["pattern"].as_str().unwrap_or("?");
+            if in_progress {
+                ("搜索", pattern.to_string())
+            } else {
+                ("已搜索", pattern.to_string())
+            }
+        }
+        "glob" => {
+            let pattern = args["pattern"].as_str().unwrap_or("?");
+            if in_progress {
+                ("查找文件", pattern.to_string())
+            } else {
+                ("已查找文件", pattern.to_string())
+            }
+        }
+        "count_tokens" => {
+            let target = args["path"].as_str().unwrap_or("文本");
+            if in_progress {
+                ("统计 token", target.to_string())
+            } else {
+                ("已统计 token", target.to_string())
+            }
+        }
+        "env_info" => {
+            if in_progress {
+                ("检查环境", String::new())
+            } else {
+                ("已检查环境", String::new())
+            }
+        }
+        "edit" => {
+            let path = args["path"].as_str().unwrap_or("?");
+            if in_progress {
+                ("编辑文件", path.to_string())
+            } else {
+                ("已编辑", path.to_string())
+            }
+        }
+        "delete_file" => {
+            let path = args["path"].as_str().unwrap_or("?");
+            if in_progress {
+                ("删除文件", path.to_string())
+            } else {
+                ("已删除", path.to_string())
+            }
+        }
+        "fetch" => {
+            let url = args["url"].as_str().unwrap_or("?");
+            if in_progress {
+                ("获取网页", url.to_string())
+            } else {
+                ("已获取", url.to_string())
+            }
+        }
+        "multi_edit" => {
+            let path = args["path"].as_str().unwrap_or("?");
+            let count = args["edits"].as_array().map_or(0, |e| e.len());
+            let target = format!("{} ({} 处修改)", path, count);
+            if in_progress {
+                ("批量编辑", target)
+            } else {
+                ("已批量编辑", target)
             }
         }
         "bash" => {
@@ -939,12 +1900,69 @@ fn tool_display_text(name: &str, arguments: &str, in_progress: bool) -> String {
     }
 }
 
+/// Editor command from `$VISUAL`, falling back to `$EDITOR` (unset or blank values
+/// are skipped), split into program and arguments (e.g. `code --wait`).
+fn resolve_editor(visual: Option<String>, editor: Option<String>) -> Option<Vec<String>> {
+    [visual, editor].into_iter().flatten().find_map(|cmd| {
+        let parts: Vec<String> = cmd.split_whitespace().map(str::to_string).collect();
+        (!parts.is_empty()).then_some(parts)
+    })
+}
+
+/// Max chars of each side of an `edit` preview.
+const TOOL_ARGS_SNIPPET_CHARS: usize = 30;
+
+/// Short single-line preview of a snippet for the progress line.
+fn args_snippet(text: &str) -> String {
+    let flat = text.replace('\n', "⏎");
+    let mut snippet: String = flat.chars().take(TOOL_ARGS_SNIPPET_CHARS).collect();
+    if flat.chars().count() > TOOL_ARGS_SNIPPET_CHARS {
+        snippet.push('…');
+    }
+    format!("{:?}", snippet)
+}
+
+/// Preview of the key arguments of a file-modifying tool (`ui.preview_tool_args`):
+/// content size for `write_file`, old → new snippet for `edit`.
+fn tool_args_preview(name: &str, arguments: &str) -> Option<String> {
+    let args: serde_json::Value = serde_json::from_str(arguments).ok()?;
+    match name {
+        "write_file" => {
+            let content = args["content"].as_str()?;
+            Some(format!(
+                "({} chars, {} lines)",
+                content.chars().count(),
+                content.lines().count()
+            ))
+        }
+        "edit" => {
+            let old = args["old_text"].as_str()?;
+            let new = args["new_text"].as_str().unwrap_or("");
+            let all = if args["replace_all"].as_bool().unwrap_or(false) {
+                " (all)"
+            } else {
+                ""
+            };
+            Some(format!(
+                "{} → {}{}",
+                args_snippet(old),
+                args_snippet(new),
+                all
+            ))
+        }
+        _ => None,
+    }
+}
+
 fn tool_display_text_error(name: &str, arguments: &str) -> String {
     let args: serde_json::Value =
         serde_json::from_str(arguments).unwrap_or(serde_json::Value::Null);
     let target = match name {
-        "read_file" | "write_file" | "edit" => args["path"].as_str().unwrap_or("?").to_string(),
+        "read_file" | "write_file" | "edit" | "multi_edit" | "delete_file" => {
+            args["path"].as_str().unwrap_or("?").to_string()
+        }
         "list_directory" => args["path"].as_str().unwrap_or(".").to_string(),
+        "fetch" => args["url"].as_str().unwrap_or("?").to_string(),
         "bash" => {
             let cmd = args["command"].as_str().unwrap_or("?");
             cmd.chars().take(40).collect()
@@ -954,6 +1972,300 @@ fn tool_display_text_error(name: &str, arguments: &str) -> String {
     format!("TOOL_ERROR:✗ {} {} 失败", name, target)
 }
 
+/// Max chars of each message / of the whole transcript sent for `/summarize`.
+const SUMMARY_MAX_MESSAGE_CHARS: usize = 2000;
+const SUMMARY_MAX_TRANSCRIPT_CHARS: usize = 40_000;
+
+/// Build the `/summarize` prompt from the UI transcript; None if there is no conversation.
+/// Keeps the most recent messages when the transcript is too long.
+fn summary_prompt(messages: &[String]) -> Option<String> {
+    let mut entries: Vec<String> = Vec::new();
+    let mut total = 0;
+    for msg in messages.iter().rev() {
+        let (role, content) = if let Some(c) = msg.strip_prefix("You: ") {
+            ("User", c)
+        } else if let Some(c) = msg.strip_prefix("Assistant: ") {
+            ("Assistant", c)
+        } else {
+            continue;
+        };
+        let content: String = content.chars().take(SUMMARY_MAX_MESSAGE_CHARS).collect();
+        total += content.chars().count();
+        if total > SUMMARY_MAX_TRANSCRIPT_CHARS && !entries.is_empty() {
+            break;
+        }
+        entries.push(format!("{}: {}", role, content));
+    }
+    if entries.is_empty() {
+        return None;
+    }
+    entries.reverse();
+    Some(format!(
+        "Summarize the following conversation as concise Markdown notes: the goal, key \
+         findings and decisions, changes made, and open questions or next steps. \
+         Use the conversation's language. Reply with ONLY the summary.\n\n{}",
+        entries.join("\n\n")
+    ))
+}
+
+/// `HH:MM` part of a `%Y-%m-%d %H:%M:%S` timestamp (falls back to the input).
+fn short_time(timestamp: &str) -> &str {
+    timestamp.get(11..16).unwrap_or(timestamp)
+}
+
+/// Max lines / chars per line shown in the inline tool output preview.
+const TOOL_PREVIEW_MAX_LINES: usize = 5;
+const TOOL_PREVIEW_MAX_LINE_CHARS: usize = 120;
+
+/// Effect of pressing Esc, in precedence order (see `RatatuiUi::esc_action`).
+#[derive(Debug, PartialEq)]
+enum EscAction {
+    DismissSessionPicker,
+    DismissModelPicker,
+    CloseNotes,
+    ExitSelectMode,
+    ClearSearch,
+    DismissAutocomplete,
+    CancelTurn,
+    ClearInput,
+    Nothing,
+}
+
+/// Narrowest session column for the "auto" tab layout to split side by side.
+const MIN_SPLIT_COLUMN_WIDTH: usize = 60;
+
+/// Max chars of one rendered line; longer lines (base64 blobs, minified code) are cut
+/// so they don't wrap across dozens of rows. The full text stays in the message
+/// and can still be copied in select mode.
+const MAX_DISPLAY_LINE_CHARS: usize = 1000;
+
+/// `/whoami`: where miniclaw is running and with what model, config, rules and
+/// tools, one message line each.
+fn whoami_summary(
+    agent: &Agent,
+    config: &AppConfig,
+    project_root: &std::path::Path,
+    config_path: &std::path::Path,
+) -> Vec<String> {
+    let model_id = agent.current_model_id();
+    let api_base = config
+        .get_model_entry(model_id)
+        .and_then(|m| m.api_base)
+        .map(|base| match reqwest::Url::parse(&base) {
+            Ok(url) => match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                (None, _) => base.clone(),
+            },
+            Err(_) => base.clone(),
+        })
+        .unwrap_or_else(|| "(provider default)".to_string());
+    let mut config_line = config_path.display().to_string();
+    let overlay = AppConfig::project_config_path(project_root);
+    if overlay.exists() {
+        config_line.push_str(&format!(" + {}", overlay.display()));
+    }
+    [
+        "[Environment]".to_string(),
+        format!("  project:    {}", project_root.display()),
+        format!(
+            "  model:      {} ({})",
+            agent.current_model_display(),
+            model_id
+        ),
+        format!("  api base:   {}", api_base),
+        format!("  config:     {}", config_line),
+        format!("  rule files: {}", rules::load_rules(project_root).len()),
+        format!("  tools:      {}", agent.tool_count()),
+    ]
+    .to_vec()
+}
+
+/// Conversation box within `area`, narrowed so its text is at most
+/// `ui.max_content_width` columns wide (0 = no cap).
+fn content_rect(area: Rect, ui: &UiConfig) -> Rect {
+    let width = ui.max_content_width.saturating_add(2); // plus borders
+    if ui.max_content_width == 0 || area.width <= width {
+        return area;
+    }
+    let x = match ui.content_align {
+        ContentAlign::Center => area.x + (area.width - width) / 2,
+        ContentAlign::Left => area.x,
+    };
+    Rect { x, width, ..area }
+}
+
+/// The last six You/Assistant messages, each cut to 100 chars, one per line:
+/// what the title model sees, so a renamed tab reflects where the chat is now.
+fn title_source(messages: &[String]) -> String {
+    let mut recent: Vec<&str> = messages
+        .iter()
+        .rev()
+        .filter_map(|m| {
+            m.strip_prefix("You: ")
+                .or_else(|| m.strip_prefix("Assistant: "))
+        })
+        .take(6)
+        .collect();
+    recent.reverse();
+    recent
+        .iter()
+        .map(|content| content.chars().take(100).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `/search` state for one tab.
+struct ConversationSearch {
+    query: String,
+    /// Index of the current match among the matching lines; wraps around.
+    current: usize,
+    /// Matching lines found by the last render (for Ctrl+N/Ctrl+P and the title).
+    total: usize,
+    /// Scroll to the current match on the next render.
+    jump: bool,
+}
+
+impl ConversationSearch {
+    fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            current: 0,
+            total: 0,
+            jump: true,
+        }
+    }
+
+    /// Move to the next (or previous) match, wrapping around.
+    fn step(&mut self, forward: bool) {
+        if self.total == 0 {
+            return;
+        }
+        self.current = if forward {
+            (self.current + 1) % self.total
+        } else {
+            (self.current + self.total - 1) % self.total
+        };
+        self.jump = true;
+    }
+}
+
+/// Shown in place of the messages left out by `ui.render_window`.
+const HIDDEN_MESSAGES_MARKER: &str = "[older messages hidden — /all to show]";
+
+/// How many leading messages to leave unrendered so only the last `window` show
+/// (0 = all). Everything renders again once the selection moves into them.
+fn hidden_message_count(len: usize, window: usize, selected: Option<usize>) -> usize {
+    let hidden = if window == 0 {
+        0
+    } else {
+        len.saturating_sub(window)
+    };
+    match selected {
+        Some(sel) if sel < hidden => 0,
+        _ => hidden,
+    }
+}
+
+/// Indices of the rendered lines containing `query` (ASCII case-insensitive).
+fn search_matches(lines: &[Line], query: &str) -> Vec<usize> {
+    let query = query.to_ascii_lowercase();
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| {
+            let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+            text.to_ascii_lowercase().contains(&query)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Restyle each occurrence of `query` within `line`'s spans.
+fn highlight_matches(line: &mut Line<'static>, query: &str, style: Style) {
+    let query = query.to_ascii_lowercase();
+    let mut spans = Vec::new();
+    for span in line.spans.drain(..) {
+        let lower = span.content.to_ascii_lowercase();
+        let mut rest = 0;
+        for (start, _) in lower.match_indices(&query) {
+            if start < rest {
+                continue;
+            }
+            if start > rest {
+                spans.push(Span::styled(
+                    span.content[rest..start].to_string(),
+                    span.style,
+                ));
+            }
+            let end = start + query.len();
+            spans.push(Span::styled(
+                span.content[start..end].to_string(),
+                span.style.patch(style),
+            ));
+            rest = end;
+        }
+        if rest == 0 {
+            spans.push(span);
+        } else if rest < span.content.len() {
+            spans.push(Span::styled(span.content[rest..].to_string(), span.style));
+        }
+    }
+    line.spans = spans;
+}
+
+/// Truncate a rendered line to [`MAX_DISPLAY_LINE_CHARS`], appending a marker.
+fn truncate_long_line(line: &mut Line<'static>) {
+    let total: usize = line.spans.iter().map(|s| s.content.chars().count()).sum();
+    if total <= MAX_DISPLAY_LINE_CHARS {
+        return;
+    }
+    let mut budget = MAX_DISPLAY_LINE_CHARS;
+    let mut kept = Vec::new();
+    for span in line.spans.drain(..) {
+        if budget == 0 {
+            break;
+        }
+        let len = span.content.chars().count();
+        if len <= budget {
+            budget -= len;
+            kept.push(span);
+        } else {
+            let cut: String = span.content.chars().take(budget).collect();
+            kept.push(Span::styled(cut, span.style));
+            budget = 0;
+        }
+    }
+    kept.push(Span::styled(
+        format!(" … ({} chars truncated)", total - MAX_DISPLAY_LINE_CHARS),
+        Style::default().fg(Color::DarkGray),
+    ));
+    line.spans = kept;
+}
+
+/// Build `TOOL_OUTPUT:` messages previewing the first few lines of a tool result.
+fn tool_output_preview(result: &str) -> Vec<String> {
+    let lines: Vec<&str> = result.trim_end().lines().collect();
+    let mut preview: Vec<String> = lines
+        .iter()
+        .take(TOOL_PREVIEW_MAX_LINES)
+        .map(|line| {
+            let mut text: String = line.chars().take(TOOL_PREVIEW_MAX_LINE_CHARS).collect();
+            if line.chars().count() > TOOL_PREVIEW_MAX_LINE_CHARS {
+                text.push('…');
+            }
+            format!("TOOL_OUTPUT:{}", text)
+        })
+        .collect();
+    if lines.len() > TOOL_PREVIEW_MAX_LINES {
+        preview.push(format!(
+            "TOOL_OUTPUT:… ({} more lines)",
+            lines.len() - TOOL_PREVIEW_MAX_LINES
+        ));
+    }
+    preview
+}
+
 // ── TUI State ───────────────────────────────────────────────
 
 const HEADER_HEIGHT: u16 = 10;
@@ -967,6 +2279,8 @@ struct SessionPicker {
     visible: bool,
     selected: usize,
     sessions: Vec<session::SessionData>,
+    /// Id of the session waiting for y/n after 'd' was pressed.
+    confirm_delete: Option<String>,
 }
 
 impl SessionPicker {
@@ -975,14 +2289,22 @@ impl SessionPicker {
             visible: false,
             selected: 0,
             sessions: Vec::new(),
+            confirm_delete: None,
         }
     }
 
     fn open(&mut self) {
+        self.selected = 0;
+        self.refresh();
+    }
+
+    /// Re-read the saved sessions, keeping the selection in range.
+    fn refresh(&mut self) {
+        self.confirm_delete = None;
         match session::list_sessions() {
             Ok(sessions) => {
                 self.sessions = sessions;
-                self.selected = 0;
+                self.selected = self.selected.min(self.sessions.len().saturating_sub(1));
                 self.visible = !self.sessions.is_empty();
             }
             Err(_) => {
@@ -1015,6 +2337,7 @@ impl SessionPicker {
         self.visible = false;
         self.sessions.clear();
         self.selected = 0;
+        self.confirm_delete = None;
     }
 }
 
@@ -1076,7 +2399,8 @@ impl ModelPicker {
 }
 
 pub struct RatatuiUi {
-    anim_tick: u32,
+    /// Start of the pet animation clock.
+    anim_start: std::time::Instant,
     idle_ticks: u32,
     typing_intensity: u32,
     header_widgets: Vec<Box<dyn HeaderWidget>>,
@@ -1088,12 +2412,24 @@ pub struct RatatuiUi {
     active_tab: usize,
     config: AppConfig,
     project_root: PathBuf,
+    /// Config file the UI was started with (`--config` or the default), for `/whoami`.
+    config_path: PathBuf,
     tab_bar_rect: Rect,
     session_rects: Vec<Rect>,
     /// Input area rect of the active session (for mouse click positioning).
     active_input_rect: Rect,
     /// Pet name displayed in the pet panel. Default "huhu".
     pet_name: String,
+    /// Prefix conversation messages with their timestamp (`ui.show_timestamps`, `/timestamps`).
+    show_timestamps: bool,
+    /// File requested with `/open`; the run loop suspends the UI and opens it in the editor.
+    pending_editor: Option<PathBuf>,
+    /// Provider reachability from the last health check (`ui.health_check_interval_secs`).
+    api_status: ApiStatus,
+    health_task: Option<tokio::task::JoinHandle<ApiStatus>>,
+    last_health_check: Option<std::time::Instant>,
+    /// Submitted inputs, persisted across runs and recalled with ↑/↓ on an empty input.
+    input_history: InputHistory,
 }
 
 impl RatatuiUi {
@@ -1107,8 +2443,9 @@ impl RatatuiUi {
         }
 
         let pet_name = config.ui.pet_name.clone();
+        let show_timestamps = config.ui.show_timestamps;
         Self {
-            anim_tick: 0,
+            anim_start: std::time::Instant::now(),
             idle_ticks: 0,
             typing_intensity: 0,
             header_widgets,
@@ -1120,130 +2457,639 @@ impl RatatuiUi {
             active_tab: 0,
             config,
             project_root,
+            config_path: AppConfig::config_path().unwrap_or_default(),
             tab_bar_rect: Rect::default(),
             session_rects: Vec::new(),
             active_input_rect: Rect::default(),
             pet_name,
+            show_timestamps,
+            pending_editor: None,
+            api_status: ApiStatus::Unknown,
+            health_task: None,
+            last_health_check: None,
+            input_history: InputHistory::load_default(),
         }
     }
 
-    fn clamp_active_tab(&mut self) {
-        if !self.tabs.is_empty() && self.active_tab >= self.tabs.len() {
-            self.active_tab = self.tabs.len() - 1;
-        }
+    /// Use `path` as the config file shown by `/whoami` (from `--config`).
+    pub fn with_config_path(mut self, path: PathBuf) -> Self {
+        self.config_path = path;
+        self
     }
 
-    fn active(&self) -> &SessionTab {
-        &self.tabs[self.active_tab.min(self.tabs.len() - 1)]
+    /// Replace the active input with a recalled history entry (empty past the newest).
+    fn recall_history(&mut self, older: bool) {
+        let entry = if older {
+            self.input_history.previous()
+        } else {
+            self.input_history.next()
+        }
+        .unwrap_or("")
+        .to_string();
+        let tab = self.active_mut();
+        tab.cursor_position = entry.chars().count();
+        tab.input = entry;
     }
 
-    fn active_mut(&mut self) -> &mut SessionTab {
-        let idx = self.active_tab.min(self.tabs.len() - 1);
-        &mut self.tabs[idx]
+    /// Tab index for a `/sendto` target: 1-based tab number or tab name.
+    fn find_tab(&self, target: &str) -> Option<usize> {
+        if let Ok(n) = target.parse::<usize>() {
+            return (1..=self.tabs.len()).contains(&n).then(|| n - 1);
+        }
+        self.tabs
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(target))
     }
 
-    fn request_title_update(&mut self, tab_idx: usize) {
-        if tab_idx >= self.tabs.len() {
+    /// `/sendto`: submit the active tab's last user message to another tab's agent.
+    fn send_last_message_to(&mut self, target: &str) {
+        let active = self.active_tab.min(self.tabs.len() - 1);
+        if target.is_empty() {
+            self.tabs[active]
+                .messages
+                .push("Usage: /sendto <tab number|name>".into());
             return;
         }
-        let tab = &self.tabs[tab_idx];
-        let recent_msgs: Vec<String> = tab
-            .messages
-            .iter()
-            .filter(|m| m.starts_with("You: ") || m.starts_with("Assistant: "))
-            .take(6)
-            .cloned()
-            .collect();
-        if recent_msgs.is_empty() {
+        let Some(idx) = self.find_tab(target) else {
+            self.tabs[active]
+                .messages
+                .push(format!("[No tab named or numbered '{}']", target));
+            return;
+        };
+        if idx == active {
+            self.tabs[active]
+                .messages
+                .push("[That is the current tab]".into());
             return;
         }
-        let summary_input: String = recent_msgs
-            .iter()
-            .map(|m| {
-                let content = m
-                    .strip_prefix("You: ")
-                    .or_else(|| m.strip_prefix("Assistant: "))
-                    .unwrap_or(m);
-                content.chars().take(100).collect::<String>()
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+        self.ensure_tab_loaded(idx);
+        let Some(msg) = self.tabs[active].last_user_message().map(str::to_string) else {
+            self.tabs[active]
+                .messages
+                .push("[No message to send yet]".into());
+            return;
+        };
+        let dest = &mut self.tabs[idx];
+        let status = if dest.processing {
+            "Queued for"
+        } else {
+            "Sent to"
+        };
+        let note = format!("[{} tab {} ({})]", status, idx + 1, dest.name);
+        dest.enqueue_message(msg);
+        self.tabs[active].messages.push(note);
+    }
+
+    /// Switch the active tab's agent to `model_id` (id or alias), from `/model <id>`
+    /// or the model picker. A model without an API key is rejected up front so the
+    /// tab keeps working with its current model.
+    fn switch_active_model(&mut self, model_id: &str) {
         let config = self.config.clone();
-        let project_root = self.project_root.clone();
-        let handle = tokio::spawn(async move {
-            let agent_result = Agent::create(&config, &project_root);
-            let mut agent = match agent_result {
-                Ok(a) => a,
-                Err(_) => return None,
-            };
-            let prompt = format!(
-                "Based on the following conversation, generate a very short title (max 15 characters, in the conversation's language). \
-                 Reply with ONLY the title, nothing else.\n\n{}",
-                summary_input
-            );
-            match agent.process_message(&prompt, None, None).await {
-                Ok(title) => {
-                    let title = title.trim().trim_matches('"').trim().to_string();
-                    if title.len() <= 50 && !title.is_empty() {
-                        Some(title)
-                    } else {
-                        Some(title.chars().take(15).collect())
-                    }
-                }
-                Err(_) => None,
+        let tab = self.active_mut();
+        if tab.processing {
+            tab.messages
+                .push("[Cannot switch model while processing]".into());
+            return;
+        }
+        let Some(agent) = tab.agent.as_mut() else {
+            tab.messages.push("[No agent available to switch]".into());
+            return;
+        };
+        if let Ok(id) = config.resolve_model_id(model_id) {
+            if let Err(e) = config.api_key_for_model(&id) {
+                tab.messages.push(format!(
+                    "[Cannot switch to {}: no API key. Still using {}]\n{}",
+                    id,
+                    agent.current_model_display(),
+                    e
+                ));
+                return;
             }
-        });
-        self.tabs[tab_idx].title_task = Some(handle);
+        }
+        match agent.switch_model(model_id, &config) {
+            Ok(()) => {
+                tab.current_model_id = agent.current_model_id().to_string();
+                let display = agent.current_model_display();
+                tab.messages
+                    .push(format!("[Switched to model: {}]", display));
+            }
+            Err(e) => {
+                tab.messages.push(format!("Error: {}", e));
+            }
+        }
     }
 
-    fn create_new_tab(&mut self, name: Option<String>) -> Result<()> {
-        let id = session::generate_session_id();
-        let tab_name = name.unwrap_or_else(|| format!("Session {}", self.tabs.len() + 1));
-        let agent = Agent::create(&self.config, &self.project_root)?;
-        self.tabs.push(SessionTab::new(id, tab_name, agent));
+    /// `/replay <session> [model]`: re-submit a saved session's user messages, in order,
+    /// to a fresh agent in a new tab (optionally on a different model).
+    fn replay_session(&mut self, arg: &str) {
+        let mut parts = arg.split_whitespace();
+        let (Some(id), model, None) = (parts.next(), parts.next(), parts.next()) else {
+            self.active_mut()
+                .messages
+                .push("Usage: /replay <session-id> [model]".into());
+            return;
+        };
+        if self.tab_limit_reached() {
+            return;
+        }
+        let data = match session::load_session(id) {
+            Ok(data) => data,
+            Err(e) => {
+                self.active_mut()
+                    .messages
+                    .push(format!("Error loading session: {}", e));
+                return;
+            }
+        };
+        let turns = session::user_turns(&data.agent_messages);
+        if turns.is_empty() {
+            self.active_mut()
+                .messages
+                .push(format!("[Session {} has no user messages to replay]", id));
+            return;
+        }
+        let model_id = match model {
+            Some(name) => self.config.resolve_model_id(name).map(Some),
+            None => Ok(Some(data.current_model_id.clone()).filter(|m| !m.is_empty())),
+        };
+        let created = model_id.and_then(|model_id| {
+            Agent::create_with_model(&self.config, &self.project_root, model_id.as_deref())
+        });
+        let agent = match created {
+            Ok(agent) => agent,
+            Err(e) => {
+                self.active_mut().messages.push(format!("Error: {}", e));
+                return;
+            }
+        };
+        let note = format!(
+            "[Replaying {} user message(s) from session {} ({}) on {}]",
+            turns.len(),
+            data.id,
+            data.name,
+            agent.current_model_display()
+        );
+        let mut tab = SessionTab::new(
+            session::generate_session_id(),
+            format!("replay: {}", data.name),
+            agent,
+        );
+        tab.messages.push(note);
+        self.tabs.push(tab);
         self.active_tab = self.tabs.len() - 1;
-        Ok(())
+        for turn in turns {
+            self.active_mut().enqueue_message(turn);
+        }
     }
 
-    fn toggle_widget(&mut self, id: &str) -> bool {
-        if let Some(pos) = self.header_widgets.iter().position(|w| w.id() == id) {
-            self.header_widgets.remove(pos);
-            false
-        } else {
-            match id {
-                "stats" => self.header_widgets.insert(0, Box::new(StatsWidget)),
-                "pet" => self.header_widgets.push(Box::new(PetWidget)),
-                _ => return false,
+    /// `/compare <modelA> <modelB> <prompt>`: open a tab per model next to each other
+    /// and send both the same prompt.
+    fn compare_models(&mut self, arg: &str) {
+        let split = |s: &str| {
+            s.trim_start()
+                .split_once(char::is_whitespace)
+                .map(|(head, rest)| (head.to_string(), rest.trim().to_string()))
+        };
+        let parsed =
+            split(arg).and_then(|(a, rest)| split(&rest).map(|(b, prompt)| (a, b, prompt)));
+        let Some((model_a, model_b, prompt)) = parsed.filter(|(_, _, p)| !p.is_empty()) else {
+            self.active_mut()
+                .messages
+                .push("Usage: /compare <modelA> <modelB> <prompt>".into());
+            return;
+        };
+        let max = self.config.ui.max_tabs;
+        if max != 0 && self.tabs.len() + 2 > max {
+            self.active_mut()
+                .messages
+                .push(format!("[Maximum {} tabs reached]", max));
+            return;
+        }
+
+        // Create both agents before adding any tab so a bad model id changes nothing
+        let mut agents = Vec::new();
+        for name in [&model_a, &model_b] {
+            let created = self.config.resolve_model_id(name).and_then(|id| {
+                Agent::create_with_model(&self.config, &self.project_root, Some(&id))
+            });
+            match created {
+                Ok(agent) => agents.push(agent),
+                Err(e) => {
+                    self.active_mut().messages.push(format!("Error: {}", e));
+                    return;
+                }
             }
-            true
         }
+
+        let first = self.tabs.len();
+        for agent in agents {
+            let name = agent.current_model_display();
+            self.tabs
+                .push(SessionTab::new(session::generate_session_id(), name, agent));
+        }
+        let note = format!(
+            "[Comparing {} (tab {}) and {} (tab {})]",
+            model_a,
+            first + 1,
+            model_b,
+            first + 2
+        );
+        for tab in &mut self.tabs[first..] {
+            tab.messages.push(note.clone());
+            tab.enqueue_message(prompt.clone());
+        }
+        self.active_tab = first;
     }
 
-    fn handle_key_event(&mut self, key: KeyEvent) {
-        let wrap_width = self.active_input_rect.width.saturating_sub(2) as usize;
-        let tab = self.active_mut();
-        match key.code {
-            KeyCode::Char(c) => {
-                if key.modifiers.contains(KeyModifiers::CONTROL) {
-                    match c {
-                        'u' => {
-                            tab.input.clear();
-                            tab.cursor_position = 0;
-                        }
-                        'k' => {
-                            let b = tab.byte_index();
-                            tab.input.drain(b..);
-                        }
-                        'w' => {
-                            let end = tab.byte_index();
-                            let chars: Vec<char> = tab.input.chars().collect();
-                            while tab.cursor_position > 0
-                                && chars[tab.cursor_position - 1].is_whitespace()
-                            {
-                                tab.cursor_position -= 1;
-                            }
-                            while tab.cursor_position > 0
-                                && !chars[tab.cursor_position - 1].is_whitespace()
+    /// Start a health check of the active tab's model when one is due.
+    fn maybe_start_health_check(&mut self) {
+        let interval = self.config.ui.health_check_interval_secs;
+        if interval == 0 || self.health_task.is_some() || self.tabs.is_empty() {
+            return;
+        }
+        if self
+            .last_health_check
+            .is_some_and(|t| t.elapsed() < std::time::Duration::from_secs(interval))
+        {
+            return;
+        }
+        self.last_health_check = Some(std::time::Instant::now());
+        let config = self.config.clone();
+        let model_id = self.active().current_model_id.clone();
+        self.health_task = Some(tokio::spawn(async move {
+            match Agent::create_provider(&config, &model_id) {
+                Ok(llm) => Agent::probe_health(llm.as_ref()).await,
+                Err(_) => ApiStatus::Unknown,
+            }
+        }));
+    }
+
+    /// Apply the result of a finished health check to the header status.
+    fn poll_health_check(&mut self) {
+        if self.health_task.as_ref().is_some_and(|h| h.is_finished()) {
+            if let Some(Some(Ok(status))) = self.health_task.take().map(|t| t.now_or_never()) {
+                self.api_status = status;
+            }
+        }
+    }
+
+    /// Suspend the TUI, run the user's editor on `path`, then restore the TUI.
+    fn open_in_editor(
+        &mut self,
+        terminal: &mut ratatui::DefaultTerminal,
+        keyboard_enhanced: bool,
+        path: &std::path::Path,
+    ) {
+        let Some(editor) =
+            resolve_editor(std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok())
+        else {
+            self.active_mut()
+                .messages
+                .push("[No editor configured: set $VISUAL or $EDITOR]".into());
+            return;
+        };
+
+        if keyboard_enhanced {
+            let _ = crossterm::execute!(
+                std::io::stdout(),
+                crossterm::event::PopKeyboardEnhancementFlags
+            );
+        }
+        let _ = crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture);
+        ratatui::restore();
+
+        let status = std::process::Command::new(&editor[0])
+            .args(&editor[1..])
+            .arg(path)
+            .status();
+
+        *terminal = ratatui::init();
+        let _ = crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture);
+        if keyboard_enhanced {
+            push_keyboard_enhancement();
+        }
+        let _ = terminal.clear();
+
+        let msg = match status {
+            Ok(s) if s.success() => format!("[Closed editor: {}]", path.display()),
+            Ok(s) => format!("[Editor exited with {}]", s),
+            Err(e) => format!("Error: failed to run {}: {}", editor[0], e),
+        };
+        self.active_mut().messages.push(msg);
+    }
+
+    /// Export `tab` as Markdown to `ui.auto_export_dir`. None when auto-export is
+    /// off or the session has no conversation yet.
+    fn auto_export_tab(&self, tab: &SessionTab) -> Option<Result<PathBuf>> {
+        let dir = self.config.ui.auto_export_dir.as_deref()?;
+        let data = if tab.unloaded {
+            session::load_session(&tab.id).ok()?
+        } else {
+            tab.to_session_data()
+        };
+        if !session::has_conversation(&data) {
+            return None;
+        }
+        Some(session::export_markdown_to_dir(
+            &data,
+            &self.project_root.join(dir),
+        ))
+    }
+
+    /// Save every tab and auto-export it before exiting.
+    fn save_all_on_quit(&self) {
+        for tab in &self.tabs {
+            let _ = tab.auto_save();
+            // Errors can't be shown once the UI is gone; the session is still saved
+            let _ = self.auto_export_tab(tab);
+        }
+    }
+
+    /// What Esc does now. Popups take priority, then select mode; otherwise it
+    /// cancels a running turn or clears the input buffer.
+    fn esc_action(&self) -> EscAction {
+        if self.session_picker.visible {
+            EscAction::DismissSessionPicker
+        } else if self.model_picker.visible {
+            EscAction::DismissModelPicker
+        } else if self.tabs.is_empty() {
+            EscAction::Nothing
+        } else if self.active().notes_open {
+            EscAction::CloseNotes
+        } else if self.active().pending_confirm.is_some() {
+            // Confirmation needs an explicit Y/A/N
+            EscAction::Nothing
+        } else if self.active().selected_message.is_some() {
+            EscAction::ExitSelectMode
+        } else if self.autocomplete.visible {
+            EscAction::DismissAutocomplete
+        } else if self.active().search.is_some() {
+            EscAction::ClearSearch
+        } else if self.active().processing {
+            EscAction::CancelTurn
+        } else {
+            EscAction::ClearInput
+        }
+    }
+
+    fn handle_esc(&mut self) {
+        match self.esc_action() {
+            EscAction::DismissSessionPicker => self.session_picker.dismiss(),
+            EscAction::DismissModelPicker => self.model_picker.dismiss(),
+            EscAction::CloseNotes => self.active_mut().notes_open = false,
+            EscAction::ExitSelectMode => {
+                let tab = self.active_mut();
+                tab.selected_message = None;
+                tab.follow_tail = true;
+            }
+            EscAction::DismissAutocomplete => self.autocomplete.dismiss(),
+            EscAction::ClearSearch => {
+                let tab = self.active_mut();
+                tab.search = None;
+                tab.follow_tail = true;
+            }
+            EscAction::CancelTurn => self.cancel_active_turn(),
+            EscAction::ClearInput => {
+                let tab = self.active_mut();
+                tab.input.clear();
+                tab.cursor_position = 0;
+                self.autocomplete.dismiss();
+            }
+            EscAction::Nothing => {}
+        }
+    }
+
+    /// Route a key to the open notes overlay. Returns false for keys it does not
+    /// handle (e.g. Ctrl+C), which then take their normal path.
+    fn handle_notes_key(&mut self, key: KeyEvent) -> bool {
+        if self.tabs.is_empty()
+            || !self.active().notes_open
+            || self.session_picker.visible
+            || self.model_picker.visible
+        {
+            return false;
+        }
+        let notes = &mut self.active_mut().notes;
+        match key.code {
+            KeyCode::Esc => self.handle_esc(),
+            KeyCode::Enter => notes.push('\n'),
+            KeyCode::Backspace => {
+                notes.pop();
+            }
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                notes.push(c)
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn render_notes(&self, f: &mut Frame) {
+        let area = f.area();
+        let popup_w = (area.width * 3 / 5).max(30).min(area.width);
+        let popup_h = (area.height * 3 / 5).max(6).min(area.height);
+        let popup_area = Rect {
+            x: (area.width - popup_w) / 2,
+            y: (area.height - popup_h) / 2,
+            width: popup_w,
+            height: popup_h,
+        };
+        f.render_widget(Clear, popup_area);
+
+        let notes = &self.active().notes;
+        let mut lines: Vec<Line> = vec![Line::from(Span::styled(
+            " 不会发送给模型 · Enter 换行 · Esc 关闭",
+            Style::default().fg(Color::DarkGray),
+        ))];
+        let wrapped = Self::manual_wrap(notes, popup_w.saturating_sub(2) as usize);
+        lines.extend(wrapped.split('\n').map(|l| Line::from(l.to_string())));
+        // Keep the end of long notes (where typing happens) visible
+        let inner_h = popup_h.saturating_sub(2) as usize;
+        let skip = lines.len().saturating_sub(inner_h);
+        let popup = Paragraph::new(lines.split_off(skip)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" 笔记 · {} ", self.active().name))
+                .title_style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+        f.render_widget(popup, popup_area);
+    }
+
+    fn clamp_active_tab(&mut self) {
+        if !self.tabs.is_empty() && self.active_tab >= self.tabs.len() {
+            self.active_tab = self.tabs.len() - 1;
+        }
+    }
+
+    fn active(&self) -> &SessionTab {
+        &self.tabs[self.active_tab.min(self.tabs.len() - 1)]
+    }
+
+    fn active_mut(&mut self) -> &mut SessionTab {
+        let idx = self.active_tab.min(self.tabs.len() - 1);
+        &mut self.tabs[idx]
+    }
+
+    fn request_title_update(&mut self, tab_idx: usize) {
+        if tab_idx >= self.tabs.len() {
+            return;
+        }
+        let summary_input = title_source(&self.tabs[tab_idx].messages);
+        if summary_input.is_empty() {
+            return;
+        }
+        let config = self.config.clone();
+        let project_root = self.project_root.clone();
+        let handle = tokio::spawn(async move {
+            let agent_result = Agent::create(&config, &project_root);
+            let mut agent = match agent_result {
+                Ok(a) => a,
+                Err(_) => return None,
+            };
+            let prompt = format!(
+                "Based on the following conversation, generate a very short title (max 15 characters, in the conversation's language). \
+                 Reply with ONLY the title, nothing else.\n\n{}",
+                summary_input
+            );
+            match agent.process_message(&prompt, None, None).await {
+                Ok(title) => {
+                    let title = title.trim().trim_matches('"').trim().to_string();
+                    if title.len() <= 50 && !title.is_empty() {
+                        Some(title)
+                    } else {
+                        Some(title.chars().take(15).collect())
+                    }
+                }
+                Err(_) => None,
+            }
+        });
+        self.tabs[tab_idx].title_task = Some(handle);
+    }
+
+    /// Ask the current model for a summary of the active session (`/summarize`).
+    fn request_summary(&mut self, note_path: Option<std::path::PathBuf>) {
+        let tab = self.active();
+        if tab.processing || tab.summary_task.is_some() {
+            self.active_mut()
+                .messages
+                .push("[Cannot summarize while processing]".into());
+            return;
+        }
+        let Some(prompt) = summary_prompt(&tab.messages) else {
+            self.active_mut()
+                .messages
+                .push("[Nothing to summarize yet]".into());
+            return;
+        };
+        let config = self.config.clone();
+        let project_root = self.project_root.clone();
+        let model_id = tab.current_model_id.clone();
+        let handle = tokio::spawn(async move {
+            let mut agent = Agent::create_with_model(&config, &project_root, Some(&model_id))?;
+            let summary = agent.process_message(&prompt, None, None).await?;
+            Ok(summary.trim().to_string())
+        });
+        let tab = self.active_mut();
+        tab.summary_task = Some((handle, note_path));
+        tab.messages.push("[Summarizing session...]".into());
+        tab.follow_tail = true;
+    }
+
+    /// Whether `ui.max_tabs` is reached; if so, tell the user in the active tab.
+    fn tab_limit_reached(&mut self) -> bool {
+        let max = self.config.ui.max_tabs;
+        if max == 0 || self.tabs.len() < max {
+            return false;
+        }
+        self.active_mut()
+            .messages
+            .push(format!("[Maximum {} tabs reached]", max));
+        true
+    }
+
+    /// Create a tab for `/new` or the tab bar's `[+]`, reporting the outcome.
+    fn open_new_tab(&mut self, name: Option<String>) {
+        if self.tab_limit_reached() {
+            return;
+        }
+        match self.create_new_tab(name) {
+            Ok(()) => {
+                let n = self.active().name.clone();
+                self.active_mut()
+                    .messages
+                    .push(format!("[Created new session: {}]", n));
+            }
+            Err(e) => {
+                self.active_mut()
+                    .messages
+                    .push(format!("Error creating session: {}", e));
+            }
+        }
+    }
+
+    fn create_new_tab(&mut self, name: Option<String>) -> Result<()> {
+        let id = session::generate_session_id();
+        let tab_name = name.unwrap_or_else(|| format!("Session {}", self.tabs.len() + 1));
+        let agent = Agent::create(&self.config, &self.project_root)?;
+        self.tabs.push(SessionTab::new(id, tab_name, agent));
+        self.active_tab = self.tabs.len() - 1;
+        Ok(())
+    }
+
+    fn toggle_widget(&mut self, id: &str) -> bool {
+        if let Some(pos) = self.header_widgets.iter().position(|w| w.id() == id) {
+            self.header_widgets.remove(pos);
+            false
+        } else {
+            match id {
+                "stats" => self.header_widgets.insert(0, Box::new(StatsWidget)),
+                "pet" => self.header_widgets.push(Box::new(PetWidget)),
+                _ => return false,
+            }
+            true
+        }
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) {
+        let wrap_width = self.active_input_rect.width.saturating_sub(2) as usize;
+        let tab = self.active_mut();
+        match key.code {
+            KeyCode::Char(c) => {
+                if key.modifiers.contains(KeyModifiers::CONTROL) {
+                    match c {
+                        // Step through /search matches; plain n/p still type
+                        'n' | 'p' => {
+                            if let Some(search) = tab.search.as_mut() {
+                                search.step(c == 'n');
+                            }
+                        }
+                        'u' => {
+                            tab.input.clear();
+                            tab.cursor_position = 0;
+                        }
+                        'k' => {
+                            let b = tab.byte_index();
+                            tab.input.drain(b..);
+                        }
+                        'w' => {
+                            let end = tab.byte_index();
+                            let chars: Vec<char> = tab.input.chars().collect();
+                            while tab.cursor_position > 0
+                                && chars[tab.cursor_position - 1].is_whitespace()
+                            {
+                                tab.cursor_position -= 1;
+                            }
+                            while tab.cursor_position > 0
+                                && !chars[tab.cursor_position - 1].is_whitespace()
                             {
                                 tab.cursor_position -= 1;
                             }
@@ -1258,51 +3104,43 @@ impl RatatuiUi {
                     tab.cursor_position += 1;
                 }
             }
-            KeyCode::Backspace => {
-                if tab.cursor_position > 0 {
-                    tab.cursor_position -= 1;
-                    let b = tab.byte_index();
-                    tab.input.remove(b);
-                }
+            KeyCode::Backspace if tab.cursor_position > 0 => {
+                tab.cursor_position -= 1;
+                let b = tab.byte_index();
+                tab.input.remove(b);
             }
-            KeyCode::Delete => {
-                if tab.cursor_position < tab.char_count() {
-                    let b = tab.byte_index();
-                    tab.input.remove(b);
-                }
+            KeyCode::Delete if tab.cursor_position < tab.char_count() => {
+                let b = tab.byte_index();
+                tab.input.remove(b);
             }
-            KeyCode::Left if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if tab.cursor_position > 0 {
-                    tab.cursor_position -= 1;
-                }
+            KeyCode::Left
+                if !key.modifiers.contains(KeyModifiers::CONTROL) && tab.cursor_position > 0 =>
+            {
+                tab.cursor_position -= 1;
             }
-            KeyCode::Right if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if tab.cursor_position < tab.char_count() {
-                    tab.cursor_position += 1;
-                }
+            KeyCode::Right
+                if !key.modifiers.contains(KeyModifiers::CONTROL)
+                    && tab.cursor_position < tab.char_count() =>
+            {
+                tab.cursor_position += 1;
             }
-            KeyCode::Up => {
-                if wrap_width > 0 {
-                    let (r, c) =
-                        Self::cursor_row_col_wrapped(&tab.input, tab.cursor_position, wrap_width);
-                    if r > 0 {
-                        let new_pos = Self::row_col_to_cursor_pos(&tab.input, r - 1, c, wrap_width);
-                        tab.cursor_position = new_pos;
-                    }
+            KeyCode::Up if wrap_width > 0 => {
+                let (r, c) =
+                    Self::cursor_row_col_wrapped(&tab.input, tab.cursor_position, wrap_width);
+                if r > 0 {
+                    let new_pos = Self::row_col_to_cursor_pos(&tab.input, r - 1, c, wrap_width);
+                    tab.cursor_position = new_pos;
                 }
             }
-            KeyCode::Down => {
-                if wrap_width > 0 {
-                    let (r, c) =
-                        Self::cursor_row_col_wrapped(&tab.input, tab.cursor_position, wrap_width);
-                    let max_row =
-                        Self::count_wrapped_lines(&tab.input, wrap_width).saturating_sub(1);
-                    if r < max_row {
-                        let new_pos = Self::row_col_to_cursor_pos(&tab.input, r + 1, c, wrap_width);
-                        tab.cursor_position = new_pos.min(tab.char_count());
-                    } else {
-                        tab.cursor_position = tab.char_count();
-                    }
+            KeyCode::Down if wrap_width > 0 => {
+                let (r, c) =
+                    Self::cursor_row_col_wrapped(&tab.input, tab.cursor_position, wrap_width);
+                let max_row = Self::count_wrapped_lines(&tab.input, wrap_width).saturating_sub(1);
+                if r < max_row {
+                    let new_pos = Self::row_col_to_cursor_pos(&tab.input, r + 1, c, wrap_width);
+                    tab.cursor_position = new_pos.min(tab.char_count());
+                } else {
+                    tab.cursor_position = tab.char_count();
                 }
             }
             KeyCode::Home => {
@@ -1315,35 +3153,91 @@ impl RatatuiUi {
         }
         let input_snapshot = self.active().input.clone();
         self.autocomplete.update_filter(&input_snapshot);
+        if !input_snapshot.is_empty() {
+            self.active_mut().search = None;
+        }
     }
 
     fn apply_autocomplete_selection(&mut self) {
         if let Some(cmd) = self.autocomplete.selected_command() {
             let tab = self.active_mut();
-            tab.input = cmd.to_string();
+            tab.input = cmd;
             tab.cursor_position = tab.input.chars().count();
             self.autocomplete.dismiss();
         }
     }
 
-    fn build_conversation_lines(messages: &[String]) -> Vec<Line<'static>> {
+    /// Render messages as styled lines, labelled with `ui.prompt_user` /
+    /// `ui.prompt_assistant`. With `times`, each message's first line is
+    /// prefixed by a dim `HH:MM` timestamp. The first `hidden` messages are
+    /// replaced by `HIDDEN_MESSAGES_MARKER` (see `hidden_message_count`).
+    fn build_conversation_lines(
+        messages: &[String],
+        times: Option<&[Option<String>]>,
+        selected: Option<usize>,
+        ui: &UiConfig,
+        hidden: usize,
+    ) -> Vec<Line<'static>> {
         let mut text_lines = Vec::new();
-        for msg in messages {
+        if hidden > 0 {
+            text_lines.push(Line::from(Span::styled(
+                HIDDEN_MESSAGES_MARKER.to_string(),
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC),
+            )));
+            text_lines.push(Line::from(""));
+        }
+        for (i, msg) in messages.iter().enumerate().skip(hidden) {
+            let first_line = text_lines.len();
             if let Some(rest) = msg.strip_prefix("You: ") {
                 text_lines.push(Line::from(vec![
-                    Span::styled("You: ".to_string(), Style::default().fg(Color::Green)),
+                    Span::styled(
+                        ui.prompt_user.as_deref().unwrap_or("You: ").to_string(),
+                        Style::default().fg(Color::Green),
+                    ),
                     Span::raw(rest.to_string()),
                 ]));
                 text_lines.push(Line::from(""));
             } else if let Some(rest) = msg.strip_prefix("Assistant: ") {
                 text_lines.push(Line::from(Span::styled(
-                    "Assistant:".to_string(),
+                    ui.prompt_assistant
+                        .as_deref()
+                        .unwrap_or("Assistant:")
+                        .to_string(),
                     Style::default()
                         .fg(Color::Blue)
                         .add_modifier(Modifier::BOLD),
                 )));
                 let md_lines = crate::ui::markdown::markdown_to_lines(rest);
                 text_lines.extend(md_lines);
+            } else if let Some(rest) = msg.strip_prefix("SUMMARY:") {
+                text_lines.push(Line::from(Span::styled(
+                    "Session summary:".to_string(),
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                text_lines.extend(crate::ui::markdown::markdown_to_lines(rest));
+            } else if let Some(rest) = msg.strip_prefix("HISTORY:") {
+                text_lines.push(Line::from(Span::styled(
+                    "Message history:".to_string(),
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                let md = format!("```json\n{}\n```", rest);
+                text_lines.extend(crate::ui::markdown::markdown_to_lines(&md));
+            } else if let Some(rest) = msg.strip_prefix("RERUN:") {
+                let (command, output) = rest.split_once('\n').unwrap_or((rest, ""));
+                text_lines.push(Line::from(Span::styled(
+                    format!("$ {}", command),
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                let md = format!("```\n{}\n```", output.trim_end());
+                text_lines.extend(crate::ui::markdown::markdown_to_lines(&md));
             } else if let Some(rest) = msg.strip_prefix("TOOL_PROGRESS:") {
                 text_lines.push(Line::from(Span::styled(
                     format!("  {}", rest),
@@ -1361,10 +3255,37 @@ impl RatatuiUi {
                     format!("  {}", rest),
                     Style::default().fg(Color::Red),
                 )));
+            } else if let Some(rest) = msg.strip_prefix("TOOL_OUTPUT:") {
+                text_lines.push(Line::from(Span::styled(
+                    format!("    │ {}", rest),
+                    Style::default().fg(Color::DarkGray),
+                )));
             } else {
                 text_lines.push(Line::from(msg.clone()));
                 text_lines.push(Line::from(""));
             }
+            for line in &mut text_lines[first_line..] {
+                truncate_long_line(line);
+            }
+            let time = times.and_then(|t| t.get(i)).and_then(|t| t.as_deref());
+            if let (Some(time), Some(line)) = (time, text_lines.get_mut(first_line)) {
+                line.spans.insert(
+                    0,
+                    Span::styled(
+                        format!("{} ", short_time(time)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                );
+            }
+            if selected == Some(i) {
+                if let Some(line) = text_lines.get_mut(first_line) {
+                    line.spans
+                        .insert(0, Span::styled("▶ ", Style::default().fg(Color::Yellow)));
+                }
+                for line in &mut text_lines[first_line..] {
+                    line.style = line.style.bg(Color::DarkGray);
+                }
+            }
         }
         text_lines
     }
@@ -1391,15 +3312,21 @@ impl RatatuiUi {
             .sum()
     }
 
+    fn tab_label(tab: &SessionTab) -> String {
+        if tab.processing {
+            format!(" {}⏳ ", tab.name)
+        } else if tab.unloaded {
+            format!(" {} (unloaded) ", tab.name)
+        } else {
+            format!(" {} ", tab.name)
+        }
+    }
+
     fn render_tab_bar(&mut self, f: &mut Frame, area: Rect) {
         self.tab_bar_rect = area;
         let mut spans = Vec::new();
         for (i, tab) in self.tabs.iter().enumerate() {
-            let label = if tab.processing {
-                format!(" {}⏳ ", tab.name)
-            } else {
-                format!(" {} ", tab.name)
-            };
+            let label = Self::tab_label(tab);
             if i == self.active_tab {
                 spans.push(Span::styled(
                     label,
@@ -1421,20 +3348,40 @@ impl RatatuiUi {
         f.render_widget(widget, area);
     }
 
+    /// Whether to render only the active tab full-width instead of side-by-side columns.
+    fn use_single_layout(layout: TabLayout, width: u16, tab_count: usize) -> bool {
+        match layout {
+            _ if tab_count <= 1 => true,
+            TabLayout::Single => true,
+            TabLayout::Split => false,
+            TabLayout::Auto => (width as usize / tab_count) < MIN_SPLIT_COLUMN_WIDTH,
+        }
+    }
+
     fn render_sessions(&mut self, f: &mut Frame, area: Rect) {
         let tab_count = self.tabs.len();
         let active = self.active_tab.min(tab_count.saturating_sub(1));
 
-        if tab_count == 1 {
-            self.session_rects = vec![area];
-            let tab = &self.tabs[0];
+        if Self::use_single_layout(self.config.ui.tab_layout, area.width, tab_count) {
+            // Hidden tabs get an empty rect so mouse clicks never select them
+            self.session_rects = (0..tab_count)
+                .map(|i| if i == active { area } else { Rect::default() })
+                .collect();
+            let tab = &self.tabs[active];
             let wrap_width = area.width.saturating_sub(2) as usize;
             let input_rendered_lines = Self::count_wrapped_lines(&tab.input, wrap_width);
-            let input_h = (input_rendered_lines as u16 + 2).max(3).min(10);
+            let input_h = (input_rendered_lines as u16 + 2).clamp(3, 10);
             let rows =
                 Layout::vertical([Constraint::Min(3), Constraint::Length(input_h)]).split(area);
             self.active_input_rect = rows[1];
-            Self::render_session_panel(&mut self.tabs[0], true, f, area);
+            Self::render_session_panel(
+                &mut self.tabs[active],
+                true,
+                self.show_timestamps,
+                &self.config.ui,
+                f,
+                area,
+            );
             return;
         }
 
@@ -1451,34 +3398,84 @@ impl RatatuiUi {
             let area = cols[i];
             let wrap_width = area.width.saturating_sub(2) as usize;
             let input_rendered_lines = Self::count_wrapped_lines(&tab.input, wrap_width);
-            let input_h = (input_rendered_lines as u16 + 2).max(3).min(10);
+            let input_h = (input_rendered_lines as u16 + 2).clamp(3, 10);
             let rows =
                 Layout::vertical([Constraint::Min(3), Constraint::Length(input_h)]).split(area);
             if is_active {
                 self.active_input_rect = rows[1];
             }
-            Self::render_session_panel(tab, is_active, f, area);
+            Self::render_session_panel(
+                tab,
+                is_active,
+                self.show_timestamps,
+                &self.config.ui,
+                f,
+                area,
+            );
         }
     }
 
-    fn render_session_panel(tab: &mut SessionTab, is_active: bool, f: &mut Frame, area: Rect) {
+    fn render_session_panel(
+        tab: &mut SessionTab,
+        is_active: bool,
+        show_timestamps: bool,
+        ui: &UiConfig,
+        f: &mut Frame,
+        area: Rect,
+    ) {
         let wrap_width = area.width.saturating_sub(2) as usize; // minus borders
         let input_rendered_lines = Self::count_wrapped_lines(&tab.input, wrap_width);
-        let input_h = (input_rendered_lines as u16 + 2).max(3).min(10);
+        let input_h = (input_rendered_lines as u16 + 2).clamp(3, 10);
 
         let rows = Layout::vertical([Constraint::Min(3), Constraint::Length(input_h)]).split(area);
 
-        Self::render_conversation(tab, is_active, f, rows[0]);
+        let conversation = content_rect(rows[0], ui);
+        Self::render_conversation(tab, is_active, show_timestamps, ui, f, conversation);
         Self::render_session_input(tab, is_active, f, rows[1]);
     }
 
-    fn render_conversation(tab: &mut SessionTab, is_active: bool, f: &mut Frame, area: Rect) {
-        let text_lines = Self::build_conversation_lines(&tab.messages);
+    fn render_conversation(
+        tab: &mut SessionTab,
+        is_active: bool,
+        show_timestamps: bool,
+        ui: &UiConfig,
+        f: &mut Frame,
+        area: Rect,
+    ) {
+        let times = show_timestamps.then_some(tab.message_times.as_slice());
+        // Search and `/all` look at the whole conversation
+        let window = if tab.show_all || tab.search.is_some() {
+            0
+        } else {
+            ui.render_window
+        };
+        let hidden = hidden_message_count(tab.messages.len(), window, tab.selected_message);
+        let mut text_lines =
+            Self::build_conversation_lines(&tab.messages, times, tab.selected_message, ui, hidden);
         let visible_height = area.height.saturating_sub(2) as usize;
         let wrap_width = area.width.saturating_sub(2) as usize;
         let total_rendered = Self::estimate_rendered_lines(&text_lines, wrap_width);
         let max_scroll = total_rendered.saturating_sub(visible_height);
 
+        if let Some(search) = tab.search.as_mut() {
+            let matches = search_matches(&text_lines, &search.query);
+            search.total = matches.len();
+            search.current = search.current.min(matches.len().saturating_sub(1));
+            for (n, &i) in matches.iter().enumerate() {
+                let style = if n == search.current {
+                    Style::default().fg(Color::Black).bg(Color::Yellow)
+                } else {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                };
+                highlight_matches(&mut text_lines[i], &search.query, style);
+            }
+            if let (true, Some(&line)) = (search.jump, matches.get(search.current)) {
+                tab.scroll_offset = Self::estimate_rendered_lines(&text_lines[..line], wrap_width);
+                tab.follow_tail = false;
+            }
+            search.jump = false;
+        }
+
         if tab.follow_tail {
             tab.scroll_offset = max_scroll;
         } else {
@@ -1487,6 +3484,21 @@ impl RatatuiUi {
                 tab.follow_tail = true;
             }
         }
+        // Keep the selected message in view
+        if let Some(sel) = tab.selected_message {
+            let before = Self::build_conversation_lines(
+                &tab.messages[..sel.min(tab.messages.len())],
+                times,
+                None,
+                ui,
+                hidden,
+            );
+            let sel_top = Self::estimate_rendered_lines(&before, wrap_width);
+            if sel_top < tab.scroll_offset || sel_top >= tab.scroll_offset + visible_height {
+                tab.scroll_offset = sel_top.min(max_scroll);
+                tab.follow_tail = false;
+            }
+        }
         let scroll = tab.scroll_offset;
 
         let border_color = if is_active {
@@ -1501,11 +3513,22 @@ impl RatatuiUi {
         } else {
             Style::default().fg(Color::DarkGray)
         };
-        let title = if tab.processing {
+        let mut title = if tab.processing {
             format!(" {} ⏳ ", tab.name)
         } else {
             format!(" {} ", tab.name)
         };
+        if let Some(search) = &tab.search {
+            title.push_str(&match search.total {
+                0 => format!("─ no matches for \"{}\" ", search.query),
+                n => format!(
+                    "─ \"{}\" {}/{} (^N/^P, Esc) ",
+                    search.query,
+                    search.current + 1,
+                    n
+                ),
+            });
+        }
 
         let p = Paragraph::new(text_lines)
             .block(
@@ -1532,7 +3555,7 @@ impl RatatuiUi {
         };
 
         if let Some(desc) = &tab.pending_confirm {
-            let title = "⚠️  确认执行? [Y] 确认 / [N] 取消";
+            let title = "⚠️  确认执行? [Y] 确认 / [A] 本会话始终允许 / [N] 取消";
             let p = Paragraph::new(desc.as_str())
                 .block(
                     Block::default()
@@ -1590,105 +3613,111 @@ impl RatatuiUi {
         }
     }
 
-    /// Count rendered lines using character-by-character wrapping (same logic as cursor).
-    fn count_wrapped_lines(text: &str, wrap_width: usize) -> usize {
-        if wrap_width == 0 {
-            return text.split('\n').count().max(1);
-        }
-        let mut row = 1usize;
+    /// Rows of `text` wrapped to `wrap_width` columns (0 = no wrapping), as char
+    /// index ranges without the newlines. Rows break after the last whitespace that
+    /// fits; only a word wider than the row is split mid-word. The input box
+    /// rendering, line count and cursor math all go through this.
+    fn wrap_rows(text: &str, wrap_width: usize) -> Vec<std::ops::Range<usize>> {
+        let chars: Vec<char> = text.chars().collect();
+        let width_of = |range: std::ops::Range<usize>| -> usize {
+            chars[range]
+                .iter()
+                .map(|&c| Self::char_display_width(c))
+                .sum()
+        };
+        let mut rows = Vec::new();
+        let mut start = 0usize;
         let mut col = 0usize;
-        for c in text.chars() {
+        // Char index just past the last whitespace in the current row
+        let mut last_break: Option<usize> = None;
+        for (i, &c) in chars.iter().enumerate() {
             if c == '\n' {
-                row += 1;
+                rows.push(start..i);
+                start = i + 1;
                 col = 0;
-            } else {
-                let cw = Self::char_display_width(c);
-                if col + cw > wrap_width {
-                    row += 1;
-                    col = cw;
-                } else {
-                    col += cw;
+                last_break = None;
+                continue;
+            }
+            let cw = Self::char_display_width(c);
+            if wrap_width > 0 && col + cw > wrap_width && i > start {
+                let brk = last_break.filter(|&b| b > start).unwrap_or(i);
+                rows.push(start..brk);
+                start = brk;
+                col = width_of(brk..i);
+                last_break = None;
+                if col + cw > wrap_width && i > start {
+                    rows.push(start..i);
+                    start = i;
+                    col = 0;
                 }
             }
+            col += cw;
+            if c.is_whitespace() {
+                last_break = Some(i + 1);
+            }
         }
-        row
+        rows.push(start..chars.len());
+        rows
+    }
+
+    /// Count rendered lines of the input box (see `wrap_rows`).
+    fn count_wrapped_lines(text: &str, wrap_width: usize) -> usize {
+        Self::wrap_rows(text, wrap_width).len()
     }
 
     /// Convert display (row, col) to cursor position. Inverse of cursor_row_col_wrapped.
+    /// A `col` past the end of the row clamps to the end of that row.
     fn row_col_to_cursor_pos(input: &str, row: usize, col: usize, wrap_width: usize) -> usize {
-        let mut cur_row = 0usize;
+        let rows = Self::wrap_rows(input, wrap_width);
+        let Some(range) = rows.get(row) else {
+            return input.chars().count();
+        };
+        // The end of a wrapped (not newline-ended) row is shown at the start of the
+        // next one, so stay before its last char
+        let wrapped = rows
+            .get(row + 1)
+            .is_some_and(|next| next.start == range.end);
+        let last = if wrapped {
+            range.end.saturating_sub(1).max(range.start)
+        } else {
+            range.end
+        };
         let mut cur_col = 0usize;
-        for (i, c) in input.chars().enumerate() {
-            if cur_row == row && cur_col >= col {
-                return i;
-            }
-            if cur_row > row {
-                return i;
-            }
-            if c == '\n' {
-                cur_row += 1;
-                cur_col = 0;
-            } else {
-                let cw = Self::char_display_width(c);
-                if wrap_width > 0 && cur_col + cw > wrap_width {
-                    cur_row += 1;
-                    cur_col = cw;
-                } else {
-                    cur_col += cw;
-                }
+        for (pos, c) in (range.start..last).zip(input.chars().skip(range.start)) {
+            if cur_col >= col {
+                return pos;
             }
+            cur_col += Self::char_display_width(c);
         }
-        input.chars().count()
+        last
     }
 
-    /// Calculate cursor (row, col) with character-by-character wrapping.
+    /// Calculate cursor (row, col) in the wrapped input (see `wrap_rows`).
     fn cursor_row_col_wrapped(input: &str, cursor_pos: usize, wrap_width: usize) -> (usize, usize) {
-        let mut row = 0usize;
-        let mut col = 0usize;
-        for (i, c) in input.chars().enumerate() {
-            if i >= cursor_pos {
-                break;
-            }
-            if c == '\n' {
-                row += 1;
-                col = 0;
-            } else {
-                let cw = Self::char_display_width(c);
-                if wrap_width > 0 && col + cw > wrap_width {
-                    row += 1;
-                    col = cw;
-                } else {
-                    col += cw;
-                }
-            }
-        }
+        let rows = Self::wrap_rows(input, wrap_width);
+        let row = rows
+            .iter()
+            .rposition(|r| r.start <= cursor_pos)
+            .unwrap_or(0);
+        let start = rows[row].start;
+        let col = input
+            .chars()
+            .skip(start)
+            .take(cursor_pos.saturating_sub(start))
+            .map(Self::char_display_width)
+            .sum();
         (row, col)
     }
 
-    /// Manually wrap text at exact character boundaries.
-    /// Ensures rendered output matches cursor_row_col_wrapped exactly.
+    /// Wrap text for the input box, one line per row of `wrap_rows`, so the
+    /// rendered output matches cursor_row_col_wrapped exactly.
     fn manual_wrap(text: &str, wrap_width: usize) -> String {
-        if wrap_width == 0 {
-            return text.to_string();
-        }
-        let mut result = String::new();
-        let mut col = 0usize;
-        for c in text.chars() {
-            if c == '\n' {
-                result.push('\n');
-                col = 0;
-            } else {
-                let cw = Self::char_display_width(c);
-                if col + cw > wrap_width {
-                    result.push('\n');
-                    col = cw;
-                } else {
-                    col += cw;
-                }
-                result.push(c);
-            }
-        }
-        result
+        let chars: Vec<char> = text.chars().collect();
+        Self::wrap_rows(text, wrap_width)
+            .into_iter()
+            .map(|r| chars[r].iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
     fn render_autocomplete(&self, f: &mut Frame, input_area: Rect) {
@@ -1696,7 +3725,8 @@ impl RatatuiUi {
             return;
         }
 
-        let item_count = self.autocomplete.filtered.len() as u16;
+        let items = self.autocomplete.items();
+        let item_count = items.len() as u16;
         let popup_height = item_count + 2;
         let popup_width = 50u16.min(input_area.width);
 
@@ -1709,13 +3739,10 @@ impl RatatuiUi {
 
         f.render_widget(Clear, popup_area);
 
-        let lines: Vec<Line> = self
-            .autocomplete
-            .filtered
+        let lines: Vec<Line> = items
             .iter()
             .enumerate()
-            .map(|(i, &cmd_idx)| {
-                let cmd = &SLASH_COMMANDS[cmd_idx];
+            .map(|(i, (name, description))| {
                 let is_selected = i == self.autocomplete.selected;
                 let (bg, fg_name, fg_desc) = if is_selected {
                     (Color::Cyan, Color::Black, Color::DarkGray)
@@ -1724,7 +3751,7 @@ impl RatatuiUi {
                 };
                 Line::from(vec![
                     Span::styled(
-                        format!(" {:<12}", cmd.name),
+                        format!(" {:<12}", name),
                         Style::default()
                             .fg(fg_name)
                             .bg(bg)
@@ -1735,7 +3762,7 @@ impl RatatuiUi {
                             }),
                     ),
                     Span::styled(
-                        format!(" {}", cmd.description),
+                        format!(" {}", description),
                         Style::default().fg(fg_desc).bg(bg),
                     ),
                 ])
@@ -1757,11 +3784,17 @@ impl RatatuiUi {
             return;
         }
         let tab = self.active();
+        let speed = self.config.ui.pet_animation_speed;
+        let speed = if speed.is_finite() {
+            speed.clamp(0.0, 100.0)
+        } else {
+            1.0
+        };
         let ctx = WidgetContext {
             stats: &tab.cached_stats,
             messages: &tab.messages,
             processing: tab.processing,
-            anim_tick: self.anim_tick,
+            anim_elapsed: self.anim_start.elapsed().mul_f64(speed),
             pet_state: tab.pet_state,
             pet_name: &self.pet_name,
             idle_ticks: self.idle_ticks,
@@ -1770,6 +3803,7 @@ impl RatatuiUi {
             context_used: tab.context_used,
             context_limit: tab.context_limit,
             current_model_id: &tab.current_model_id,
+            api_status: self.api_status,
         };
 
         let constraints: Vec<Constraint> = self
@@ -1844,7 +3878,7 @@ impl RatatuiUi {
                 let sess_rect = self.session_rects[active_idx];
                 let tab = &self.tabs[active_idx];
                 let input_line_count = tab.input.matches('\n').count() + 1;
-                let input_h = (input_line_count as u16 + 2).max(3).min(8);
+                let input_h = (input_line_count as u16 + 2).clamp(3, 8);
                 let input_area = Rect {
                     x: sess_rect.x,
                     y: sess_rect.y + sess_rect.height - input_h,
@@ -1855,6 +3889,9 @@ impl RatatuiUi {
             }
         }
 
+        if !self.tabs.is_empty() && self.active().notes_open {
+            self.render_notes(f);
+        }
         // Session picker popup (centered)
         if self.session_picker.visible {
             self.render_session_picker(f);
@@ -1888,11 +3925,18 @@ impl RatatuiUi {
         for (i, m) in self.model_picker.models.iter().enumerate() {
             let is_selected = i == self.model_picker.selected;
             let is_current = m.id == self.model_picker.current_model_id;
-            let label = if is_current {
-                format!(" {} *", m.id)
-            } else {
-                format!(" {}", m.id)
-            };
+            let aliases = self.config.aliases_for(&m.id);
+            let mut label = format!(" {}", m.id);
+            if !aliases.is_empty() {
+                label.push_str(&format!(" ({})", aliases.join(", ")));
+            }
+            if is_current {
+                label.push_str(" *");
+            }
+            let has_key = self.config.api_key_for_model(&m.id).is_ok();
+            if !has_key {
+                label.push_str(" (no API key)");
+            }
             if is_selected {
                 lines.push(Line::from(Span::styled(
                     format!("▶ {}", label),
@@ -1902,9 +3946,14 @@ impl RatatuiUi {
                         .add_modifier(Modifier::BOLD),
                 )));
             } else {
+                let color = if has_key {
+                    Color::White
+                } else {
+                    Color::DarkGray
+                };
                 lines.push(Line::from(Span::styled(
                     format!("  {}", label),
-                    Style::default().fg(Color::White),
+                    Style::default().fg(color),
                 )));
             }
         }
@@ -1937,10 +3986,21 @@ impl RatatuiUi {
         f.render_widget(Clear, popup_area);
 
         let mut lines: Vec<Line> = Vec::new();
-        lines.push(Line::from(Span::styled(
-            " ↑/↓ 选择  Enter 加载  Esc 取消",
-            Style::default().fg(Color::DarkGray),
-        )));
+        let pending_delete = self
+            .session_picker
+            .confirm_delete
+            .as_ref()
+            .and_then(|id| self.session_picker.sessions.iter().find(|s| &s.id == id));
+        lines.push(match pending_delete {
+            Some(s) => Line::from(Span::styled(
+                format!(" 删除会话 {}？ y 确认  其他键取消", s.name),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )),
+            None => Line::from(Span::styled(
+                " ↑/↓ 选择  Enter 加载  d 删除  Esc 取消",
+                Style::default().fg(Color::DarkGray),
+            )),
+        });
         lines.push(Line::from(""));
 
         for (i, s) in self.session_picker.sessions.iter().enumerate() {
@@ -1988,9 +4048,7 @@ impl RatatuiUi {
 
         match command {
             "/quit" | "/exit" => {
-                for tab in &self.tabs {
-                    tab.auto_save();
-                }
+                self.save_all_on_quit();
                 return Some(UiExitAction::Quit);
             }
             "/trust" => {
@@ -2023,18 +4081,7 @@ impl RatatuiUi {
             }
             "/stop" => {
                 if self.active().processing {
-                    let tab_idx = self.active_tab.min(self.tabs.len().saturating_sub(1));
-                    let handle = self.tabs[tab_idx].agent_handle.take();
-                    if let Some(h) = handle {
-                        h.abort();
-                        if let Err(e) = self.restore_agent_after_abort(tab_idx) {
-                            self.tabs[tab_idx]
-                                .messages
-                                .push(format!("Error restoring: {}", e));
-                        } else {
-                            self.tabs[tab_idx].messages.push("  ⏹ 已中断".to_string());
-                        }
-                    }
+                    self.cancel_active_turn();
                 } else {
                     self.active_mut().messages.push("[Not processing]".into());
                 }
@@ -2047,6 +4094,7 @@ impl RatatuiUi {
                     tab.context_limit = agent.context_window();
                 }
                 tab.messages.clear();
+                tab.message_times.clear();
                 tab.messages.push("Conversation cleared.".into());
                 tab.scroll_offset = 0;
                 tab.follow_tail = true;
@@ -2057,19 +4105,7 @@ impl RatatuiUi {
                 } else {
                     Some(arg.to_string())
                 };
-                match self.create_new_tab(name) {
-                    Ok(()) => {
-                        let n = self.active().name.clone();
-                        self.active_mut()
-                            .messages
-                            .push(format!("[Created new session: {}]", n));
-                    }
-                    Err(e) => {
-                        self.active_mut()
-                            .messages
-                            .push(format!("Error creating session: {}", e));
-                    }
-                }
+                self.open_new_tab(name);
             }
             "/close" => {
                 if self.tabs.len() <= 1 {
@@ -2077,24 +4113,67 @@ impl RatatuiUi {
                         .messages
                         .push("[Cannot close the last session]".into());
                 } else {
+                    let exported = self.auto_export_tab(self.active());
                     self.tabs.remove(self.active_tab);
                     if self.active_tab >= self.tabs.len() {
                         self.active_tab = self.tabs.len() - 1;
                     }
+                    match exported {
+                        Some(Ok(path)) => self
+                            .active_mut()
+                            .messages
+                            .push(format!("[Closed session exported to {}]", path.display())),
+                        Some(Err(e)) => self
+                            .active_mut()
+                            .messages
+                            .push(format!("Error exporting closed session: {}", e)),
+                        None => {}
+                    }
+                }
+            }
+            "/title" if arg.is_empty() => {
+                let idx = self.active_tab.min(self.tabs.len() - 1);
+                if self.tabs[idx].title_task.is_some() {
+                    self.active_mut()
+                        .messages
+                        .push("[Title update already in progress]".into());
+                } else {
+                    self.request_title_update(idx);
+                    let note = if self.tabs[idx].title_task.is_some() {
+                        "[Generating title...]"
+                    } else {
+                        "[Nothing to title yet — send a message first]"
+                    };
+                    self.active_mut().messages.push(note.into());
                 }
             }
-            "/rename" => {
+            "/rename" | "/title" => {
                 if arg.is_empty() {
                     self.active_mut()
                         .messages
                         .push("Usage: /rename <name>".into());
                 } else {
+                    // A manual name wins over any title still being generated
+                    if let Some(task) = self.active_mut().title_task.take() {
+                        task.abort();
+                    }
                     self.active_mut().name = arg.to_string();
                     self.active_mut()
                         .messages
                         .push(format!("[Session renamed to: {}]", arg));
                 }
             }
+            "/delete" => {
+                let msg = if arg.is_empty() {
+                    "Usage: /delete <id>  (see /sessions for ids)".to_string()
+                } else {
+                    match self.delete_saved_session(arg) {
+                        Ok(()) => format!("[Session deleted: {}]", arg),
+                        Err(e) => format!("Error deleting session: {}", e),
+                    }
+                };
+                self.active_mut().messages.push(msg);
+            }
             "/sessions" => match session::list_sessions() {
                 Ok(sessions) if sessions.is_empty() => {
                     self.active_mut()
@@ -2147,6 +4226,9 @@ impl RatatuiUi {
                 }
             }
             "/load" => {
+                if self.tab_limit_reached() {
+                    return None;
+                }
                 if arg.is_empty() {
                     self.session_picker.open();
                     if !self.session_picker.visible {
@@ -2165,6 +4247,7 @@ impl RatatuiUi {
                     }
                 }
             }
+            "/replay" => self.replay_session(arg),
             "/export" => {
                 if arg.is_empty() {
                     self.active_mut()
@@ -2186,7 +4269,25 @@ impl RatatuiUi {
                     }
                 }
             }
+            "/export-md" => {
+                if arg.is_empty() {
+                    self.active_mut()
+                        .messages
+                        .push("Usage: /export-md <path>".into());
+                } else {
+                    let data = self.active().to_session_data();
+                    let msg =
+                        match session::export_session_markdown(&data, std::path::Path::new(arg)) {
+                            Ok(()) => format!("[Transcript exported to {}]", arg),
+                            Err(e) => format!("Error exporting: {}", e),
+                        };
+                    self.active_mut().messages.push(msg);
+                }
+            }
             "/import" => {
+                if self.tab_limit_reached() {
+                    return None;
+                }
                 if arg.is_empty() {
                     self.active_mut()
                         .messages
@@ -2216,6 +4317,261 @@ impl RatatuiUi {
                     if visible { "enabled" } else { "disabled" }
                 ));
             }
+            "/history" => {
+                let tab = self.active_mut();
+                let Some(agent) = tab.agent.as_ref() else {
+                    tab.messages
+                        .push("[History unavailable while processing]".into());
+                    return None;
+                };
+                let msg = if arg.is_empty() {
+                    session::history_json(agent.history()).map(|json| format!("HISTORY:{}", json))
+                } else {
+                    session::export_history(agent.history(), std::path::Path::new(arg)).map(|()| {
+                        format!("[{} messages exported to {}]", agent.history().len(), arg)
+                    })
+                };
+                tab.messages
+                    .push(msg.unwrap_or_else(|e| format!("Error: {}", e)));
+            }
+            "/summarize" => {
+                let note_path = (!arg.is_empty()).then(|| std::path::PathBuf::from(arg));
+                self.request_summary(note_path);
+            }
+            "/iter" => {
+                let tab = self.active_mut();
+                let Some(agent) = tab.agent.as_mut() else {
+                    tab.messages
+                        .push("[Cannot change iterations while processing]".into());
+                    return None;
+                };
+                if arg.is_empty() {
+                    tab.messages
+                        .push(format!("[Max iterations: {}]", agent.max_iterations()));
+                } else {
+                    match arg.parse::<u32>() {
+                        Ok(n) => {
+                            let applied = agent.set_max_iterations(n);
+                            tab.messages
+                                .push(format!("[Max iterations set to {}]", applied));
+                        }
+                        Err(_) => tab.messages.push("Usage: /iter <n>".into()),
+                    }
+                }
+            }
+            "/queue" => {
+                let tab = self.active_mut();
+                match arg {
+                    "" => {
+                        let lines = tab.queue_lines();
+                        tab.messages.extend(lines);
+                    }
+                    "clear" => {
+                        let n = tab.clear_queue();
+                        tab.messages
+                            .push(format!("[Cleared {} queued message(s)]", n));
+                    }
+                    _ => tab.messages.push("Usage: /queue [clear]".into()),
+                }
+            }
+            "/rerun" => self.active_mut().start_rerun(),
+            "/run" => self.run_user_command(arg),
+            "/sendto" => self.send_last_message_to(arg),
+            "/compare" => self.compare_models(arg),
+            "/open" => {
+                let target = if arg.is_empty() {
+                    self.active().last_modified_path.clone()
+                } else {
+                    Some(arg.to_string())
+                };
+                match target {
+                    Some(path) => self.pending_editor = Some(self.project_root.join(path)),
+                    None => self
+                        .active_mut()
+                        .messages
+                        .push("[No file modified yet; usage: /open <path>]".into()),
+                }
+            }
+            "/env" => self.active_mut().env_command(arg),
+            "/search" => {
+                let tab = self.active_mut();
+                if arg.is_empty() {
+                    tab.search = None;
+                    tab.messages.push(
+                        "Usage: /search <text>  (Ctrl+N/Ctrl+P next/previous, Esc clears)".into(),
+                    );
+                } else {
+                    tab.search = Some(ConversationSearch::new(arg));
+                }
+            }
+            "/snippet" => {
+                let tab = self.active_mut();
+                let dir = match crate::snippets::dir() {
+                    Ok(dir) => dir,
+                    Err(e) => {
+                        tab.messages.push(format!("Error: {}", e));
+                        return None;
+                    }
+                };
+                if arg.is_empty() {
+                    let names = crate::snippets::list_in(&dir);
+                    if names.is_empty() {
+                        tab.messages.push(format!(
+                            "[No snippets yet; save files in {} and insert one with /snippet <name>]",
+                            dir.display()
+                        ));
+                    } else {
+                        tab.messages.push(format!("Snippets ({}):", dir.display()));
+                        tab.messages
+                            .extend(names.iter().map(|name| format!("  {}", name)));
+                    }
+                    return None;
+                }
+                match crate::snippets::load_in(&dir, arg) {
+                    Ok(content) => {
+                        let b = tab.byte_index();
+                        tab.input.insert_str(b, &content);
+                        tab.cursor_position += content.chars().count();
+                    }
+                    Err(e) => tab.messages.push(format!("Error: {}", e)),
+                }
+            }
+            "/notes" => {
+                let tab = self.active_mut();
+                match arg {
+                    "" => tab.notes_open = !tab.notes_open,
+                    "clear" => {
+                        tab.notes.clear();
+                        tab.messages.push("[Notes cleared]".into());
+                    }
+                    _ => tab.messages.push("Usage: /notes [clear]".into()),
+                }
+            }
+            "/stream" => {
+                let tab = self.active_mut();
+                let Some(agent) = tab.agent.as_mut() else {
+                    tab.messages
+                        .push("[Cannot change streaming while processing]".into());
+                    return None;
+                };
+                match arg {
+                    "" => {}
+                    "on" => agent.set_streaming(true),
+                    "off" => agent.set_streaming(false),
+                    _ => {
+                        tab.messages.push("Usage: /stream [on|off]".into());
+                        return None;
+                    }
+                }
+                let state = if agent.streaming() { "on" } else { "off" };
+                tab.messages.push(format!("[Streaming: {}]", state));
+            }
+            "/whoami" | "/info" => {
+                let lines = match self.active().agent.as_ref() {
+                    Some(agent) => {
+                        whoami_summary(agent, &self.config, &self.project_root, &self.config_path)
+                    }
+                    None => vec!["[Environment unavailable while processing]".to_string()],
+                };
+                for line in lines {
+                    self.active_mut().messages.push(line);
+                }
+            }
+            "/all" => {
+                let tab = self.active_mut();
+                tab.show_all = !tab.show_all;
+                let msg = if tab.show_all {
+                    "[Showing all messages]"
+                } else {
+                    "[Showing recent messages only]"
+                };
+                tab.messages.push(msg.into());
+            }
+            "/dryrun" => {
+                let dry_run = match arg {
+                    "" => !self.config.agent.dry_run,
+                    "on" => true,
+                    "off" => false,
+                    _ => {
+                        self.active_mut()
+                            .messages
+                            .push("Usage: /dryrun [on|off]".into());
+                        return None;
+                    }
+                };
+                // Applies to every tab; busy agents pick it up when their turn ends
+                self.config.agent.dry_run = dry_run;
+                for agent in self.tabs.iter_mut().filter_map(|t| t.agent.as_mut()) {
+                    agent.set_dry_run(dry_run);
+                }
+                let msg = if dry_run {
+                    "[Dry run: on — reads run, changes are only described]"
+                } else {
+                    "[Dry run: off]"
+                };
+                self.active_mut().messages.push(msg.into());
+            }
+            "/verbose" => {
+                let ui = self.config.ui.clone();
+                let tab = self.active_mut();
+                let current = tab
+                    .tool_verbosity
+                    .unwrap_or_else(|| ToolVerbosity::from_ui(&ui));
+                let level = if arg.is_empty() {
+                    Some(current.next())
+                } else {
+                    ToolVerbosity::parse(arg)
+                };
+                match level {
+                    Some(level) => {
+                        tab.tool_verbosity = Some(level);
+                        tab.messages
+                            .push(format!("[Tool status detail: {}]", level.label()));
+                    }
+                    None => tab
+                        .messages
+                        .push("Usage: /verbose [minimal|args|full]".into()),
+                }
+            }
+            "/stream-to" => {
+                if arg.is_empty() {
+                    self.active_mut()
+                        .messages
+                        .push("Usage: /stream-to <path>".into());
+                } else {
+                    let path = std::path::PathBuf::from(arg);
+                    let tab = self.active_mut();
+                    match tab.start_stream_to(&path) {
+                        Ok(()) => {
+                            let when = if tab.processing {
+                                "the rest of this turn"
+                            } else {
+                                "the next turn"
+                            };
+                            tab.messages.push(format!(
+                                "[Streaming {} to {}]",
+                                when,
+                                path.display()
+                            ));
+                        }
+                        Err(e) => {
+                            tab.messages
+                                .push(format!("Error opening {}: {}", path.display(), e));
+                        }
+                    }
+                }
+            }
+            "/timestamps" => {
+                self.show_timestamps = !self.show_timestamps;
+                let state = if self.show_timestamps {
+                    "enabled"
+                } else {
+                    "disabled"
+                };
+                self.active_mut()
+                    .messages
+                    .push(format!("[Message timestamps {}]", state));
+            }
             "/petname" => {
                 if arg.is_empty() {
                     let name = self.pet_name.clone();
@@ -2244,28 +4600,21 @@ impl RatatuiUi {
                         let current_id = self.active().current_model_id.clone();
                         self.model_picker.open(models, &current_id);
                     }
-                } else if self.active().processing {
-                    self.active_mut()
-                        .messages
-                        .push("[Cannot switch model while processing]".into());
+                } else if let Some(id) = arg
+                    .strip_prefix("info")
+                    .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+                {
+                    let id = match id.trim() {
+                        "" => self.active().current_model_id.clone(),
+                        id => id.to_string(),
+                    };
+                    let msg = match self.config.model_info(&id) {
+                        Ok(info) => info,
+                        Err(e) => format!("Error: {}", e),
+                    };
+                    self.active_mut().messages.push(msg);
                 } else {
-                    let config = self.config.clone();
-                    let tab = self.active_mut();
-                    if let Some(agent) = tab.agent.as_mut() {
-                        match agent.switch_model(arg, &config) {
-                            Ok(()) => {
-                                tab.current_model_id = agent.current_model_id().to_string();
-                                let display = agent.current_model_display();
-                                tab.messages
-                                    .push(format!("[Switched to model: {}]", display));
-                            }
-                            Err(e) => {
-                                tab.messages.push(format!("Error: {}", e));
-                            }
-                        }
-                    } else {
-                        tab.messages.push("[No agent available to switch]".into());
-                    }
+                    self.switch_active_model(arg);
                 }
             }
             "/help" => {
@@ -2276,24 +4625,52 @@ impl RatatuiUi {
                     "  /new [name]        Create new session tab",
                     "  /close             Close current session tab",
                     "  /rename <name>     Rename current session",
+                    "  /title [name]      Regenerate the title, or set it like /rename",
                     "  /save [name]       Save current session",
                     "  /load <id>         Load saved session",
                     "  /sessions          List saved sessions",
+                    "  /delete <id>       Delete a saved session (or d in the /load picker)",
                     "  /export <path>     Export session to file",
+                    "  /export-md <path>  Export a readable Markdown transcript",
                     "  /import <path>     Import session from file",
+                    "  /history [path]    Show raw message history as JSON (or export it)",
+                    "  /summarize [file]  Summarize session (optionally write a .md note)",
+                    "  /iter [n]          Show or set max tool iterations per message",
+                    "  /queue [clear]     List or clear messages queued while processing",
+                    "  /open [path]       Open a file (default: last one the agent modified) in $VISUAL/$EDITOR",
+                    "  /rerun             Re-run the agent's last bash command yourself (output shown here)",
+                    "  /run <cmd>         Run a command yourself and add its output as context",
+                    "  /sendto <tab>      Send your last message to another tab (number or name; queued if busy)",
+                    "  /compare <a> <b> <prompt>  Send a prompt to two models in new side-by-side tabs",
+                    "  /replay <id> [model]  Re-send a saved session's user messages to a fresh agent in a new tab",
+                    "  /notes [clear]     Private per-tab notes (not sent to the model; Esc closes)",
+                    "  /search <text>     Highlight matches in the conversation; Ctrl+N/P cycle, Esc clears",
+                    "  /snippet [name]    Insert a snippet from ~/.miniclaw/snippets (no name lists them)",
+                    "  /env [set [--persist] KEY=VAL | unset KEY]  Session env for bash commands",
+                    "  /stream [on|off]   Show or toggle streaming responses",
+                    "  /dryrun [on|off]   Describe changes instead of making them",
+                    "  /all               Show or hide older messages beyond ui.render_window",
+                    "  /whoami            Show project, model, API host, config and tools (/info)",
+                    "  /stream-to <path>  Also write streamed replies to a file",
+                    "  /verbose [level]   Cycle tool status detail: minimal, args, full (this tab)",
                     "  /stats             Toggle stats panel",
                     "  /pet               Toggle pet panel",
+                    "  /timestamps        Toggle message timestamps",
                     "  /petname [name]    Set or show pet name",
-                    "  /model [id]        List models or switch to model",
+                    "  /model [id|alias]  List models or switch to model",
+                    "  /model info [id]   Show a model's resolved settings and API key source",
                     "  /stop              Interrupt agent (when processing)",
                     "  /trust             Add workspace to trusted (auto-approve dangerous tools)",
                     "  /untrust           Remove workspace from trusted",
                     "  /quit              Exit the program",
                     "",
                     "  Shift+Enter/Alt+N  Insert newline (multi-line input)",
+                    "  Esc                Cancel the running turn, else clear the input",
                     "  Ctrl+Left/Right    Switch session tabs",
                     "  PageUp/PageDown    Scroll conversation",
                     "  Shift+mouse drag   Select and copy text",
+                    "  Alt+Up             Select a message (↑/↓ move, y copy, Esc exit)",
+                    "  Alt+Backspace      Remove last queued message (empty input)",
                     "  Ctrl+.             Interrupt agent (when processing)",
                     "  Ctrl+C             Exit the program",
                 ];
@@ -2311,8 +4688,8 @@ impl RatatuiUi {
         None
     }
 
-    fn load_session_as_tab(&mut self, id: &str) -> Result<()> {
-        let data = session::load_session(id)?;
+    /// Build a tab (with a fresh agent) from saved session data.
+    fn tab_from_session(&self, data: SessionData) -> Result<SessionTab> {
         let model_id = if data.current_model_id.is_empty() {
             None
         } else {
@@ -2321,58 +4698,158 @@ impl RatatuiUi {
         let mut agent = Agent::create_with_model(&self.config, &self.project_root, model_id)?;
         agent.set_messages(data.agent_messages);
         agent.stats = data.stats.to_session_stats();
-        let mut tab = SessionTab::new(data.id, data.name.clone(), agent);
-        tab.messages = data.ui_messages;
+        let mut tab = SessionTab::new(data.id, data.name, agent);
+        tab.restore_messages(data.ui_messages, data.ui_message_times);
+        tab.summary = data.summary;
+        tab.notes = data.notes;
+        tab.set_env(data.env, HashSet::new());
         tab.cached_stats = data.stats.to_session_stats();
+        Ok(tab)
+    }
+
+    fn load_session_as_tab(&mut self, id: &str) -> Result<()> {
+        let tab = self.tab_from_session(session::load_session(id)?)?;
+        let name = tab.name.clone();
         self.tabs.push(tab);
         self.active_tab = self.tabs.len() - 1;
         self.active_mut()
             .messages
-            .push(format!("[Loaded session: {}]", data.name));
+            .push(format!("[Loaded session: {}]", name));
         Ok(())
     }
 
-    /// Restore agent after abort. Tries to load from saved session, else creates fresh agent.
-    fn restore_agent_after_abort(&mut self, tab_idx: usize) -> Result<()> {
-        let tab = &mut self.tabs[tab_idx];
-        tab.event_rx = None;
-        tab.confirm_tx = None;
-        tab.pending_confirm = None;
-        if let Ok(data) = session::load_session(&tab.id) {
-            let model_id = if data.current_model_id.is_empty() {
-                None
-            } else {
-                Some(data.current_model_id.as_str())
-            };
-            let mut agent = Agent::create_with_model(&self.config, &self.project_root, model_id)?;
-            agent.set_messages(data.agent_messages);
-            agent.stats = data.stats.to_session_stats();
-            tab.agent = Some(agent);
-            tab.cached_stats = data.stats.to_session_stats();
-            if let Some(a) = tab.agent.as_ref() {
-                tab.context_used = a.estimate_context_tokens();
-                tab.context_limit = a.context_window();
-                tab.current_model_id = a.current_model_id().to_string();
+    /// Delete a saved session unless it is open in a tab (`/delete`, picker 'd').
+    fn delete_saved_session(&mut self, id: &str) -> Result<()> {
+        if let Some(tab) = self.tabs.iter().find(|t| t.id == id) {
+            return Err(anyhow::anyhow!(
+                "Session '{}' is open in tab '{}'; close it first",
+                id,
+                tab.name
+            ));
+        }
+        session::delete_session(id)
+    }
+
+    /// 'd' in the session picker: ask to confirm deleting the highlighted session.
+    fn request_picker_delete(&mut self) {
+        let Some(s) = self.session_picker.selected_session() else {
+            return;
+        };
+        let id = s.id.clone();
+        if self.tabs.iter().any(|t| t.id == id) {
+            self.active_mut().messages.push(format!(
+                "[Session '{}' is open in a tab; close it before deleting]",
+                id
+            ));
+            return;
+        }
+        self.session_picker.confirm_delete = Some(id);
+    }
+
+    /// 'y' after 'd' in the session picker: delete and refresh the list.
+    fn confirm_picker_delete(&mut self) {
+        let Some(id) = self.session_picker.confirm_delete.take() else {
+            return;
+        };
+        let msg = match self.delete_saved_session(&id) {
+            Ok(()) => format!("[Session deleted: {}]", id),
+            Err(e) => format!("Error deleting session: {}", e),
+        };
+        self.active_mut().messages.push(msg);
+        self.session_picker.refresh();
+    }
+
+    /// Save and unload background tabs idle past `ui.unload_idle_tabs_after_mins`.
+    fn unload_idle_tabs(&mut self, now: std::time::Instant) {
+        let mins = self.config.ui.unload_idle_tabs_after_mins;
+        if mins == 0 {
+            return;
+        }
+        let idle = std::time::Duration::from_secs(mins * 60);
+        let active = self.active_tab;
+        for (i, tab) in self.tabs.iter_mut().enumerate() {
+            if i != active && tab.is_idle(now, idle) {
+                if let Err(e) = tab.unload() {
+                    // Keep the conversation in memory; retry after another idle period
+                    tab.messages.push(format!(
+                        "[Could not save idle tab, keeping it loaded: {}]",
+                        e
+                    ));
+                    tab.last_activity = now;
+                }
             }
-        } else {
-            let model_id = if tab.current_model_id.is_empty() {
-                None
-            } else {
-                Some(tab.current_model_id.as_str())
-            };
-            let agent = Agent::create_with_model(&self.config, &self.project_root, model_id)?;
-            tab.agent = Some(agent);
-            if let Some(a) = tab.agent.as_ref() {
-                tab.context_used = a.estimate_context_tokens();
-                tab.context_limit = a.context_window();
-                tab.current_model_id = a.current_model_id().to_string();
-            }
-        }
-        tab.processing = false;
-        tab.pet_state = PetState::Idle;
-        tab.streaming_message_idx = None;
-        tab.tool_progress_idx = None;
-        Ok(())
+        }
+    }
+
+    /// Restore an unloaded tab from its saved session. If that fails, the tab
+    /// gets a fresh agent so it stays usable.
+    fn ensure_tab_loaded(&mut self, idx: usize) {
+        if !self.tabs[idx].unloaded {
+            return;
+        }
+        let restored =
+            session::load_session(&self.tabs[idx].id).and_then(|data| self.tab_from_session(data));
+        match restored {
+            Ok(mut tab) => {
+                // Secret-looking variables were not saved; carry them over from memory
+                let old = &mut self.tabs[idx];
+                let mut env = tab.env.clone();
+                for key in &old.env_unsaved {
+                    if let Some(value) = old.env.get(key) {
+                        env.insert(key.clone(), value.clone());
+                    }
+                }
+                tab.set_env(env, std::mem::take(&mut old.env_unsaved));
+                self.tabs[idx] = tab;
+            }
+            Err(e) => {
+                let model_id = self.tabs[idx].current_model_id.clone();
+                let agent =
+                    Agent::create_with_model(&self.config, &self.project_root, Some(&model_id));
+                let tab = &mut self.tabs[idx];
+                tab.unloaded = false;
+                tab.agent = agent.ok();
+                tab.messages = vec![format!("Error: could not restore session: {}", e)];
+                tab.message_times = vec![None];
+            }
+        }
+    }
+
+    /// `/run <cmd>`: run a command for the user and add its output as context.
+    /// Dangerous commands need the same Y/N confirmation as the agent's tool calls.
+    fn run_user_command(&mut self, command: &str) {
+        if command.is_empty() {
+            self.active_mut()
+                .messages
+                .push("[Usage: /run <command>]".into());
+            return;
+        }
+        let trusted = matches!(trusted_workspaces::is_trusted(&self.project_root), Ok(true));
+        let tab = self.active_mut();
+        if tab.processing || tab.run_task.is_some() || tab.agent.is_none() {
+            tab.messages.push("[Cannot run while processing]".into());
+            return;
+        }
+        let args = serde_json::json!({ "command": command }).to_string();
+        if !trusted && risk::assess_risk("bash", &args) == RiskLevel::Dangerous {
+            let description = risk::describe_tool_call("bash", &args);
+            tab.messages
+                .push(format!("⚠️  需要确认: {} [Y/N]", description));
+            tab.pending_confirm = Some(description);
+            tab.pending_run = Some(command.to_string());
+            tab.follow_tail = true;
+            return;
+        }
+        tab.start_run(command.to_string());
+    }
+
+    /// Esc, Ctrl+. and `/stop`: cancel the active tab's running turn.
+    fn cancel_active_turn(&mut self) {
+        let tab_idx = self.active_tab.min(self.tabs.len().saturating_sub(1));
+        let tab = &mut self.tabs[tab_idx];
+        if let Err(e) = tab.cancel_turn(&self.config, &self.project_root) {
+            tab.messages.push(format!("Error restoring: {}", e));
+        }
     }
 
     fn import_session_as_tab(&mut self, path: &str) -> Result<()> {
@@ -2386,7 +4863,10 @@ impl RatatuiUi {
         agent.set_messages(data.agent_messages);
         agent.stats = data.stats.to_session_stats();
         let mut tab = SessionTab::new(data.id, data.name.clone(), agent);
-        tab.messages = data.ui_messages;
+        tab.restore_messages(data.ui_messages, data.ui_message_times);
+        tab.summary = data.summary;
+        tab.notes = data.notes;
+        tab.set_env(data.env, HashSet::new());
         tab.cached_stats = data.stats.to_session_stats();
         self.tabs.push(tab);
         self.active_tab = self.tabs.len() - 1;
@@ -2399,11 +4879,9 @@ impl RatatuiUi {
     fn handle_mouse_tab_click(&mut self, x: u16) {
         let mut current_x = 0u16;
         for (i, tab) in self.tabs.iter().enumerate() {
-            let label_width = if tab.processing {
-                tab.name.chars().count() + 4
-            } else {
-                tab.name.chars().count() + 2
-            } as u16;
+            // ⏳ is double-width
+            let label_width =
+                (Self::tab_label(tab).chars().count() + usize::from(tab.processing)) as u16;
             if x >= current_x && x < current_x + label_width {
                 self.active_tab = i;
                 return;
@@ -2411,6 +4889,11 @@ impl RatatuiUi {
             current_x += label_width;
             current_x += 3; // separator " │ "
         }
+        // "  [+]" follows the last label (which has no separator)
+        let plus_x = current_x.saturating_sub(3);
+        if x >= plus_x && x < plus_x + 5 {
+            self.open_new_tab(None);
+        }
     }
 
     pub async fn run(mut self, agent: Agent) -> Result<UiExitAction> {
@@ -2421,14 +4904,7 @@ impl RatatuiUi {
 
         crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture)?;
 
-        let keyboard_enhanced = crossterm::execute!(
-            std::io::stdout(),
-            crossterm::event::PushKeyboardEnhancementFlags(
-                crossterm::event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
-                    | crossterm::event::KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
-            )
-        )
-        .is_ok();
+        let keyboard_enhanced = push_keyboard_enhancement();
 
         let mut terminal = ratatui::init();
         let _guard = TerminalGuard { keyboard_enhanced };
@@ -2439,7 +4915,12 @@ impl RatatuiUi {
             .push(SessionTab::new(id, "Session 1".into(), agent));
 
         loop {
-            self.anim_tick = self.anim_tick.wrapping_add(1);
+            if let Some(path) = self.pending_editor.take() {
+                self.open_in_editor(&mut terminal, keyboard_enhanced, &path);
+            }
+            for tab in &mut self.tabs {
+                tab.stamp_messages();
+            }
             terminal.draw(|f| self.draw_ui(f))?;
 
             // Process events for ALL tabs
@@ -2447,38 +4928,31 @@ impl RatatuiUi {
                 let mut rx_taken = tab.event_rx.take();
                 if let Some(rx) = &mut rx_taken {
                     let mut terminal_reached = false;
-                    while let Ok(evt) = rx.try_recv() {
-                        let is_terminal = matches!(evt, AgentEvent::Done(_) | AgentEvent::Error(_));
-                        tab.handle_agent_event(evt);
-                        if is_terminal {
-                            terminal_reached = true;
-                            break;
+                    loop {
+                        match rx.try_recv() {
+                            Ok(evt) => {
+                                let is_terminal =
+                                    matches!(evt, AgentEvent::Done(_) | AgentEvent::Error(_));
+                                tab.handle_agent_event(evt, &self.config.ui);
+                                if is_terminal {
+                                    terminal_reached = true;
+                                    break;
+                                }
+                            }
+                            // The task ended without Done/Error (it failed or panicked)
+                            Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
+                                terminal_reached = true;
+                                break;
+                            }
+                            Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
                         }
                     }
                     if terminal_reached {
                         if let Some(handle) = tab.agent_handle.take() {
-                            match handle.await {
-                                Ok(Ok(returned_agent)) => {
-                                    tab.cached_stats = returned_agent.stats.clone();
-                                    tab.context_used = returned_agent.estimate_context_tokens();
-                                    tab.context_limit = returned_agent.context_window();
-                                    tab.current_model_id =
-                                        returned_agent.current_model_id().to_string();
-                                    tab.agent = Some(returned_agent);
-                                }
-                                Ok(Err(e)) => {
-                                    tab.messages.push(format!("Error: {}", e));
-                                    tab.pet_state = PetState::Error;
-                                    tab.processing = false;
-                                }
-                                Err(e) => {
-                                    tab.messages.push(format!("Error: task panicked: {}", e));
-                                    tab.pet_state = PetState::Error;
-                                    tab.processing = false;
-                                }
-                            }
+                            let outcome = handle.await;
+                            tab.finish_turn(outcome, &self.config, &self.project_root);
                         }
-                        tab.auto_save();
+                        let _ = tab.auto_save();
                         if !tab.pending_messages.is_empty() {
                             tab.send_next_pending();
                         }
@@ -2493,37 +4967,44 @@ impl RatatuiUi {
                 match event::read()? {
                     Event::Key(key) => {
                         self.idle_ticks = 0;
+                        self.active_mut().last_activity = std::time::Instant::now();
                         self.typing_intensity = self
                             .typing_intensity
                             .saturating_add(TYPING_BOOST_PER_KEY)
                             .min(40);
 
+                        if self.handle_notes_key(key) {
+                            continue;
+                        }
+
                         match key.code {
                             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                if self.config.ui.auto_export_dir.is_some() {
+                                    self.save_all_on_quit();
+                                }
                                 exit_action = UiExitAction::Quit;
                                 break;
                             }
                             // Interrupt: Ctrl+. (period) - stop agent when processing
                             KeyCode::Char('.') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                                 if self.active().processing {
-                                    let tab_idx =
-                                        self.active_tab.min(self.tabs.len().saturating_sub(1));
-                                    let handle = self.tabs[tab_idx].agent_handle.take();
-                                    if let Some(h) = handle {
-                                        h.abort();
-                                        if let Err(e) = self.restore_agent_after_abort(tab_idx) {
-                                            self.tabs[tab_idx]
-                                                .messages
-                                                .push(format!("Error restoring: {}", e));
-                                        } else {
-                                            self.tabs[tab_idx]
-                                                .messages
-                                                .push("  ⏹ 已中断".to_string());
-                                        }
-                                    }
+                                    self.cancel_active_turn();
                                 }
                                 continue;
                             }
+                            // Session picker: answer a pending delete confirmation
+                            _ if self.session_picker.confirm_delete.is_some() => {
+                                if key.code == KeyCode::Char('y') {
+                                    self.confirm_picker_delete();
+                                } else {
+                                    self.session_picker.confirm_delete = None;
+                                }
+                                continue;
+                            }
+                            KeyCode::Char('d') if self.session_picker.visible => {
+                                self.request_picker_delete();
+                                continue;
+                            }
                             // Session picker navigation
                             KeyCode::Up if self.session_picker.visible => {
                                 self.session_picker.move_up();
@@ -2545,8 +5026,8 @@ impl RatatuiUi {
                                 }
                                 continue;
                             }
-                            KeyCode::Esc if self.session_picker.visible => {
-                                self.session_picker.dismiss();
+                            KeyCode::Esc => {
+                                self.handle_esc();
                                 continue;
                             }
                             // Model picker navigation
@@ -2562,46 +5043,91 @@ impl RatatuiUi {
                                 if let Some(m) = self.model_picker.selected_model() {
                                     let model_id = m.id.clone();
                                     self.model_picker.dismiss();
-                                    let config = self.config.clone();
-                                    let tab = self.active_mut();
-                                    if let Some(agent) = tab.agent.as_mut() {
-                                        match agent.switch_model(&model_id, &config) {
-                                            Ok(()) => {
-                                                tab.current_model_id =
-                                                    agent.current_model_id().to_string();
-                                                let display = agent.current_model_display();
-                                                tab.messages.push(format!(
-                                                    "[Switched to model: {}]",
-                                                    display
-                                                ));
-                                            }
-                                            Err(e) => {
-                                                tab.messages.push(format!("Error: {}", e));
-                                            }
-                                        }
-                                    }
+                                    self.switch_active_model(&model_id);
+                                }
+                                continue;
+                            }
+                            // Y/A/N for tool confirmation
+                            KeyCode::Char('y' | 'Y') if self.active().pending_confirm.is_some() => {
+                                let tab = self.active_mut();
+                                tab.pending_confirm = None;
+                                if let Some(command) = tab.pending_run.take() {
+                                    tab.start_run(command);
+                                } else if let Some(tx) = &tab.confirm_tx {
+                                    let _ = tx.send(ConfirmDecision::Once);
+                                }
+                                continue;
+                            }
+                            KeyCode::Char('a' | 'A') if self.active().pending_confirm.is_some() => {
+                                let tab = self.active_mut();
+                                tab.pending_confirm = None;
+                                if let Some(command) = tab.pending_run.take() {
+                                    tab.start_run(command);
+                                    continue;
                                 }
+                                tab.messages
+                                    .push("  ✓ 本会话内相同操作将自动允许".to_string());
+                                if let Some(tx) = &tab.confirm_tx {
+                                    let _ = tx.send(ConfirmDecision::Always);
+                                }
+                                continue;
+                            }
+                            KeyCode::Char('n' | 'N') if self.active().pending_confirm.is_some() => {
+                                let tab = self.active_mut();
+                                tab.pending_confirm = None;
+                                tab.pending_run = None;
+                                tab.messages.push("  ✗ 操作已取消".to_string());
+                                if let Some(tx) = &tab.confirm_tx {
+                                    let _ = tx.send(ConfirmDecision::Deny);
+                                }
+                                continue;
+                            }
+                            // Message select mode: Alt+Up enters, ↑/↓ move, y copies, Esc exits
+                            KeyCode::Up
+                                if key.modifiers.contains(KeyModifiers::ALT)
+                                    && self.active().selected_message.is_none() =>
+                            {
+                                self.active_mut().enter_select_mode();
+                                continue;
+                            }
+                            KeyCode::Up if self.active().selected_message.is_some() => {
+                                self.active_mut().move_selection(-1);
                                 continue;
                             }
-                            KeyCode::Esc if self.model_picker.visible => {
-                                self.model_picker.dismiss();
+                            KeyCode::Down if self.active().selected_message.is_some() => {
+                                self.active_mut().move_selection(1);
                                 continue;
                             }
-                            // Y/N for tool confirmation
-                            KeyCode::Char('y' | 'Y') if self.active().pending_confirm.is_some() => {
+                            KeyCode::Char('y') if self.active().selected_message.is_some() => {
                                 let tab = self.active_mut();
-                                tab.pending_confirm = None;
-                                if let Some(tx) = &tab.confirm_tx {
-                                    let _ = tx.send(true);
+                                let text = tab.selected_message_text().unwrap_or("").to_string();
+                                tab.selected_message = None;
+                                match crate::ui::clipboard::copy_text(&text) {
+                                    Ok(via) => tab.messages.push(format!(
+                                        "[Copied {} chars via {}]",
+                                        text.chars().count(),
+                                        via
+                                    )),
+                                    Err(crate::ui::clipboard::ClipboardError::Unavailable) => {
+                                        tab.messages.push("[clipboard unavailable]".into())
+                                    }
+                                    Err(e) => {
+                                        tab.messages.push(format!("Error: copy failed: {}", e))
+                                    }
                                 }
+                                tab.follow_tail = true;
                                 continue;
                             }
-                            KeyCode::Char('n' | 'N') if self.active().pending_confirm.is_some() => {
+                            // Alt+Backspace on an empty input removes the last queued message
+                            KeyCode::Backspace
+                                if key.modifiers.contains(KeyModifiers::ALT)
+                                    && self.active().input.is_empty()
+                                    && !self.active().pending_messages.is_empty() =>
+                            {
                                 let tab = self.active_mut();
-                                tab.pending_confirm = None;
-                                tab.messages.push("  ✗ 操作已取消".to_string());
-                                if let Some(tx) = &tab.confirm_tx {
-                                    let _ = tx.send(false);
+                                if let Some(msg) = tab.pop_last_queued() {
+                                    let shown: String = msg.chars().take(60).collect();
+                                    tab.messages.push(format!("[Removed queued: {}]", shown));
                                 }
                                 continue;
                             }
@@ -2615,15 +5141,22 @@ impl RatatuiUi {
                                     self.active_tab += 1;
                                 }
                             }
-                            KeyCode::Esc if self.autocomplete.visible => {
-                                self.autocomplete.dismiss();
-                            }
                             KeyCode::Up if self.autocomplete.visible => {
                                 self.autocomplete.move_up();
                             }
                             KeyCode::Down if self.autocomplete.visible => {
                                 self.autocomplete.move_down();
                             }
+                            // Input history: ↑ on an empty input starts recall, ↓ walks back
+                            KeyCode::Up
+                                if self.active().input.is_empty()
+                                    || self.input_history.is_browsing() =>
+                            {
+                                self.recall_history(true);
+                            }
+                            KeyCode::Down if self.input_history.is_browsing() => {
+                                self.recall_history(false);
+                            }
                             KeyCode::Tab if self.autocomplete.visible => {
                                 self.apply_autocomplete_selection();
                             }
@@ -2650,6 +5183,7 @@ impl RatatuiUi {
                                 if self.autocomplete.visible {
                                     self.apply_autocomplete_selection();
                                     let user_input = self.active().input.clone();
+                                    self.input_history.push(&user_input);
                                     let tab = self.active_mut();
                                     tab.input.clear();
                                     tab.cursor_position = 0;
@@ -2665,6 +5199,7 @@ impl RatatuiUi {
 
                                 let input_text = self.active().input.trim().to_string();
                                 if !input_text.is_empty() {
+                                    self.input_history.push(&input_text);
                                     let tab = self.active_mut();
                                     tab.input.clear();
                                     tab.cursor_position = 0;
@@ -2690,9 +5225,10 @@ impl RatatuiUi {
                                         tab.processing = true;
                                         tab.pet_state = PetState::Thinking;
                                         tab.follow_tail = true;
-                                        tab.auto_save();
+                                        let _ = tab.auto_save();
 
                                         if let Some(mut moved_agent) = tab.agent.take() {
+                                            tab.history_snapshot = moved_agent.history().to_vec();
                                             let (evt_tx, evt_rx) =
                                                 tokio::sync::mpsc::unbounded_channel();
                                             let (cfm_tx, mut cfm_rx) =
@@ -2762,7 +5298,7 @@ impl RatatuiUi {
                                     let wrap_width = sess.width.saturating_sub(2) as usize;
                                     let input_rendered_lines =
                                         Self::count_wrapped_lines(&input, wrap_width);
-                                    let input_h = (input_rendered_lines as u16 + 2).max(3).min(10);
+                                    let input_h = (input_rendered_lines as u16 + 2).clamp(3, 10);
                                     let inp_y = sess.y + sess.height - input_h;
                                     let content_x = sess.x + 1;
                                     let content_y = inp_y + 1;
@@ -2816,6 +5352,65 @@ impl RatatuiUi {
                 }
             }
 
+            // Unload idle background tabs; restore the active one if it was unloaded
+            self.unload_idle_tabs(std::time::Instant::now());
+            if !self.tabs.is_empty() {
+                let active = self.active_tab.min(self.tabs.len() - 1);
+                self.ensure_tab_loaded(active);
+            }
+
+            // API health check (startup + every ui.health_check_interval_secs)
+            self.maybe_start_health_check();
+            self.poll_health_check();
+
+            // Poll /rerun tasks
+            for tab in &mut self.tabs {
+                let finished = tab
+                    .rerun_task
+                    .as_ref()
+                    .is_some_and(|(handle, _)| handle.is_finished());
+                if !finished {
+                    continue;
+                }
+                let Some((task, command)) = tab.rerun_task.take() else {
+                    continue;
+                };
+                match task.now_or_never() {
+                    Some(Ok(Ok(output))) => {
+                        tab.messages.push(format!("RERUN:{}\n{}", command, output))
+                    }
+                    Some(Ok(Err(e))) => tab.messages.push(format!("Error: {}", e)),
+                    _ => tab.messages.push("Error: re-run failed".into()),
+                }
+                tab.follow_tail = true;
+            }
+
+            for tab in &mut self.tabs {
+                tab.poll_run_task();
+            }
+
+            // Poll /summarize tasks
+            for tab in &mut self.tabs {
+                let finished = tab
+                    .summary_task
+                    .as_ref()
+                    .is_some_and(|(handle, _)| handle.is_finished());
+                if !finished {
+                    continue;
+                }
+                let Some((task, note_path)) = tab.summary_task.take() else {
+                    continue;
+                };
+                match task.now_or_never() {
+                    Some(Ok(Ok(summary))) if !summary.is_empty() => {
+                        tab.apply_summary(summary, note_path.as_deref());
+                    }
+                    Some(Ok(Err(e))) => tab.messages.push(format!("Error: {}", e)),
+                    _ => tab.messages.push("Error: summary generation failed".into()),
+                }
+                tab.follow_tail = true;
+            }
+
             // Pet state machine for active tab
             self.clamp_active_tab();
             {
@@ -2823,24 +5418,14 @@ impl RatatuiUi {
                 let idle = self.idle_ticks;
                 let active_idx = self.active_tab.min(self.tabs.len().saturating_sub(1));
                 let input_empty = self.tabs[active_idx].input.is_empty();
+                let worried_percent = u64::from(self.config.ui.pet_worried_percent);
                 let tab = &mut self.tabs[active_idx];
-                if !tab.processing {
-                    if ti > TYPING_FAST_THRESHOLD {
-                        tab.pet_state = PetState::TypingFast;
-                    } else if ti > 0 && !input_empty {
-                        tab.pet_state = PetState::Typing;
-                    } else if idle > 300 {
-                        tab.pet_state = PetState::Sleeping;
-                    } else if ((tab.pet_state == PetState::Happy
-                        || tab.pet_state == PetState::Error)
-                        && idle > 50)
-                        || ((tab.pet_state == PetState::Typing
-                            || tab.pet_state == PetState::TypingFast)
-                            && ti == 0)
-                    {
-                        tab.pet_state = PetState::Idle;
-                    }
-                }
+                let worried = worried_percent > 0
+                    && tab.context_limit > 0
+                    && tab.context_used * 100 > tab.context_limit * worried_percent;
+                tab.pet_state = tab
+                    .pet_state
+                    .next(tab.processing, ti, idle, input_empty, worried);
             }
         }
 
@@ -2848,3 +5433,1495 @@ impl RatatuiUi {
         Ok(exit_action)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_tab() -> SessionTab {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let dir = tempfile::tempdir().unwrap();
+        let agent = Agent::create(&config, dir.path()).unwrap();
+        SessionTab::new("tab1".to_string(), "Test".to_string(), agent)
+    }
+
+    #[test]
+    fn test_message_selection_clamped() {
+        let mut tab = test_tab();
+        tab.messages.push("You: first".to_string());
+        tab.messages.push("Assistant: second".to_string());
+
+        // Movement is a no-op outside select mode
+        tab.move_selection(-1);
+        assert_eq!(tab.selected_message, None);
+
+        tab.enter_select_mode();
+        assert_eq!(tab.selected_message, Some(2));
+        assert_eq!(tab.selected_message_text(), Some("second"));
+        tab.move_selection(1);
+        assert_eq!(tab.selected_message, Some(2));
+        tab.move_selection(-1);
+        assert_eq!(tab.selected_message_text(), Some("first"));
+        tab.move_selection(-5);
+        assert_eq!(tab.selected_message, Some(0));
+
+        let lines = RatatuiUi::build_conversation_lines(
+            &tab.messages,
+            None,
+            Some(1),
+            &UiConfig::default(),
+            0,
+        );
+        let marked: Vec<_> = lines
+            .iter()
+            .filter(|l| l.spans.first().is_some_and(|s| s.content == "▶ "))
+            .collect();
+        assert_eq!(marked.len(), 1);
+        assert_eq!(marked[0].spans[1].content, "You: ");
+    }
+
+    #[test]
+    fn test_summary_stored_on_session() {
+        let mut tab = test_tab();
+        assert!(summary_prompt(&tab.messages).is_none());
+        tab.messages.push("You: why is the test flaky?".to_string());
+        tab.messages
+            .push("Assistant: A race on the data dir.".to_string());
+        let prompt = summary_prompt(&tab.messages).unwrap();
+        assert!(
+            prompt.ends_with("User: why is the test flaky?\n\nAssistant: A race on the data dir.")
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("notes.md");
+        tab.summary = Some("old".to_string());
+        tab.apply_summary("- Race on data dir; fixed".to_string(), Some(&note));
+        assert!(std::fs::read_to_string(&note)
+            .unwrap()
+            .contains("- Race on data dir; fixed"));
+
+        let path = dir.path().join("session.json");
+        session::export_session(&tab.to_session_data(), &path).unwrap();
+        let data = session::import_session(&path).unwrap();
+        assert_eq!(data.summary.as_deref(), Some("- Race on data dir; fixed"));
+    }
+
+    #[test]
+    fn test_recover_agent_after_panic() {
+        let mut tab = test_tab();
+        let history = vec![Message::system("sys"), Message::user("earlier")];
+        let mut agent = tab.agent.take().unwrap();
+        agent.set_messages(history.clone());
+        // As in send_next_pending: the agent moves into the task
+        tab.history_snapshot = agent.history().to_vec();
+        tab.processing = true;
+        drop(agent);
+        assert_eq!(tab.to_session_data().agent_messages, history);
+
+        async fn crashing_turn() -> Result<Agent> {
+            panic!("agent crashed")
+        }
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let outcome = rt.block_on(async { tokio::spawn(crashing_turn()).await });
+        assert!(outcome.as_ref().is_err_and(|e| e.is_panic()));
+
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let dir = tempfile::tempdir().unwrap();
+        tab.finish_turn(outcome, &config, dir.path());
+        assert!(!tab.processing);
+        assert!(tab.messages.iter().any(|m| m.contains("task panicked")));
+        assert_eq!(tab.agent.as_ref().unwrap().history(), history.as_slice());
+
+        // The tab accepts new messages again
+        rt.block_on(async {
+            tab.pending_messages.push_back("again".to_string());
+            tab.send_next_pending();
+            tab.agent_handle.take().expect("turn started").abort();
+        });
+    }
+
+    #[test]
+    fn test_esc_cancels_running_turn() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let history = vec![Message::system("sys"), Message::user("earlier")];
+        ui.active_mut()
+            .agent
+            .as_mut()
+            .unwrap()
+            .set_messages(history.clone());
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let _guard = rt.enter();
+        let tab = ui.active_mut();
+        tab.input = "draft".to_string();
+        tab.enqueue_message("long task".to_string());
+        tab.enqueue_message("follow-up".to_string());
+        assert!(tab.processing);
+        assert!(tab.confirm_tx.is_some());
+        assert_eq!(tab.pet_state, PetState::Thinking);
+
+        assert_eq!(ui.esc_action(), EscAction::CancelTurn);
+        ui.handle_esc();
+        let tab = ui.active();
+        assert!(!tab.processing);
+        assert!(tab.agent_handle.is_none());
+        assert!(tab.event_rx.is_none() && tab.confirm_tx.is_none());
+        assert!(tab.pending_messages.is_empty());
+        assert_eq!(tab.pet_state, PetState::Idle);
+        assert_eq!(tab.messages.last().unwrap(), "[Cancelled]");
+        // The cancelled turn's user message never reaches the restored history
+        assert_eq!(tab.agent.as_ref().unwrap().history(), history.as_slice());
+        assert_eq!(tab.input, "draft");
+        assert_eq!(ui.esc_action(), EscAction::ClearInput);
+    }
+
+    #[test]
+    fn test_pet_frame_follows_elapsed_time() {
+        let at = |ms| PetState::Sleeping.current_frame(std::time::Duration::from_millis(ms));
+        let frames = PetState::Sleeping.frames();
+        // Sleeping shows each of its 4 frames for 1000ms, then loops
+        assert_eq!(at(0), frames[0]);
+        assert_eq!(at(999), frames[0]);
+        assert_eq!(at(1000), frames[1]);
+        assert_eq!(at(3500), frames[3]);
+        assert_eq!(at(4000), frames[0]);
+        assert_eq!(
+            PetState::TypingFast.current_frame(std::time::Duration::from_millis(1000)),
+            PetState::TypingFast.frames()[1]
+        );
+    }
+
+    #[test]
+    fn test_pet_worried_under_context_pressure() {
+        // Idle tab, no typing: high context pressure turns Idle (and decayed Happy) worried
+        assert_eq!(
+            PetState::Idle.next(false, 0, 10, true, true),
+            PetState::Worried
+        );
+        assert_eq!(
+            PetState::Happy.next(false, 0, 60, true, true),
+            PetState::Worried
+        );
+        assert_eq!(
+            PetState::Worried.next(false, 0, 10, true, false),
+            PetState::Idle
+        );
+        // Typing, sleeping and processing are unaffected
+        assert_eq!(
+            PetState::Worried.next(false, 5, 0, false, true),
+            PetState::Typing
+        );
+        assert_eq!(
+            PetState::Worried.next(false, 0, 400, true, true),
+            PetState::Sleeping
+        );
+        assert_eq!(
+            PetState::Thinking.next(true, 0, 10, true, true),
+            PetState::Thinking
+        );
+        assert_eq!(
+            PetState::Idle.next(false, 0, 10, true, false),
+            PetState::Idle
+        );
+    }
+
+    #[test]
+    fn test_run_adds_command_output_as_context() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let _guard = rt.enter();
+        ui.handle_command("/run echo hi");
+        assert!(ui.active().pending_confirm.is_none());
+        let (handle, command) = ui.active().run_task.as_ref().expect("run started");
+        assert_eq!(command, "echo hi");
+        while !handle.is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        ui.active_mut().poll_run_task();
+
+        let tab = ui.active();
+        assert!(tab.run_task.is_none());
+        let context = &tab.agent.as_ref().unwrap().history().last().unwrap();
+        assert_eq!(context.role, crate::types::Role::User);
+        assert!(context.content.starts_with("Output of `echo hi`"));
+        assert!(context.content.contains("hi\n```"), "{}", context.content);
+        assert!(tab
+            .messages
+            .iter()
+            .any(|m| m.starts_with("RERUN:echo hi\n")));
+
+        // Dangerous commands wait for confirmation instead of running
+        ui.handle_command("/run rm -rf /tmp/miniclaw-run-test");
+        assert!(ui.active().pending_confirm.is_some());
+        assert!(ui.active().pending_run.is_some());
+        assert!(ui.active().run_task.is_none());
+    }
+
+    #[test]
+    fn test_vertical_cursor_movement_across_newlines() {
+        let input = "hello world\nab\nlonger line";
+        let width = 40;
+        // From "wor|ld" up/down onto the short line clamps to its end ("ab|")
+        let (r, c) = RatatuiUi::cursor_row_col_wrapped(input, 9, width);
+        assert_eq!((r, c), (0, 9));
+        assert_eq!(RatatuiUi::row_col_to_cursor_pos(input, r + 1, c, width), 14);
+        // From "lo|nger" up lands at the same column on "ab" → "ab|"; then up to "he|llo"
+        let (r, c) = RatatuiUi::cursor_row_col_wrapped(input, 17, width);
+        assert_eq!((r, c), (2, 2));
+        assert_eq!(RatatuiUi::row_col_to_cursor_pos(input, r - 1, c, width), 14);
+        assert_eq!(RatatuiUi::row_col_to_cursor_pos(input, 0, c, width), 2);
+
+        // Wrapped rows: "abcdef" at width 4 is "abcd" / "ef"; from "ab|cd" down ends at "ef|"
+        assert_eq!(RatatuiUi::row_col_to_cursor_pos("abcdef", 1, 3, 4), 6);
+        assert_eq!(RatatuiUi::row_col_to_cursor_pos("abcdef\nxy", 1, 3, 4), 6);
+    }
+
+    #[test]
+    fn test_input_wraps_at_word_boundaries_with_consistent_cursor() {
+        assert_eq!(
+            RatatuiUi::manual_wrap("hello wonderful world", 10),
+            "hello \nwonderful \nworld"
+        );
+        // Words wider than the row still break hard
+        assert_eq!(
+            RatatuiUi::manual_wrap("see /very/long/path/name ok", 8),
+            "see \n/very/lo\nng/path/\nname ok"
+        );
+        assert_eq!(RatatuiUi::manual_wrap("a\nb", 0), "a\nb");
+
+        for (input, width) in [
+            ("hello wonderful world", 10),
+            ("see /very/long/path/name ok", 8),
+            ("中文 mixed 文字 text\n\nnext line here", 7),
+        ] {
+            let wrapped = RatatuiUi::manual_wrap(input, width);
+            let rendered: Vec<&str> = wrapped.split('\n').collect();
+            assert_eq!(rendered.len(), RatatuiUi::count_wrapped_lines(input, width));
+            for pos in 0..=input.chars().count() {
+                let (r, c) = RatatuiUi::cursor_row_col_wrapped(input, pos, width);
+                assert!(r < rendered.len(), "{:?} @{}", input, pos);
+                assert!(c <= width, "{:?} @{}", input, pos);
+                assert_eq!(
+                    RatatuiUi::row_col_to_cursor_pos(input, r, c, width),
+                    pos,
+                    "{:?} @{}",
+                    input,
+                    pos
+                );
+            }
+        }
+        // The cursor before a wrapped word sits at the start of its new row
+        assert_eq!(
+            RatatuiUi::cursor_row_col_wrapped("hello wonderful world", 6, 10),
+            (1, 0)
+        );
+    }
+
+    #[test]
+    fn test_quit_auto_exports_markdown() {
+        let mut tab = test_tab();
+        let root = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        config.ui.auto_export_dir = Some("docs/chats".to_string());
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+
+        let mut empty = test_tab();
+        empty.id = "empty".to_string();
+        tab.name = "Fix flaky test".to_string();
+        tab.agent.as_mut().unwrap().set_messages(vec![
+            Message::system("sys"),
+            Message::user("why is it flaky?"),
+            Message::assistant("A race on the data dir."),
+        ]);
+        ui.tabs.push(tab);
+        ui.tabs.push(empty);
+
+        assert!(matches!(
+            ui.handle_command("/quit"),
+            Some(UiExitAction::Quit)
+        ));
+        let files: Vec<_> = std::fs::read_dir(root.path().join("docs/chats"))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        // The empty tab is skipped
+        assert_eq!(files.len(), 1);
+        let name = files[0].file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.ends_with("-fix-flaky-test-tab1.md"), "{}", name);
+        let md = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(md.starts_with("# Fix flaky test"));
+        assert!(md.contains("## You\n\nwhy is it flaky?"));
+        assert!(md.contains("## Assistant\n\nA race on the data dir."));
+        assert!(!md.contains("sys"));
+    }
+
+    #[test]
+    fn test_esc_precedence() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        let mut tab = test_tab();
+        tab.input = "draft".to_string();
+        tab.cursor_position = 5;
+        ui.tabs.push(tab);
+
+        ui.session_picker.visible = true;
+        ui.model_picker.visible = true;
+        ui.autocomplete.visible = true;
+        ui.active_mut().selected_message = Some(0);
+        assert_eq!(ui.esc_action(), EscAction::DismissSessionPicker);
+        ui.handle_esc();
+        assert_eq!(ui.esc_action(), EscAction::DismissModelPicker);
+        ui.handle_esc();
+        assert_eq!(ui.esc_action(), EscAction::ExitSelectMode);
+        ui.handle_esc();
+        assert_eq!(ui.esc_action(), EscAction::DismissAutocomplete);
+        ui.handle_esc();
+        // Popups never cleared the draft
+        assert_eq!(ui.active().input, "draft");
+
+        ui.active_mut().pending_confirm = Some("rm -rf build".to_string());
+        assert_eq!(ui.esc_action(), EscAction::Nothing);
+        ui.active_mut().pending_confirm = None;
+
+        assert_eq!(ui.esc_action(), EscAction::ClearInput);
+        ui.handle_esc();
+        assert!(ui.active().input.is_empty());
+        assert_eq!(ui.active().cursor_position, 0);
+    }
+
+    #[test]
+    fn test_notes_saved_but_not_sent() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+
+        ui.handle_command("/notes");
+        assert!(ui.active().notes_open);
+        for c in "check the retry path".chars() {
+            assert!(ui.handle_notes_key(KeyEvent::from(KeyCode::Char(c))));
+        }
+        ui.handle_notes_key(KeyEvent::from(KeyCode::Enter));
+        ui.handle_notes_key(KeyEvent::from(KeyCode::Char('x')));
+        ui.handle_notes_key(KeyEvent::from(KeyCode::Backspace));
+        // Chat input is untouched; Ctrl+C is left to the normal handler
+        assert!(ui.active().input.is_empty());
+        assert!(!ui.handle_notes_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        assert_eq!(ui.esc_action(), EscAction::CloseNotes);
+        ui.handle_notes_key(KeyEvent::from(KeyCode::Esc));
+        assert!(!ui.active().notes_open);
+
+        let data = ui.active().to_session_data();
+        assert_eq!(data.notes, "check the retry path\n");
+        session::save_session(&data).unwrap();
+        ui.load_session_as_tab(&data.id).unwrap();
+        let loaded = ui.active();
+        assert_eq!(loaded.notes, "check the retry path\n");
+        let history = loaded.agent.as_ref().unwrap().history();
+        assert!(history.iter().all(|m| !m.content.contains("retry path")));
+    }
+
+    #[test]
+    fn test_delete_session_guards_open_tabs_and_refreshes_picker() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let open = ui.active().to_session_data();
+        session::save_session(&open).unwrap();
+        let mut stale = open.clone();
+        stale.id = session::generate_session_id();
+        stale.name = "stale".to_string();
+        session::save_session(&stale).unwrap();
+        let mut other = open.clone();
+        other.id = session::generate_session_id();
+        session::save_session(&other).unwrap();
+
+        // The session open in a tab is kept
+        ui.handle_command(&format!("/delete {}", open.id));
+        assert!(ui.active().messages.last().unwrap().contains("open in tab"));
+        assert!(session::load_session(&open.id).is_ok());
+
+        ui.handle_command(&format!("/delete {}", other.id));
+        assert!(session::load_session(&other.id).is_err());
+        ui.handle_command(&format!("/delete {}", other.id));
+        assert!(ui.active().messages.last().unwrap().contains("not found"));
+
+        // Picker: 'd' on the open session is refused, on another asks first
+        ui.session_picker.open();
+        let index_of =
+            |ui: &RatatuiUi, id: &str| ui.session_picker.sessions.iter().position(|s| s.id == id);
+        ui.session_picker.selected = index_of(&ui, &open.id).unwrap();
+        ui.request_picker_delete();
+        assert!(ui.session_picker.confirm_delete.is_none());
+        ui.session_picker.selected = index_of(&ui, &stale.id).unwrap();
+        ui.request_picker_delete();
+        assert_eq!(
+            ui.session_picker.confirm_delete.as_deref(),
+            Some(stale.id.as_str())
+        );
+        assert!(session::load_session(&stale.id).is_ok());
+        ui.confirm_picker_delete();
+        assert!(session::load_session(&stale.id).is_err());
+        assert!(ui.session_picker.visible);
+        assert!(index_of(&ui, &stale.id).is_none());
+        assert!(index_of(&ui, &open.id).is_some());
+    }
+
+    #[test]
+    fn test_session_env_reaches_bash_and_round_trips() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        config
+            .tools
+            .bash_env
+            .insert("STAGE".into(), "global".into());
+        config.tools.bash_env.insert("REGION".into(), "eu".into());
+        let root = tempfile::tempdir().unwrap();
+        let agent = Agent::create(&config, root.path()).unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs
+            .push(SessionTab::new("env1".into(), "Env".into(), agent));
+
+        ui.handle_command("/env set STAGE=session");
+        ui.handle_command("/env set GITHUB_TOKEN=abc123");
+        ui.handle_command("/env set bad-key=1");
+        assert!(ui.active().messages.last().unwrap().starts_with("Usage:"));
+        let run = |tab: &SessionTab| {
+            let params = serde_json::json!({ "command": "echo $STAGE $REGION $GITHUB_TOKEN" });
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(crate::tools::Tool::execute(&tab.bash_tool(), params))
+                .unwrap()
+        };
+        // Session values override tools.bash_env, which still applies
+        assert_eq!(run(ui.active()).trim(), "session eu abc123");
+
+        // The secret-looking value stays in memory only
+        let data = ui.active().to_session_data();
+        assert_eq!(data.env.get("STAGE").map(String::as_str), Some("session"));
+        assert!(!data.env.contains_key("GITHUB_TOKEN"));
+        session::save_session(&data).unwrap();
+        ui.load_session_as_tab(&data.id).unwrap();
+        assert_eq!(run(ui.active()).trim(), "session eu");
+
+        ui.handle_command("/env unset STAGE");
+        assert_eq!(run(ui.active()).trim(), "global eu");
+    }
+
+    #[test]
+    fn test_snippet_inserted_into_input() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let snippets = crate::paths::snippets_dir().unwrap();
+        std::fs::create_dir_all(&snippets).unwrap();
+        std::fs::write(
+            snippets.join("review.md"),
+            "Review this diff for:\n- bugs\n",
+        )
+        .unwrap();
+
+        ui.autocomplete.update_filter("/snippet rv");
+        assert_eq!(
+            ui.autocomplete.selected_command().as_deref(),
+            Some("/snippet review")
+        );
+
+        ui.active_mut().input = "Please: ".into();
+        ui.active_mut().cursor_position = 8;
+        ui.handle_command("/snippet review");
+        let tab = ui.active();
+        assert_eq!(tab.input, "Please: Review this diff for:\n- bugs\n");
+        assert_eq!(tab.cursor_position, tab.input.chars().count());
+
+        ui.handle_command("/snippet");
+        assert!(ui.active().messages.iter().any(|m| m == "  review"));
+        ui.handle_command("/snippet nope");
+        assert!(ui.active().messages.last().unwrap().contains("not found"));
+    }
+
+    #[test]
+    fn test_title_command_schedules_title_task() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        ui.tabs.push(test_tab());
+        ui.active_tab = 1;
+
+        ui.handle_command("/title");
+        assert!(ui.active().title_task.is_none());
+        assert!(ui
+            .active()
+            .messages
+            .last()
+            .unwrap()
+            .contains("Nothing to title"));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let _guard = rt.enter();
+        ui.active_mut()
+            .messages
+            .push("You: fix the flaky test".into());
+        ui.handle_command("/title");
+        assert!(ui.tabs[1].title_task.is_some());
+        assert!(ui.tabs[0].title_task.is_none());
+        // A second request while one is pending is not stacked
+        ui.handle_command("/title");
+        assert!(ui
+            .active()
+            .messages
+            .last()
+            .unwrap()
+            .contains("already in progress"));
+
+        // Setting the title manually drops the pending generation
+        ui.handle_command("/title Flaky test");
+        assert_eq!(ui.active().name, "Flaky test");
+        assert!(ui.active().title_task.is_none());
+    }
+
+    #[test]
+    fn test_search_jumps_highlights_and_cycles() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let tab = ui.active_mut();
+        tab.messages = (0..40).map(|i| format!("filler {}", i)).collect();
+        tab.messages[5] = "first NEEDLE here".into();
+        tab.messages[30] = "second needle".into();
+
+        let draw = |ui: &mut RatatuiUi| {
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(40, 12)).unwrap();
+            let ui_config = ui.config.ui.clone();
+            terminal
+                .draw(|f| {
+                    RatatuiUi::render_conversation(
+                        ui.active_mut(),
+                        true,
+                        false,
+                        &ui_config,
+                        f,
+                        f.area(),
+                    )
+                })
+                .unwrap();
+            terminal.backend().buffer().clone()
+        };
+
+        ui.handle_command("/search needle");
+        let buffer = draw(&mut ui);
+        // Each plain message renders as its line plus a blank line
+        assert_eq!(ui.active().scroll_offset, 10);
+        assert!(!ui.active().follow_tail);
+        assert_eq!(ui.active().search.as_ref().unwrap().total, 2);
+        // Top visible row is the match, with only the match highlighted
+        assert_eq!(buffer[(7, 1)].symbol(), "N");
+        assert_eq!(buffer[(7, 1)].bg, Color::Yellow);
+        assert_ne!(buffer[(1, 1)].bg, Color::Yellow);
+
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        ui.handle_key_event(ctrl('n'));
+        draw(&mut ui);
+        assert_eq!(ui.active().scroll_offset, 60);
+        ui.handle_key_event(ctrl('p'));
+        draw(&mut ui);
+        assert_eq!(ui.active().scroll_offset, 10);
+
+        assert_eq!(ui.esc_action(), EscAction::ClearSearch);
+        ui.handle_esc();
+        assert!(ui.active().search.is_none());
+
+        // A plain letter starts a new message and clears the search
+        ui.handle_command("/search filler");
+        ui.handle_key_event(KeyEvent::from(KeyCode::Char('n')));
+        assert!(ui.active().search.is_none());
+        assert_eq!(ui.active().input, "n");
+    }
+
+    #[test]
+    fn test_title_source_uses_latest_turns() {
+        let mut messages: Vec<String> = (1..=8)
+            .flat_map(|i| [format!("You: q{}", i), format!("Assistant: a{}", i)])
+            .collect();
+        messages.push("[Session saved]".to_string());
+        assert_eq!(title_source(&messages), "q6\na6\nq7\na7\nq8\na8");
+        assert_eq!(title_source(&["[Session saved]".to_string()]), "");
+    }
+
+    #[test]
+    fn test_resolve_editor() {
+        let s = |v: &str| Some(v.to_string());
+        assert_eq!(resolve_editor(s("nvim"), s("vi")).unwrap(), vec!["nvim"]);
+        // Blank or unset $VISUAL falls back to $EDITOR
+        assert_eq!(resolve_editor(s("  "), s("vi")).unwrap(), vec!["vi"]);
+        assert_eq!(resolve_editor(None, s("vi")).unwrap(), vec!["vi"]);
+        assert_eq!(
+            resolve_editor(s("code --wait"), None).unwrap(),
+            vec!["code", "--wait"]
+        );
+        assert!(resolve_editor(None, s("")).is_none());
+        assert!(resolve_editor(None, None).is_none());
+    }
+
+    #[test]
+    fn test_sendto_queues_last_user_message() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let mut other = test_tab();
+        other.name = "Sonnet".to_string();
+        other.processing = true;
+        ui.tabs.push(other);
+
+        ui.handle_command("/sendto 2");
+        assert_eq!(
+            ui.active().messages.last().unwrap(),
+            "[No message to send yet]"
+        );
+
+        let tab = ui.active_mut();
+        tab.messages.push("You: first question".to_string());
+        tab.messages.push("Assistant: answer".to_string());
+        tab.messages.push("You: why is the build slow?".to_string());
+        tab.messages.push("Assistant: because".to_string());
+        assert_eq!(tab.last_user_message(), Some("why is the build slow?"));
+
+        ui.handle_command("/sendto sonnet");
+        assert_eq!(
+            ui.tabs[1].pending_messages,
+            VecDeque::from(["why is the build slow?".to_string()])
+        );
+        assert_eq!(
+            ui.active().messages.last().unwrap(),
+            "[Queued for tab 2 (Sonnet)]"
+        );
+
+        ui.handle_command("/sendto 1");
+        assert_eq!(
+            ui.active().messages.last().unwrap(),
+            "[That is the current tab]"
+        );
+        ui.handle_command("/sendto 3");
+        assert_eq!(
+            ui.active().messages.last().unwrap(),
+            "[No tab named or numbered '3']"
+        );
+        assert_eq!(ui.tabs[1].pending_messages.len(), 1);
+    }
+
+    #[test]
+    fn test_compare_opens_tab_per_model() {
+        let toml = r#"
+[llm]
+provider = "openai_compatible"
+model = "qwen-plus"
+api_key = "test-key"
+api_base = "http://127.0.0.1:9"
+max_tokens = 4096
+
+[[llm.models]]
+id = "qwen-plus"
+model = "qwen-plus"
+
+[[llm.models]]
+id = "qwen-turbo"
+name = "Qwen Turbo"
+model = "qwen-turbo"
+
+[model_aliases]
+fast = "qwen-turbo"
+
+[agent]
+max_iterations = 20
+system_prompt = ""
+
+[tools]
+enabled = []
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+
+        ui.handle_command("/compare qwen-plus");
+        assert!(ui.active().messages.last().unwrap().starts_with("Usage:"));
+        ui.handle_command("/compare qwen-plus nope hi");
+        assert_eq!(ui.tabs.len(), 1);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            ui.handle_command("/compare qwen-plus fast  Explain   lifetimes");
+        });
+        assert_eq!(ui.tabs.len(), 3);
+        assert_eq!(ui.active_tab, 1);
+        let models: Vec<&str> = ui.tabs[1..]
+            .iter()
+            .map(|t| t.current_model_id.as_str())
+            .collect();
+        assert_eq!(models, ["qwen-plus", "qwen-turbo"]);
+        assert_eq!(ui.tabs[2].name, "Qwen Turbo");
+        for tab in &ui.tabs[1..] {
+            assert_eq!(
+                tab.messages[tab.messages.len() - 2],
+                "[Comparing qwen-plus (tab 2) and fast (tab 3)]"
+            );
+            assert_eq!(tab.messages.last().unwrap(), "You: Explain   lifetimes");
+            assert!(tab.processing);
+        }
+    }
+
+    #[test]
+    fn test_idle_tabs_unload_and_restore() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        config.ui.unload_idle_tabs_after_mins = 10;
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let mut background = test_tab();
+        background.id = "idle-unload-test".to_string();
+        background.messages.push("You: remember me".to_string());
+        background.messages.push("Assistant: I will".to_string());
+        if let Some(agent) = background.agent.as_mut() {
+            agent.set_messages(vec![Message::user("remember me")]);
+        }
+        ui.tabs.push(background);
+
+        let now = std::time::Instant::now();
+        let later = now + std::time::Duration::from_secs(11 * 60);
+        ui.unload_idle_tabs(now + std::time::Duration::from_secs(5 * 60));
+        assert!(!ui.tabs[1].unloaded);
+
+        // Busy tabs are never unloaded
+        ui.tabs[1].pending_messages.push_back("queued".to_string());
+        ui.unload_idle_tabs(later);
+        assert!(!ui.tabs[1].unloaded);
+        ui.tabs[1].pending_messages.clear();
+
+        ui.unload_idle_tabs(later);
+        assert!(!ui.tabs[0].unloaded, "the active tab stays loaded");
+        let tab = &ui.tabs[1];
+        assert!(tab.unloaded && tab.agent.is_none());
+        assert_eq!(tab.messages, vec![UNLOADED_TAB_MESSAGE.to_string()]);
+        assert_eq!(RatatuiUi::tab_label(tab), " Test (unloaded) ");
+
+        ui.ensure_tab_loaded(1);
+        let tab = &ui.tabs[1];
+        assert!(!tab.unloaded);
+        assert_eq!(tab.id, "idle-unload-test");
+        assert!(tab.messages.ends_with(&[
+            "You: remember me".to_string(),
+            "Assistant: I will".to_string()
+        ]));
+        assert_eq!(tab.agent.as_ref().unwrap().history().len(), 1);
+    }
+
+    #[test]
+    fn test_idle_tab_stays_loaded_when_save_fails() {
+        let mut config = AppConfig::default();
+        config.ui.unload_idle_tabs_after_mins = 10;
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let mut background = test_tab();
+        // The session file would go in a directory that doesn't exist
+        background.id = "no-such-dir/unsaveable".to_string();
+        background.messages.push("You: remember me".to_string());
+        ui.tabs.push(background);
+
+        let later = std::time::Instant::now() + std::time::Duration::from_secs(11 * 60);
+        ui.unload_idle_tabs(later);
+        let tab = &ui.tabs[1];
+        assert!(!tab.unloaded && tab.agent.is_some());
+        assert!(tab.messages.iter().any(|m| m == "You: remember me"));
+        assert!(tab
+            .messages
+            .last()
+            .unwrap()
+            .starts_with("[Could not save idle tab"));
+        assert!(!tab.is_idle(later, std::time::Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_max_tabs_refuses_new_tabs() {
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        config.ui.max_tabs = 2;
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+
+        ui.handle_command("/new");
+        assert_eq!(ui.tabs.len(), 2);
+        ui.handle_command("/new third");
+        assert_eq!(ui.tabs.len(), 2);
+        assert_eq!(
+            ui.active().messages.last().unwrap(),
+            "[Maximum 2 tabs reached]"
+        );
+        ui.handle_command("/load");
+        assert!(!ui.session_picker.visible);
+        assert_eq!(ui.tabs.len(), 2);
+    }
+
+    #[test]
+    fn test_last_bash_command_tracking() {
+        let mut tab = test_tab();
+        let ui = UiConfig::default();
+        let start = |name: &str, args: &str| AgentEvent::ToolStart {
+            name: name.to_string(),
+            arguments: args.to_string(),
+        };
+        assert_eq!(tab.last_bash_command(), None);
+        tab.handle_agent_event(start("bash", r#"{"command":"cargo build"}"#), &ui);
+        tab.handle_agent_event(start("read_file", r#"{"path":"src/main.rs"}"#), &ui);
+        tab.handle_agent_event(
+            start("bash", r#"{"command":"cargo test -- agent","timeout":300}"#),
+            &ui,
+        );
+        tab.handle_agent_event(
+            start("edit", r#"{"path":"a","old_text":"x","new_text":"y"}"#),
+            &ui,
+        );
+        assert_eq!(
+            tab.last_bash_command().as_deref(),
+            Some("cargo test -- agent")
+        );
+        // The full arguments (e.g. timeout) are kept for the re-run
+        assert!(tab
+            .last_bash_args
+            .as_deref()
+            .unwrap()
+            .contains("\"timeout\":300"));
+    }
+
+    #[test]
+    fn test_replay_queues_user_turns_in_new_tab() {
+        let toml = r#"
+[llm]
+model = "qwen-plus"
+api_key = "test-key"
+api_base = "http://127.0.0.1:9"
+
+[[llm.models]]
+id = "qwen-plus"
+model = "qwen-plus"
+
+[[llm.models]]
+id = "qwen-turbo"
+model = "qwen-turbo"
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+
+        let id = session::generate_session_id();
+        session::save_session(&session::SessionData {
+            id: id.clone(),
+            name: "bug repro".to_string(),
+            created_at: session::now_timestamp(),
+            agent_messages: vec![
+                Message::system("prompt"),
+                Message::user("first"),
+                Message::assistant("reply"),
+                Message::user("second"),
+                Message::user("third"),
+            ],
+            ui_messages: vec![],
+            ui_message_times: vec![],
+            stats: session::SessionStatsData::default(),
+            current_model_id: "qwen-plus".to_string(),
+            summary: None,
+            notes: String::new(),
+            env: Default::default(),
+        })
+        .unwrap();
+
+        ui.handle_command("/replay");
+        assert!(ui.active().messages.last().unwrap().starts_with("Usage:"));
+        ui.handle_command(&format!("/replay {} nope", id));
+        assert_eq!(ui.tabs.len(), 1);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            ui.handle_command(&format!("/replay {} qwen-turbo", id));
+        });
+        assert_eq!(ui.tabs.len(), 2);
+        assert_eq!(ui.active_tab, 1);
+        let tab = ui.active();
+        assert_eq!(tab.name, "replay: bug repro");
+        assert_eq!(tab.current_model_id, "qwen-turbo");
+        assert_ne!(tab.id, id);
+        // The first turn is sent right away, the rest wait their turn in order
+        assert_eq!(tab.messages.last().unwrap(), "You: first");
+        assert_eq!(tab.pending_messages, ["second", "third"]);
+    }
+
+    #[test]
+    fn test_verbose_cycles_tool_detail() {
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(AppConfig::default(), root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let edit = r#"{"path":"a.rs","old_text":"x","new_text":"y"}"#;
+        let run_edit = |ui: &mut RatatuiUi| {
+            let config = ui.config.ui.clone();
+            let tab = ui.active_mut();
+            tab.messages.clear();
+            tab.handle_agent_event(
+                AgentEvent::ToolStart {
+                    name: "edit".to_string(),
+                    arguments: edit.to_string(),
+                },
+                &config,
+            );
+            tab.handle_agent_event(
+                AgentEvent::ToolEnd {
+                    name: "edit".to_string(),
+                    arguments: edit.to_string(),
+                    success: true,
+                    result: "Replaced 1 occurrence".to_string(),
+                    metadata: serde_json::Value::Null,
+                },
+                &config,
+            );
+            tab.messages.clone()
+        };
+
+        // Default config: status only
+        let minimal = run_edit(&mut ui);
+        assert_eq!(minimal.len(), 1);
+
+        ui.handle_command("/verbose");
+        assert_eq!(
+            ui.active().messages.last().unwrap(),
+            "[Tool status detail: args (status + arguments)]"
+        );
+        ui.active_mut().handle_agent_event(
+            AgentEvent::ToolStart {
+                name: "edit".to_string(),
+                arguments: edit.to_string(),
+            },
+            &UiConfig::default(),
+        );
+        assert!(ui
+            .active()
+            .messages
+            .last()
+            .unwrap()
+            .ends_with(r#""x" → "y" ..."#));
+
+        ui.handle_command("/verbose");
+        let full = run_edit(&mut ui);
+        assert!(full.len() > 1);
+        assert!(full.iter().any(|l| l.contains("Replaced 1 occurrence")));
+
+        ui.handle_command("/verbose");
+        assert_eq!(ui.active().tool_verbosity, Some(ToolVerbosity::Minimal));
+        ui.handle_command("/verbose full");
+        assert_eq!(ui.active().tool_verbosity, Some(ToolVerbosity::Full));
+        ui.handle_command("/verbose loud");
+        assert!(ui.active().messages.last().unwrap().starts_with("Usage:"));
+    }
+
+    #[test]
+    fn test_pending_queue_helpers() {
+        let mut tab = test_tab();
+        assert_eq!(tab.queue_lines(), vec!["[No queued messages]"]);
+        tab.pending_messages.push_back("first".to_string());
+        tab.pending_messages
+            .push_back("second\nwith more".to_string());
+        tab.pending_messages.push_back("third".to_string());
+        assert_eq!(
+            tab.queue_lines(),
+            vec!["[3 queued]", "  1. first", "  2. second…", "  3. third"]
+        );
+
+        assert_eq!(tab.pop_last_queued().as_deref(), Some("third"));
+        assert_eq!(tab.pending_messages.len(), 2);
+        assert_eq!(tab.clear_queue(), 2);
+        assert!(tab.pending_messages.is_empty());
+        assert_eq!(tab.pop_last_queued(), None);
+    }
+
+    #[test]
+    fn test_model_switch_requires_api_key() {
+        let toml = r#"
+[llm]
+model = "qwen-plus"
+api_key = "test-key"
+
+[[llm.models]]
+id = "qwen-plus"
+model = "qwen-plus"
+
+[[llm.models]]
+id = "keyless"
+model = "keyless"
+api_key_env = "MINICLAW_TEST_UNSET_KEY"
+
+[[llm.models]]
+id = "qwen-turbo"
+model = "qwen-turbo"
+"#;
+        let config: AppConfig = toml::from_str(toml).unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+
+        ui.handle_command("/model");
+        assert!(ui.model_picker.visible);
+        assert_eq!(ui.model_picker.models.len(), 3);
+        ui.model_picker.move_down();
+        let picked = ui.model_picker.selected_model().unwrap().id.clone();
+        ui.model_picker.dismiss();
+        ui.switch_active_model(&picked);
+        assert_eq!(picked, "keyless");
+        let last = ui.active().messages.last().unwrap();
+        assert!(last.starts_with("[Cannot switch to keyless: no API key."));
+        assert!(last.contains("MINICLAW_TEST_UNSET_KEY"));
+        assert_ne!(
+            ui.active().agent.as_ref().unwrap().current_model_id(),
+            "keyless"
+        );
+
+        ui.handle_command("/model qwen-turbo");
+        assert_eq!(ui.active().current_model_id, "qwen-turbo");
+        assert_eq!(
+            ui.active().messages.last().unwrap(),
+            "[Switched to model: qwen-turbo]"
+        );
+
+        ui.handle_command("/model info keyless");
+        let last = ui.active().messages.last().unwrap();
+        assert!(last.starts_with("[Model info: keyless]"));
+        assert!(last.contains("MINICLAW_TEST_UNSET_KEY (not set)"));
+    }
+
+    #[test]
+    fn test_content_rect_capped_on_wide_area() {
+        let area = Rect::new(0, 3, 200, 40);
+        let mut ui = UiConfig::default();
+        assert_eq!(content_rect(area, &ui), area);
+
+        ui.max_content_width = 80;
+        let rect = content_rect(area, &ui);
+        // Text width (inside the borders) is capped; the box is centered
+        assert_eq!(rect.width - 2, 80);
+        assert_eq!((rect.x, rect.y, rect.height), (59, 3, 40));
+
+        ui.content_align = ContentAlign::Left;
+        assert_eq!(content_rect(area, &ui).x, 0);
+        // Narrower panes are left alone
+        assert_eq!(content_rect(Rect::new(10, 0, 60, 20), &ui).width, 60);
+    }
+
+    #[test]
+    fn test_tab_layout_selection() {
+        let single = |layout, width, tabs| RatatuiUi::use_single_layout(layout, width, tabs);
+        // One tab is always full-width
+        assert!(single(TabLayout::Split, 200, 1));
+        assert!(single(TabLayout::Single, 300, 3));
+        assert!(!single(TabLayout::Split, 80, 4));
+        // Auto splits only while each column stays at least MIN_SPLIT_COLUMN_WIDTH wide
+        assert!(!single(TabLayout::Auto, 180, 3));
+        assert!(single(TabLayout::Auto, 179, 3));
+        assert!(single(TabLayout::Auto, 100, 2));
+    }
+
+    #[test]
+    fn test_long_single_line_truncated() {
+        let blob = "A".repeat(5000);
+        let messages = vec![format!("Assistant: {}", blob), "short line".to_string()];
+        let lines =
+            RatatuiUi::build_conversation_lines(&messages, None, None, &UiConfig::default(), 0);
+        let rendered: Vec<String> = lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        let long = rendered.iter().find(|l| l.contains("AAAA")).unwrap();
+        assert!(long.ends_with(&format!(
+            " … ({} chars truncated)",
+            5000 - MAX_DISPLAY_LINE_CHARS
+        )));
+        assert!(long.chars().count() < 1100);
+        assert!(rendered.iter().any(|l| l == "short line"));
+    }
+
+    #[test]
+    fn test_render_window_hides_older_messages() {
+        let messages: Vec<String> = (0..10).map(|i| format!("note {}", i)).collect();
+        let hidden = hidden_message_count(messages.len(), 3, None);
+        assert_eq!(hidden, 7);
+        let lines = RatatuiUi::build_conversation_lines(
+            &messages,
+            None,
+            None,
+            &UiConfig::default(),
+            hidden,
+        );
+        let rendered: Vec<String> = lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .filter(|l: &String| !l.is_empty())
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![HIDDEN_MESSAGES_MARKER, "note 7", "note 8", "note 9"]
+        );
+
+        // Short conversations, no window, or selecting a hidden message render everything
+        assert_eq!(hidden_message_count(2, 3, None), 0);
+        assert_eq!(hidden_message_count(10, 0, None), 0);
+        assert_eq!(hidden_message_count(10, 3, Some(2)), 0);
+        assert_eq!(hidden_message_count(10, 3, Some(8)), 7);
+
+        let mut tab = test_tab();
+        tab.messages = messages;
+        let root = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("test-key".to_string());
+        config.ui.render_window = 3;
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(tab);
+        let draw = |ui: &mut RatatuiUi| {
+            let mut terminal =
+                ratatui::Terminal::new(ratatui::backend::TestBackend::new(60, 30)).unwrap();
+            let ui_config = ui.config.ui.clone();
+            terminal
+                .draw(|f| {
+                    RatatuiUi::render_conversation(
+                        ui.active_mut(),
+                        true,
+                        false,
+                        &ui_config,
+                        f,
+                        f.area(),
+                    )
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            (0..buffer.area.height)
+                .map(|y| {
+                    (0..buffer.area.width)
+                        .map(|x| buffer[(x, y)].symbol())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let screen = draw(&mut ui);
+        assert!(screen.contains("older messages hidden"), "{}", screen);
+        assert!(!screen.contains("note 0"));
+
+        ui.handle_command("/all");
+        assert!(ui.active().show_all);
+        let screen = draw(&mut ui);
+        assert!(!screen.contains("older messages hidden"), "{}", screen);
+        assert!(screen.contains("note 0"));
+    }
+
+    #[test]
+    fn test_health_check_updates_header_status() {
+        let mut config = AppConfig::default();
+        config.llm.provider = "anthropic".to_string();
+        config.llm.api_key = Some("sk-ant".to_string());
+        // Nothing listens on the discard port: the probe fails fast
+        config.llm.api_base = Some("http://127.0.0.1:9".to_string());
+        config.ui.health_check_interval_secs = 60;
+        let root = tempfile::tempdir().unwrap();
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            ui.maybe_start_health_check();
+            while !ui.health_task.as_ref().unwrap().is_finished() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        });
+        ui.poll_health_check();
+        assert_eq!(ui.api_status, ApiStatus::Offline);
+        assert!(ui.health_task.is_none());
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(200, 8)).unwrap();
+        terminal.draw(|f| ui.render_header(f, f.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("API: offline"), "{}", text);
+    }
+
+    #[test]
+    fn test_whoami_summary_shows_host_but_not_key() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("CLAUDE.md"), "Use tabs.\n").unwrap();
+        let mut config = AppConfig::default();
+        config.llm.api_key = Some("sk-very-secret".to_string());
+        config.llm.api_base = Some("https://llm.example.com:8443/v1".to_string());
+        let agent = Agent::create(&config, root.path()).unwrap();
+        let config_path = root.path().join("config.toml");
+
+        let lines = whoami_summary(&agent, &config, root.path(), &config_path);
+        assert_eq!(lines[0], "[Environment]");
+        assert_eq!(lines[1], format!("  project:    {}", root.path().display()));
+        assert!(lines[2].ends_with(&format!("({})", agent.current_model_id())));
+        assert_eq!(lines[3], "  api base:   llm.example.com:8443");
+        assert_eq!(lines[4], format!("  config:     {}", config_path.display()));
+        assert_eq!(lines[5], "  rule files: 1");
+        assert_eq!(lines[6], format!("  tools:      {}", agent.tool_count()));
+        let all = lines.join("\n");
+        assert!(!all.contains("sk-very-secret"));
+        assert!(!all.contains("/v1"));
+
+        // Each line is its own message, so it renders as its own row
+        let mut ui = RatatuiUi::new(config, root.path().to_path_buf());
+        ui.tabs.push(test_tab());
+        let before = ui.active().messages.len();
+        ui.handle_command("/whoami");
+        assert_eq!(ui.active().messages.len() - before, lines.len());
+        let rendered: Vec<String> = RatatuiUi::build_conversation_lines(
+            &ui.active().messages,
+            None,
+            None,
+            &UiConfig::default(),
+            0,
+        )
+        .iter()
+        .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+        assert!(
+            rendered.iter().any(|l| l == "[Environment]"),
+            "{:?}",
+            rendered
+        );
+        assert!(
+            rendered.iter().any(|l| l == "  rule files: 1"),
+            "{:?}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn test_configured_conversation_labels() {
+        let ui = UiConfig {
+            prompt_user: Some("me › ".to_string()),
+            prompt_assistant: Some("claw ›".to_string()),
+            ..UiConfig::default()
+        };
+        let messages = vec!["You: hi".to_string(), "Assistant: hello".to_string()];
+        let lines = RatatuiUi::build_conversation_lines(&messages, None, None, &ui, 0);
+        assert_eq!(lines[0].spans[0].content, "me › ");
+        assert_eq!(lines[0].spans[1].content, "hi");
+        assert!(lines
+            .iter()
+            .any(|l| l.spans.first().is_some_and(|s| s.content == "claw ›")));
+
+        let defaults =
+            RatatuiUi::build_conversation_lines(&messages, None, None, &UiConfig::default(), 0);
+        assert_eq!(defaults[0].spans[0].content, "You: ");
+    }
+
+    #[test]
+    fn test_stream_to_writes_deltas_in_order() {
+        let mut tab = test_tab();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.md");
+        std::fs::write(&path, "existing\n").unwrap();
+        tab.start_stream_to(&path).unwrap();
+
+        for delta in ["# Title\n", "first ", "second", "\nthird"] {
+            tab.handle_agent_event(
+                AgentEvent::StreamDelta(delta.to_string()),
+                &UiConfig::default(),
+            );
+        }
+        tab.handle_agent_event(AgentEvent::Done(String::new()), &UiConfig::default());
+        assert!(tab.stream_file.is_none());
+        assert!(tab.messages.last().unwrap().contains("out.md"));
+
+        // Later turns are not written once the file is closed
+        tab.handle_agent_event(
+            AgentEvent::StreamDelta("ignored".to_string()),
+            &UiConfig::default(),
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "existing\n# Title\nfirst second\nthird"
+        );
+    }
+
+    #[test]
+    fn test_tool_preview_keeps_timestamps_aligned() {
+        let mut tab = test_tab();
+        tab.tool_verbosity = Some(ToolVerbosity::Full);
+        let config = UiConfig::default();
+        for cmd in ["echo a", "echo b"] {
+            tab.handle_agent_event(
+                AgentEvent::ToolStart {
+                    name: "bash".to_string(),
+                    arguments: serde_json::json!({ "command": cmd }).to_string(),
+                },
+                &config,
+            );
+        }
+        tab.stamp_messages();
+        let second_time = Some("2026-10-15 09:30:00".to_string());
+        *tab.message_times.last_mut().unwrap() = second_time.clone();
+
+        tab.handle_agent_event(
+            AgentEvent::ToolEnd {
+                name: "bash".to_string(),
+                arguments: r#"{"command":"echo a"}"#.to_string(),
+                success: true,
+                result: "a".to_string(),
+                metadata: serde_json::Value::Null,
+            },
+            &config,
+        );
+        tab.stamp_messages();
+        assert_eq!(tab.message_times.len(), tab.messages.len());
+        let second = tab
+            .messages
+            .iter()
+            .rposition(|m| m.contains("echo b"))
+            .unwrap();
+        assert!(second > 2, "preview lines were inserted before it");
+        assert_eq!(tab.message_times[second], second_time);
+    }
+
+    #[test]
+    fn test_message_timestamps_roundtrip() {
+        let mut tab = test_tab();
+        tab.messages.push("You: hello".to_string());
+        tab.stamp_messages();
+        tab.message_times[1] = Some("2026-10-15 09:30:00".to_string());
+        tab.messages.push("Assistant: hi".to_string());
+        tab.stamp_messages();
+        assert_eq!(tab.message_times.len(), 3);
+        assert!(tab.message_times.iter().all(|t| t.is_some()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        session::export_session(&tab.to_session_data(), &path).unwrap();
+        let data = session::import_session(&path).unwrap();
+
+        let mut restored = test_tab();
+        restored.restore_messages(data.ui_messages, data.ui_message_times);
+        assert_eq!(restored.messages, tab.messages);
+        assert_eq!(restored.message_times, tab.message_times);
+
+        // Legacy sessions without timestamps render without a time prefix
+        restored.restore_messages(vec!["You: old".to_string()], vec![]);
+        assert_eq!(restored.message_times, vec![None]);
+        let lines = RatatuiUi::build_conversation_lines(
+            &restored.messages,
+            Some(&restored.message_times),
+            None,
+            &UiConfig::default(),
+            0,
+        );
+        assert_eq!(lines[0].spans[0].content, "You: ");
+
+        let lines = RatatuiUi::build_conversation_lines(
+            &tab.messages,
+            Some(&tab.message_times),
+            None,
+            &UiConfig::default(),
+            0,
+        );
+        let first_user_line = lines
+            .iter()
+            .find(|l| l.spans.iter().any(|s| s.content == "You: "))
+            .unwrap();
+        assert_eq!(first_user_line.spans[0].content, "09:30 ");
+    }
+
+    #[test]
+    fn test_tool_output_preview_truncates() {
+        let long_line = "x".repeat(TOOL_PREVIEW_MAX_LINE_CHARS + 10);
+        let result = format!("{}\nb\nc\nd\ne\nf\ng\n", long_line);
+        let preview = tool_output_preview(&result);
+        assert_eq!(preview.len(), TOOL_PREVIEW_MAX_LINES + 1);
+        assert_eq!(
+            preview[0],
+            format!("TOOL_OUTPUT:{}…", "x".repeat(TOOL_PREVIEW_MAX_LINE_CHARS))
+        );
+        assert_eq!(preview[1], "TOOL_OUTPUT:b");
+        assert_eq!(preview.last().unwrap(), "TOOL_OUTPUT:… (2 more lines)");
+
+        assert_eq!(tool_output_preview("ok"), vec!["TOOL_OUTPUT:ok"]);
+        assert!(tool_output_preview("").is_empty());
+    }
+
+    #[test]
+    fn test_tool_args_preview() {
+        let write = r#"{"path":"a.txt","content":"one\ntwo\nthree"}"#;
+        assert_eq!(
+            tool_args_preview("write_file", write).unwrap(),
+            "(13 chars, 3 lines)"
+        );
+
+        let edit = serde_json::json!({
+            "path": "src/main.rs",
+            "old_text": "fn main() {\n    println!(\"hi\");",
+            "new_text": "fn main() {}",
+        })
+        .to_string();
+        assert_eq!(
+            tool_args_preview("edit", &edit).unwrap(),
+            r#""fn main() {⏎    println!(\"hi\")…" → "fn main() {}""#
+        );
+        let edit_all = r#"{"path":"a","old_text":"x","new_text":"","replace_all":true}"#;
+        assert_eq!(
+            tool_args_preview("edit", edit_all).unwrap(),
+            r#""x" → "" (all)"#
+        );
+
+        assert!(tool_args_preview("read_file", r#"{"path":"a"}"#).is_none());
+        assert!(tool_args_preview("write_file", "not json").is_none());
+
+        let mut tab = test_tab();
+        let ui = UiConfig {
+            preview_tool_args: true,
+            ..UiConfig::default()
+        };
+        tab.handle_agent_event(
+            AgentEvent::ToolStart {
+                name: "write_file".to_string(),
+                arguments: write.to_string(),
+            },
+            &ui,
+        );
+        assert_eq!(
+            tab.messages.last().unwrap(),
+            "TOOL_PROGRESS:⚡ 写入文件 a.txt (13 chars, 3 lines) ..."
+        );
+    }
+}